@@ -0,0 +1,58 @@
+use std::env;
+use std::fs::File;
+use std::io::BufReader;
+use std::process;
+
+use flowly_mp4::blocking::read_box_tree;
+use flowly_mp4::{BoxNode, BoxTree, Error};
+
+const USAGE: &str = "Usage: mp4dump [--json] <filename> [path]
+
+Prints the box tree of a file, or only the boxes matching a path such as
+moov/trak[0]/mdia/minf/stbl/stsd";
+
+fn main() {
+    let mut json = false;
+    let mut args = Vec::new();
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            _ => args.push(arg),
+        }
+    }
+
+    if args.is_empty() || args.len() > 2 {
+        eprintln!("{USAGE}");
+        process::exit(1);
+    }
+
+    if let Err(err) = dump(&args[0], args.get(1).map(String::as_str), json) {
+        eprintln!("{err}");
+        process::exit(1);
+    }
+}
+
+fn dump(filename: &str, path: Option<&str>, json: bool) -> Result<(), Error> {
+    let f = File::open(filename)?;
+    let tree = read_box_tree(&mut BufReader::new(f))?;
+
+    let tree = match path {
+        Some(path) => {
+            let boxes: Vec<BoxNode> = tree.find_all(path).into_iter().cloned().collect();
+            if boxes.is_empty() {
+                return Err(Error::InvalidData("no box matches the path"));
+            }
+
+            BoxTree { boxes }
+        }
+        None => tree,
+    };
+
+    if json {
+        println!("{}", tree.to_json()?);
+    } else {
+        print!("{tree}");
+    }
+
+    Ok(())
+}
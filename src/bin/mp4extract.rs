@@ -0,0 +1,237 @@
+use std::env;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::process;
+
+use flowly::Fourcc;
+use flowly_mp4::blocking::Mp4File;
+use flowly_mp4::{Error, Mp4Track};
+
+const USAGE: &str = "Usage: mp4extract <filename> <track_id> <output>
+
+Writes the elementary stream of a track: H.264 and H.265 as Annex B,
+AAC with ADTS headers and VP9 in an IVF container";
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let track_id = args.get(2).and_then(|id| id.parse().ok());
+    let (Some(track_id), 4) = (track_id, args.len()) else {
+        eprintln!("{USAGE}");
+        process::exit(1);
+    };
+
+    if let Err(err) = extract(&args[1], track_id, &args[3]) {
+        eprintln!("{err}");
+        process::exit(1);
+    }
+}
+
+enum Output {
+    AnnexB {
+        length_size: usize,
+        parameter_sets: Vec<u8>,
+    },
+    Adts {
+        profile: u8,
+        freq_index: u8,
+        chan_conf: u8,
+    },
+    Ivf,
+}
+
+fn extract(filename: &str, track_id: u32, output: &str) -> Result<(), Error> {
+    let f = File::open(filename)?;
+    let mut mp4_file = Mp4File::new(BufReader::new(f));
+    mp4_file.read_header()?;
+
+    let track = mp4_file
+        .tracks
+        .get(&track_id)
+        .ok_or(Error::TrakNotFound(track_id))?;
+    if track.protection().is_some() {
+        return Err(Error::InvalidData("encrypted tracks can't be extracted"));
+    }
+
+    let format = output_format(track)?;
+    let timescale = track.timescale();
+    let (width, height) = (track.tkhd.width.value(), track.tkhd.height.value());
+    let frame_count = track.samples.len() as u32;
+
+    let mut writer = BufWriter::new(File::create(output)?);
+    if let Output::Ivf = format {
+        write_ivf_header(&mut writer, width, height, timescale, frame_count)?;
+    }
+
+    for sample in mp4_file.samples(&[track_id]) {
+        let (_, sample) = sample?;
+
+        match &format {
+            Output::AnnexB {
+                length_size,
+                parameter_sets,
+            } => {
+                if sample.is_sync {
+                    writer.write_all(parameter_sets)?;
+                }
+                write_annexb(&mut writer, &sample.bytes, *length_size)?;
+            }
+
+            Output::Adts {
+                profile,
+                freq_index,
+                chan_conf,
+            } => {
+                let len = sample.bytes.len() + 7;
+                if len >= 1 << 13 {
+                    return Err(Error::InvalidData("AAC frame too large for ADTS"));
+                }
+
+                writer.write_all(&[
+                    0xff,
+                    0xf1,
+                    (profile - 1) << 6 | freq_index << 2 | chan_conf >> 2,
+                    (chan_conf & 3) << 6 | (len >> 11) as u8,
+                    (len >> 3) as u8,
+                    ((len & 7) << 5) as u8 | 0x1f,
+                    0xfc,
+                ])?;
+                writer.write_all(&sample.bytes)?;
+            }
+
+            Output::Ivf => {
+                writer.write_all(&(sample.bytes.len() as u32).to_le_bytes())?;
+                writer.write_all(&sample.start_time.to_le_bytes())?;
+                writer.write_all(&sample.bytes)?;
+            }
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+fn output_format(track: &Mp4Track) -> Result<Output, Error> {
+    let stsd = &track.mdia.minf.stbl.stsd;
+
+    Ok(match track.codec() {
+        Fourcc::VIDEO_AVC => {
+            let avcc = &stsd
+                .avc1
+                .as_ref()
+                .ok_or(Error::InvalidData("avc track without avc1"))?
+                .avcc;
+            let nalus = avcc
+                .sequence_parameter_sets
+                .iter()
+                .chain(&avcc.picture_parameter_sets)
+                .map(|nalu| &nalu.bytes[..]);
+
+            Output::AnnexB {
+                length_size: avcc.length_size_minus_one as usize + 1,
+                parameter_sets: with_start_codes(nalus),
+            }
+        }
+
+        Fourcc::VIDEO_HEVC => {
+            let hvcc = &stsd
+                .hev1
+                .as_ref()
+                .ok_or(Error::InvalidData("hevc track without hev1"))?
+                .hvcc;
+            let nalus = hvcc
+                .arrays
+                .iter()
+                .flat_map(|array| &array.nalus)
+                .map(|nalu| &nalu.data[..]);
+
+            Output::AnnexB {
+                length_size: hvcc.length_size_minus_one as usize + 1,
+                parameter_sets: with_start_codes(nalus),
+            }
+        }
+
+        Fourcc::AUDIO_AAC => {
+            let esds = stsd
+                .mp4a
+                .as_ref()
+                .and_then(|mp4a| mp4a.esds.as_ref())
+                .ok_or(Error::InvalidData("mp4a without esds"))?;
+            let config = &esds.es_desc.dec_config.dec_specific;
+
+            // ADTS only has room for the first four object types, SBR and PS are
+            // signalled implicitly on top of an AAC LC core
+            let profile = match config.profile {
+                1..=4 => config.profile,
+                5 | 29 => 2,
+                _ => return Err(Error::InvalidData("AAC object type not supported by ADTS")),
+            };
+
+            Output::Adts {
+                profile,
+                freq_index: config.freq_index,
+                chan_conf: config.chan_conf,
+            }
+        }
+
+        Fourcc::VIDEO_VP9 => Output::Ivf,
+
+        _ => return Err(Error::InvalidData("unsupported codec")),
+    })
+}
+
+fn with_start_codes<'a>(nalus: impl Iterator<Item = &'a [u8]>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for nalu in nalus {
+        buf.extend_from_slice(&[0, 0, 0, 1]);
+        buf.extend_from_slice(nalu);
+    }
+
+    buf
+}
+
+/// Replaces the NAL unit length prefixes of a sample with start codes.
+fn write_annexb<W: Write>(
+    writer: &mut W,
+    mut data: &[u8],
+    length_size: usize,
+) -> Result<(), Error> {
+    while !data.is_empty() {
+        if data.len() < length_size {
+            return Err(Error::NaluLengthDelimetedRedFail);
+        }
+
+        let len = data[..length_size]
+            .iter()
+            .fold(0usize, |len, &b| len << 8 | b as usize);
+        let Some(nalu) = data.get(length_size..length_size + len) else {
+            return Err(Error::NaluLengthDelimetedRedFail);
+        };
+
+        writer.write_all(&[0, 0, 0, 1])?;
+        writer.write_all(nalu)?;
+        data = &data[length_size + len..];
+    }
+
+    Ok(())
+}
+
+fn write_ivf_header<W: Write>(
+    writer: &mut W,
+    width: u16,
+    height: u16,
+    timescale: u32,
+    frame_count: u32,
+) -> Result<(), Error> {
+    writer.write_all(b"DKIF")?;
+    writer.write_all(&0u16.to_le_bytes())?; // version
+    writer.write_all(&32u16.to_le_bytes())?; // header size
+    writer.write_all(b"VP90")?;
+    writer.write_all(&width.to_le_bytes())?;
+    writer.write_all(&height.to_le_bytes())?;
+    writer.write_all(&timescale.to_le_bytes())?; // time base denominator
+    writer.write_all(&1u32.to_le_bytes())?; // time base numerator
+    writer.write_all(&frame_count.to_le_bytes())?;
+    writer.write_all(&0u32.to_le_bytes())?; // unused
+    Ok(())
+}
@@ -0,0 +1,179 @@
+use std::env;
+use std::fs::File;
+use std::io::BufReader;
+use std::process;
+
+use flowly_mp4::blocking::Mp4File;
+use flowly_mp4::{DataType, Error, IlstBox, KeysBox, MetaBox, MetadataKey, Mp4Track, TrackType};
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() != 2 {
+        eprintln!("Usage: mp4info <filename>");
+        process::exit(1);
+    }
+
+    if let Err(err) = info(&args[1]) {
+        eprintln!("{err}");
+        process::exit(1);
+    }
+}
+
+fn info(filename: &str) -> Result<(), Error> {
+    let f = File::open(filename)?;
+    let mut mp4_file = Mp4File::new(BufReader::new(f));
+    mp4_file.read_header()?;
+
+    println!("File:");
+    if let Some(ftyp) = &mp4_file.ftyp {
+        let brands: Vec<_> = ftyp
+            .compatible_brands
+            .iter()
+            .map(|b| b.to_string())
+            .collect();
+
+        println!("  major brand: {}", ftyp.major_brand);
+        println!("  minor version: {}", ftyp.minor_version);
+        println!("  compatible brands: {}", brands.join(" "));
+    }
+
+    let duration = mp4_file
+        .tracks
+        .values()
+        .map(duration_secs)
+        .fold(0.0, f64::max);
+    println!("  duration: {duration:.3}s");
+
+    let mut track_ids: Vec<_> = mp4_file.tracks.keys().copied().collect();
+    track_ids.sort();
+
+    println!("Tracks:");
+    for track_id in track_ids {
+        print_track(&mp4_file.tracks[&track_id]);
+    }
+
+    let metas = [
+        mp4_file.udta.as_ref().and_then(|u| u.meta.as_ref()),
+        mp4_file.meta.as_ref(),
+    ];
+    if metas.iter().any(Option::is_some) {
+        println!("Metadata:");
+        for meta in metas.into_iter().flatten() {
+            print_meta(meta);
+        }
+    }
+
+    let chapters = mp4_file.chapters()?;
+    if !chapters.is_empty() {
+        println!("Chapters:");
+        for chapter in chapters {
+            let millis = chapter.start.as_millis();
+            println!(
+                "  {:02}:{:02}:{:02}.{:03} {}",
+                millis / 3_600_000,
+                millis / 60_000 % 60,
+                millis / 1000 % 60,
+                millis % 1000,
+                chapter.title
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn duration_secs(track: &Mp4Track) -> f64 {
+    // fragments are not accounted for in `Mp4Track::duration`
+    let end = track
+        .samples
+        .last()
+        .map_or(0, |s| s.start_time + s.duration as u64);
+
+    end.max(track.duration) as f64 / track.timescale().max(1) as f64
+}
+
+fn print_track(track: &Mp4Track) {
+    let stsd = &track.mdia.minf.stbl.stsd;
+    let codec = track.codec().to_string();
+    let codec = if codec.trim_matches('\0').is_empty() {
+        "unknown"
+    } else {
+        &codec
+    };
+
+    let details = match track.track_type() {
+        TrackType::Video => format!("{}x{}", track.tkhd.width.value(), track.tkhd.height.value()),
+        TrackType::Audio => match &stsd.mp4a {
+            Some(mp4a) => format!(
+                "{} Hz, {} channels",
+                mp4a.samplerate.value(),
+                mp4a.channelcount
+            ),
+            None => String::new(),
+        },
+        _ => String::new(),
+    };
+
+    let duration = duration_secs(track);
+    let size: u64 = track.samples.iter().map(|s| s.size as u64).sum();
+    let bitrate = if duration > 0.0 {
+        size as f64 * 8.0 / duration / 1000.0
+    } else {
+        0.0
+    };
+
+    println!(
+        "  #{} {} {} {} {:.3}s {} samples {:.0} kb/s lang={}{}",
+        track.track_id,
+        track.track_type(),
+        codec,
+        details,
+        duration,
+        track.samples.len(),
+        bitrate,
+        track.mdia.mdhd.language,
+        if track.protection().is_some() {
+            " encrypted"
+        } else {
+            ""
+        }
+    );
+}
+
+fn print_meta(meta: &MetaBox) {
+    match meta {
+        MetaBox::Mdir {
+            ilst: Some(ilst), ..
+        } => print_ilst(ilst, None),
+        MetaBox::Mdta {
+            keys,
+            ilst: Some(ilst),
+            ..
+        } => print_ilst(ilst, Some(keys)),
+        _ => {}
+    }
+}
+
+fn print_ilst(ilst: &IlstBox, keys: Option<&KeysBox>) {
+    for item in &ilst.items {
+        let name = match (&item.key, keys) {
+            // `mdta` items are typed by their 1-based index into `keys`
+            (MetadataKey::Other(fourcc), Some(keys)) => {
+                let idx = u32::from_be_bytes(fourcc.value) as usize;
+                match keys.entries.get(idx.wrapping_sub(1)) {
+                    Some(entry) => entry.name.clone(),
+                    None => format!("{:?}", item.key),
+                }
+            }
+            (key, _) => format!("{key:?}"),
+        };
+
+        for value in &item.values {
+            match value.data_type {
+                DataType::Text => println!("  {name}: {}", String::from_utf8_lossy(&value.data)),
+                _ => println!("  {name}: <{} bytes>", value.data.len()),
+            }
+        }
+    }
+}
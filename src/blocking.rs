@@ -0,0 +1,171 @@
+//! Blocking MP4 reading over `std::io::Read + Seek`, without an async runtime.
+//!
+//! [`Mp4File`] runs the same parser as [`crate::Mp4File`] on a [`SyncReader`], whose
+//! reads complete immediately, so every call is driven to completion in place.
+
+use bytes::Bytes;
+use futures::executor::{block_on, block_on_stream};
+use std::io::{Read, Seek, Write};
+use std::ops::{Deref, DerefMut};
+
+use crate::{
+    Annexb, BoxTree, CaptionChannel, CaptionPacket, Chapter, Diagnostic, Error, LengthDelimited,
+    Mp4Sample, ReadSampleFormat, RepairReport, SubtitleCue, SubtitleFormat, SyncReader,
+};
+
+/// Blocking counterpart of [`BoxTree::read`].
+pub fn read_box_tree<R: Read + Seek>(reader: &mut R) -> Result<BoxTree, Error> {
+    block_on(BoxTree::read(&mut SyncReader(reader)))
+}
+
+/// Blocking counterpart of [`crate::validate`].
+pub fn validate<R: Read + Seek>(reader: &mut R) -> Result<Vec<Diagnostic>, Error> {
+    block_on(crate::validate(&mut SyncReader(reader)))
+}
+
+/// Blocking counterpart of [`crate::repair`].
+pub fn repair<R, F, D, W>(
+    reference: &Mp4File<R, F>,
+    damaged: &mut D,
+    writer: W,
+) -> Result<(W, RepairReport), Error>
+where
+    R: Read + Seek,
+    F: ReadSampleFormat,
+    D: Read + Seek,
+    W: Write,
+{
+    block_on(crate::repair(
+        &reference.inner,
+        &mut SyncReader(damaged),
+        writer,
+    ))
+}
+
+/// Blocking counterpart of [`crate::Mp4File`].
+///
+/// Dereferences to the inner file for the parsed tracks and boxes and for the
+/// non I/O methods such as [`add_decryption_key`](crate::Mp4File::add_decryption_key).
+pub struct Mp4File<R, F = Annexb>
+where
+    R: Read + Seek,
+    F: ReadSampleFormat,
+{
+    inner: crate::Mp4File<SyncReader<R>, F>,
+}
+
+impl<R: Read + Seek> Mp4File<R> {
+    pub fn new_annexb(reader: R) -> Self {
+        Self {
+            inner: crate::Mp4File::new_annexb(SyncReader(reader)),
+        }
+    }
+}
+
+impl<R: Read + Seek> Mp4File<R, LengthDelimited> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            inner: crate::Mp4File::new(SyncReader(reader)),
+        }
+    }
+}
+
+impl<R, F> Mp4File<R, F>
+where
+    R: Read + Seek,
+    F: ReadSampleFormat,
+{
+    pub fn into_inner(self) -> crate::Mp4File<SyncReader<R>, F> {
+        self.inner
+    }
+
+    pub fn read_header(&mut self) -> Result<bool, Error> {
+        block_on(self.inner.read_header())
+    }
+
+    /// See [`crate::Mp4File::read_header_indexed`].
+    pub fn read_header_indexed(&mut self) -> Result<bool, Error> {
+        block_on(self.inner.read_header_indexed())
+    }
+
+    /// See [`crate::Mp4File::read_fragment_index`].
+    pub fn read_fragment_index(&mut self) -> Result<bool, Error> {
+        block_on(self.inner.read_fragment_index())
+    }
+
+    /// See [`crate::Mp4File::read_fragment_at`].
+    pub fn read_fragment_at(&mut self, track_id: u32, time: u64) -> Result<Option<usize>, Error> {
+        block_on(self.inner.read_fragment_at(track_id, time))
+    }
+
+    pub fn read_sample_data(
+        &mut self,
+        track_id: u32,
+        sample_idx: usize,
+    ) -> Result<Option<Bytes>, Error> {
+        block_on(self.inner.read_sample_data(track_id, sample_idx))
+    }
+
+    /// See [`crate::Mp4File::samples`].
+    pub fn samples(
+        &mut self,
+        track_ids: &[u32],
+    ) -> impl Iterator<Item = Result<(u32, Mp4Sample), Error>> + '_ {
+        block_on_stream(Box::pin(self.inner.samples(track_ids)))
+    }
+
+    /// See [`crate::Mp4File::chapters`].
+    pub fn chapters(&mut self) -> Result<Vec<Chapter>, Error> {
+        block_on(self.inner.chapters())
+    }
+
+    /// See [`crate::Mp4File::subtitle_cues`].
+    pub fn subtitle_cues(&mut self, track_id: u32) -> Result<Vec<SubtitleCue>, Error> {
+        block_on(self.inner.subtitle_cues(track_id))
+    }
+
+    /// See [`crate::Mp4File::export_subtitles`].
+    pub fn export_subtitles(
+        &mut self,
+        track_id: u32,
+        format: SubtitleFormat,
+    ) -> Result<String, Error> {
+        block_on(self.inner.export_subtitles(track_id, format))
+    }
+
+    /// See [`crate::Mp4File::caption_packets`].
+    pub fn caption_packets(&mut self, track_id: u32) -> Result<Vec<CaptionPacket>, Error> {
+        block_on(self.inner.caption_packets(track_id))
+    }
+
+    /// See [`crate::Mp4File::closed_captions`].
+    pub fn closed_captions(
+        &mut self,
+        track_id: u32,
+        channel: CaptionChannel,
+    ) -> Result<Vec<SubtitleCue>, Error> {
+        block_on(self.inner.closed_captions(track_id, channel))
+    }
+}
+
+impl<R, F> Deref for Mp4File<R, F>
+where
+    R: Read + Seek,
+    F: ReadSampleFormat,
+{
+    type Target = crate::Mp4File<SyncReader<R>, F>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<R, F> DerefMut for Mp4File<R, F>
+where
+    R: Read + Seek,
+    F: ReadSampleFormat,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
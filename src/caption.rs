@@ -0,0 +1,696 @@
+//! CEA-608/708 closed captions carried in H.264/H.265 SEI messages (ATSC A/53) and in
+//! QuickTime `c608` tracks.
+
+use std::time::Duration;
+
+use crate::file::nal_units;
+use crate::{Error, SubtitleCue};
+
+/// `user_data_registered_itu_t_t35` SEI payload type.
+const SEI_USER_DATA_REGISTERED: u32 = 4;
+
+/// ITU-T T.35 country code, provider code and user identifier of ATSC A/53 captions.
+const ATSC_T35_PREFIX: [u8; 7] = [0xb5, 0x00, 0x31, b'G', b'A', b'9', b'4'];
+const ATSC_CC_DATA: u8 = 0x03;
+
+const ROWS: usize = 15;
+const COLUMNS: usize = 32;
+
+/// A `cc_data` triplet. `cc_type` 0 and 1 are CEA-608 field 1 and 2 byte pairs, 2 and 3
+/// carry CEA-708 DTVCC packet data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CcData {
+    pub cc_type: u8,
+    pub data: [u8; 2],
+}
+
+/// The caption data of a single sample.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptionPacket {
+    /// Presentation time in track timescale units.
+    pub pts: u64,
+    pub cc_data: Vec<CcData>,
+}
+
+/// CEA-608 caption channel, CC1 and CC2 are sent in field 1, CC3 and CC4 in field 2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptionChannel {
+    Cc1,
+    Cc2,
+    Cc3,
+    Cc4,
+}
+
+impl CaptionChannel {
+    fn field(self) -> u8 {
+        match self {
+            CaptionChannel::Cc1 | CaptionChannel::Cc2 => 0,
+            CaptionChannel::Cc3 | CaptionChannel::Cc4 => 1,
+        }
+    }
+
+    fn data_channel(self) -> u8 {
+        match self {
+            CaptionChannel::Cc1 | CaptionChannel::Cc3 => 1,
+            CaptionChannel::Cc2 | CaptionChannel::Cc4 => 2,
+        }
+    }
+}
+
+/// Extracts the valid `cc_data` triplets from the SEI NAL units of a length-prefixed
+/// H.264 or H.265 sample.
+pub fn sei_cc_data(sample: &[u8], length_size: usize, hevc: bool) -> Result<Vec<CcData>, Error> {
+    let mut cc_data = Vec::new();
+
+    for nal in nal_units(sample, length_size) {
+        let nal = nal?;
+        let Some(&header) = nal.first() else {
+            continue;
+        };
+
+        let payload = if hevc {
+            // prefix and suffix SEI
+            if !matches!((header >> 1) & 0x3f, 39 | 40) || nal.len() < 2 {
+                continue;
+            }
+            &nal[2..]
+        } else {
+            if header & 0x1f != 6 {
+                continue;
+            }
+            &nal[1..]
+        };
+
+        let rbsp = remove_emulation_prevention(payload);
+        read_sei_messages(&rbsp, &mut cc_data);
+    }
+
+    Ok(cc_data)
+}
+
+fn remove_emulation_prevention(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut zeros = 0;
+
+    for &b in data {
+        if zeros >= 2 && b == 0x03 {
+            zeros = 0;
+            continue;
+        }
+
+        zeros = if b == 0 { zeros + 1 } else { 0 };
+        out.push(b);
+    }
+
+    out
+}
+
+fn read_sei_messages(mut data: &[u8], cc_data: &mut Vec<CcData>) {
+    // stop at the rbsp trailing bits
+    while data.len() >= 2 && data[0] != 0x80 {
+        let mut read_value = || {
+            let mut value = 0u32;
+            while let Some((&b, rest)) = data.split_first() {
+                data = rest;
+                value += b as u32;
+                if b != 0xff {
+                    break;
+                }
+            }
+            value
+        };
+
+        let payload_type = read_value();
+        let payload_size = read_value() as usize;
+        if payload_size > data.len() {
+            return;
+        }
+
+        let (payload, rest) = data.split_at(payload_size);
+        data = rest;
+
+        if payload_type == SEI_USER_DATA_REGISTERED {
+            read_atsc_cc_data(payload, cc_data);
+        }
+    }
+}
+
+fn read_atsc_cc_data(payload: &[u8], cc_data: &mut Vec<CcData>) {
+    let Some(data) = payload.strip_prefix(&ATSC_T35_PREFIX) else {
+        return;
+    };
+
+    let [ATSC_CC_DATA, flags, _em_data, triplets @ ..] = data else {
+        return;
+    };
+
+    // process_cc_data_flag
+    if flags & 0x40 == 0 {
+        return;
+    }
+
+    let cc_count = (flags & 0x1f) as usize;
+    for triplet in triplets.chunks_exact(3).take(cc_count) {
+        // cc_valid
+        if triplet[0] & 0x04 == 0 {
+            continue;
+        }
+
+        cc_data.push(CcData {
+            cc_type: triplet[0] & 0x03,
+            data: [triplet[1], triplet[2]],
+        });
+    }
+}
+
+/// Extracts the caption byte pairs of a `c608` track sample.
+pub fn c608_cc_data(sample: &[u8]) -> Result<Vec<CcData>, Error> {
+    let mut cc_data = Vec::new();
+    let mut data = sample;
+
+    while data.len() >= 8 {
+        let size = u32::from_be_bytes(data[..4].try_into().unwrap()) as usize;
+        if size < 8 || size > data.len() {
+            return Err(Error::InvalidData("invalid c608 sample box size"));
+        }
+
+        let cc_type = match &data[4..8] {
+            b"cdat" => Some(0),
+            b"cdt2" => Some(1),
+            _ => None,
+        };
+
+        if let Some(cc_type) = cc_type {
+            cc_data.extend(data[8..size].chunks_exact(2).map(|pair| CcData {
+                cc_type,
+                data: [pair[0], pair[1]],
+            }));
+        }
+
+        data = &data[size..];
+    }
+
+    Ok(cc_data)
+}
+
+/// Encodes the CEA-608 byte pairs as a `c608` track sample, CEA-708 data is dropped.
+pub fn c608_sample(cc_data: &[CcData]) -> Vec<u8> {
+    let mut sample = Vec::new();
+
+    for (cc_type, kind) in [(0, b"cdat"), (1, b"cdt2")] {
+        let pairs = cc_data
+            .iter()
+            .filter(|cc| cc.cc_type == cc_type)
+            .flat_map(|cc| cc.data)
+            .collect::<Vec<_>>();
+
+        if !pairs.is_empty() {
+            sample.extend_from_slice(&(8 + pairs.len() as u32).to_be_bytes());
+            sample.extend_from_slice(kind);
+            sample.extend_from_slice(&pairs);
+        }
+    }
+
+    sample
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    PopOn,
+    PaintOn,
+    RollUp(usize),
+}
+
+type Screen = [[char; COLUMNS]; ROWS];
+
+const BLANK_SCREEN: Screen = [[' '; COLUMNS]; ROWS];
+
+/// Decodes one CEA-608 caption channel into timed cues.
+///
+/// A cue is emitted every time the displayed caption changes: on end of caption for
+/// pop-on captions, on carriage return for roll-up and paint-on captions, and when the
+/// display is erased.
+pub struct Cea608Decoder {
+    channel: CaptionChannel,
+    active_channel: u8,
+    last_control: Option<[u8; 2]>,
+    mode: Mode,
+    displayed: Screen,
+    non_displayed: Screen,
+    row: usize,
+    column: usize,
+
+    /// Time of the triplet being decoded.
+    time: Duration,
+
+    /// Time of the first change to the displayed memory since the last update.
+    dirty_since: Option<Duration>,
+    current: Option<SubtitleCue>,
+    cues: Vec<SubtitleCue>,
+}
+
+impl Cea608Decoder {
+    pub fn new(channel: CaptionChannel) -> Self {
+        Cea608Decoder {
+            channel,
+            active_channel: 1,
+            last_control: None,
+            mode: Mode::PopOn,
+            displayed: BLANK_SCREEN,
+            non_displayed: BLANK_SCREEN,
+            row: ROWS - 1,
+            column: 0,
+            time: Duration::ZERO,
+            dirty_since: None,
+            current: None,
+            cues: Vec::new(),
+        }
+    }
+
+    /// Feeds a `cc_data` triplet presented at `time`, triplets of other fields are ignored.
+    pub fn push(&mut self, time: Duration, cc: &CcData) {
+        if cc.cc_type != self.channel.field() {
+            return;
+        }
+
+        self.time = time;
+        let b1 = cc.data[0] & 0x7f;
+        let b2 = cc.data[1] & 0x7f;
+
+        match b1 {
+            0x00 => {}
+            // XDS packets in field 2
+            0x01..=0x0f => self.last_control = None,
+            0x10..=0x1f => {
+                if self.last_control == Some([b1, b2]) {
+                    // control codes are usually sent twice
+                    self.last_control = None;
+                    return;
+                }
+
+                self.last_control = Some([b1, b2]);
+                self.active_channel = if b1 & 0x08 != 0 { 2 } else { 1 };
+
+                if self.active_channel == self.channel.data_channel() {
+                    self.control(time, b1 & !0x08, b2);
+                }
+            }
+            _ => {
+                self.last_control = None;
+                if self.active_channel == self.channel.data_channel() {
+                    self.put_char(standard_char(b1));
+                    if b2 >= 0x20 {
+                        self.put_char(standard_char(b2));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Closes the caption still on screen at `end` and returns all cues.
+    pub fn finish(mut self, end: Duration) -> Vec<SubtitleCue> {
+        self.update(end);
+        self.displayed = BLANK_SCREEN;
+        self.update(end);
+        self.cues
+    }
+
+    fn control(&mut self, time: Duration, b1: u8, b2: u8) {
+        match (b1, b2) {
+            (0x14 | 0x15, 0x20..=0x2f) => self.command(time, b2),
+            (0x17, 0x21..=0x23) => {
+                self.column = (self.column + (b2 - 0x20) as usize).min(COLUMNS - 1)
+            }
+            // mid-row codes are shown as a space
+            (0x11, 0x20..=0x2f) => self.put_char(' '),
+            (0x11, 0x30..=0x3f) => self.put_char(SPECIAL_CHARS[(b2 - 0x30) as usize]),
+            (0x12, 0x20..=0x3f) => {
+                self.backspace();
+                self.put_char(EXTENDED_CHARS_1[(b2 - 0x20) as usize]);
+            }
+            (0x13, 0x20..=0x3f) => {
+                self.backspace();
+                self.put_char(EXTENDED_CHARS_2[(b2 - 0x20) as usize]);
+            }
+            (0x10..=0x17, 0x40..=0x7f) => self.preamble(b1, b2),
+            _ => {}
+        }
+    }
+
+    fn command(&mut self, time: Duration, code: u8) {
+        match code {
+            // RCL
+            0x20 => self.mode = Mode::PopOn,
+            // BS
+            0x21 => self.backspace(),
+            // DER
+            0x24 => {
+                let (row, column) = (self.row, self.column);
+                self.screen()[row][column..].fill(' ');
+            }
+            // RU2, RU3, RU4
+            0x25..=0x27 => {
+                let rows = (code - 0x23) as usize;
+                if !matches!(self.mode, Mode::RollUp(_)) {
+                    self.displayed = BLANK_SCREEN;
+                    self.non_displayed = BLANK_SCREEN;
+                    self.update(time);
+                    self.row = ROWS - 1;
+                }
+
+                self.mode = Mode::RollUp(rows);
+                self.column = 0;
+            }
+            // RDC
+            0x29 => self.mode = Mode::PaintOn,
+            // EDM
+            0x2c => {
+                self.displayed = BLANK_SCREEN;
+                self.update(time);
+            }
+            // CR
+            0x2d => {
+                if self.mode == Mode::PopOn {
+                    return;
+                }
+
+                self.update(time);
+
+                if let Mode::RollUp(rows) = self.mode {
+                    let top = (self.row + 1).saturating_sub(rows);
+                    for row in 0..ROWS {
+                        if row < top || row > self.row {
+                            self.displayed[row] = [' '; COLUMNS];
+                        } else if row < self.row {
+                            self.displayed[row] = self.displayed[row + 1];
+                        }
+                    }
+                    self.displayed[self.row] = [' '; COLUMNS];
+                } else if self.row + 1 < ROWS {
+                    self.row += 1;
+                }
+
+                self.column = 0;
+            }
+            // ENM
+            0x2e => self.non_displayed = BLANK_SCREEN,
+            // EOC
+            0x2f => {
+                std::mem::swap(&mut self.displayed, &mut self.non_displayed);
+                self.mode = Mode::PopOn;
+                self.update(time);
+            }
+            _ => {}
+        }
+    }
+
+    /// Preamble address code, moves the cursor to a row and indentation.
+    fn preamble(&mut self, b1: u8, b2: u8) {
+        let rows = match b1 {
+            0x10 => [11, 11],
+            0x11 => [1, 2],
+            0x12 => [3, 4],
+            0x13 => [12, 13],
+            0x14 => [14, 15],
+            0x15 => [5, 6],
+            0x16 => [7, 8],
+            _ => [9, 10],
+        };
+
+        let row = rows[usize::from(b2 & 0x20 != 0)] - 1;
+        let column = if b2 & 0x10 != 0 {
+            ((b2 & 0x0e) >> 1) as usize * 4
+        } else {
+            0
+        };
+
+        if let Mode::RollUp(rows) = self.mode {
+            if row != self.row {
+                // move the roll-up window with the base row
+                let window = self.displayed;
+                self.displayed = BLANK_SCREEN;
+                for offset in 0..rows.min(row + 1).min(self.row + 1) {
+                    self.displayed[row - offset] = window[self.row - offset];
+                }
+            }
+        }
+
+        self.row = row;
+        self.column = column;
+    }
+
+    /// Memory written by characters, pop-on captions are built off screen.
+    fn screen(&mut self) -> &mut Screen {
+        match self.mode {
+            Mode::PopOn => &mut self.non_displayed,
+            Mode::PaintOn | Mode::RollUp(_) => {
+                self.dirty_since.get_or_insert(self.time);
+                &mut self.displayed
+            }
+        }
+    }
+
+    fn put_char(&mut self, c: char) {
+        let (row, column) = (self.row, self.column);
+        self.screen()[row][column] = c;
+        self.column = (column + 1).min(COLUMNS - 1);
+    }
+
+    fn backspace(&mut self) {
+        if self.column > 0 {
+            self.column -= 1;
+            let (row, column) = (self.row, self.column);
+            self.screen()[row][column] = ' ';
+        }
+    }
+
+    /// Emits a cue if the displayed text differs from the current cue. Text drawn
+    /// directly on screen is shown from the time it was first changed.
+    fn update(&mut self, time: Duration) {
+        let time = self.dirty_since.take().unwrap_or(time);
+        let text = self
+            .displayed
+            .iter()
+            .map(|row| row.iter().collect::<String>().trim().to_string())
+            .filter(|row| !row.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if self.current.as_ref().map(|cue| &cue.text) == Some(&text) {
+            return;
+        }
+
+        if let Some(mut cue) = self.current.take() {
+            if time > cue.start {
+                cue.end = time;
+                self.cues.push(cue);
+            }
+        }
+
+        if !text.is_empty() {
+            self.current = Some(SubtitleCue {
+                start: time,
+                end: time,
+                text,
+                ..Default::default()
+            });
+        }
+    }
+}
+
+/// Decodes a channel of CEA-608 captions from packets in presentation order.
+pub fn decode_cea608(
+    packets: &[CaptionPacket],
+    timescale: u32,
+    channel: CaptionChannel,
+) -> Vec<SubtitleCue> {
+    let timescale = timescale.max(1) as u128;
+    let to_duration =
+        |pts: u64| Duration::from_nanos((pts as u128 * 1_000_000_000 / timescale) as u64);
+
+    let mut decoder = Cea608Decoder::new(channel);
+    for packet in packets {
+        let time = to_duration(packet.pts);
+        for cc in &packet.cc_data {
+            decoder.push(time, cc);
+        }
+    }
+
+    let end = packets.last().map_or(0, |p| p.pts);
+    decoder.finish(to_duration(end))
+}
+
+/// Characters differing from ASCII in the basic CEA-608 character set.
+fn standard_char(b: u8) -> char {
+    match b {
+        0x2a => 'á',
+        0x5c => 'é',
+        0x5e => 'í',
+        0x5f => 'ó',
+        0x60 => 'ú',
+        0x7b => 'ç',
+        0x7c => '÷',
+        0x7d => 'Ñ',
+        0x7e => 'ñ',
+        0x7f => '█',
+        _ => b as char,
+    }
+}
+
+const SPECIAL_CHARS: [char; 16] = [
+    '®', '°', '½', '¿', '™', '¢', '£', '♪', 'à', ' ', 'è', 'â', 'ê', 'î', 'ô', 'û',
+];
+
+const EXTENDED_CHARS_1: [char; 32] = [
+    'Á', 'É', 'Ó', 'Ú', 'Ü', 'ü', '‘', '¡', '*', '\'', '—', '©', '℠', '•', '“', '”', 'À', 'Â', 'Ç',
+    'È', 'Ê', 'Ë', 'ë', 'Î', 'Ï', 'ï', 'Ô', 'Ù', 'ù', 'Û', '«', '»',
+];
+
+const EXTENDED_CHARS_2: [char; 32] = [
+    'Ã', 'ã', 'Í', 'Ì', 'ì', 'Ò', 'ò', 'Õ', 'õ', '{', '}', '\\', '^', '_', '|', '~', 'Ä', 'ä', 'Ö',
+    'ö', 'ß', '¥', '¤', '¦', 'Å', 'å', 'Ø', 'ø', '┌', '┐', '└', '┘',
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sets odd parity on a CEA-608 byte.
+    fn parity(b: u8) -> u8 {
+        if b.count_ones() % 2 == 1 {
+            b
+        } else {
+            b | 0x80
+        }
+    }
+
+    fn cc(b1: u8, b2: u8) -> CcData {
+        CcData {
+            cc_type: 0,
+            data: [parity(b1), parity(b2)],
+        }
+    }
+
+    fn text(s: &str) -> Vec<CcData> {
+        s.as_bytes()
+            .chunks(2)
+            .map(|c| cc(c[0], c.get(1).copied().unwrap_or(0)))
+            .collect()
+    }
+
+    fn sei_sample(cc_data: &[CcData]) -> Vec<u8> {
+        let mut payload = ATSC_T35_PREFIX.to_vec();
+        payload.extend_from_slice(&[ATSC_CC_DATA, 0x40 | cc_data.len() as u8, 0xff]);
+        for cc in cc_data {
+            payload.extend_from_slice(&[0xfc | cc.cc_type, cc.data[0], cc.data[1]]);
+        }
+        // an invalid triplet is skipped
+        payload[8] += 1;
+        payload.extend_from_slice(&[0xf8, 0, 0]);
+
+        let mut nal = vec![0x06, SEI_USER_DATA_REGISTERED as u8, payload.len() as u8];
+        nal.extend_from_slice(&payload);
+        nal.push(0x80);
+
+        let mut sample = vec![0, 0, 0, 2, 0x09, 0xf0];
+        sample.extend_from_slice(&(nal.len() as u32).to_be_bytes());
+        sample.extend_from_slice(&nal);
+        sample
+    }
+
+    #[test]
+    fn test_sei_cc_data() {
+        let cc_data = vec![
+            cc(0x14, 0x20),
+            CcData {
+                cc_type: 1,
+                data: [0, 0],
+            },
+        ];
+
+        let sample = sei_sample(&cc_data);
+        assert_eq!(sei_cc_data(&sample, 4, false).unwrap(), cc_data);
+        assert!(sei_cc_data(&sample[..sample.len() - 1], 4, false).is_err());
+
+        let c608 = c608_sample(&cc_data);
+        assert_eq!(c608_cc_data(&c608).unwrap(), cc_data);
+
+        assert_eq!(
+            remove_emulation_prevention(&[0, 0, 3, 1, 0, 0, 3]),
+            [0, 0, 1, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_cea608_pop_on() {
+        let mut packets = Vec::new();
+        let mut push =
+            |pts: u64, cc_data: Vec<CcData>| packets.push(CaptionPacket { pts, cc_data });
+
+        // RCL RCL, PAC row 15, text, CR is ignored in pop-on mode
+        push(0, vec![cc(0x14, 0x20), cc(0x14, 0x20), cc(0x14, 0x70)]);
+        let mut line = text("HELLO");
+        line.extend([cc(0x11, 0x37), cc(0x14, 0x2d)]);
+        push(1000, line);
+        // PAC row 14, extended characters replace the preceding one
+        let mut line = vec![cc(0x14, 0x50)];
+        line.extend(text("Cafe"));
+        line.push(cc(0x12, 0x21));
+        push(2000, line);
+        // EOC EOC
+        push(3000, vec![cc(0x14, 0x2f), cc(0x14, 0x2f)]);
+        // CC2 text is ignored
+        push(4000, vec![cc(0x1c, 0x20), cc(0x41, 0x42), cc(0x1c, 0x2f)]);
+        // EDM
+        push(6000, vec![cc(0x14, 0x2c)]);
+
+        let cues = decode_cea608(&packets, 1000, CaptionChannel::Cc1);
+        assert_eq!(
+            cues,
+            vec![SubtitleCue {
+                start: Duration::from_secs(3),
+                end: Duration::from_secs(6),
+                text: "CafÉ\nHELLO♪".to_string(),
+                ..Default::default()
+            }]
+        );
+
+        let cues = decode_cea608(&packets, 1000, CaptionChannel::Cc2);
+        assert_eq!(cues.len(), 1);
+        assert_eq!(cues[0].text, "AB");
+    }
+
+    #[test]
+    fn test_cea608_roll_up() {
+        let mut decoder = Cea608Decoder::new(CaptionChannel::Cc1);
+        let mut push = |secs: u64, cc_data: Vec<CcData>| {
+            for cc in &cc_data {
+                decoder.push(Duration::from_secs(secs), cc);
+            }
+        };
+
+        // RU2, first line
+        let mut line = vec![cc(0x14, 0x25)];
+        line.extend(text("ONE"));
+        push(1, line);
+        // CR, second line
+        let mut line = vec![cc(0x14, 0x2d)];
+        line.extend(text("TWO"));
+        push(2, line);
+        // CR, third line
+        let mut line = vec![cc(0x14, 0x2d)];
+        line.extend(text("THREE"));
+        push(3, line);
+
+        let cues = decoder.finish(Duration::from_secs(5));
+        let cues = cues
+            .iter()
+            .map(|c| (c.start.as_secs(), c.end.as_secs(), c.text.as_str()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            cues,
+            vec![(1, 2, "ONE"), (2, 3, "ONE\nTWO"), (3, 5, "TWO\nTHREE")]
+        );
+    }
+}
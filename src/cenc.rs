@@ -0,0 +1,777 @@
+use aes::cipher::{generic_array::GenericArray, BlockDecrypt, BlockEncrypt, KeyInit};
+use aes::Aes128;
+use std::collections::HashMap;
+
+use crate::file::nal_units;
+use crate::{
+    Error, FourCC, FrmaBox, PsshBox, SampleEncryption, SchiBox, SchmBox, SinfBox,
+    SubsampleEncryption, TencBox, TrackType,
+};
+
+const BLOCK_SIZE: usize = 16;
+
+/// Pattern used for `cbcs` video tracks: one encrypted block out of ten.
+const CBCS_VIDEO_PATTERN: (u8, u8) = (1, 9);
+
+/// Bytes at the start of a slice NAL unit left in clear with `cbcs`, which requires the
+/// slice header in clear. Slice headers aren't parsed, this covers them in practice.
+const CBCS_CLEAR_LEADER: usize = 32;
+
+/// Common encryption scheme (ISO/IEC 23001-7).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtectionScheme {
+    /// AES-CTR, full sample encryption.
+    Cenc,
+    /// AES-CTR, pattern encryption.
+    Cens,
+    /// AES-CBC, full sample encryption.
+    Cbc1,
+    /// AES-CBC, pattern encryption with constant IV.
+    Cbcs,
+}
+
+impl ProtectionScheme {
+    pub fn from_fourcc(fourcc: FourCC) -> Option<Self> {
+        match &fourcc.value {
+            b"cenc" => Some(Self::Cenc),
+            b"cens" => Some(Self::Cens),
+            b"cbc1" => Some(Self::Cbc1),
+            b"cbcs" => Some(Self::Cbcs),
+            _ => None,
+        }
+    }
+
+    pub fn fourcc(&self) -> FourCC {
+        match self {
+            Self::Cenc => (*b"cenc").into(),
+            Self::Cens => (*b"cens").into(),
+            Self::Cbc1 => (*b"cbc1").into(),
+            Self::Cbcs => (*b"cbcs").into(),
+        }
+    }
+
+    #[inline]
+    fn is_ctr(&self) -> bool {
+        matches!(self, Self::Cenc | Self::Cens)
+    }
+}
+
+/// Decrypts protected samples with keys looked up by KID.
+#[derive(Debug, Clone, Default)]
+pub struct CencDecryptor {
+    keys: HashMap<[u8; 16], [u8; 16]>,
+}
+
+impl CencDecryptor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_key(&mut self, kid: [u8; 16], key: [u8; 16]) {
+        self.keys.insert(kid, key);
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Decrypts a sample in place using the track protection info and the
+    /// sample's IV and subsample map.
+    pub fn decrypt_sample(
+        &self,
+        sinf: &SinfBox,
+        info: &SampleEncryption,
+        data: &mut [u8],
+    ) -> Result<(), Error> {
+        let (scheme, tenc) = protection_params(sinf)?;
+
+        if tenc.default_is_protected == 0 {
+            return Ok(());
+        }
+
+        let key = self
+            .keys
+            .get(&tenc.default_kid)
+            .ok_or(Error::DecryptionKeyNotFound(tenc.default_kid))?;
+
+        Cipher::new(scheme, key, tenc).apply(&info.iv, &info.subsamples, data, Direction::Decrypt)
+    }
+}
+
+/// Content protection applied to a track when packaging fragments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CencConfig {
+    /// Either [`ProtectionScheme::Cenc`] or [`ProtectionScheme::Cbcs`].
+    pub scheme: ProtectionScheme,
+    pub kid: [u8; 16],
+    pub key: [u8; 16],
+
+    /// 8 byte initial IV for `cenc`, incremented for every sample, or the 16 byte
+    /// constant IV for `cbcs`.
+    pub iv: Vec<u8>,
+
+    /// Protection system specific headers written to `moov`.
+    pub psshs: Vec<PsshBox>,
+}
+
+/// Encrypts the samples of a single track.
+pub struct CencEncryptor {
+    cipher: Cipher,
+    tenc: TencBox,
+    iv: [u8; BLOCK_SIZE],
+}
+
+impl CencEncryptor {
+    pub fn new(config: &CencConfig, track_type: TrackType) -> Result<Self, Error> {
+        let mut iv = [0u8; BLOCK_SIZE];
+
+        let tenc = match config.scheme {
+            ProtectionScheme::Cenc => {
+                if config.iv.len() != 8 {
+                    return Err(Error::InvalidData("cenc requires an 8 byte IV"));
+                }
+                iv[..8].copy_from_slice(&config.iv);
+
+                TencBox {
+                    default_is_protected: 1,
+                    default_per_sample_iv_size: 8,
+                    default_kid: config.kid,
+                    ..Default::default()
+                }
+            }
+
+            ProtectionScheme::Cbcs => {
+                if config.iv.len() != 16 {
+                    return Err(Error::InvalidData("cbcs requires a 16 byte constant IV"));
+                }
+                iv.copy_from_slice(&config.iv);
+
+                let (crypt, skip) = if track_type == TrackType::Video {
+                    CBCS_VIDEO_PATTERN
+                } else {
+                    (0, 0)
+                };
+
+                TencBox {
+                    version: 1,
+                    default_crypt_byte_block: crypt,
+                    default_skip_byte_block: skip,
+                    default_is_protected: 1,
+                    default_per_sample_iv_size: 0,
+                    default_kid: config.kid,
+                    default_constant_iv: config.iv.clone(),
+                    ..Default::default()
+                }
+            }
+
+            _ => {
+                return Err(Error::InvalidData(
+                    "only cenc and cbcs are supported for encryption",
+                ))
+            }
+        };
+
+        Ok(Self {
+            cipher: Cipher::new(config.scheme, &config.key, &tenc),
+            tenc,
+            iv,
+        })
+    }
+
+    pub fn scheme(&self) -> ProtectionScheme {
+        self.cipher.scheme
+    }
+
+    pub fn tenc(&self) -> &TencBox {
+        &self.tenc
+    }
+
+    /// Protection scheme info for an `encv`/`enca` sample entry wrapping `original_format`.
+    pub fn sinf(&self, original_format: FourCC) -> SinfBox {
+        SinfBox {
+            frma: FrmaBox { original_format },
+            schm: Some(SchmBox {
+                scheme_type: self.cipher.scheme.fourcc(),
+                scheme_version: 0x10000,
+                ..Default::default()
+            }),
+            schi: Some(SchiBox {
+                tenc: Some(self.tenc.clone()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// Encrypts a sample in place and returns the entry to be stored in `senc`.
+    ///
+    /// With no subsamples the whole sample is protected.
+    pub fn encrypt_sample(
+        &mut self,
+        data: &mut [u8],
+        subsamples: Vec<SubsampleEncryption>,
+    ) -> Result<SampleEncryption, Error> {
+        self.cipher
+            .apply(&self.iv, &subsamples, data, Direction::Encrypt)?;
+
+        if self.tenc.default_per_sample_iv_size == 0 {
+            return Ok(SampleEncryption {
+                iv: Vec::new(),
+                subsamples,
+            });
+        }
+
+        let iv_size = self.tenc.default_per_sample_iv_size as usize;
+        let iv = self.iv[..iv_size].to_vec();
+
+        // the block counter lives in the lower 8 bytes, so bump the IV itself
+        let next = u64::from_be_bytes(self.iv[..8].try_into().unwrap()).wrapping_add(1);
+        self.iv[..8].copy_from_slice(&next.to_be_bytes());
+
+        Ok(SampleEncryption { iv, subsamples })
+    }
+}
+
+/// Splits a length-prefixed AVC/HEVC sample into subsamples.
+///
+/// Slice NAL units are protected except for the length prefix and NAL header, with the
+/// protected range aligned to whole blocks. With `cbcs` the first [`CBCS_CLEAR_LEADER`]
+/// bytes of a slice stay in clear so its slice header does, and the protected range
+/// starts right after them. Other NAL units are left in clear.
+pub(crate) fn nal_subsamples(
+    data: &[u8],
+    length_size: usize,
+    hevc: bool,
+    scheme: ProtectionScheme,
+) -> Result<Vec<SubsampleEncryption>, Error> {
+    let header_size = if hevc { 2 } else { 1 };
+
+    let mut subsamples = Vec::new();
+    let mut clear = 0usize;
+
+    for nal in nal_units(data, length_size) {
+        let nal = nal?;
+        if nal.is_empty() {
+            return Err(Error::InvalidData("empty NAL unit"));
+        }
+
+        let is_slice = if hevc {
+            (nal[0] >> 1) & 0x3f < 32
+        } else {
+            matches!(nal[0] & 0x1f, 1..=5)
+        };
+
+        let protected = match scheme {
+            _ if !is_slice => 0,
+            // pattern encryption leaves a trailing partial block in clear by itself
+            ProtectionScheme::Cbcs if nal.len() >= CBCS_CLEAR_LEADER + BLOCK_SIZE => {
+                nal.len() - CBCS_CLEAR_LEADER
+            }
+            ProtectionScheme::Cbcs => 0,
+            _ => {
+                let payload = nal.len().saturating_sub(header_size);
+                payload - payload % BLOCK_SIZE
+            }
+        };
+
+        clear += length_size + nal.len() - protected;
+        if protected > 0 {
+            push_subsample(&mut subsamples, clear, protected as u32);
+            clear = 0;
+        }
+    }
+
+    if clear > 0 {
+        push_subsample(&mut subsamples, clear, 0);
+    }
+
+    Ok(subsamples)
+}
+
+fn push_subsample(subsamples: &mut Vec<SubsampleEncryption>, mut clear: usize, protected: u32) {
+    while clear > u16::MAX as usize {
+        subsamples.push(SubsampleEncryption {
+            bytes_of_clear_data: u16::MAX,
+            bytes_of_protected_data: 0,
+        });
+        clear -= u16::MAX as usize;
+    }
+
+    subsamples.push(SubsampleEncryption {
+        bytes_of_clear_data: clear as u16,
+        bytes_of_protected_data: protected,
+    });
+}
+
+fn protection_params(sinf: &SinfBox) -> Result<(ProtectionScheme, &TencBox), Error> {
+    let scheme = sinf
+        .scheme_type()
+        .and_then(ProtectionScheme::from_fourcc)
+        .ok_or(Error::InvalidData("unsupported protection scheme"))?;
+
+    let tenc = sinf
+        .tenc()
+        .ok_or(Error::BoxNotFound(crate::BoxType::TencBox))?;
+
+    Ok((scheme, tenc))
+}
+
+#[derive(Clone, Copy)]
+enum Direction {
+    Encrypt,
+    Decrypt,
+}
+
+struct Cipher {
+    scheme: ProtectionScheme,
+    aes: Aes128,
+    crypt_byte_block: usize,
+    skip_byte_block: usize,
+}
+
+impl Cipher {
+    fn new(scheme: ProtectionScheme, key: &[u8; 16], tenc: &TencBox) -> Self {
+        let (crypt_byte_block, skip_byte_block) = match scheme {
+            ProtectionScheme::Cens | ProtectionScheme::Cbcs => (
+                tenc.default_crypt_byte_block as usize,
+                tenc.default_skip_byte_block as usize,
+            ),
+            _ => (0, 0),
+        };
+
+        Self {
+            scheme,
+            aes: Aes128::new(GenericArray::from_slice(key)),
+            crypt_byte_block,
+            skip_byte_block,
+        }
+    }
+
+    fn apply(
+        &self,
+        sample_iv: &[u8],
+        subsamples: &[SubsampleEncryption],
+        data: &mut [u8],
+        direction: Direction,
+    ) -> Result<(), Error> {
+        let mut iv = [0u8; BLOCK_SIZE];
+        match sample_iv.len() {
+            8 | 16 => iv[..sample_iv.len()].copy_from_slice(sample_iv),
+            _ => return Err(Error::InvalidData("IV must be 8 or 16 bytes")),
+        }
+
+        let mut ranges = Vec::new();
+        if subsamples.is_empty() {
+            ranges.push(0..data.len());
+        } else {
+            let mut pos = 0usize;
+            for sub in subsamples {
+                let start = pos + sub.bytes_of_clear_data as usize;
+                let end = start + sub.bytes_of_protected_data as usize;
+                if end > data.len() {
+                    return Err(Error::InvalidData("subsample map exceeds sample size"));
+                }
+
+                ranges.push(start..end);
+                pos = end;
+            }
+        }
+
+        if self.scheme.is_ctr() {
+            let mut ctr = Ctr::new(iv);
+            for range in ranges {
+                self.for_each_pattern_block(&mut data[range], |block| ctr.apply(&self.aes, block));
+            }
+        } else {
+            let mut chain = iv;
+            for range in ranges {
+                // cbcs restarts the chain with the constant IV for every subsample
+                if self.scheme == ProtectionScheme::Cbcs {
+                    chain = iv;
+                }
+
+                let range = &mut data[range];
+                let len = range.len() - range.len() % BLOCK_SIZE;
+                self.for_each_pattern_block(&mut range[..len], |block| match direction {
+                    Direction::Encrypt => cbc_encrypt(&self.aes, &mut chain, block),
+                    Direction::Decrypt => cbc_decrypt(&self.aes, &mut chain, block),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Calls `f` for every encrypted part of the range, honouring the crypt/skip pattern.
+    fn for_each_pattern_block(&self, data: &mut [u8], mut f: impl FnMut(&mut [u8])) {
+        if self.crypt_byte_block == 0 {
+            f(data);
+            return;
+        }
+
+        let crypt = self.crypt_byte_block * BLOCK_SIZE;
+        let skip = self.skip_byte_block * BLOCK_SIZE;
+
+        for chunk in data.chunks_mut(crypt + skip) {
+            let len = chunk.len().min(crypt);
+            // partial blocks are left in clear with pattern encryption
+            let len = len - len % BLOCK_SIZE;
+            chunk[..len].chunks_mut(BLOCK_SIZE).for_each(&mut f);
+        }
+    }
+}
+
+/// AES-CTR keystream, continuous across the protected ranges of a sample.
+struct Ctr {
+    counter: [u8; BLOCK_SIZE],
+    keystream: [u8; BLOCK_SIZE],
+    pos: usize,
+}
+
+impl Ctr {
+    fn new(iv: [u8; BLOCK_SIZE]) -> Self {
+        Self {
+            counter: iv,
+            keystream: [0; BLOCK_SIZE],
+            pos: BLOCK_SIZE,
+        }
+    }
+
+    fn apply(&mut self, aes: &Aes128, data: &mut [u8]) {
+        for b in data.iter_mut() {
+            if self.pos == BLOCK_SIZE {
+                let block = GenericArray::from_mut_slice(&mut self.keystream);
+                block.copy_from_slice(&self.counter);
+                aes.encrypt_block(block);
+
+                self.counter = u128::from_be_bytes(self.counter)
+                    .wrapping_add(1)
+                    .to_be_bytes();
+                self.pos = 0;
+            }
+
+            *b ^= self.keystream[self.pos];
+            self.pos += 1;
+        }
+    }
+}
+
+fn cbc_encrypt(aes: &Aes128, chain: &mut [u8; BLOCK_SIZE], data: &mut [u8]) {
+    for block in data.chunks_exact_mut(BLOCK_SIZE) {
+        for (b, c) in block.iter_mut().zip(chain.iter()) {
+            *b ^= c;
+        }
+
+        aes.encrypt_block(GenericArray::from_mut_slice(block));
+        chain.copy_from_slice(block);
+    }
+}
+
+fn cbc_decrypt(aes: &Aes128, chain: &mut [u8; BLOCK_SIZE], data: &mut [u8]) {
+    for block in data.chunks_exact_mut(BLOCK_SIZE) {
+        let cipher_text: [u8; BLOCK_SIZE] = block.try_into().unwrap();
+
+        aes.decrypt_block(GenericArray::from_mut_slice(block));
+        for (b, c) in block.iter_mut().zip(chain.iter()) {
+            *b ^= c;
+        }
+
+        *chain = cipher_text;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FrmaBox, SchiBox, SchmBox, SubsampleEncryption};
+
+    // NIST SP 800-38A test vectors
+    const KEY: [u8; 16] = [
+        0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f,
+        0x3c,
+    ];
+
+    const PLAIN: [u8; 64] = [
+        0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17,
+        0x2a, 0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c, 0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf,
+        0x8e, 0x51, 0x30, 0xc8, 0x1c, 0x46, 0xa3, 0x5c, 0xe4, 0x11, 0xe5, 0xfb, 0xc1, 0x19, 0x1a,
+        0x0a, 0x52, 0xef, 0xf6, 0x9f, 0x24, 0x45, 0xdf, 0x4f, 0x9b, 0x17, 0xad, 0x2b, 0x41, 0x7b,
+        0xe6, 0x6c, 0x37, 0x10,
+    ];
+
+    const CTR_IV: [u8; 16] = [
+        0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9, 0xfa, 0xfb, 0xfc, 0xfd, 0xfe,
+        0xff,
+    ];
+
+    const CTR_CIPHER: [u8; 64] = [
+        0x87, 0x4d, 0x61, 0x91, 0xb6, 0x20, 0xe3, 0x26, 0x1b, 0xef, 0x68, 0x64, 0x99, 0x0d, 0xb6,
+        0xce, 0x98, 0x06, 0xf6, 0x6b, 0x79, 0x70, 0xfd, 0xff, 0x86, 0x17, 0x18, 0x7b, 0xb9, 0xff,
+        0xfd, 0xff, 0x5a, 0xe4, 0xdf, 0x3e, 0xdb, 0xd5, 0xd3, 0x5e, 0x5b, 0x4f, 0x09, 0x02, 0x0d,
+        0xb0, 0x3e, 0xab, 0x1e, 0x03, 0x1d, 0xda, 0x2f, 0xbe, 0x03, 0xd1, 0x79, 0x21, 0x70, 0xa0,
+        0xf3, 0x00, 0x9c, 0xee,
+    ];
+
+    const CBC_IV: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f,
+    ];
+
+    const CBC_CIPHER: [u8; 64] = [
+        0x76, 0x49, 0xab, 0xac, 0x81, 0x19, 0xb2, 0x46, 0xce, 0xe9, 0x8e, 0x9b, 0x12, 0xe9, 0x19,
+        0x7d, 0x50, 0x86, 0xcb, 0x9b, 0x50, 0x72, 0x19, 0xee, 0x95, 0xdb, 0x11, 0x3a, 0x91, 0x76,
+        0x78, 0xb2, 0x73, 0xbe, 0xd6, 0xb8, 0xe3, 0xc1, 0x74, 0x3b, 0x71, 0x16, 0xe6, 0x9e, 0x22,
+        0x22, 0x95, 0x16, 0x3f, 0xf1, 0xca, 0xa1, 0x68, 0x1f, 0xac, 0x09, 0x12, 0x0e, 0xca, 0x30,
+        0x75, 0x86, 0xe1, 0xa7,
+    ];
+
+    const KID: [u8; 16] = [0x42; 16];
+
+    fn sinf(scheme: &str, tenc: TencBox) -> SinfBox {
+        SinfBox {
+            frma: FrmaBox {
+                original_format: str::parse("avc1").unwrap(),
+            },
+            schm: Some(SchmBox {
+                scheme_type: str::parse(scheme).unwrap(),
+                scheme_version: 0x10000,
+                ..Default::default()
+            }),
+            schi: Some(SchiBox {
+                tenc: Some(tenc),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn decryptor() -> CencDecryptor {
+        let mut decryptor = CencDecryptor::new();
+        decryptor.add_key(KID, KEY);
+        decryptor
+    }
+
+    fn subsample(clear: u16, protected: u32) -> SubsampleEncryption {
+        SubsampleEncryption {
+            bytes_of_clear_data: clear,
+            bytes_of_protected_data: protected,
+        }
+    }
+
+    #[test]
+    fn test_cenc_full_sample() {
+        let sinf = sinf(
+            "cenc",
+            TencBox {
+                default_is_protected: 1,
+                default_per_sample_iv_size: 16,
+                default_kid: KID,
+                ..Default::default()
+            },
+        );
+
+        let info = SampleEncryption {
+            iv: CTR_IV.to_vec(),
+            subsamples: vec![],
+        };
+
+        let mut data = CTR_CIPHER;
+        decryptor().decrypt_sample(&sinf, &info, &mut data).unwrap();
+        assert_eq!(data, PLAIN);
+    }
+
+    #[test]
+    fn test_cenc_subsamples() {
+        let sinf = sinf(
+            "cenc",
+            TencBox {
+                default_is_protected: 1,
+                default_per_sample_iv_size: 16,
+                default_kid: KID,
+                ..Default::default()
+            },
+        );
+
+        // the counter continues across protected ranges, including partial blocks
+        let info = SampleEncryption {
+            iv: CTR_IV.to_vec(),
+            subsamples: vec![subsample(4, 20), subsample(3, 44)],
+        };
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&[0, 0, 0, 1]);
+        data.extend_from_slice(&CTR_CIPHER[..20]);
+        data.extend_from_slice(&[0x65, 0x88, 0x84]);
+        data.extend_from_slice(&CTR_CIPHER[20..]);
+
+        decryptor().decrypt_sample(&sinf, &info, &mut data).unwrap();
+
+        assert_eq!(&data[..4], &[0, 0, 0, 1]);
+        assert_eq!(&data[4..24], &PLAIN[..20]);
+        assert_eq!(&data[24..27], &[0x65, 0x88, 0x84]);
+        assert_eq!(&data[27..], &PLAIN[20..]);
+    }
+
+    #[test]
+    fn test_cbc1_full_sample() {
+        let sinf = sinf(
+            "cbc1",
+            TencBox {
+                default_is_protected: 1,
+                default_per_sample_iv_size: 16,
+                default_kid: KID,
+                ..Default::default()
+            },
+        );
+
+        let info = SampleEncryption {
+            iv: CBC_IV.to_vec(),
+            subsamples: vec![],
+        };
+
+        let mut data = CBC_CIPHER.to_vec();
+        data.extend_from_slice(&[1, 2, 3]); // trailing partial block stays in clear
+
+        decryptor().decrypt_sample(&sinf, &info, &mut data).unwrap();
+        assert_eq!(&data[..64], &PLAIN);
+        assert_eq!(&data[64..], &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_cbcs_pattern() {
+        let sinf = sinf(
+            "cbcs",
+            TencBox {
+                version: 1,
+                default_crypt_byte_block: 1,
+                default_skip_byte_block: 1,
+                default_is_protected: 1,
+                default_per_sample_iv_size: 0,
+                default_kid: KID,
+                default_constant_iv: CBC_IV.to_vec(),
+                ..Default::default()
+            },
+        );
+
+        let info = SampleEncryption {
+            iv: CBC_IV.to_vec(),
+            subsamples: vec![subsample(2, 16), subsample(0, 32)],
+        };
+
+        // 1:1 pattern, block 0 of each subsample is encrypted with a fresh chain
+        let mut data = vec![9, 9];
+        data.extend_from_slice(&CBC_CIPHER[..16]);
+        data.extend_from_slice(&CBC_CIPHER[..16]);
+        data.extend_from_slice(&PLAIN[16..32]);
+
+        decryptor().decrypt_sample(&sinf, &info, &mut data).unwrap();
+
+        assert_eq!(&data[..2], &[9, 9]);
+        assert_eq!(&data[2..18], &PLAIN[..16]);
+        assert_eq!(&data[18..34], &PLAIN[..16]);
+        assert_eq!(&data[34..], &PLAIN[16..32]);
+    }
+
+    #[test]
+    fn test_missing_key() {
+        let sinf = sinf(
+            "cenc",
+            TencBox {
+                default_is_protected: 1,
+                default_per_sample_iv_size: 8,
+                default_kid: [1; 16],
+                ..Default::default()
+            },
+        );
+
+        let info = SampleEncryption {
+            iv: vec![0; 8],
+            subsamples: vec![],
+        };
+
+        let result = decryptor().decrypt_sample(&sinf, &info, &mut [0; 16]);
+        assert!(matches!(result, Err(Error::DecryptionKeyNotFound(_))));
+    }
+
+    #[test]
+    fn test_encrypt_cenc() {
+        let config = CencConfig {
+            scheme: ProtectionScheme::Cenc,
+            kid: KID,
+            key: KEY,
+            iv: CTR_IV[..8].to_vec(),
+            psshs: Vec::new(),
+        };
+
+        let mut encryptor = CencEncryptor::new(&config, TrackType::Audio).unwrap();
+        let sinf = encryptor.sinf(str::parse("mp4a").unwrap());
+
+        let mut data = PLAIN;
+        let info = encryptor.encrypt_sample(&mut data, vec![]).unwrap();
+        assert_eq!(info.iv, &CTR_IV[..8]);
+        assert_ne!(data, PLAIN);
+
+        decryptor().decrypt_sample(&sinf, &info, &mut data).unwrap();
+        assert_eq!(data, PLAIN);
+
+        // the next sample gets the following IV
+        let info = encryptor.encrypt_sample(&mut data, vec![]).unwrap();
+        assert_eq!(info.iv, &[0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf8]);
+    }
+
+    #[test]
+    fn test_encrypt_cbcs_video() {
+        let config = CencConfig {
+            scheme: ProtectionScheme::Cbcs,
+            kid: KID,
+            key: KEY,
+            iv: CBC_IV.to_vec(),
+            psshs: Vec::new(),
+        };
+
+        let mut encryptor = CencEncryptor::new(&config, TrackType::Video).unwrap();
+        let sinf = encryptor.sinf(str::parse("avc1").unwrap());
+        let tenc = sinf.tenc().unwrap();
+        assert_eq!(tenc.version, 1);
+        assert_eq!(
+            (tenc.default_crypt_byte_block, tenc.default_skip_byte_block),
+            (1, 9)
+        );
+
+        // an SPS left in clear followed by a 250 byte IDR slice
+        let mut sample = vec![0, 0, 0, 4, 0x67, 0x64, 0x00, 0x1f];
+        sample.extend_from_slice(&250u32.to_be_bytes());
+        sample.push(0x65);
+        sample.extend((0..249).map(|x| x as u8));
+
+        // the slice header stays in clear, protection starts right after the leader
+        let subsamples = nal_subsamples(&sample, 4, false, ProtectionScheme::Cbcs).unwrap();
+        assert_eq!(subsamples, vec![subsample(8 + 4 + 32, 218)]);
+
+        // cenc only keeps the NAL header in clear
+        let cenc = nal_subsamples(&sample, 4, false, ProtectionScheme::Cenc).unwrap();
+        assert_eq!(cenc, vec![subsample(8 + 4 + 1 + 9, 240)]);
+
+        // a slice too short to protect past its leader stays in clear
+        let short = [&40u32.to_be_bytes()[..], &[0x65; 40]].concat();
+        let short = nal_subsamples(&short, 4, false, ProtectionScheme::Cbcs).unwrap();
+        assert_eq!(short, vec![subsample(44, 0)]);
+
+        let mut data = sample.clone();
+        let info = encryptor.encrypt_sample(&mut data, subsamples).unwrap();
+        assert!(info.iv.is_empty());
+        assert_eq!(&data[..44], &sample[..44]);
+        assert_ne!(&data[44..60], &sample[44..60]);
+        assert_eq!(&data[60..204], &sample[60..204]);
+        assert_ne!(&data[204..220], &sample[204..220]);
+        assert_eq!(&data[220..], &sample[220..]);
+
+        let info = SampleEncryption {
+            iv: CBC_IV.to_vec(),
+            ..info
+        };
+        decryptor().decrypt_sample(&sinf, &info, &mut data).unwrap();
+        assert_eq!(data, sample);
+    }
+}
@@ -1,3 +1,5 @@
+use std::fmt;
+
 use crate::mp4box::BoxType;
 
 #[derive(Debug, thiserror::Error)]
@@ -8,6 +10,9 @@ pub enum Error {
     #[error("{0}")]
     InvalidData(&'static str),
 
+    #[error("{0} limit exceeded")]
+    LimitExceeded(&'static str),
+
     #[error("{0} not found")]
     BoxNotFound(BoxType),
 
@@ -43,4 +48,150 @@ pub enum Error {
 
     #[error("unsupported media type")]
     UnsupportedMediaType,
+
+    /// Error raised while parsing a box, with the location of that box.
+    #[error("{0}: {1}")]
+    Context(ErrorContext, #[source] Box<Error>),
+
+    #[error("decryption key for kid {} not found", .0.iter().map(|b| format!("{b:02x}")).collect::<String>())]
+    DecryptionKeyNotFound([u8; 16]),
+}
+
+/// Location of a parse error, see [`Error::Context`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// Boxes from the top level down to the one that failed, with the index of the box
+    /// among its siblings of the same type where it matters, such as `trak[2]`.
+    pub path: Vec<(BoxType, Option<usize>)>,
+
+    /// Absolute offset in the file where parsing stopped, when the error was raised
+    /// while reading a file.
+    pub offset: Option<u64>,
+    pub track_id: Option<u32>,
+
+    /// Bytes left in the body of the box enclosing `path` when parsing stopped.
+    remaining: usize,
+}
+
+impl ErrorContext {
+    /// Path in the syntax of [`BoxTree::find`](crate::BoxTree::find), such as
+    /// `moov/trak[2]/mdia/minf/stbl/stsz`.
+    pub fn box_path(&self) -> String {
+        let segments: Vec<_> = self
+            .path
+            .iter()
+            .map(|(kind, nth)| match nth {
+                Some(nth) => format!("{kind}[{nth}]"),
+                None => kind.to_string(),
+            })
+            .collect();
+
+        segments.join("/")
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.box_path())?;
+
+        if let Some(offset) = self.offset {
+            write!(f, " @ {offset:#x}")?;
+        }
+
+        if let Some(track_id) = self.track_id {
+            write!(f, " (track {track_id})")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error {
+    /// The error without its [`Error::Context`].
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::Context(_, err) => err.root(),
+            err => err,
+        }
+    }
+
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Error::Context(ctx, _) => Some(ctx),
+            _ => None,
+        }
+    }
+
+    /// Adds the box the error was raised in to the path.
+    ///
+    /// `remaining` is what was left of the box body when parsing stopped, it is only
+    /// used for errors without a context yet. `after` is what follows the box in its
+    /// parent.
+    pub(crate) fn in_box(self, kind: BoxType, remaining: usize, after: usize) -> Self {
+        let (mut ctx, err) = match self {
+            Error::Context(ctx, err) => (ctx, err),
+            err => (
+                ErrorContext {
+                    remaining,
+                    ..Default::default()
+                },
+                Box::new(err),
+            ),
+        };
+
+        ctx.path.insert(0, (kind, None));
+        ctx.remaining += after;
+        Error::Context(ctx, err)
+    }
+
+    /// Adds a top level box whose body ends at the absolute offset `end`, which
+    /// resolves the offset of the error.
+    pub(crate) fn in_file_box(self, kind: BoxType, end: u64, remaining: usize) -> Self {
+        match self.in_box(kind, remaining, 0) {
+            Error::Context(mut ctx, err) => {
+                ctx.offset = Some(end.saturating_sub(ctx.remaining as u64));
+                Error::Context(ctx, err)
+            }
+            err => err,
+        }
+    }
+
+    /// Locates an error raised outside of the box parsers, such as while building the
+    /// samples of a track, unless it already has a location.
+    pub(crate) fn at(self, path: &[(BoxType, Option<usize>)], offset: u64) -> Self {
+        match self {
+            err @ Error::Context(..) => err,
+            err => Error::Context(
+                ErrorContext {
+                    path: path.to_vec(),
+                    offset: Some(offset),
+                    ..Default::default()
+                },
+                Box::new(err),
+            ),
+        }
+    }
+
+    /// Sets the index of the box the error was raised in among its siblings.
+    pub(crate) fn with_index(self, idx: usize) -> Self {
+        match self {
+            Error::Context(mut ctx, err) => {
+                if let Some((_, nth)) = ctx.path.first_mut() {
+                    *nth = Some(idx);
+                }
+                Error::Context(ctx, err)
+            }
+            err => err,
+        }
+    }
+
+    pub(crate) fn with_track_id(self, track_id: u32) -> Self {
+        match self {
+            Error::Context(mut ctx, err) => {
+                ctx.track_id = ctx.track_id.or(Some(track_id));
+                Error::Context(ctx, err)
+            }
+            err => err,
+        }
+    }
 }
@@ -1,30 +1,41 @@
 use bytes::Bytes;
-use futures::Future;
+use futures::Stream;
 use std::collections::{BTreeSet, HashMap};
-use std::convert::TryInto;
-use std::iter::FromIterator;
+use std::io::SeekFrom;
 use std::ops::Range;
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, SeekFrom};
-
-use crate::{BlockReader, BoxHeader, BoxType, EmsgBox, Error, FtypBox, MoofBox, MoovBox};
-use crate::{Mp4Track, HEADER_SIZE};
+use std::time::Duration;
+
+use crate::{
+    caption, tx3g, CaptionChannel, CaptionPacket, Chapter, MetaBox, ReferenceType, SubtitleCue,
+    SubtitleFormat, TextSample, UdtaBox, VttSample,
+};
+use crate::{
+    BlockReader, BoxHeader, BoxRegistry, BoxType, EmsgBox, Error, FtypBox, MoofBox, MoovBox,
+    Reader, HEADER_SIZE,
+};
+use crate::{ByteSource, PositionalReader, ReadAt};
+use crate::{CencDecryptor, MfraBox, MfroBox, Mp4Sample, Mp4Track, TfdtBox, TfraEntry};
+use crate::{Diagnostic, ParseMode, ParseOptions, Severity};
 
 const MAX_MEM_MDAT_SIZE: u64 = 128 * 1024 * 1024; // 128mb
+const DEFAULT_READAHEAD: u64 = 8 * 1024 * 1024; // 8mb
+const SAMPLE_OFFSET_SIZE: u64 = std::mem::size_of::<crate::track::Mp4SampleOffset>() as u64;
 
+#[cfg(feature = "tokio")]
 pub trait DataStorage {
     type Error;
     type Id;
 
     fn save_data(
         &mut self,
-        reader: &mut (impl AsyncRead + Unpin),
-    ) -> impl Future<Output = Result<Self::Id, Self::Error>>;
+        reader: &mut (impl tokio::io::AsyncRead + Unpin),
+    ) -> impl std::future::Future<Output = Result<Self::Id, Self::Error>>;
 
     fn read_data(
         &self,
         id: &Self::Id,
         range: Range<u64>,
-    ) -> impl Future<Output = Result<Bytes, Self::Error>>;
+    ) -> impl std::future::Future<Output = Result<Bytes, Self::Error>>;
 }
 
 #[derive(Default)]
@@ -32,6 +43,7 @@ pub struct MemoryStorage {
     pub data: Vec<Bytes>,
 }
 
+#[cfg(feature = "tokio")]
 impl DataStorage for MemoryStorage {
     type Error = Error;
     type Id = usize;
@@ -39,7 +51,7 @@ impl DataStorage for MemoryStorage {
     #[inline]
     async fn save_data(
         &mut self,
-        reader: &mut (impl AsyncRead + Unpin),
+        reader: &mut (impl tokio::io::AsyncRead + Unpin),
     ) -> Result<Self::Id, Self::Error> {
         let mut buffer = Vec::new();
         let index = self.data.len();
@@ -66,6 +78,9 @@ pub struct DataBlock {
     _kind: BoxType,
     offset: u64,
     size: u64,
+
+    /// Bytes of the body present in the file, less than `size` for a truncated `mdat`.
+    available: u64,
     buffer: DataBlockBody,
 }
 
@@ -88,26 +103,48 @@ impl ReadSampleFormat for Annexb {
         // * Copy only the slice data. In particular, don't copy SEI, which confuses
         //   Safari: <https://github.com/scottlamb/retina/issues/60#issuecomment-1178369955>
 
+        let lengths = nal_units(data, 4)
+            .map(|nal| nal.map(<[u8]>::len))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| Error::NaluLengthDelimetedRedFail)?;
+
         let mut i = 0;
-        while i < data.len() - 3 {
+        for nalu_length in lengths {
             // Replace each NAL's length with the Annex B start code b"\x00\x00\x00\x01".
-            let bytes = &mut data[i..i + 4];
-            let nalu_length = u32::from_be_bytes(bytes.try_into().unwrap()) as usize;
-            bytes.copy_from_slice(&[0, 0, 0, 1]);
-
+            data[i..i + 4].copy_from_slice(&[0, 0, 0, 1]);
             i += 4 + nalu_length;
+        }
 
-            if i > data.len() {
-                return Err(Error::NaluLengthDelimetedRedFail);
-            }
+        Ok(())
+    }
+}
+
+/// Iterates over the NAL units of a length-prefixed sample.
+pub(crate) fn nal_units(
+    mut data: &[u8],
+    length_size: usize,
+) -> impl Iterator<Item = Result<&[u8], Error>> {
+    std::iter::from_fn(move || {
+        if data.is_empty() {
+            return None;
         }
 
-        if i < data.len() {
-            return Err(Error::NaluLengthDelimetedRedFail);
+        if data.len() < length_size {
+            data = &[];
+            return Some(Err(Error::InvalidData("truncated NAL unit length")));
         }
 
-        Ok(())
-    }
+        let (len, rest) = data.split_at(length_size);
+        let len = len.iter().fold(0usize, |acc, &b| (acc << 8) | b as usize);
+        if len > rest.len() {
+            data = &[];
+            return Some(Err(Error::InvalidData("NAL unit exceeds sample size")));
+        }
+
+        let (nal, rest) = rest.split_at(len);
+        data = rest;
+        Some(Ok(nal))
+    })
 }
 
 #[derive(Default)]
@@ -121,7 +158,7 @@ impl ReadSampleFormat for LengthDelimited {
 
 pub struct Mp4File<R, F = Annexb>
 where
-    R: AsyncRead + AsyncSeek + Unpin,
+    R: ByteSource,
     F: ReadSampleFormat,
 {
     pub ftyp: Option<FtypBox>,
@@ -130,12 +167,29 @@ where
     pub reader: R,
     pub offsets: BTreeSet<u64>,
     pub data_blocks: Vec<DataBlock>,
+    pub mfra: Option<MfraBox>,
+    pub udta: Option<UdtaBox>,
+
+    /// Movie level metadata in `moov/meta`, usually QuickTime `mdta` keys.
+    pub meta: Option<MetaBox>,
+
+    /// Boxes and tracks skipped or repaired in [`ParseMode::Lenient`], as well as
+    /// problems every mode reads past, such as a truncated `mdat`.
+    pub warnings: Vec<Diagnostic>,
+    loaded_fragments: BTreeSet<u64>,
+    decryptor: CencDecryptor,
     format_conv: F,
+    memory_block_limit: u64,
+    readahead: u64,
+    read_buffer: ReadBuffer,
+    registry: BoxRegistry,
+    options: ParseOptions,
+    allocated: u64,
 }
 
 impl<R> Mp4File<R>
 where
-    R: AsyncRead + Unpin + AsyncSeek,
+    R: ByteSource,
 {
     pub fn new_annexb(reader: R) -> Self {
         Self {
@@ -145,14 +199,26 @@ where
             reader,
             offsets: BTreeSet::new(),
             data_blocks: Vec::new(),
+            mfra: None,
+            udta: None,
+            meta: None,
+            warnings: Vec::new(),
+            loaded_fragments: BTreeSet::new(),
+            decryptor: CencDecryptor::new(),
             format_conv: Default::default(),
+            memory_block_limit: MAX_MEM_MDAT_SIZE,
+            readahead: DEFAULT_READAHEAD,
+            read_buffer: ReadBuffer::default(),
+            registry: BoxRegistry::default(),
+            options: ParseOptions::default(),
+            allocated: 0,
         }
     }
 }
 
 impl<R> Mp4File<R, LengthDelimited>
 where
-    R: AsyncRead + Unpin + AsyncSeek,
+    R: ByteSource,
 {
     pub fn new(reader: R) -> Self {
         Self {
@@ -162,27 +228,248 @@ where
             reader,
             offsets: BTreeSet::new(),
             data_blocks: Vec::new(),
+            mfra: None,
+            udta: None,
+            meta: None,
+            warnings: Vec::new(),
+            loaded_fragments: BTreeSet::new(),
+            decryptor: CencDecryptor::new(),
             format_conv: Default::default(),
+            memory_block_limit: MAX_MEM_MDAT_SIZE,
+            readahead: DEFAULT_READAHEAD,
+            read_buffer: ReadBuffer::default(),
+            registry: BoxRegistry::default(),
+            options: ParseOptions::default(),
+            allocated: 0,
         }
     }
 }
 
 impl<R, F> Mp4File<R, F>
 where
-    R: AsyncRead + Unpin + AsyncSeek,
+    R: ByteSource,
     F: ReadSampleFormat,
 {
     pub async fn read_header(&mut self) -> Result<bool, Error> {
+        self.read_boxes(0, false).await
+    }
+
+    /// Reads the header using the `mfra` random access index if the file has one.
+    ///
+    /// With an index only the boxes before the first `moof` are parsed, fragments are
+    /// loaded on demand with [`read_fragment_at`](Self::read_fragment_at). Without an
+    /// index this falls back to [`read_header`](Self::read_header).
+    pub async fn read_header_indexed(&mut self) -> Result<bool, Error> {
+        let indexed = self.read_fragment_index().await?;
+        self.reader.seek(SeekFrom::Start(0)).await?;
+
+        self.read_boxes(0, indexed).await
+    }
+
+    /// Locates the `mfra` box through the trailing `mfro` box.
+    ///
+    /// Returns `false` if the file has no fragment index.
+    pub async fn read_fragment_index(&mut self) -> Result<bool, Error> {
+        let file_size = self.reader.seek(SeekFrom::End(0)).await?;
+        if file_size < MfroBox::SIZE {
+            return Ok(false);
+        }
+
+        let mut buff = vec![0u8; MfroBox::SIZE as usize];
+        self.reader
+            .seek(SeekFrom::Start(file_size - MfroBox::SIZE))
+            .await?;
+        self.reader.read_exact(&mut buff).await?;
+
+        let mut reader = &buff[..];
+        let header = BoxHeader::read_sync(&mut reader, &mut 0)?;
+        if !matches!(header, Some(BoxHeader { kind: BoxType::MfroBox, size }) if size == MfroBox::SIZE)
+        {
+            return Ok(false);
+        }
+
+        let mfro = MfroBox::read_block(&mut reader)?;
+        let mfra_size = mfro.size as u64;
+        if mfra_size < HEADER_SIZE + MfroBox::SIZE || mfra_size > file_size {
+            return Err(Error::InvalidData("mfro size points outside of the file"));
+        }
+        self.options.limits.check_box_size(mfra_size)?;
+
+        buff.resize(mfra_size as usize, 0);
+        self.reader
+            .seek(SeekFrom::Start(file_size - mfra_size))
+            .await?;
+        self.reader.read_exact(&mut buff).await?;
+
+        let mut reader = &buff[..];
+        match BoxHeader::read_sync(&mut reader, &mut 0)? {
+            Some(BoxHeader {
+                kind: BoxType::MfraBox,
+                size,
+            }) if size == mfra_size => {}
+            _ => return Err(Error::BoxNotFound(BoxType::MfraBox)),
+        }
+
+        self.mfra = Some(parse_body(BoxType::MfraBox, reader, file_size)?);
+
+        Ok(true)
+    }
+
+    /// Loads the fragment containing the random access point at or before `time`
+    /// (in track timescale units) and returns the index of the sample at that point,
+    /// which is not necessarily the first sample of the fragment.
+    ///
+    /// Returns `None` if the track has no random access point at or before `time`.
+    ///
+    /// Requires the `mfra` index, see [`read_fragment_index`](Self::read_fragment_index).
+    pub async fn read_fragment_at(
+        &mut self,
+        track_id: u32,
+        time: u64,
+    ) -> Result<Option<usize>, Error> {
+        let Some(entry) = self
+            .mfra
+            .as_ref()
+            .and_then(|mfra| mfra.tfra(track_id))
+            .and_then(|tfra| tfra.find_entry(time))
+            .copied()
+        else {
+            return Ok(None);
+        };
+
+        if !self.loaded_fragments.contains(&entry.moof_offset) {
+            self.load_fragment(track_id, &entry).await?;
+        }
+
+        let Some(track) = self.tracks.get(&track_id) else {
+            return Err(Error::TrakNotFound(track_id));
+        };
+
+        let idx = track.samples.partition_point(|s| s.start_time < entry.time);
+
+        Ok((idx < track.samples.len()).then_some(idx))
+    }
+
+    async fn load_fragment(&mut self, track_id: u32, entry: &TfraEntry) -> Result<(), Error> {
+        let moof_offset = entry.moof_offset;
+        let mut offset = moof_offset;
+        self.reader.seek(SeekFrom::Start(offset)).await?;
+
+        let Some(BoxHeader {
+            kind: BoxType::MoofBox,
+            size,
+        }) = BoxHeader::read_from(&mut self.reader, &mut offset).await?
+        else {
+            return Err(Error::BoxNotFound(BoxType::MoofBox));
+        };
+
+        let s = size.saturating_sub(HEADER_SIZE);
+        self.options.limits.check_box_size(s)?;
+        let mut buff = vec![0u8; s as usize];
+        self.reader.read_exact(&mut buff).await?;
+        offset += s;
+
+        let mut moof: MoofBox = parse_body(BoxType::MoofBox, &buff[..], offset)?;
+
+        // without tfdt the decode time would be derived from the previously loaded fragment,
+        // the entry time is only the decode time of the fragment if it points at its first
+        // sample
+        if let Some(traf) = moof.trafs.iter_mut().find(|t| t.tfhd.track_id == track_id) {
+            if traf.tfdt.is_none() && entry.trun_number == 1 && entry.sample_number == 1 {
+                traf.tfdt = Some(TfdtBox {
+                    version: 1,
+                    flags: 0,
+                    base_media_decode_time: entry.time,
+                });
+            }
+        }
+
+        let mdat_start = offset;
+        if let Some(BoxHeader {
+            kind: BoxType::MdatBox,
+            size,
+        }) = BoxHeader::read_from(&mut self.reader, &mut offset).await?
+        {
+            let s = size.saturating_sub(HEADER_SIZE);
+            if !self.data_blocks.iter().any(|b| b.offset == offset) {
+                self.save_box(BoxType::MdatBox, s, offset, mdat_start)
+                    .await?;
+            }
+        }
+
+        self.add_moof(moof_offset, None, moof)?;
+        self.loaded_fragments.insert(moof_offset);
+
+        Ok(())
+    }
+
+    async fn read_boxes(&mut self, mut offset: u64, stop_at_moof: bool) -> Result<bool, Error> {
         let mut buff = Vec::with_capacity(8192);
         let mut got_moov = false;
-        let mut offset = 0u64;
+        let mut moof_count = 0;
+
+        let file_size = self.reader.seek(SeekFrom::End(0)).await?;
+        self.reader.seek(SeekFrom::Start(offset)).await?;
+
+        loop {
+            let box_start = offset;
+            let header = match BoxHeader::read_from(&mut self.reader, &mut offset).await {
+                Ok(header) => header,
+                Err(err) => {
+                    // nothing after a damaged header can be located
+                    self.recover(String::new(), box_start, err)?;
+                    break;
+                }
+            };
+
+            let Some(BoxHeader { kind, size: mut s }) = header else {
+                break;
+            };
 
-        while let Some(BoxHeader { kind, size: mut s }) =
-            BoxHeader::read(&mut self.reader, &mut offset).await?
-        {
             if s >= HEADER_SIZE {
                 s -= HEADER_SIZE; // size without header
             }
+
+            // boxes other than `mdat` parsed from memory
+            let in_memory = matches!(
+                kind,
+                BoxType::FtypBox
+                    | BoxType::MoovBox
+                    | BoxType::MoofBox
+                    | BoxType::MfraBox
+                    | BoxType::EmsgBox
+            );
+
+            if offset + s > file_size && kind != BoxType::MdatBox {
+                match self.options.mode {
+                    ParseMode::Strict => {
+                        return Err(Error::InvalidData("box extends past the end of the file"))
+                    }
+                    // fail before allocating a body that can't be read
+                    ParseMode::Standard if in_memory => {
+                        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into())
+                    }
+                    ParseMode::Standard => {}
+                    ParseMode::Lenient => {
+                        self.warn(
+                            kind.to_string(),
+                            box_start,
+                            format!("box is truncated to {} bytes", file_size - offset),
+                        );
+                        s = file_size - offset;
+                    }
+                }
+            }
+
+            if in_memory {
+                if let Err(err) = self.options.limits.check_box_size(s) {
+                    self.recover(kind.to_string(), box_start, err)?;
+                    self.skip_box(kind, s).await?;
+                    offset += s;
+                    continue;
+                }
+            }
+
             match kind {
                 BoxType::FtypBox => {
                     log::debug!("ftyp");
@@ -193,42 +480,62 @@ where
                     self.reader.read_exact(&mut buff[0..s as usize]).await?;
                     offset += s;
 
-                    self.ftyp = Some(FtypBox::read_block(&mut &buff[0..s as usize])?);
+                    match parse_body(BoxType::FtypBox, &buff[0..s as usize], offset) {
+                        Ok(ftyp) => self.ftyp = Some(ftyp),
+                        Err(err) => self.recover("ftyp".to_string(), box_start, err)?,
+                    }
                 }
 
                 BoxType::MoovBox => {
                     log::debug!("moov");
 
-                    if buff.len() < s as usize {
-                        buff.resize(s as usize, 0);
+                    // payloads such as cover art are kept as slices of this buffer
+                    self.allocate(s)?;
+                    let mut data = vec![0u8; s as usize];
+                    self.reader.read_exact(&mut data).await?;
+
+                    got_moov = true;
+                    let data = Bytes::from(data);
+                    if self.options.mode == ParseMode::Lenient {
+                        let (moov, traks) = self.read_moov_lenient(offset, data);
+                        self.set_moov(moov, box_start, &traks)?;
+                    } else {
+                        let moov = parse_body(BoxType::MoovBox, data, offset + s)?;
+                        self.set_moov(moov, box_start, &[])?;
                     }
 
-                    self.reader.read_exact(&mut buff[0..s as usize]).await?;
                     offset += s;
-
-                    got_moov = true;
-                    self.set_moov(MoovBox::read_block(&mut &buff[0..s as usize])?)?;
                 }
 
                 BoxType::MoofBox => {
                     log::debug!("moof");
 
+                    if stop_at_moof {
+                        break;
+                    }
+
                     if buff.len() < s as usize {
                         buff.resize(s as usize, 0);
                     }
 
-                    let begin_offset = offset;
                     self.reader.read_exact(&mut buff[0..s as usize]).await?;
                     offset += s;
 
-                    self.add_moof(
-                        begin_offset,
-                        MoofBox::read_block(&mut &buff[0..s as usize])?,
-                    )?;
+                    let idx = moof_count;
+                    moof_count += 1;
+                    if self.loaded_fragments.insert(box_start) {
+                        match parse_body(BoxType::MoofBox, &buff[0..s as usize], offset) {
+                            Ok(moof) => self.add_moof(box_start, Some(idx), moof)?,
+                            Err(err) => {
+                                let err = err.with_index(idx);
+                                self.recover(format!("moof[{idx}]"), box_start, err)?;
+                            }
+                        }
+                    }
                 }
 
-                BoxType::EmsgBox => {
-                    log::debug!("emsg");
+                BoxType::MfraBox => {
+                    log::debug!("mfra");
 
                     if buff.len() < s as usize {
                         buff.resize(s as usize, 0);
@@ -237,13 +544,35 @@ where
                     self.reader.read_exact(&mut buff[0..s as usize]).await?;
                     offset += s;
 
-                    self.emsgs
-                        .push(EmsgBox::read_block(&mut &buff[0..s as usize])?);
+                    match parse_body(BoxType::MfraBox, &buff[0..s as usize], offset) {
+                        Ok(mfra) => self.mfra = Some(mfra),
+                        Err(err) => self.recover("mfra".to_string(), box_start, err)?,
+                    }
+                }
+
+                BoxType::EmsgBox => {
+                    log::debug!("emsg");
+
+                    self.allocate(s)?;
+                    let mut data = vec![0u8; s as usize];
+                    self.reader.read_exact(&mut data).await?;
+                    offset += s;
+
+                    match parse_body(BoxType::EmsgBox, Bytes::from(data), offset) {
+                        Ok(emsg) => self.emsgs.push(emsg),
+                        Err(err) => self.recover("emsg".to_string(), box_start, err)?,
+                    }
                 }
 
                 BoxType::MdatBox => {
                     log::debug!("mdat");
-                    self.save_box(BoxType::MdatBox, s, offset).await?;
+
+                    if self.options.mode == ParseMode::Strict && offset + s > file_size {
+                        return Err(Error::InvalidData("mdat extends past the end of the file"));
+                    }
+
+                    self.save_box(BoxType::MdatBox, s, offset, box_start)
+                        .await?;
                     offset += s;
                 }
 
@@ -259,23 +588,110 @@ where
         Ok(got_moov)
     }
 
+    /// Parses the children of a `moov` box one at a time, skipping those that fail.
+    ///
+    /// Returns the box along with the index among the `trak` boxes and the offset of each
+    /// parsed track.
+    fn read_moov_lenient(
+        &mut self,
+        data_offset: u64,
+        mut data: Bytes,
+    ) -> (MoovBox, Vec<(usize, u64)>) {
+        let mut moov = MoovBox::default();
+        let mut mvhd = None;
+        let mut traks = Vec::new();
+        let mut counts = HashMap::new();
+        let len = data.len();
+        let end = data_offset + len as u64;
+
+        for position in 0.. {
+            let offset = data_offset + (len - data.remaining()) as u64;
+            let mut bx = match data.get_box() {
+                Ok(Some(bx)) => bx,
+                Ok(None) => break,
+                Err(err) => {
+                    let err = err.in_file_box(BoxType::MoovBox, end, (end - offset) as usize);
+                    self.warn_error("moov".to_string(), offset, err);
+                    break;
+                }
+            };
+
+            let kind = bx.kind;
+            let nth = counts.entry(kind).or_insert(0usize);
+            let path = format!("moov/{kind}[{nth}]");
+            let idx = *nth;
+            *nth += 1;
+
+            let result = match kind {
+                BoxType::MvhdBox => bx.read().map(|b| mvhd = Some(b)),
+                BoxType::MetaBox => bx.read().map(|b| moov.meta = Some(b)),
+                BoxType::MvexBox => bx.read().map(|b| moov.mvex = Some(b)),
+                BoxType::UdtaBox => bx.read().map(|b| moov.udta = Some(b)),
+                BoxType::PsshBox => bx.read().map(|b| moov.psshs.push(b)),
+                BoxType::TrakBox => bx.read().map(|b| {
+                    moov.traks.push(b);
+                    traks.push((idx, offset));
+                }),
+                _ => {
+                    moov.unknown.push_at(position, bx.read_raw());
+                    Ok(())
+                }
+            };
+
+            if let Err(err) = result {
+                let err = err.with_index(idx).in_file_box(BoxType::MoovBox, end, 0);
+                self.warn_error(path, offset, err);
+            }
+        }
+
+        match mvhd {
+            Some(mvhd) => moov.mvhd = mvhd,
+            None => self.warn(
+                "moov".to_string(),
+                data_offset - HEADER_SIZE,
+                "missing mvhd, using defaults".to_string(),
+            ),
+        }
+
+        (moov, traks)
+    }
+
     async fn skip_box(&mut self, bt: BoxType, size: u64) -> Result<(), Error> {
         log::debug!("skip {:?}", bt);
         self.reader.seek(SeekFrom::Current(size as _)).await?;
         Ok(())
     }
 
-    async fn save_box(&mut self, kind: BoxType, size: u64, offset: u64) -> Result<(), Error> {
+    async fn save_box(
+        &mut self,
+        kind: BoxType,
+        size: u64,
+        offset: u64,
+        box_start: u64,
+    ) -> Result<(), Error> {
         log::debug!("data_block {:?} {} - {}", kind, offset, offset + size);
-        let reader = &mut self.reader;
 
-        if size < MAX_MEM_MDAT_SIZE {
-            let mut buffer = Vec::new();
-            tokio::io::copy(&mut reader.take(size), &mut buffer).await?;
+        // the size comes from the file, a truncated mdat keeps the samples it still holds
+        let file_size = self.reader.seek(SeekFrom::End(0)).await?;
+        self.reader.seek(SeekFrom::Start(offset)).await?;
+        let available = size.min(file_size.saturating_sub(offset));
+        if available < size {
+            self.warn(
+                kind.to_string(),
+                box_start,
+                format!("truncated, {available} of {size} bytes present"),
+            );
+        }
+
+        if available < self.memory_block_limit && self.allocate(available).is_ok() {
+            let mut buffer = vec![0u8; available as usize];
+            self.reader.read_exact(&mut buffer).await?;
+
             self.data_blocks.push(DataBlock {
                 _kind: kind,
                 offset,
                 size,
+                available,
                 buffer: DataBlockBody::Memory(buffer.into()),
             });
         } else {
@@ -285,6 +701,7 @@ where
                 _kind: kind,
                 offset,
                 size,
+                available,
                 buffer: DataBlockBody::Reader,
             });
         }
@@ -292,70 +709,698 @@ where
         Ok(())
     }
 
-    fn set_moov(&mut self, moov: MoovBox) -> Result<(), Error> {
-        for trak in moov.traks {
-            self.tracks
-                .insert(trak.tkhd.track_id, Mp4Track::new(trak, &mut self.offsets)?);
+    fn set_moov(
+        &mut self,
+        mut moov: MoovBox,
+        moov_offset: u64,
+        traks: &[(usize, u64)],
+    ) -> Result<(), Error> {
+        if let Err(err) = self.registry.parse_moov(&mut moov) {
+            self.recover("moov".to_string(), moov_offset, err)?;
+        }
+        self.udta = moov.udta;
+        self.meta = moov.meta;
+
+        for (idx, trak) in moov.traks.into_iter().enumerate() {
+            let (nth, offset) = traks.get(idx).copied().unwrap_or((idx, moov_offset));
+            let path = format!("moov/trak[{nth}]");
+            let location = [(BoxType::MoovBox, None), (BoxType::TrakBox, Some(nth))];
+            let track_id = trak.tkhd.track_id;
+
+            if self.tracks.len() >= self.options.limits.max_tracks {
+                let err = Error::LimitExceeded("max_tracks").at(&location, offset);
+                self.recover(path, offset, err)?;
+                break;
+            }
+
+            let sample_count = trak.mdia.minf.stbl.stsz.sample_count as u64;
+            let result = self
+                .options
+                .limits
+                .check_sample_count(sample_count)
+                .and_then(|()| self.allocate(sample_count * SAMPLE_OFFSET_SIZE));
+            if let Err(err) = result {
+                let err = err.at(&location, offset).with_track_id(track_id);
+                self.recover(path, offset, err)?;
+                continue;
+            }
+
+            if self.options.mode != ParseMode::Standard && self.tracks.contains_key(&track_id) {
+                let err = Error::InvalidData("duplicate track id");
+                self.recover(path, offset, err.at(&location, offset))?;
+                continue;
+            }
+
+            match Mp4Track::with_options(trak, &mut self.offsets, &self.options) {
+                Ok((track, warnings)) => {
+                    for warning in warnings {
+                        self.warn(path.clone(), offset, warning);
+                    }
+                    self.tracks.insert(track_id, track);
+                }
+                Err(err) => {
+                    let err = err.at(&location, offset).with_track_id(track_id);
+                    self.recover(path, offset, err)?;
+                }
+            }
         }
 
         Ok(())
     }
 
-    fn add_moof(&mut self, offset: u64, moof: MoofBox) -> Result<(), Error> {
-        for traf in moof.trafs {
+    /// Adds the samples of a fragment, `idx` is the index of the `moof` box in the file
+    /// if known.
+    fn add_moof(&mut self, offset: u64, idx: Option<usize>, moof: MoofBox) -> Result<(), Error> {
+        for (traf_idx, traf) in moof.trafs.into_iter().enumerate() {
             let track_id = traf.tfhd.track_id;
 
-            if let Some(track) = self.tracks.get_mut(&track_id) {
-                track.add_traf(offset, moof.mfhd.sequence_number, traf, &mut self.offsets)
+            let sample_count = traf
+                .trun
+                .as_ref()
+                .map_or(0, |trun| trun.sample_count as u64);
+            let result = if self.tracks.contains_key(&track_id) {
+                self.allocate(sample_count * SAMPLE_OFFSET_SIZE)
+                    .and_then(|()| {
+                        self.tracks.get_mut(&track_id).unwrap().add_traf(
+                            offset,
+                            moof.mfhd.sequence_number,
+                            traf,
+                            &mut self.offsets,
+                            &self.options.limits,
+                        )
+                    })
             } else {
-                return Err(Error::TrakNotFound(track_id));
+                Err(Error::TrakNotFound(track_id))
+            };
+
+            match result {
+                Ok(None) => {}
+                Ok(Some(warning)) => {
+                    let path = match idx {
+                        Some(idx) => format!("moof[{idx}]/traf[{traf_idx}]"),
+                        None => format!("moof/traf[{traf_idx}]"),
+                    };
+                    self.warn(path, offset, warning);
+                }
+                Err(err) => {
+                    let location = [(BoxType::MoofBox, idx), (BoxType::TrafBox, Some(traf_idx))];
+                    let err = err.at(&location, offset).with_track_id(track_id);
+                    self.recover(String::new(), offset, err)?;
+                }
             }
         }
 
         Ok(())
     }
 
+    /// Accounts for `size` more bytes held by the file.
+    fn allocate(&mut self, size: u64) -> Result<(), Error> {
+        if self.allocated + size > self.options.limits.max_allocation {
+            return Err(Error::LimitExceeded("max_allocation"));
+        }
+
+        self.allocated += size;
+        Ok(())
+    }
+
+    /// Returns `err` unless parsing leniently, in which case it is kept as a warning.
+    fn recover(&mut self, path: String, offset: u64, err: Error) -> Result<(), Error> {
+        if self.options.mode != ParseMode::Lenient {
+            return Err(err);
+        }
+
+        self.warn_error(path, offset, err);
+        Ok(())
+    }
+
+    /// Records `err` as a warning at its location, or at `path` and `offset` if it
+    /// has none.
+    fn warn_error(&mut self, path: String, offset: u64, err: Error) {
+        match err {
+            Error::Context(ctx, err) => match ctx.offset {
+                Some(offset) => self.warn(ctx.box_path(), offset, err.to_string()),
+                None => self.warn(path, offset, err.to_string()),
+            },
+            err => self.warn(path, offset, err.to_string()),
+        }
+    }
+
+    fn warn(&mut self, path: String, offset: u64, message: String) {
+        log::warn!("{path} @{offset}: {message}");
+
+        self.warnings.push(Diagnostic {
+            severity: Severity::Warning,
+            path,
+            offset,
+            message,
+        });
+    }
+
+    /// Registers a content key used to decrypt samples of protected tracks.
+    ///
+    /// Without any keys configured [`Mp4File::read_sample_data`] returns samples as stored,
+    /// with keys it fails on protected samples it can't decrypt.
+    pub fn add_decryption_key(&mut self, kid: [u8; 16], key: [u8; 16]) {
+        self.decryptor.add_key(kid, key);
+    }
+
+    /// Sets how many bytes are fetched at once from data blocks too large to be kept in
+    /// memory, 8 MiB by default.
+    ///
+    /// A read fetches the rest of the sample's chunk and the chunks stored after it that
+    /// fit in `size`, so following samples are served from the buffer. With `0` every
+    /// sample is read on its own.
+    pub fn set_readahead(&mut self, size: u64) {
+        self.readahead = size;
+        self.read_buffer = ReadBuffer::default();
+    }
+
+    /// Sets the parsers used for vendor boxes in the `moov` box, must be called before
+    /// the header is read.
+    ///
+    /// Parsed boxes are kept in [`Mp4File::udta`], [`Mp4File::meta`] and in the tracks'
+    /// `mdia` boxes.
+    pub fn set_box_registry(&mut self, registry: BoxRegistry) {
+        self.registry = registry;
+    }
+
+    /// Sets how malformed input is handled, must be called before the header is read.
+    pub fn set_parse_options(&mut self, options: ParseOptions) {
+        self.options = options;
+    }
+
     #[inline]
     pub async fn read_sample_data(
         &mut self,
         track_id: u32,
         sample_idx: usize,
     ) -> Result<Option<Bytes>, Error> {
-        let Some(track) = self.tracks.get(&track_id) else {
+        let Some(mut buff) = self.read_sample(track_id, sample_idx).await? else {
             return Ok(None);
         };
 
-        let Some(sample) = track.samples.get(sample_idx) else {
-            return Ok(None);
+        self.format_conv.format(&mut buff)?;
+        Ok(Some(Bytes::from(buff)))
+    }
+
+    /// Chapters from the QuickTime chapter track if there is one, otherwise from the
+    /// Nero `chpl` box.
+    pub async fn chapters(&mut self) -> Result<Vec<Chapter>, Error> {
+        let mut track_ids = self.tracks.keys().copied().collect::<Vec<_>>();
+        track_ids.sort_unstable();
+
+        let chapter_track = track_ids
+            .iter()
+            .filter_map(|id| self.tracks[id].tref.as_ref())
+            .flat_map(|tref| tref.track_ids(ReferenceType::Chapter))
+            .copied()
+            .find(|id| self.tracks.contains_key(id));
+
+        if let Some(track_id) = chapter_track {
+            let track = &self.tracks[&track_id];
+            let timescale = track.timescale().max(1) as u64;
+            let starts = track
+                .samples
+                .iter()
+                .map(|s| s.start_time)
+                .collect::<Vec<_>>();
+
+            let mut chapters = Vec::with_capacity(starts.len());
+            for (idx, start) in starts.into_iter().enumerate() {
+                let Some(data) = self.read_sample(track_id, idx).await? else {
+                    continue;
+                };
+
+                chapters.push(Chapter {
+                    start: Duration::from_nanos(
+                        (start as u128 * 1_000_000_000 / timescale as u128) as u64,
+                    ),
+                    title: tx3g::sample_text(&data).unwrap_or_default(),
+                });
+            }
+
+            return Ok(chapters);
+        }
+
+        let Some(chpl) = self.udta.as_ref().and_then(|udta| udta.chpl.as_ref()) else {
+            return Ok(Vec::new());
         };
 
-        for block in &self.data_blocks {
-            let range = block.offset..block.offset + block.size;
+        Ok(chpl
+            .chapters
+            .iter()
+            .map(|c| Chapter {
+                start: Duration::from_nanos(c.start.saturating_mul(100)),
+                title: c.title.clone(),
+            })
+            .collect())
+    }
 
-            if range.contains(&sample.offset) {
-                return Ok(Some(match &block.buffer {
-                    DataBlockBody::Memory(mem) => {
-                        let offset = sample.offset - block.offset;
-                        let mut slice = mem
-                            .slice(offset as usize..offset as usize + sample.size as usize)
-                            .to_vec();
+    /// Decodes the samples of a `tx3g` or `wvtt` track into subtitle cues.
+    ///
+    /// Empty samples are skipped and WebVTT cues split over consecutive samples are
+    /// joined again. Fails with [`Error::UnsupportedMediaType`] for other sample entries.
+    pub async fn subtitle_cues(&mut self, track_id: u32) -> Result<Vec<SubtitleCue>, Error> {
+        let track = self
+            .tracks
+            .get(&track_id)
+            .ok_or(Error::TrakNotFound(track_id))?;
+
+        let stsd = &track.mdia.minf.stbl.stsd;
+        let is_wvtt = stsd.wvtt.is_some();
+        if stsd.tx3g.is_none() && !is_wvtt {
+            return Err(Error::UnsupportedMediaType);
+        }
 
-                        self.format_conv.format(&mut slice).unwrap();
-                        Bytes::from(slice)
-                    }
+        let timescale = track.timescale().max(1) as u128;
+        let to_duration =
+            |time: u64| Duration::from_nanos((time as u128 * 1_000_000_000 / timescale) as u64);
+
+        let times = track
+            .samples
+            .iter()
+            .map(|s| (s.start_time, s.start_time + s.duration as u64))
+            .collect::<Vec<_>>();
+
+        let mut cues: Vec<SubtitleCue> = Vec::new();
+        // cues of the previous sample, which may continue in the current one
+        let mut open = 0..0;
+
+        for (idx, (start, end)) in times.into_iter().enumerate() {
+            let Some(data) = self.read_sample(track_id, idx).await? else {
+                continue;
+            };
+
+            let (start, end) = (to_duration(start), to_duration(end));
+
+            if !is_wvtt {
+                let sample = TextSample::read(&data)?;
+                if !sample.text.is_empty() {
+                    cues.push(SubtitleCue {
+                        start,
+                        end,
+                        text: sample.text,
+                        ..Default::default()
+                    });
+                }
+
+                continue;
+            }
 
-                    DataBlockBody::Reader => {
-                        let mut buff = vec![0u8; sample.size as _];
-                        self.reader.seek(SeekFrom::Start(sample.offset)).await?;
-                        self.reader.read_exact(&mut buff).await?;
-                        self.format_conv.format(&mut buff).unwrap();
-                        Bytes::from_iter(buff)
+            let mut current = cues.len()..cues.len();
+            for vtt_cue in VttSample::read(&data)?.cues {
+                let cue = SubtitleCue {
+                    start,
+                    end,
+                    text: vtt_cue.payload,
+                    id: vtt_cue.id,
+                    settings: vtt_cue.settings,
+                };
+
+                let continued = cues[open.clone()].iter().position(|prev| {
+                    prev.end == start
+                        && prev.text == cue.text
+                        && prev.id == cue.id
+                        && prev.settings == cue.settings
+                });
+
+                match continued {
+                    Some(pos) => {
+                        let prev = open.start + pos;
+                        cues[prev].end = end;
+                        current.start = current.start.min(prev);
                     }
-                }));
+                    None => {
+                        cues.push(cue);
+                        current.end = cues.len();
+                    }
+                }
             }
+
+            open = current;
         }
 
-        Ok(None)
+        cues.sort_by_key(|cue| cue.start);
+        Ok(cues)
+    }
+
+    /// Exports a `tx3g` or `wvtt` track as SRT or WebVTT.
+    pub async fn export_subtitles(
+        &mut self,
+        track_id: u32,
+        format: SubtitleFormat,
+    ) -> Result<String, Error> {
+        Ok(format.write(&self.subtitle_cues(track_id).await?))
+    }
+
+    /// Extracts the closed caption data of a track in presentation order, either from
+    /// the SEI messages of an AVC/HEVC track or from the samples of a `c608` track.
+    pub async fn caption_packets(&mut self, track_id: u32) -> Result<Vec<CaptionPacket>, Error> {
+        let track = self
+            .tracks
+            .get(&track_id)
+            .ok_or(Error::TrakNotFound(track_id))?;
+
+        let stsd = &track.mdia.minf.stbl.stsd;
+        let nal_format = if let Some(ref avc1) = stsd.avc1 {
+            Some(((avc1.avcc.length_size_minus_one & 0x03) as usize + 1, false))
+        } else if let Some(ref hev1) = stsd.hev1 {
+            Some(((hev1.hvcc.length_size_minus_one & 0x03) as usize + 1, true))
+        } else if stsd.c608.is_some() {
+            None
+        } else {
+            return Err(Error::UnsupportedMediaType);
+        };
+
+        let pts = track
+            .samples
+            .iter()
+            .map(|s| {
+                s.start_time
+                    .saturating_add_signed(s.rendering_offset as i64)
+            })
+            .collect::<Vec<_>>();
+
+        let mut packets = Vec::new();
+        for (idx, pts) in pts.into_iter().enumerate() {
+            let Some(data) = self.read_sample(track_id, idx).await? else {
+                continue;
+            };
+
+            let cc_data = match nal_format {
+                Some((length_size, hevc)) => caption::sei_cc_data(&data, length_size, hevc)?,
+                None => caption::c608_cc_data(&data)?,
+            };
+
+            if !cc_data.is_empty() {
+                packets.push(CaptionPacket { pts, cc_data });
+            }
+        }
+
+        packets.sort_by_key(|p| p.pts);
+        Ok(packets)
+    }
+
+    /// Decodes a CEA-608 caption channel of a track, see
+    /// [`caption_packets`](Self::caption_packets).
+    pub async fn closed_captions(
+        &mut self,
+        track_id: u32,
+        channel: CaptionChannel,
+    ) -> Result<Vec<SubtitleCue>, Error> {
+        let packets = self.caption_packets(track_id).await?;
+        let timescale = self.tracks[&track_id].timescale();
+
+        Ok(caption::decode_cea608(&packets, timescale, channel))
+    }
+
+    /// Streams the samples of the given tracks, or of all tracks if `track_ids` is empty,
+    /// merged in decode time order together with their track ID.
+    ///
+    /// Samples are fetched with the configured [readahead](Self::set_readahead), so an
+    /// interleaved file is read sequentially in one pass. A sample outside of any `mdat`
+    /// yields an error, after which the stream ends.
+    pub fn samples(
+        &mut self,
+        track_ids: &[u32],
+    ) -> impl Stream<Item = Result<(u32, Mp4Sample), Error>> + '_ {
+        let plan = self.sample_plan(track_ids);
+
+        async_stream::try_stream! {
+            for PlannedSample { track_id, idx, .. } in plan {
+                // every planned sample exists, so it's only missing when no mdat holds it
+                let mut data = self.read_sample(track_id, idx).await?.ok_or_else(|| {
+                    let offset = self.tracks[&track_id].samples[idx].offset;
+                    Error::InvalidData("sample is outside of any mdat")
+                        .at(&[], offset)
+                        .with_track_id(track_id)
+                })?;
+
+                self.format_conv.format(&mut data)?;
+
+                let sample = &self.tracks[&track_id].samples[idx];
+                yield (
+                    track_id,
+                    Mp4Sample {
+                        start_time: sample.start_time,
+                        duration: sample.duration,
+                        rendering_offset: sample.rendering_offset,
+                        is_sync: sample.is_sync,
+                        bytes: Bytes::from(data),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Samples of the selected tracks ordered by decode time, then by file offset.
+    fn sample_plan(&self, track_ids: &[u32]) -> Vec<PlannedSample> {
+        let mut plan = self
+            .tracks
+            .values()
+            .filter(|track| track_ids.is_empty() || track_ids.contains(&track.track_id))
+            .flat_map(|track| {
+                let timescale = track.timescale().max(1);
+                track
+                    .samples
+                    .iter()
+                    .enumerate()
+                    .map(move |(idx, sample)| PlannedSample {
+                        track_id: track.track_id,
+                        idx,
+                        timescale,
+                        start_time: sample.start_time,
+                        offset: sample.offset,
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        plan.sort_by(|a, b| {
+            let a_time = a.start_time as u128 * b.timescale as u128;
+            let b_time = b.start_time as u128 * a.timescale as u128;
+            a_time.cmp(&b_time).then(a.offset.cmp(&b.offset))
+        });
+
+        plan
+    }
+
+    /// Reads and decrypts a sample without converting its format.
+    async fn read_sample(
+        &mut self,
+        track_id: u32,
+        sample_idx: usize,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let Some((offset, size, block)) = self.locate_sample(track_id, sample_idx)? else {
+            return Ok(None);
+        };
+
+        let mut buff = match &block.buffer {
+            DataBlockBody::Memory(mem) => {
+                let start = (offset - block.offset) as usize;
+                mem.slice(start..start + size as usize).to_vec()
+            }
+
+            DataBlockBody::Reader => {
+                if !self.read_buffer.contains(offset, size) {
+                    let range = self.read_range(track_id, sample_idx, block);
+                    let buffer = &mut self.read_buffer;
+
+                    buffer.offset = range.start;
+                    buffer.data.resize((range.end - range.start) as usize, 0);
+                    self.reader.seek(SeekFrom::Start(range.start)).await?;
+                    self.reader.read_exact(&mut buffer.data).await?;
+                }
+
+                self.read_buffer.get(offset, size).to_vec()
+            }
+        };
+
+        self.decrypt_sample(track_id, sample_idx, &mut buff)?;
+        Ok(Some(buff))
+    }
+
+    /// Offset and size of a sample together with the data block holding it.
+    ///
+    /// Fails if the sample runs past the end of a truncated `mdat`.
+    fn locate_sample(
+        &self,
+        track_id: u32,
+        sample_idx: usize,
+    ) -> Result<Option<(u64, u64, &DataBlock)>, Error> {
+        let Some(sample) = self
+            .tracks
+            .get(&track_id)
+            .and_then(|t| t.samples.get(sample_idx))
+        else {
+            return Ok(None);
+        };
+        let Some(block) = self.data_blocks.iter().find(|b| b.contains(sample.offset)) else {
+            return Ok(None);
+        };
+
+        if sample.offset - block.offset + sample.size as u64 > block.available {
+            return Err(Error::InvalidData(
+                "sample extends past the end of the truncated mdat",
+            ));
+        }
+
+        Ok(Some((sample.offset, sample.size as u64, block)))
+    }
+
+    /// File range fetched for a sample that is not in memory: the sample itself, the rest
+    /// of its chunk and following chunks of the track stored back to back, then any other
+    /// chunks that end within the readahead size.
+    ///
+    /// The range stays within the part of the block present in the file, so a truncated
+    /// `mdat` isn't read past the end of the file.
+    fn read_range(&self, track_id: u32, sample_idx: usize, block: &DataBlock) -> Range<u64> {
+        let samples = &self.tracks[&track_id].samples;
+        let start = samples[sample_idx].offset;
+        let block_end = block.offset + block.available;
+        let limit = start.saturating_add(self.readahead).min(block_end);
+        let mut end = start + samples[sample_idx].size as u64;
+
+        for next in &samples[sample_idx + 1..] {
+            if next.offset != end || end + next.size as u64 > limit {
+                break;
+            }
+            end += next.size as u64;
+        }
+
+        // chunk offsets of all tracks mark where a whole chunk ends
+        if limit == block_end {
+            end = end.max(limit);
+        } else if end >= limit {
+            return start..end;
+        } else if let Some(&boundary) = self.offsets.range(end..=limit).next_back() {
+            end = boundary;
+        }
+
+        start..end
+    }
+
+    /// Decrypts a sample of a protected track if decryption keys are configured.
+    ///
+    /// Fails for protected samples without encryption info rather than returning them
+    /// still encrypted.
+    fn decrypt_sample(
+        &self,
+        track_id: u32,
+        sample_idx: usize,
+        data: &mut [u8],
+    ) -> Result<(), Error> {
+        if self.decryptor.is_empty() {
+            return Ok(());
+        }
+
+        let track = &self.tracks[&track_id];
+        let Some(sinf) = track.protection() else {
+            return Ok(());
+        };
+
+        match &track.samples[sample_idx].encryption {
+            Some(info) => self.decryptor.decrypt_sample(sinf, info, data)?,
+            None if !matches!(sinf.tenc(), Some(tenc) if tenc.default_is_protected == 0) => {
+                return Err(Error::InvalidData(
+                    "protected sample has no encryption info",
+                ));
+            }
+            None => {}
+        }
+
+        Ok(())
+    }
+}
+
+impl<T, F> Mp4File<PositionalReader<T>, F>
+where
+    T: ReadAt + Unpin,
+    F: ReadSampleFormat,
+{
+    /// Reads a sample from a shared reference, so the file can be put behind an `Arc`
+    /// and serve many tasks at once.
+    ///
+    /// Works like [`read_sample_data`](Self::read_sample_data) but reads with positional
+    /// I/O and bypasses the readahead buffer.
+    ///
+    /// The read blocks the calling thread, so from async code over a file use
+    /// [`spawn_sample_data`](Self::spawn_sample_data) or wrap the call in
+    /// `spawn_blocking`.
+    pub fn sample_data(&self, track_id: u32, sample_idx: usize) -> Result<Option<Bytes>, Error> {
+        let Some((offset, size, block)) = self.locate_sample(track_id, sample_idx)? else {
+            return Ok(None);
+        };
+
+        let mut buff = match &block.buffer {
+            DataBlockBody::Memory(mem) => {
+                let start = (offset - block.offset) as usize;
+                mem.slice(start..start + size as usize).to_vec()
+            }
+
+            DataBlockBody::Reader => {
+                let mut buff = vec![0u8; size as usize];
+                self.reader.get_ref().read_exact_at(offset, &mut buff)?;
+                buff
+            }
+        };
+
+        self.decrypt_sample(track_id, sample_idx, &mut buff)?;
+        self.format_conv.format(&mut buff)?;
+        Ok(Some(Bytes::from(buff)))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T, F> Mp4File<PositionalReader<T>, F>
+where
+    T: ReadAt + Unpin + Send + Sync + 'static,
+    F: ReadSampleFormat + Send + Sync + 'static,
+{
+    /// Reads a sample like [`sample_data`](Self::sample_data) on tokio's blocking thread
+    /// pool, so a slow disk doesn't stall the async runtime.
+    pub async fn spawn_sample_data(
+        self: &std::sync::Arc<Self>,
+        track_id: u32,
+        sample_idx: usize,
+    ) -> Result<Option<Bytes>, Error> {
+        let file = self.clone();
+        tokio::task::spawn_blocking(move || file.sample_data(track_id, sample_idx))
+            .await
+            .map_err(std::io::Error::other)?
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PlannedSample {
+    track_id: u32,
+    idx: usize,
+    timescale: u32,
+    start_time: u64,
+    offset: u64,
+}
+
+/// File range read ahead for the samples following the last one read.
+#[derive(Default)]
+struct ReadBuffer {
+    offset: u64,
+    data: Vec<u8>,
+}
+
+impl ReadBuffer {
+    fn contains(&self, offset: u64, size: u64) -> bool {
+        offset >= self.offset && offset + size <= self.offset + self.data.len() as u64
+    }
+
+    fn get(&self, offset: u64, size: u64) -> &[u8] {
+        let start = (offset - self.offset) as usize;
+        &self.data[start..start + size as usize]
+    }
+}
+
+impl DataBlock {
+    #[inline]
+    fn contains(&self, offset: u64) -> bool {
+        (self.offset..self.offset + self.size).contains(&offset)
     }
 }
 
@@ -381,3 +1426,546 @@ where
 //         async_stream::stream! {}
 //     }
 // }
+
+/// Parses the body of a top level box that ends at the absolute offset `end`, errors
+/// are located in the file.
+pub(crate) fn parse_body<'a, T: BlockReader>(
+    kind: BoxType,
+    mut body: impl Reader<'a>,
+    end: u64,
+) -> Result<T, Error> {
+    T::read_block(&mut body).map_err(|err| err.in_file_box(kind, end, body.remaining()))
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::test_util::{aac_track, fragmented_file, two_track_file};
+    use crate::WriteBox;
+    use crate::{FrmaBox, KeyEntry, KeysBox, Mp4Box, SchiBox, SinfBox, TencBox, TfraBox, TrakBox};
+    use std::io::Cursor;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+
+    struct SeekCounter {
+        inner: Cursor<Vec<u8>>,
+        seeks: usize,
+    }
+
+    impl AsyncRead for SeekCounter {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            Pin::new(&mut self.inner).poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncSeek for SeekCounter {
+        fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+            self.seeks += 1;
+            Pin::new(&mut self.inner).start_seek(position)
+        }
+
+        fn poll_complete(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<std::io::Result<u64>> {
+            Pin::new(&mut self.inner).poll_complete(cx)
+        }
+    }
+
+    async fn read_all(readahead: u64) -> usize {
+        read_samples(two_track_file(), readahead, 40).await
+    }
+
+    /// Seeks taken to read the first `count` samples of both tracks.
+    async fn read_samples(data: Vec<u8>, readahead: u64, count: usize) -> usize {
+        let inner = Cursor::new(data);
+        let mut mp4_file = Mp4File::new(SeekCounter { inner, seeks: 0 });
+        mp4_file.memory_block_limit = 0;
+        mp4_file.set_readahead(readahead);
+        assert!(mp4_file.read_header().await.unwrap());
+        assert!(mp4_file
+            .data_blocks
+            .iter()
+            .all(|b| matches!(b.buffer, DataBlockBody::Reader)));
+
+        let seeks = mp4_file.reader.seeks;
+        for track_id in [1, 2] {
+            for idx in 0..count {
+                let data = mp4_file.read_sample_data(track_id, idx).await.unwrap();
+                assert_eq!(data.unwrap()[..2], [track_id as u8, idx as u8]);
+            }
+        }
+
+        mp4_file.reader.seeks - seeks
+    }
+
+    #[tokio::test]
+    async fn test_readahead() {
+        assert_eq!(read_all(0).await, 80);
+
+        // four fragments, each fetched once per track
+        assert_eq!(read_all(DEFAULT_READAHEAD).await, 8);
+
+        // two samples of the same chunk per read
+        assert_eq!(read_all(12).await, 40);
+    }
+
+    #[tokio::test]
+    async fn test_readahead_truncated() {
+        let mut data = two_track_file();
+        let tree = crate::BoxTree::parse(Bytes::from(data.clone())).unwrap();
+        let mdat = *tree.find_all("mdat").last().unwrap();
+        data.truncate(mdat.end() as usize - 9);
+
+        // the last fragment is still fetched once per track, up to the end of the file
+        assert_eq!(read_samples(data, DEFAULT_READAHEAD, 38).await, 8);
+    }
+
+    #[test]
+    fn test_annexb() {
+        let mut data = vec![0, 0, 0, 2, 0x65, 0xaa, 0, 0, 0, 1, 0x06];
+        Annexb {}.format(&mut data).unwrap();
+        assert_eq!(data, [0, 0, 0, 1, 0x65, 0xaa, 0, 0, 0, 1, 0x06]);
+
+        Annexb {}.format(&mut []).unwrap();
+
+        // NAL running past the end and a truncated length
+        assert!(Annexb {}.format(&mut [0, 0, 0, 3, 0x65, 0xaa]).is_err());
+        assert!(Annexb {}.format(&mut [0, 0, 0, 1, 0x65, 0, 0]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sample_data_shared() {
+        let data = Bytes::from(two_track_file());
+        let mut mp4_file = Mp4File::new(PositionalReader::new(data));
+        mp4_file.memory_block_limit = 0;
+        assert!(mp4_file.read_header().await.unwrap());
+
+        let mp4_file = std::sync::Arc::new(mp4_file);
+        let handles = [1u32, 2].map(|track_id| {
+            let mp4_file = mp4_file.clone();
+            std::thread::spawn(move || {
+                for idx in 0..40 {
+                    let data = mp4_file.sample_data(track_id, idx).unwrap().unwrap();
+                    assert_eq!(data[..2], [track_id as u8, idx as u8]);
+                }
+            })
+        });
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert!(mp4_file.sample_data(1, 40).unwrap().is_none());
+    }
+
+    async fn read_with(
+        data: Vec<u8>,
+        options: ParseOptions,
+    ) -> Result<Mp4File<Cursor<Vec<u8>>, LengthDelimited>, Error> {
+        let mut mp4_file = Mp4File::new(Cursor::new(data));
+        mp4_file.set_parse_options(options);
+        mp4_file.read_header().await?;
+        Ok(mp4_file)
+    }
+
+    #[tokio::test]
+    async fn test_parse_modes() {
+        let data = two_track_file();
+        let tree = crate::BoxTree::parse(Bytes::from(data.clone())).unwrap();
+
+        let mp4_file = read_with(data.clone(), ParseOptions::strict())
+            .await
+            .unwrap();
+        assert_eq!(mp4_file.tracks[&2].samples.len(), 40);
+        assert!(mp4_file.warnings.is_empty());
+
+        // a tkhd larger than its trak
+        let mut damaged = data.clone();
+        let trak = tree.find("moov/trak[1]").unwrap();
+        let tkhd = trak.find("tkhd").unwrap().offset as usize;
+        damaged[tkhd..tkhd + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+
+        assert!(read_with(damaged.clone(), ParseOptions::default())
+            .await
+            .is_err());
+
+        let mp4_file = read_with(damaged, ParseOptions::lenient()).await.unwrap();
+        assert_eq!(mp4_file.tracks.len(), 1);
+        assert_eq!(mp4_file.tracks[&1].samples.len(), 40);
+
+        let warnings = &mp4_file.warnings;
+        // located after the header of the tkhd box
+        assert_eq!(warnings[0].path, "moov/trak[1]");
+        assert_eq!(warnings[0].offset, trak.find("tkhd").unwrap().offset + 8);
+        assert_eq!(warnings.len(), 5);
+        assert_eq!(warnings[4].path, "moof[3]/traf[1]");
+
+        // cut in the middle of the last moof
+        let moof = tree.find_all("moof").pop().unwrap();
+        let cut = data[..moof.data_offset() as usize + 32].to_vec();
+
+        assert!(read_with(cut.clone(), ParseOptions::strict())
+            .await
+            .is_err());
+
+        let mp4_file = read_with(cut, ParseOptions::lenient()).await.unwrap();
+        assert_eq!(mp4_file.tracks[&1].samples.len(), 30);
+        let warnings = &mp4_file.warnings;
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].offset, moof.offset);
+
+        // the first traf is cut after its header
+        assert_eq!(warnings[1].path, "moof[3]");
+        assert_eq!(warnings[1].offset, moof.find("traf").unwrap().offset + 8);
+
+        // cut in the middle of the last mdat, its samples can't be read
+        let mdat = tree.find_all("mdat").pop().unwrap();
+        let cut = data[..mdat.data_offset() as usize + 4].to_vec();
+
+        let mut mp4_file = read_with(cut, ParseOptions::default()).await.unwrap();
+        assert_eq!(mp4_file.tracks[&1].samples.len(), 40);
+        assert!(mp4_file.read_sample_data(1, 29).await.unwrap().is_some());
+        assert!(matches!(
+            mp4_file.read_sample_data(1, 39).await,
+            Err(Error::InvalidData(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_limits() {
+        let data = two_track_file();
+        let tree = crate::BoxTree::parse(Bytes::from(data.clone())).unwrap();
+
+        let read_limited = |limits: crate::Limits| {
+            let data = data.clone();
+            async move {
+                let options = ParseOptions {
+                    limits,
+                    ..Default::default()
+                };
+                match read_with(data, options)
+                    .await
+                    .err()
+                    .as_ref()
+                    .map(Error::root)
+                {
+                    Some(Error::LimitExceeded(limit)) => *limit,
+                    _ => panic!("limit not enforced"),
+                }
+            }
+        };
+
+        let limits = crate::Limits::default();
+        let cases = [
+            (
+                crate::Limits {
+                    max_box_size: 64,
+                    ..limits.clone()
+                },
+                "max_box_size",
+            ),
+            (
+                crate::Limits {
+                    max_sample_count: 30,
+                    ..limits.clone()
+                },
+                "max_sample_count",
+            ),
+            (
+                crate::Limits {
+                    max_tracks: 1,
+                    ..limits.clone()
+                },
+                "max_tracks",
+            ),
+            (
+                crate::Limits {
+                    max_allocation: 1024,
+                    ..limits.clone()
+                },
+                "max_allocation",
+            ),
+        ];
+        for (limits, limit) in cases {
+            assert_eq!(read_limited(limits).await, limit);
+        }
+
+        // four billion samples of one byte each
+        let mut hostile = data.clone();
+        let stsz = tree
+            .find("moov/trak/mdia/minf/stbl/stsz")
+            .unwrap()
+            .data_offset() as usize;
+        hostile[stsz + 4..stsz + 8].copy_from_slice(&1u32.to_be_bytes());
+        hostile[stsz + 8..stsz + 12].copy_from_slice(&u32::MAX.to_be_bytes());
+
+        let err = read_with(hostile, ParseOptions::default())
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.root(),
+            Error::LimitExceeded("max_sample_count")
+        ));
+        assert_eq!(err.context().unwrap().track_id, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_hostile_boxes() {
+        let data = two_track_file();
+        let tree = crate::BoxTree::parse(Bytes::from(data.clone())).unwrap();
+        let modes = [
+            ParseOptions::strict(),
+            ParseOptions::default(),
+            ParseOptions::lenient(),
+        ];
+
+        // a box smaller than its own header deep in a trak
+        let mut hostile = data.clone();
+        let smhd = tree.find("moov/trak/mdia/minf/smhd").unwrap().offset as usize;
+        hostile[smhd..smhd + 4].copy_from_slice(&4u32.to_be_bytes());
+        for options in modes.clone() {
+            let lenient = options.mode == ParseMode::Lenient;
+            match read_with(hostile.clone(), options).await {
+                Ok(mp4_file) => assert!(lenient && !mp4_file.warnings.is_empty()),
+                Err(err) => assert!(matches!(err.root(), Error::InvalidData(_))),
+            }
+        }
+
+        // a version 1 tkhd in the size of a version 0 one
+        let mut hostile = data.clone();
+        let tkhd = tree.find("moov/trak/tkhd").unwrap().data_offset() as usize;
+        hostile[tkhd] = 1;
+        let err = read_with(hostile, ParseOptions::default())
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err.root(), Error::InvalidData(_)));
+
+        // an mdat claiming a gigabyte at the end of the file only holds what's there
+        let mut hostile = data.clone();
+        hostile.extend_from_slice(&(1u32 << 30).to_be_bytes());
+        hostile.extend_from_slice(b"mdat");
+        hostile.extend_from_slice(&[0; 16]);
+        let mp4_file = read_with(hostile, ParseOptions::default()).await.unwrap();
+        let mdat = mp4_file.data_blocks.last().unwrap();
+        assert!(matches!(&mdat.buffer, DataBlockBody::Memory(mem) if mem.len() == 16));
+        assert!(mp4_file.allocated < 1 << 20);
+        assert_eq!(mp4_file.warnings.len(), 1);
+        assert_eq!(mp4_file.warnings[0].path, "mdat");
+        assert_eq!(mp4_file.warnings[0].offset, data.len() as u64);
+    }
+
+    #[tokio::test]
+    async fn test_truncated_mdat_on_disk() {
+        let mut data = two_track_file();
+        let tree = crate::BoxTree::parse(Bytes::from(data.clone())).unwrap();
+        let mdat = *tree.find_all("mdat").last().unwrap();
+
+        // the last sample of track 2 is gone and the one before it is cut in half
+        data.truncate(mdat.end() as usize - 9);
+
+        let mut mp4_file = Mp4File::new(Cursor::new(data));
+        mp4_file.memory_block_limit = 16;
+        assert!(mp4_file.read_header().await.unwrap());
+        let block = mp4_file.data_blocks.last().unwrap();
+        assert!(matches!(block.buffer, DataBlockBody::Reader));
+        assert_eq!(block.available, block.size - 9);
+
+        for idx in 0..40 {
+            let data = mp4_file.read_sample_data(1, idx).await.unwrap().unwrap();
+            assert_eq!(data[..2], [1, idx as u8]);
+        }
+        for idx in 0..38 {
+            let data = mp4_file.read_sample_data(2, idx).await.unwrap().unwrap();
+            assert_eq!(data[..2], [2, idx as u8]);
+        }
+        for idx in [38, 39] {
+            let err = mp4_file.read_sample_data(2, idx).await.unwrap_err();
+            assert!(matches!(err, Error::InvalidData(_)));
+        }
+    }
+
+    /// The init segment and second fragment of `fragmented_file` with the video `tfdt`
+    /// dropped, indexed by a `tfra` entry for the given sample of the fragment.
+    fn fragment_without_tfdt(time: u64, sample_number: u32) -> Vec<u8> {
+        let data = fragmented_file();
+
+        let mut boxes = Vec::new();
+        let mut offset = 0;
+        while offset < data.len() as u64 {
+            let header = BoxHeader::read_sync(&mut &data[offset as usize..], &mut 0)
+                .unwrap()
+                .unwrap();
+            boxes.push(offset..offset + header.size);
+            offset += header.size;
+        }
+
+        // ftyp, moov, then the moof and mdat of the second fragment
+        let mut out = data[..boxes[1].end as usize].to_vec();
+        let moof_offset = out.len() as u64;
+
+        let moof = &data[boxes[4].start as usize..boxes[4].end as usize];
+        let mut moof: MoofBox = parse_body(BoxType::MoofBox, &moof[8..], 0).unwrap();
+        let tfdt = moof.trafs[0].tfdt.take().unwrap();
+        for traf in moof.trafs.iter_mut() {
+            let trun = traf.trun.as_mut().unwrap();
+            trun.data_offset = trun.data_offset.map(|o| o - tfdt.box_size() as i32);
+        }
+        moof.write_box(&mut out).unwrap();
+        out.extend_from_slice(&data[boxes[5].start as usize..boxes[5].end as usize]);
+
+        let mfra = MfraBox::new(vec![TfraBox {
+            track_id: 1,
+            entries: vec![TfraEntry {
+                time,
+                moof_offset,
+                traf_number: 1,
+                trun_number: 1,
+                sample_number,
+            }],
+            ..Default::default()
+        }]);
+        mfra.write_box(&mut out).unwrap();
+
+        out
+    }
+
+    #[tokio::test]
+    async fn test_fragment_without_tfdt() {
+        // the entry for the first sample gives the decode time of the fragment
+        let mut mp4_file = Mp4File::new(Cursor::new(fragment_without_tfdt(100, 1)));
+        assert!(mp4_file.read_header_indexed().await.unwrap());
+        assert_eq!(mp4_file.read_fragment_at(1, 100).await.unwrap(), Some(0));
+
+        let samples = &mp4_file.tracks[&1].samples;
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].start_time, 100);
+        assert_eq!(samples[1].start_time, 200);
+
+        // an entry for a later sample isn't the decode time of the first one
+        let mut mp4_file = Mp4File::new(Cursor::new(fragment_without_tfdt(200, 2)));
+        assert!(mp4_file.read_header_indexed().await.unwrap());
+        mp4_file.read_fragment_at(1, 200).await.unwrap();
+
+        let samples = &mp4_file.tracks[&1].samples;
+        assert_eq!(samples.len(), 2);
+        assert_ne!(samples[0].start_time, 200);
+    }
+
+    #[tokio::test]
+    async fn test_moov_meta() {
+        let meta = MetaBox::Mdta {
+            keys: KeysBox {
+                entries: vec![KeyEntry {
+                    namespace: str::parse("mdta").unwrap(),
+                    name: "com.apple.quicktime.make".to_string(),
+                }],
+                ..Default::default()
+            },
+            ilst: None,
+            unknown: Default::default(),
+        };
+        let moov = MoovBox {
+            meta: Some(meta.clone()),
+            ..Default::default()
+        };
+
+        let mut data = Vec::new();
+        moov.write_box(&mut data).unwrap();
+
+        let mp4_file = read_with(data, ParseOptions::default()).await.unwrap();
+        assert_eq!(mp4_file.meta, Some(meta));
+        assert_eq!(mp4_file.udta, None);
+    }
+
+    #[tokio::test]
+    async fn test_protected_track() {
+        let mut trak = TrakBox::new(1, &aac_track(1000));
+
+        let tenc = TencBox {
+            default_is_protected: 1,
+            default_per_sample_iv_size: 0,
+            default_constant_iv: vec![7; 16],
+            ..Default::default()
+        };
+        trak.mdia.minf.stbl.stsd.mp4a.as_mut().unwrap().sinf = Some(SinfBox {
+            frma: FrmaBox {
+                original_format: BoxType::Mp4aBox.into(),
+            },
+            schi: Some(SchiBox {
+                tenc: Some(tenc.clone()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+
+        let stbl = &mut trak.mdia.minf.stbl;
+        stbl.stsz.sample_size = 8;
+        stbl.stsz.sample_count = 2;
+        stbl.stsc.entries.push(crate::stsc::StscEntry {
+            first_chunk: 1,
+            samples_per_chunk: 2,
+            sample_description_index: 1,
+            first_sample: 1,
+        });
+        stbl.stco.as_mut().unwrap().entries.push(0);
+
+        let write_file = |trak: &TrakBox| {
+            let mut moov = MoovBox {
+                traks: vec![trak.clone()],
+                ..Default::default()
+            };
+            let offset = moov.box_size() as u32 + 8;
+            moov.traks[0].mdia.minf.stbl.stco.as_mut().unwrap().entries[0] = offset;
+
+            let mut data = Vec::new();
+            moov.write_box(&mut data).unwrap();
+            BoxHeader::new(BoxType::MdatBox, 8 + 16)
+                .write(&mut data)
+                .unwrap();
+            data.extend_from_slice(&[0; 16]);
+            data
+        };
+
+        // constant IV, the whole sample is encrypted
+        let mp4_file = read_with(write_file(&trak), ParseOptions::default())
+            .await
+            .unwrap();
+        let encryption = mp4_file.tracks[&1].samples[1].encryption.as_ref();
+        assert_eq!(encryption.unwrap().iv, vec![7; 16]);
+        assert!(mp4_file.warnings.is_empty());
+
+        // per-sample IVs need a senc, which only fragments have
+        let sinf = trak
+            .mdia
+            .minf
+            .stbl
+            .stsd
+            .mp4a
+            .as_mut()
+            .unwrap()
+            .sinf
+            .as_mut();
+        sinf.unwrap().schi.as_mut().unwrap().tenc = Some(TencBox {
+            default_per_sample_iv_size: 8,
+            default_constant_iv: Vec::new(),
+            ..tenc
+        });
+        let mut mp4_file = read_with(write_file(&trak), ParseOptions::default())
+            .await
+            .unwrap();
+        assert!(mp4_file.tracks[&1].samples[1].encryption.is_none());
+        assert_eq!(mp4_file.warnings[0].path, "moov/trak[0]");
+
+        // such samples are returned as stored without keys, and can't be decrypted with
+        assert!(mp4_file.read_sample_data(1, 1).await.unwrap().is_some());
+        mp4_file.add_decryption_key([0; 16], [1; 16]);
+        assert!(mp4_file.read_sample_data(1, 1).await.is_err());
+    }
+}
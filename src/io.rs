@@ -0,0 +1,268 @@
+use bytes::Bytes;
+use futures::Future;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::Arc;
+
+/// Byte source parsed by [`Mp4File`](crate::Mp4File).
+///
+/// Implemented for every tokio `AsyncRead + AsyncSeek` reader with the `tokio` feature,
+/// for [`SyncReader`] over `std::io::Read + Seek` and for [`PositionalReader`].
+pub trait ByteSource {
+    /// Reads some bytes, returning how many were read or `0` at the end of the source.
+    fn read<'a>(&'a mut self, buf: &'a mut [u8]) -> impl Future<Output = io::Result<usize>> + 'a;
+
+    /// Seeks to a position and returns the new offset from the start of the source.
+    fn seek(&mut self, pos: SeekFrom) -> impl Future<Output = io::Result<u64>> + '_;
+
+    fn read_exact<'a>(
+        &'a mut self,
+        mut buf: &'a mut [u8],
+    ) -> impl Future<Output = io::Result<()>> + 'a {
+        async move {
+            while !buf.is_empty() {
+                match self.read(buf).await {
+                    Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                    Ok(n) => buf = &mut buf[n..],
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e),
+                }
+            }
+
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T> ByteSource for T
+where
+    T: tokio::io::AsyncRead + tokio::io::AsyncSeek + Unpin,
+{
+    fn read<'a>(&'a mut self, buf: &'a mut [u8]) -> impl Future<Output = io::Result<usize>> + 'a {
+        tokio::io::AsyncReadExt::read(self, buf)
+    }
+
+    fn seek(&mut self, pos: SeekFrom) -> impl Future<Output = io::Result<u64>> + '_ {
+        tokio::io::AsyncSeekExt::seek(self, pos)
+    }
+}
+
+/// Blocking `std::io::Read + Seek` reader used as a [`ByteSource`], its futures are
+/// always ready. See [`blocking::Mp4File`](crate::blocking::Mp4File).
+#[derive(Debug)]
+pub struct SyncReader<R>(pub R);
+
+impl<R: Read + Seek> ByteSource for SyncReader<R> {
+    fn read<'a>(&'a mut self, buf: &'a mut [u8]) -> impl Future<Output = io::Result<usize>> + 'a {
+        std::future::ready(self.0.read(buf))
+    }
+
+    fn seek(&mut self, pos: SeekFrom) -> impl Future<Output = io::Result<u64>> + '_ {
+        std::future::ready(self.0.seek(pos))
+    }
+}
+
+/// Source supporting positional (`pread` style) reads, which don't move a shared cursor
+/// and can be issued from many threads at once.
+pub trait ReadAt {
+    /// Reads bytes starting at `offset`, returning how many were read.
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize>;
+
+    /// Total size of the source in bytes.
+    fn size(&self) -> io::Result<u64>;
+
+    fn read_exact_at(&self, mut offset: u64, mut buf: &mut [u8]) -> io::Result<()> {
+        while !buf.is_empty() {
+            match self.read_at(offset, buf) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => {
+                    offset += n as u64;
+                    buf = &mut buf[n..];
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ReadAt for [u8] {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        let start = offset.min(self.len() as u64) as usize;
+        let len = buf.len().min(self.len() - start);
+        buf[..len].copy_from_slice(&self[start..start + len]);
+        Ok(len)
+    }
+
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.len() as u64)
+    }
+}
+
+impl ReadAt for Vec<u8> {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        self.as_slice().read_at(offset, buf)
+    }
+
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.len() as u64)
+    }
+}
+
+impl ReadAt for Bytes {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        self.as_ref().read_at(offset, buf)
+    }
+
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.len() as u64)
+    }
+}
+
+impl ReadAt for std::fs::File {
+    #[cfg(unix)]
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        std::os::unix::fs::FileExt::read_at(self, buf, offset)
+    }
+
+    #[cfg(windows)]
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        std::os::windows::fs::FileExt::seek_read(self, buf, offset)
+    }
+
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl ReadAt for memmap2::Mmap {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        self.as_ref().read_at(offset, buf)
+    }
+
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.len() as u64)
+    }
+}
+
+impl<T: ReadAt + ?Sized> ReadAt for &T {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        (**self).read_at(offset, buf)
+    }
+
+    fn size(&self) -> io::Result<u64> {
+        (**self).size()
+    }
+}
+
+impl<T: ReadAt + ?Sized> ReadAt for Arc<T> {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        (**self).read_at(offset, buf)
+    }
+
+    fn size(&self) -> io::Result<u64> {
+        (**self).size()
+    }
+}
+
+/// Adapts a [`ReadAt`] source to a [`ByteSource`] for parsing the header with
+/// [`Mp4File`](crate::Mp4File), while samples can still be read from a shared reference
+/// with [`Mp4File::sample_data`](crate::Mp4File::sample_data).
+///
+/// Reads are served synchronously, which suits memory backed sources. A file on a slow
+/// disk is better read with [`Mp4File::spawn_sample_data`](crate::Mp4File::spawn_sample_data).
+#[derive(Debug)]
+pub struct PositionalReader<T> {
+    inner: T,
+    pos: u64,
+}
+
+impl<T: ReadAt> PositionalReader<T> {
+    pub fn new(inner: T) -> Self {
+        Self { inner, pos: 0 }
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: ReadAt> Read for PositionalReader<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read_at(self.pos, buf)?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<T: ReadAt> Seek for PositionalReader<T> {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let pos = match position {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::End(delta) => self.inner.size()?.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.pos.checked_add_signed(delta),
+        };
+
+        self.pos = pos.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        Ok(self.pos)
+    }
+}
+
+impl<T: ReadAt> ByteSource for PositionalReader<T> {
+    fn read<'a>(&'a mut self, buf: &'a mut [u8]) -> impl Future<Output = io::Result<usize>> + 'a {
+        std::future::ready(Read::read(self, buf))
+    }
+
+    fn seek(&mut self, pos: SeekFrom) -> impl Future<Output = io::Result<u64>> + '_ {
+        std::future::ready(Seek::seek(self, pos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_at() {
+        let data = Bytes::from_static(b"0123456789");
+        let mut buf = [0u8; 4];
+
+        assert_eq!(data.read_at(8, &mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], b"89");
+        assert_eq!(data.read_at(12, &mut buf).unwrap(), 0);
+
+        data.read_exact_at(3, &mut buf).unwrap();
+        assert_eq!(&buf, b"3456");
+        assert!(data.read_exact_at(7, &mut buf).is_err());
+    }
+
+    #[test]
+    fn test_positional_reader() {
+        let mut reader = PositionalReader::new(b"0123456789".to_vec());
+        let mut buf = [0u8; 3];
+
+        Seek::seek(&mut reader, SeekFrom::End(-3)).unwrap();
+        Read::read_exact(&mut reader, &mut buf).unwrap();
+        assert_eq!(&buf, b"789");
+
+        Seek::seek(&mut reader, SeekFrom::Start(2)).unwrap();
+        Seek::seek(&mut reader, SeekFrom::Current(1)).unwrap();
+        Read::read_exact(&mut reader, &mut buf).unwrap();
+        assert_eq!(&buf, b"345");
+
+        assert!(Seek::seek(&mut reader, SeekFrom::Current(-10)).is_err());
+    }
+}
@@ -1,13 +1,39 @@
+pub mod blocking;
+mod caption;
+mod cenc;
 mod error;
 mod file;
 mod frame;
+mod io;
 mod mp4box;
+mod options;
+mod registry;
+mod repair;
+mod subtitle;
+#[cfg(all(test, feature = "tokio"))]
+mod test_util;
 mod track;
+mod tree;
 mod types;
+mod validate;
+mod writer;
 
-pub use error::Error;
+pub use caption::{
+    c608_cc_data, c608_sample, decode_cea608, sei_cc_data, CaptionChannel, CaptionPacket, CcData,
+    Cea608Decoder,
+};
+pub use cenc::{CencConfig, CencDecryptor, CencEncryptor, ProtectionScheme};
+pub use error::{Error, ErrorContext};
 pub use file::*;
 pub use frame::{Mp4Frame, Mp4FrameSource};
+pub use io::{ByteSource, PositionalReader, ReadAt, SyncReader};
 pub use mp4box::*;
+pub use options::{Limits, ParseMode, ParseOptions};
+pub use registry::BoxRegistry;
+pub use repair::{repair, RepairReport};
+pub use subtitle::{parse_srt, parse_webvtt, SubtitleCue, SubtitleFormat};
 pub use track::Mp4Track;
+pub use tree::{BoxNode, BoxTree};
 pub use types::*;
+pub use validate::{validate, Diagnostic, Severity};
+pub use writer::Mp4FragmentWriter;
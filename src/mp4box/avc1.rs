@@ -18,6 +18,13 @@ pub struct Avc1Box {
     pub frame_count: u16,
     pub depth: u16,
     pub avcc: AvcCBox,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sinf: Option<SinfBox>,
+
+    /// Children such as `colr`, `pasp` or `btrt`.
+    #[serde(skip)]
+    pub unknown: UnknownBoxes,
 }
 
 impl Default for Avc1Box {
@@ -31,6 +38,8 @@ impl Default for Avc1Box {
             frame_count: 1,
             depth: 0x0018,
             avcc: AvcCBox::default(),
+            sinf: None,
+            unknown: UnknownBoxes::new(),
         }
     }
 }
@@ -46,15 +55,26 @@ impl Avc1Box {
             frame_count: 1,
             depth: 0x0018,
             avcc: AvcCBox::new(&config.seq_param_set, &config.pic_param_set),
+            sinf: None,
+            unknown: UnknownBoxes::new(),
         }
     }
 
     pub fn get_size(&self) -> u64 {
-        HEADER_SIZE + 8 + 70 + self.avcc.box_size()
+        HEADER_SIZE
+            + 8
+            + 70
+            + self.avcc.box_size()
+            + self.sinf.as_ref().map_or(0, |x| x.box_size())
+            + unknown_boxes_size(&self.unknown)
     }
 
-    fn box_type(&self) -> BoxType {
-        BoxType::Avc1Box
+    pub fn get_type(&self) -> BoxType {
+        if self.sinf.is_some() {
+            BoxType::EncvBox
+        } else {
+            BoxType::Avc1Box
+        }
     }
 }
 
@@ -80,30 +100,49 @@ impl Mp4Box for Avc1Box {
 
 impl BlockReader for Avc1Box {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        reader.get_u32(); // reserved
-        reader.get_u16(); // reserved
+        reader.try_get_u32()?; // reserved
+        reader.try_get_u16()?; // reserved
 
-        let data_reference_index = reader.get_u16();
+        let data_reference_index = reader.try_get_u16()?;
 
-        reader.get_u32(); // pre-defined, reserved
-        reader.get_u64(); // pre-defined
-        reader.get_u32(); // pre-defined
+        reader.try_get_u32()?; // pre-defined, reserved
+        reader.try_get_u64()?; // pre-defined
+        reader.try_get_u32()?; // pre-defined
 
-        let width = reader.get_u16();
-        let height = reader.get_u16();
+        let width = reader.try_get_u16()?;
+        let height = reader.try_get_u16()?;
 
-        let horizresolution = FixedPointU16::new_raw(reader.get_u32());
-        let vertresolution = FixedPointU16::new_raw(reader.get_u32());
+        let horizresolution = FixedPointU16::new_raw(reader.try_get_u32()?);
+        let vertresolution = FixedPointU16::new_raw(reader.try_get_u32()?);
 
-        reader.get_u32(); // reserved
+        reader.try_get_u32()?; // reserved
 
-        let frame_count = reader.get_u16();
+        let frame_count = reader.try_get_u16()?;
 
         reader.skip(32); // compressorname
 
-        let depth = reader.get_u16();
+        let depth = reader.try_get_u16()?;
+
+        reader.try_get_i16()?; // pre-defined
+
+        let mut avcc = None;
+        let mut sinf = None;
+        let mut unknown = UnknownBoxes::new();
+
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
 
-        reader.get_i16(); // pre-defined
+            match bx.kind {
+                BoxType::AvcCBox => avcc = Some(bx.read()?),
+                BoxType::SinfBox => sinf = Some(bx.read()?),
+                _ => unknown.push_at(position, bx.read_raw()),
+            }
+        }
+        let Some(avcc) = avcc else {
+            return Err(Error::BoxNotFound(BoxType::AvcCBox));
+        };
 
         Ok(Avc1Box {
             data_reference_index,
@@ -113,7 +152,9 @@ impl BlockReader for Avc1Box {
             vertresolution,
             frame_count,
             depth,
-            avcc: reader.find_box::<AvcCBox>()?,
+            avcc,
+            sinf,
+            unknown,
         })
     }
 
@@ -125,7 +166,7 @@ impl BlockReader for Avc1Box {
 impl<W: Write> WriteBox<&mut W> for Avc1Box {
     fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
         let size = self.box_size();
-        BoxHeader::new(self.box_type(), size).write(writer)?;
+        BoxHeader::new(self.get_type(), size).write(writer)?;
 
         writer.write_u32::<BigEndian>(0)?; // reserved
         writer.write_u16::<BigEndian>(0)?; // reserved
@@ -145,7 +186,14 @@ impl<W: Write> WriteBox<&mut W> for Avc1Box {
         writer.write_u16::<BigEndian>(self.depth)?;
         writer.write_i16::<BigEndian>(-1)?; // pre-defined
 
-        self.avcc.write_box(writer)?;
+        let mut children = self.unknown.writer(writer);
+
+        children.write(&self.avcc)?;
+
+        if let Some(ref sinf) = self.sinf {
+            children.write(sinf)?;
+        }
+        children.finish()?;
 
         Ok(size)
     }
@@ -202,12 +250,12 @@ impl Mp4Box for AvcCBox {
 
 impl BlockReader for AvcCBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let configuration_version = reader.get_u8();
-        let avc_profile_indication = reader.get_u8();
-        let profile_compatibility = reader.get_u8();
-        let avc_level_indication = reader.get_u8();
-        let length_size_minus_one = reader.get_u8() & 0x3;
-        let num_of_spss = reader.get_u8() & 0x1F;
+        let configuration_version = reader.try_get_u8()?;
+        let avc_profile_indication = reader.try_get_u8()?;
+        let profile_compatibility = reader.try_get_u8()?;
+        let avc_level_indication = reader.try_get_u8()?;
+        let length_size_minus_one = reader.try_get_u8()? & 0x3;
+        let num_of_spss = reader.try_get_u8()? & 0x1F;
 
         let mut sequence_parameter_sets = Vec::with_capacity(num_of_spss as usize);
         for _ in 0..num_of_spss {
@@ -215,7 +263,7 @@ impl BlockReader for AvcCBox {
             sequence_parameter_sets.push(nal_unit);
         }
 
-        let num_of_ppss = reader.get_u8();
+        let num_of_ppss = reader.try_get_u8()?;
 
         let mut picture_parameter_sets = Vec::with_capacity(num_of_ppss as usize);
         for _ in 0..num_of_ppss {
@@ -263,17 +311,23 @@ impl<W: Write> WriteBox<&mut W> for AvcCBox {
 
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
 pub struct NalUnit {
-    pub bytes: Vec<u8>,
+    pub bytes: Bytes,
 }
 
 impl From<&[u8]> for NalUnit {
     fn from(bytes: &[u8]) -> Self {
         Self {
-            bytes: bytes.to_vec(),
+            bytes: Bytes::copy_from_slice(bytes),
         }
     }
 }
 
+impl From<Bytes> for NalUnit {
+    fn from(bytes: Bytes) -> Self {
+        Self { bytes }
+    }
+}
+
 impl NalUnit {
     fn size(&self) -> usize {
         2 + self.bytes.len()
@@ -283,7 +337,7 @@ impl NalUnit {
         let length = reader.try_get_u16()? as usize;
 
         Ok(NalUnit {
-            bytes: reader.collect(length)?,
+            bytes: reader.collect_bytes(length)?,
         })
     }
 
@@ -297,8 +351,8 @@ impl NalUnit {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::mp4box::BoxHeader;
 
+    #[cfg(feature = "tokio")]
     #[tokio::test]
     async fn test_avc1() {
         let src_box = Avc1Box {
@@ -319,12 +373,15 @@ mod tests {
                     bytes: vec![
                         0x67, 0x64, 0x00, 0x0D, 0xAC, 0xD9, 0x41, 0x41, 0xFA, 0x10, 0x00, 0x00,
                         0x03, 0x00, 0x10, 0x00, 0x00, 0x03, 0x03, 0x20, 0xF1, 0x42, 0x99, 0x60,
-                    ],
+                    ]
+                    .into(),
                 }],
                 picture_parameter_sets: vec![NalUnit {
-                    bytes: vec![0x68, 0xEB, 0xE3, 0xCB, 0x22, 0xC0],
+                    bytes: vec![0x68, 0xEB, 0xE3, 0xCB, 0x22, 0xC0].into(),
                 }],
             },
+            sinf: None,
+            unknown: UnknownBoxes::new(),
         };
         let mut buf = Vec::new();
         src_box.write_box(&mut buf).unwrap();
@@ -340,4 +397,22 @@ mod tests {
         let dst_box = Avc1Box::read_block(&mut &buf[8..]).unwrap();
         assert_eq!(src_box, dst_box);
     }
+
+    #[test]
+    fn test_avc1_unknown() {
+        let src_box = Avc1Box {
+            unknown: vec![UnknownBox::Raw(
+                BoxType::from(u32::from_be_bytes(*b"pasp")),
+                Bytes::from_static(&[0, 0, 0, 1, 0, 0, 0, 1]),
+            )]
+            .into(),
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let dst_box = Avc1Box::read_block(&mut &buf[8..]).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
 }
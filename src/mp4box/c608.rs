@@ -0,0 +1,136 @@
+use byteorder::{BigEndian, WriteBytesExt};
+use serde::Serialize;
+use std::io::Write;
+
+use crate::mp4box::*;
+
+/// QuickTime CEA-608 closed caption sample entry.
+///
+/// Samples hold `cdat` (field 1) and `cdt2` (field 2) boxes with the caption byte pairs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct C608Box {
+    pub data_reference_index: u16,
+
+    #[serde(skip)]
+    pub unknown: UnknownBoxes,
+}
+
+impl Default for C608Box {
+    fn default() -> Self {
+        C608Box {
+            data_reference_index: 1,
+            unknown: UnknownBoxes::new(),
+        }
+    }
+}
+
+impl C608Box {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::C608Box
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE + 8 + unknown_boxes_size(&self.unknown)
+    }
+}
+
+impl Mp4Box for C608Box {
+    const TYPE: BoxType = BoxType::C608Box;
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String, Error> {
+        let s = format!("data_reference_index={}", self.data_reference_index);
+        Ok(s)
+    }
+}
+
+impl BlockReader for C608Box {
+    fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        reader.try_get_u32()?; // reserved
+        reader.try_get_u16()?; // reserved
+        let data_reference_index = reader.try_get_u16()?;
+
+        let mut unknown = UnknownBoxes::new();
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
+            unknown.push_at(position, bx.read_raw());
+        }
+
+        Ok(C608Box {
+            data_reference_index,
+            unknown,
+        })
+    }
+
+    fn size_hint() -> usize {
+        8
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for C608Box {
+    fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+        let size = self.box_size();
+        BoxHeader::new(Self::TYPE, size).write(writer)?;
+
+        writer.write_u32::<BigEndian>(0)?; // reserved
+        writer.write_u16::<BigEndian>(0)?; // reserved
+        writer.write_u16::<BigEndian>(self.data_reference_index)?;
+
+        self.unknown.writer(writer).finish()?;
+
+        Ok(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_c608() {
+        let src_box = C608Box::default();
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.kind, BoxType::C608Box);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = C608Box::read_block(&mut reader).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+
+    #[test]
+    fn test_c608_unknown() {
+        let src_box = C608Box {
+            unknown: vec![UnknownBox::Raw(
+                BoxType::from(u32::from_be_bytes(*b"btrt")),
+                Bytes::from_static(&[0, 0, 0, 0, 0, 0, 0, 0x40, 0, 0, 0, 0x40]),
+            )]
+            .into(),
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let dst_box = C608Box::read_block(&mut &buf[8..]).unwrap();
+        assert_eq!(src_box, dst_box);
+
+        let mut rewritten = Vec::new();
+        dst_box.write_box(&mut rewritten).unwrap();
+        assert_eq!(rewritten, buf);
+    }
+}
@@ -0,0 +1,175 @@
+use byteorder::{BigEndian, WriteBytesExt};
+use serde::Serialize;
+use std::io::Write;
+
+use crate::mp4box::*;
+
+/// Nero chapter list, found in `moov/udta`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChplBox {
+    pub version: u8,
+    pub flags: u32,
+    pub chapters: Vec<ChplEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChplEntry {
+    /// Start time in 100ns units.
+    pub start: u64,
+    pub title: String,
+}
+
+impl Default for ChplBox {
+    fn default() -> Self {
+        ChplBox {
+            version: 1,
+            flags: 0,
+            chapters: Vec::new(),
+        }
+    }
+}
+
+impl ChplBox {
+    /// Timescale of the chapter start times.
+    pub const TIMESCALE: u64 = 10_000_000;
+
+    pub fn get_type(&self) -> BoxType {
+        BoxType::ChplBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + HEADER_EXT_SIZE + 1;
+        if self.version > 0 {
+            size += 4;
+        }
+        size + self
+            .chapters
+            .iter()
+            .map(|c| 9 + truncate_str(&c.title, u8::MAX as usize).len() as u64)
+            .sum::<u64>()
+    }
+}
+
+impl Mp4Box for ChplBox {
+    const TYPE: BoxType = BoxType::ChplBox;
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String, Error> {
+        let s = format!("chapter_count={}", self.chapters.len());
+        Ok(s)
+    }
+}
+
+impl BlockReader for ChplBox {
+    fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        let (version, flags) = read_box_header_ext(reader)?;
+
+        if version > 0 {
+            reader.try_get_u32()?; // reserved
+        }
+
+        let count = reader.try_get_u8()?;
+        let mut chapters = Vec::with_capacity(count as usize);
+
+        for _ in 0..count {
+            let start = reader.try_get_u64()?;
+            let len = reader.try_get_u8()?;
+            let title = String::from_utf8_lossy(&reader.collect(len as usize)?).into_owned();
+
+            chapters.push(ChplEntry { start, title });
+        }
+
+        Ok(ChplBox {
+            version,
+            flags,
+            chapters,
+        })
+    }
+
+    fn size_hint() -> usize {
+        5
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for ChplBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+        if self.chapters.len() > u8::MAX as usize {
+            return Err(Error::InvalidData("chpl can hold at most 255 chapters"));
+        }
+
+        let size = self.box_size();
+        BoxHeader::new(Self::TYPE, size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        if self.version > 0 {
+            writer.write_u32::<BigEndian>(0)?; // reserved
+        }
+
+        writer.write_u8(self.chapters.len() as u8)?;
+        for chapter in &self.chapters {
+            let title = truncate_str(&chapter.title, u8::MAX as usize);
+
+            writer.write_u64::<BigEndian>(chapter.start)?;
+            writer.write_u8(title.len() as u8)?;
+            writer.write_all(title.as_bytes())?;
+        }
+
+        Ok(size)
+    }
+}
+
+/// Cuts `s` to at most `max` bytes on a char boundary.
+fn truncate_str(s: &str, max: usize) -> &str {
+    if s.len() <= max {
+        return s;
+    }
+
+    let mut end = max;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+
+    #[tokio::test]
+    async fn test_chpl() {
+        let src_box = ChplBox {
+            version: 1,
+            flags: 0,
+            chapters: vec![
+                ChplEntry {
+                    start: 0,
+                    title: "Intro".to_string(),
+                },
+                ChplEntry {
+                    start: 95 * ChplBox::TIMESCALE,
+                    title: "Kapitel 2 – Überblick".to_string(),
+                },
+            ],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.kind, BoxType::ChplBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = ChplBox::read_block(&mut reader).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+}
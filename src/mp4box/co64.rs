@@ -63,12 +63,10 @@ impl Mp4Box for Co64Box {
 
 impl BlockReader for Co64Box {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
         let entry_size = size_of::<u64>(); // chunk_offset
-        let entry_count = reader.get_u32();
-        println!("{}", reader.remaining() / entry_size);
-        println!("entry_count: {}", entry_count);
+        let entry_count = reader.try_get_u32()?;
         if entry_count as usize > reader.remaining() / entry_size {
             return Err(Error::InvalidData(
                 "co64 entry_count indicates more entries than could fit in the box",
@@ -77,7 +75,7 @@ impl BlockReader for Co64Box {
 
         let mut entries = Vec::with_capacity(entry_count as usize);
         for _i in 0..entry_count {
-            let chunk_offset = reader.get_u64();
+            let chunk_offset = reader.try_get_u64()?;
             entries.push(chunk_offset);
         }
 
@@ -109,7 +107,7 @@ impl<W: Write> WriteBox<&mut W> for Co64Box {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
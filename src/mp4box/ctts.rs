@@ -49,9 +49,9 @@ impl Mp4Box for CttsBox {
 
 impl BlockReader for CttsBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
-        let entry_count = reader.get_u32();
+        let entry_count = reader.try_get_u32()?;
         let entry_size = size_of::<u32>() + size_of::<i32>(); // sample_count + sample_offset
                                                               // (sample_offset might be a u32, but the size is the same.)
 
@@ -64,8 +64,8 @@ impl BlockReader for CttsBox {
         let mut entries = Vec::with_capacity(entry_count as usize);
         for _ in 0..entry_count {
             let entry = CttsEntry {
-                sample_count: reader.get_u32(),
-                sample_offset: reader.get_i32(),
+                sample_count: reader.try_get_u32()?,
+                sample_offset: reader.try_get_i32()?,
             };
             entries.push(entry);
         }
@@ -99,7 +99,7 @@ impl<W: Write> WriteBox<&mut W> for CttsBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -5,7 +5,7 @@ use crate::mp4box::*;
 
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
 pub struct DataBox {
-    pub data: Vec<u8>,
+    pub data: Bytes,
     pub data_type: DataType,
 }
 
@@ -42,11 +42,11 @@ impl Mp4Box for DataBox {
 
 impl BlockReader for DataBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let data_type = DataType::try_from(reader.get_u32())?;
-        reader.get_u32(); // reserved = 0
+        let data_type = DataType::try_from(reader.try_get_u32()?)?;
+        reader.try_get_u32()?; // reserved = 0
 
         Ok(DataBox {
-            data: reader.collect(reader.remaining())?,
+            data: reader.collect_remaining_bytes(),
             data_type,
         })
     }
@@ -72,13 +72,13 @@ impl<W: Write> WriteBox<&mut W> for DataBox {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::mp4box::BoxHeader;
 
+    #[cfg(feature = "tokio")]
     #[tokio::test]
     async fn test_data() {
         let src_box = DataBox {
             data_type: DataType::Text,
-            data: b"test_data".to_vec(),
+            data: b"test_data".to_vec().into(),
         };
         let mut buf = Vec::new();
         src_box.write_box(&mut buf).unwrap();
@@ -93,6 +93,7 @@ mod tests {
         assert_eq!(src_box, dst_box);
     }
 
+    #[cfg(feature = "tokio")]
     #[tokio::test]
     async fn test_data_empty() {
         let src_box = DataBox::default();
@@ -108,4 +109,23 @@ mod tests {
         let dst_box = DataBox::read_block(&mut reader).unwrap();
         assert_eq!(src_box, dst_box);
     }
+
+    #[test]
+    fn test_data_zero_copy() {
+        let src_box = DataBox {
+            data_type: DataType::Binary,
+            data: vec![0xff; 64].into(),
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+
+        let buf = Bytes::from(buf);
+        let mut reader = buf.clone();
+        let mut bx = reader.get_box().unwrap().unwrap();
+        assert_eq!(bx.kind, BoxType::DataBox);
+
+        let dst_box = DataBox::read_block(&mut bx.inner).unwrap();
+        assert_eq!(src_box, dst_box);
+        assert_eq!(dst_box.data.as_ptr(), buf[16..].as_ptr());
+    }
 }
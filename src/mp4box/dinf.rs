@@ -104,9 +104,9 @@ impl Mp4Box for DrefBox {
 
 impl BlockReader for DrefBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
         let mut url = None;
-        let entry_count = reader.get_u32();
+        let entry_count = reader.try_get_u32()?;
 
         for _i in 0..entry_count {
             url = reader.try_find_box()?;
@@ -189,7 +189,7 @@ impl Mp4Box for UrlBox {
 
 impl BlockReader for UrlBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
         Ok(UrlBox {
             version,
@@ -7,6 +7,9 @@ use crate::mp4box::*;
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
 pub struct EdtsBox {
     pub elst: Option<ElstBox>,
+
+    #[serde(skip)]
+    pub unknown: UnknownBoxes,
 }
 
 impl EdtsBox {
@@ -19,7 +22,7 @@ impl EdtsBox {
         if let Some(ref elst) = self.elst {
             size += elst.box_size();
         }
-        size
+        size + unknown_boxes_size(&self.unknown)
     }
 }
 
@@ -42,9 +45,21 @@ impl Mp4Box for EdtsBox {
 
 impl BlockReader for EdtsBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        Ok(EdtsBox {
-            elst: reader.try_find_box::<ElstBox>()?,
-        })
+        let mut elst = None;
+        let mut unknown = UnknownBoxes::new();
+
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
+
+            match bx.kind {
+                BoxType::ElstBox => elst = Some(bx.read()?),
+                _ => unknown.push_at(position, bx.read_raw()),
+            }
+        }
+
+        Ok(EdtsBox { elst, unknown })
     }
 
     fn size_hint() -> usize {
@@ -57,10 +72,40 @@ impl<W: Write> WriteBox<&mut W> for EdtsBox {
         let size = self.box_size();
         BoxHeader::new(Self::TYPE, size).write(writer)?;
 
+        let mut children = self.unknown.writer(writer);
         if let Some(ref elst) = self.elst {
-            elst.write_box(writer)?;
+            children.write(elst)?;
         }
+        children.finish()?;
 
         Ok(size)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edts_unknown() {
+        let src_box = EdtsBox {
+            elst: Some(ElstBox::default()),
+            unknown: vec![UnknownBox::Raw(
+                BoxType::from(u32::from_be_bytes(*b"xedt")),
+                Bytes::from_static(b"vendor"),
+            )]
+            .into(),
+        };
+
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let dst_box = EdtsBox::read_block(&mut &buf[8..]).unwrap();
+        assert_eq!(dst_box, src_box);
+
+        let mut rewritten = Vec::new();
+        dst_box.write_box(&mut rewritten).unwrap();
+        assert_eq!(rewritten, buf);
+    }
+}
@@ -57,9 +57,9 @@ impl Mp4Box for ElstBox {
 
 impl BlockReader for ElstBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
-        let entry_count = reader.get_u32();
+        let entry_count = reader.try_get_u32()?;
         let entry_size = {
             let mut entry_size = 0;
             entry_size += if version == 1 {
@@ -81,16 +81,16 @@ impl BlockReader for ElstBox {
         let mut entries = Vec::with_capacity(entry_count as usize);
         for _ in 0..entry_count {
             let (segment_duration, media_time) = if version == 1 {
-                (reader.get_u64(), reader.get_u64())
+                (reader.try_get_u64()?, reader.try_get_u64()?)
             } else {
-                (reader.get_u32() as u64, reader.get_u32() as u64)
+                (reader.try_get_u32()? as u64, reader.try_get_u32()? as u64)
             };
 
             entries.push(ElstEntry {
                 segment_duration,
                 media_time,
-                media_rate: reader.get_u16(),
-                media_rate_fraction: reader.get_u16(),
+                media_rate: reader.try_get_u16()?,
+                media_rate_fraction: reader.try_get_u16()?,
             });
         }
 
@@ -130,7 +130,7 @@ impl<W: Write> WriteBox<&mut W> for ElstBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -16,7 +16,7 @@ pub struct EmsgBox {
     pub id: u32,
     pub scheme_id_uri: String,
     pub value: String,
-    pub message_data: Vec<u8>,
+    pub message_data: Bytes,
 }
 
 impl EmsgBox {
@@ -57,7 +57,7 @@ impl Mp4Box for EmsgBox {
 
 impl BlockReader for EmsgBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
         let (
             timescale,
@@ -73,21 +73,21 @@ impl BlockReader for EmsgBox {
                 let value = reader.get_null_terminated_string();
 
                 (
-                    reader.get_u32(),
+                    reader.try_get_u32()?,
                     None,
-                    Some(reader.get_u32()),
-                    reader.get_u32(),
-                    reader.get_u32(),
+                    Some(reader.try_get_u32()?),
+                    reader.try_get_u32()?,
+                    reader.try_get_u32()?,
                     scheme_id_uri,
                     value,
                 )
             }
             1 => (
-                reader.get_u32(),
-                Some(reader.get_u64()),
+                reader.try_get_u32()?,
+                Some(reader.try_get_u64()?),
                 None,
-                reader.get_u32(),
-                reader.get_u32(),
+                reader.try_get_u32()?,
+                reader.try_get_u32()?,
                 reader.get_null_terminated_string(),
                 reader.get_null_terminated_string(),
             ),
@@ -104,7 +104,7 @@ impl BlockReader for EmsgBox {
             id,
             scheme_id_uri,
             value,
-            message_data: reader.collect(reader.remaining())?,
+            message_data: reader.collect_remaining_bytes(),
         })
     }
 
@@ -155,7 +155,7 @@ fn write_null_terminated_str<W: Write>(writer: &mut W, string: &str) -> Result<(
     Ok(())
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
 
     use crate::mp4box::BoxHeader;
@@ -174,7 +174,7 @@ mod tests {
             id: 8,
             scheme_id_uri: String::from("foo"),
             value: String::from("foo"),
-            message_data: vec![1, 2, 3],
+            message_data: vec![1, 2, 3].into(),
         };
         let mut buf = Vec::new();
         src_box.write_box(&mut buf).unwrap();
@@ -201,7 +201,7 @@ mod tests {
             id: 8,
             scheme_id_uri: String::from("foo"),
             value: String::from("foo"),
-            message_data: vec![3, 2, 1],
+            message_data: vec![3, 2, 1].into(),
         };
         let mut buf = Vec::new();
         src_box.write_box(&mut buf).unwrap();
@@ -51,12 +51,12 @@ impl BlockReader for FtypBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
         let brand_count = (reader.remaining() - 8) / 4; // major + minor
 
-        let major = reader.get_u32();
-        let minor = reader.get_u32();
+        let major = reader.try_get_u32()?;
+        let minor = reader.try_get_u32()?;
 
         let mut brands = Vec::new();
         for _ in 0..brand_count {
-            let b = reader.get_u32();
+            let b = reader.try_get_u32()?;
             brands.push(From::from(b));
         }
 
@@ -86,7 +86,7 @@ impl<W: Write> WriteBox<&mut W> for FtypBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
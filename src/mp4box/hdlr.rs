@@ -41,11 +41,11 @@ impl Mp4Box for HdlrBox {
 
 impl BlockReader for HdlrBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
-        reader.get_u32(); // pre-defined
+        reader.try_get_u32()?; // pre-defined
 
-        let handler = reader.get_u32();
+        let handler = reader.try_get_u32()?;
 
         reader.skip(12);
 
@@ -84,7 +84,7 @@ impl<W: Write> WriteBox<&mut W> for HdlrBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -18,6 +18,13 @@ pub struct Hev1Box {
     pub frame_count: u16,
     pub depth: u16,
     pub hvcc: HvcCBox,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sinf: Option<SinfBox>,
+
+    /// Children such as `colr`, `pasp` or `btrt`.
+    #[serde(skip)]
+    pub unknown: UnknownBoxes,
 }
 
 impl Default for Hev1Box {
@@ -31,6 +38,8 @@ impl Default for Hev1Box {
             frame_count: 1,
             depth: 0x0018,
             hvcc: HvcCBox::default(),
+            sinf: None,
+            unknown: UnknownBoxes::new(),
         }
     }
 }
@@ -46,15 +55,26 @@ impl Hev1Box {
             frame_count: 1,
             depth: 0x0018,
             hvcc: HvcCBox::new(),
+            sinf: None,
+            unknown: UnknownBoxes::new(),
         }
     }
 
     pub fn get_type(&self) -> BoxType {
-        BoxType::Hev1Box
+        if self.sinf.is_some() {
+            BoxType::EncvBox
+        } else {
+            BoxType::Hev1Box
+        }
     }
 
     pub fn get_size(&self) -> u64 {
-        HEADER_SIZE + 8 + 70 + self.hvcc.box_size()
+        HEADER_SIZE
+            + 8
+            + 70
+            + self.hvcc.box_size()
+            + self.sinf.as_ref().map_or(0, |x| x.box_size())
+            + unknown_boxes_size(&self.unknown)
     }
 }
 
@@ -80,30 +100,49 @@ impl Mp4Box for Hev1Box {
 
 impl BlockReader for Hev1Box {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        reader.get_u32(); // reserved
-        reader.get_u16(); // reserved
+        reader.try_get_u32()?; // reserved
+        reader.try_get_u16()?; // reserved
 
-        let data_reference_index = reader.get_u16();
+        let data_reference_index = reader.try_get_u16()?;
 
-        reader.get_u32(); // pre-defined, reserved
-        reader.get_u64(); // pre-defined
-        reader.get_u32(); // pre-defined
+        reader.try_get_u32()?; // pre-defined, reserved
+        reader.try_get_u64()?; // pre-defined
+        reader.try_get_u32()?; // pre-defined
 
-        let width = reader.get_u16();
-        let height = reader.get_u16();
+        let width = reader.try_get_u16()?;
+        let height = reader.try_get_u16()?;
 
-        let horizresolution = FixedPointU16::new_raw(reader.get_u32());
-        let vertresolution = FixedPointU16::new_raw(reader.get_u32());
+        let horizresolution = FixedPointU16::new_raw(reader.try_get_u32()?);
+        let vertresolution = FixedPointU16::new_raw(reader.try_get_u32()?);
 
-        reader.get_u32(); // reserved
+        reader.try_get_u32()?; // reserved
 
-        let frame_count = reader.get_u16();
+        let frame_count = reader.try_get_u16()?;
 
         reader.skip(32); // compressorname
 
-        let depth = reader.get_u16();
+        let depth = reader.try_get_u16()?;
+
+        reader.try_get_i16()?; // pre-defined
+
+        let mut hvcc = None;
+        let mut sinf = None;
+        let mut unknown = UnknownBoxes::new();
+
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
 
-        reader.get_i16(); // pre-defined
+            match bx.kind {
+                BoxType::HvcCBox => hvcc = Some(bx.read()?),
+                BoxType::SinfBox => sinf = Some(bx.read()?),
+                _ => unknown.push_at(position, bx.read_raw()),
+            }
+        }
+        let Some(hvcc) = hvcc else {
+            return Err(Error::BoxNotFound(BoxType::HvcCBox));
+        };
 
         Ok(Hev1Box {
             data_reference_index,
@@ -113,7 +152,9 @@ impl BlockReader for Hev1Box {
             vertresolution,
             frame_count,
             depth,
-            hvcc: reader.find_box::<HvcCBox>()?,
+            hvcc,
+            sinf,
+            unknown,
         })
     }
 
@@ -125,7 +166,7 @@ impl BlockReader for Hev1Box {
 impl<W: Write> WriteBox<&mut W> for Hev1Box {
     fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
         let size = self.box_size();
-        BoxHeader::new(Self::TYPE, size).write(writer)?;
+        BoxHeader::new(self.get_type(), size).write(writer)?;
 
         writer.write_u32::<BigEndian>(0)?; // reserved
         writer.write_u16::<BigEndian>(0)?; // reserved
@@ -145,7 +186,14 @@ impl<W: Write> WriteBox<&mut W> for Hev1Box {
         writer.write_u16::<BigEndian>(self.depth)?;
         writer.write_i16::<BigEndian>(-1)?; // pre-defined
 
-        self.hvcc.write_box(writer)?;
+        let mut children = self.unknown.writer(writer);
+
+        children.write(&self.hvcc)?;
+
+        if let Some(ref sinf) = self.sinf {
+            children.write(sinf)?;
+        }
+        children.finish()?;
 
         Ok(size)
     }
@@ -225,7 +273,7 @@ impl Mp4Box for HvcCBox {
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
 pub struct HvcCArrayNalu {
     pub size: u16,
-    pub data: Vec<u8>,
+    pub data: Bytes,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
@@ -237,30 +285,30 @@ pub struct HvcCArray {
 
 impl BlockReader for HvcCBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let configuration_version = reader.get_u8();
-        let params = reader.get_u8();
+        let configuration_version = reader.try_get_u8()?;
+        let params = reader.try_get_u8()?;
         let general_profile_space = params & 0b11000000 >> 6;
         let general_tier_flag = (params & 0b00100000 >> 5) > 0;
         let general_profile_idc = params & 0b00011111;
 
-        let general_profile_compatibility_flags = reader.get_u32();
-        let general_constraint_indicator_flag = reader.get_u48();
+        let general_profile_compatibility_flags = reader.try_get_u32()?;
+        let general_constraint_indicator_flag = reader.try_get_u48()?;
 
-        let general_level_idc = reader.get_u8();
-        let min_spatial_segmentation_idc = reader.get_u16() & 0x0FFF;
-        let parallelism_type = reader.get_u8() & 0b11;
-        let chroma_format_idc = reader.get_u8() & 0b11;
-        let bit_depth_luma_minus8 = reader.get_u8() & 0b111;
-        let bit_depth_chroma_minus8 = reader.get_u8() & 0b111;
-        let avg_frame_rate = reader.get_u16();
+        let general_level_idc = reader.try_get_u8()?;
+        let min_spatial_segmentation_idc = reader.try_get_u16()? & 0x0FFF;
+        let parallelism_type = reader.try_get_u8()? & 0b11;
+        let chroma_format_idc = reader.try_get_u8()? & 0b11;
+        let bit_depth_luma_minus8 = reader.try_get_u8()? & 0b111;
+        let bit_depth_chroma_minus8 = reader.try_get_u8()? & 0b111;
+        let avg_frame_rate = reader.try_get_u16()?;
 
-        let params = reader.get_u8();
+        let params = reader.try_get_u8()?;
         let constant_frame_rate = params & 0b11000000 >> 6;
         let num_temporal_layers = params & 0b00111000 >> 3;
         let temporal_id_nested = (params & 0b00000100 >> 2) > 0;
         let length_size_minus_one = params & 0b000011;
 
-        let num_of_arrays = reader.get_u8();
+        let num_of_arrays = reader.try_get_u8()?;
 
         if reader.remaining() < num_of_arrays as usize * 3 {
             return Err(Error::InvalidData(""));
@@ -269,8 +317,8 @@ impl BlockReader for HvcCBox {
         let mut arrays = Vec::with_capacity(num_of_arrays as _);
 
         for _ in 0..num_of_arrays {
-            let params = reader.get_u8();
-            let num_nalus = reader.get_u16();
+            let params = reader.try_get_u8()?;
+            let num_nalus = reader.try_get_u16()?;
 
             if reader.remaining() < num_nalus as usize * 2 {
                 return Err(Error::InvalidData(""));
@@ -279,11 +327,11 @@ impl BlockReader for HvcCBox {
             let mut nalus = Vec::with_capacity(num_nalus as usize);
 
             for _ in 0..num_nalus {
-                let size = reader.get_u16();
+                let size = reader.try_get_u16()?;
 
                 nalus.push(HvcCArrayNalu {
                     size,
-                    data: reader.collect(size as _)?,
+                    data: reader.collect_bytes(size as _)?,
                 })
             }
 
@@ -365,7 +413,7 @@ impl<W: Write> WriteBox<&mut W> for HvcCBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -384,6 +432,8 @@ mod tests {
                 configuration_version: 1,
                 ..Default::default()
             },
+            sinf: None,
+            unknown: UnknownBoxes::new(),
         };
         let mut buf = Vec::new();
         src_box.write_box(&mut buf).unwrap();
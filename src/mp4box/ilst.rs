@@ -1,5 +1,4 @@
 use std::borrow::Cow;
-use std::collections::HashMap;
 
 use byteorder::ByteOrder;
 use serde::Serialize;
@@ -7,9 +6,128 @@ use serde::Serialize;
 use crate::mp4box::data::DataBox;
 use crate::mp4box::*;
 
+const MEAN: u32 = u32::from_be_bytes(*b"mean");
+const NAME: u32 = u32::from_be_bytes(*b"name");
+
+/// iTunes-style metadata item list.
+///
+/// Items are kept in file order. Items that cannot be parsed are preserved as raw
+/// payload and written back unchanged.
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
 pub struct IlstBox {
-    pub items: HashMap<MetadataKey, DataBox>,
+    pub items: Vec<IlstItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IlstItem {
+    pub key: MetadataKey,
+    pub values: Vec<DataBox>,
+
+    /// Undecoded item payload, written back as is.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw: Option<Bytes>,
+}
+
+impl IlstItem {
+    pub fn new(key: MetadataKey, value: DataBox) -> Self {
+        Self {
+            key,
+            values: vec![value],
+            raw: None,
+        }
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE;
+        if let Some(ref raw) = self.raw {
+            return size + raw.len() as u64;
+        }
+
+        if let MetadataKey::Freeform { mean, name } = &self.key {
+            size += HEADER_SIZE + HEADER_EXT_SIZE + mean.len() as u64;
+            size += HEADER_SIZE + HEADER_EXT_SIZE + name.len() as u64;
+        }
+
+        size + self.values.iter().map(|v| v.box_size()).sum::<u64>()
+    }
+
+    fn read(kind: BoxType, data: Bytes) -> Self {
+        let key = MetadataKey::from_fourcc(kind.into());
+
+        match Self::read_children(key.clone(), &mut data.clone()) {
+            Ok(item) => item,
+            Err(_) => IlstItem {
+                key,
+                values: Vec::new(),
+                raw: Some(data),
+            },
+        }
+    }
+
+    fn read_children<'a>(key: MetadataKey, reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        let mut values = Vec::new();
+        let mut mean = None;
+        let mut name = None;
+
+        while let Some(mut bx) = reader.get_box()? {
+            match bx.kind {
+                BoxType::DataBox => values.push(bx.read()?),
+
+                kind if u32::from(kind) == MEAN || u32::from(kind) == NAME => {
+                    read_box_header_ext(&mut bx.inner)?;
+                    let value = String::from_utf8(bx.inner.collect_remaining())
+                        .map_err(|_| Error::InvalidData("invalid freeform item string"))?;
+
+                    if u32::from(kind) == MEAN {
+                        mean = Some(value);
+                    } else {
+                        name = Some(value);
+                    }
+                }
+
+                _ => return Err(Error::InvalidData("unexpected box in ilst item")),
+            }
+        }
+
+        let key = match (key, mean, name) {
+            (MetadataKey::Other(fourcc), Some(mean), Some(name)) if &fourcc.value == b"----" => {
+                MetadataKey::Freeform { mean, name }
+            }
+            (key, None, None) => key,
+            _ => return Err(Error::InvalidData("unexpected mean/name in ilst item")),
+        };
+
+        Ok(IlstItem {
+            key,
+            values,
+            raw: None,
+        })
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<u64, Error> {
+        let size = self.get_size();
+        BoxHeader::new(u32::from(self.key.fourcc()).into(), size).write(writer)?;
+
+        if let Some(ref raw) = self.raw {
+            writer.write_all(raw)?;
+            return Ok(size);
+        }
+
+        if let MetadataKey::Freeform { mean, name } = &self.key {
+            for (kind, value) in [(MEAN, mean), (NAME, name)] {
+                let size = HEADER_SIZE + HEADER_EXT_SIZE + value.len() as u64;
+                BoxHeader::new(kind.into(), size).write(writer)?;
+                write_box_header_ext(writer, 0, 0)?;
+                writer.write_all(value.as_bytes())?;
+            }
+        }
+
+        for value in &self.values {
+            value.write_box(writer)?;
+        }
+
+        Ok(size)
+    }
 }
 
 impl IlstBox {
@@ -18,12 +136,48 @@ impl IlstBox {
     }
 
     pub fn get_size(&self) -> u64 {
-        let mut size = HEADER_SIZE;
-        let ilst_item_header_size = HEADER_SIZE;
-        for item in self.items.values() {
-            size += ilst_item_header_size + item.get_size();
+        HEADER_SIZE + self.items.iter().map(|item| item.get_size()).sum::<u64>()
+    }
+
+    pub fn get(&self, key: &MetadataKey) -> Option<&IlstItem> {
+        self.items.iter().find(|item| &item.key == key)
+    }
+
+    /// First value stored for `key`.
+    pub fn value(&self, key: &MetadataKey) -> Option<&DataBox> {
+        self.get(key).and_then(|item| item.values.first())
+    }
+
+    /// Replaces all values of `key` with `value`.
+    pub fn set(&mut self, key: MetadataKey, value: DataBox) {
+        match self.items.iter_mut().find(|item| item.key == key) {
+            Some(item) => {
+                item.values = vec![value];
+                item.raw = None;
+            }
+            None => self.items.push(IlstItem::new(key, value)),
+        }
+    }
+
+    /// Appends another value to `key`.
+    pub fn add(&mut self, key: MetadataKey, value: DataBox) {
+        match self
+            .items
+            .iter_mut()
+            .find(|item| item.key == key && item.raw.is_none())
+        {
+            Some(item) => item.values.push(value),
+            None => self.items.push(IlstItem::new(key, value)),
         }
-        size
+    }
+
+    pub fn remove(&mut self, key: &MetadataKey) -> Option<IlstItem> {
+        let idx = self.items.iter().position(|item| &item.key == key)?;
+        Some(self.items.remove(idx))
+    }
+
+    fn text(&self, key: MetadataKey) -> Option<Cow<'_, str>> {
+        self.value(&key).map(item_to_str)
     }
 }
 
@@ -46,38 +200,12 @@ impl Mp4Box for IlstBox {
 
 impl BlockReader for IlstBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let mut items = HashMap::new();
+        let mut items = Vec::new();
 
         while let Some(mut bx) = reader.get_box()? {
-            match bx.kind {
-                BoxType::NameBox => {
-                    if let Some(title) = bx.inner.try_find_box::<DataBox>()? {
-                        items.insert(MetadataKey::Title, title);
-                    }
-                }
-
-                BoxType::DayBox => {
-                    if let Some(day) = bx.inner.try_find_box::<DataBox>()? {
-                        items.insert(MetadataKey::Year, day);
-                    }
-                }
-
-                BoxType::CovrBox => {
-                    if let Some(cover) = bx.inner.try_find_box::<DataBox>()? {
-                        items.insert(MetadataKey::Poster, cover);
-                    }
-                }
-
-                BoxType::DescBox => {
-                    if let Some(summary) = bx.inner.try_find_box::<DataBox>()? {
-                        items.insert(MetadataKey::Summary, summary);
-                    }
-                }
-
-                _ => continue,
-            }
+            items.push(IlstItem::read(bx.kind, bx.inner.collect_remaining_bytes()));
         }
-        // dbg!(&items);
+
         Ok(IlstBox { items })
     }
 
@@ -91,38 +219,86 @@ impl<W: Write> WriteBox<&mut W> for IlstBox {
         let size = self.box_size();
         BoxHeader::new(Self::TYPE, size).write(writer)?;
 
-        for (key, value) in &self.items {
-            let name = match key {
-                MetadataKey::Title => BoxType::NameBox,
-                MetadataKey::Year => BoxType::DayBox,
-                MetadataKey::Poster => BoxType::CovrBox,
-                MetadataKey::Summary => BoxType::DescBox,
-            };
-
-            let size = HEADER_SIZE + value.box_size(); // Size of IlstItem + DataBox
-
-            BoxHeader::new(name, size).write(writer)?;
-            value.write_box(writer)?;
+        for item in &self.items {
+            item.write(writer)?;
         }
+
         Ok(size)
     }
 }
 
 impl<'a> Metadata<'a> for IlstBox {
     fn title(&self) -> Option<Cow<'_, str>> {
-        self.items.get(&MetadataKey::Title).map(item_to_str)
+        self.text(MetadataKey::Title)
     }
 
     fn year(&self) -> Option<u32> {
-        self.items.get(&MetadataKey::Year).and_then(item_to_u32)
+        self.value(&MetadataKey::Year).and_then(item_to_u32)
     }
 
     fn poster(&self) -> Option<&[u8]> {
-        self.items.get(&MetadataKey::Poster).map(item_to_bytes)
+        self.value(&MetadataKey::Poster).map(item_to_bytes)
     }
 
     fn summary(&self) -> Option<Cow<'_, str>> {
-        self.items.get(&MetadataKey::Summary).map(item_to_str)
+        self.text(MetadataKey::Summary)
+    }
+
+    fn artist(&self) -> Option<Cow<'_, str>> {
+        self.text(MetadataKey::Artist)
+    }
+
+    fn album_artist(&self) -> Option<Cow<'_, str>> {
+        self.text(MetadataKey::AlbumArtist)
+    }
+
+    fn album(&self) -> Option<Cow<'_, str>> {
+        self.text(MetadataKey::Album)
+    }
+
+    fn genre(&self) -> Option<Cow<'_, str>> {
+        self.text(MetadataKey::Genre)
+    }
+
+    fn comment(&self) -> Option<Cow<'_, str>> {
+        self.text(MetadataKey::Comment)
+    }
+
+    fn composer(&self) -> Option<Cow<'_, str>> {
+        self.text(MetadataKey::Composer)
+    }
+
+    fn copyright(&self) -> Option<Cow<'_, str>> {
+        self.text(MetadataKey::Copyright)
+    }
+
+    fn encoder(&self) -> Option<Cow<'_, str>> {
+        self.text(MetadataKey::Encoder)
+    }
+
+    fn track_number(&self) -> Option<(u16, u16)> {
+        self.value(&MetadataKey::TrackNumber)
+            .and_then(item_to_index)
+    }
+
+    fn disk_number(&self) -> Option<(u16, u16)> {
+        self.value(&MetadataKey::DiskNumber).and_then(item_to_index)
+    }
+
+    fn location(&self) -> Option<Cow<'_, str>> {
+        self.text(MetadataKey::Location)
+    }
+
+    fn rating(&self) -> Option<u8> {
+        self.value(&MetadataKey::Rating)
+            .and_then(|item| item.data.first().copied())
+    }
+
+    fn freeform(&self, mean: &str, name: &str) -> Option<Cow<'_, str>> {
+        self.text(MetadataKey::Freeform {
+            mean: mean.to_string(),
+            name: name.to_string(),
+        })
     }
 }
 
@@ -142,7 +318,19 @@ fn item_to_u32(item: &DataBox) -> Option<u32> {
     }
 }
 
-#[cfg(test)]
+/// `trkn`/`disk` payload: reserved u16, index u16, total u16 and optional padding.
+fn item_to_index(item: &DataBox) -> Option<(u16, u16)> {
+    if item.data.len() < 6 {
+        return None;
+    }
+
+    Some((
+        BigEndian::read_u16(&item.data[2..4]),
+        BigEndian::read_u16(&item.data[4..6]),
+    ))
+}
+
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -151,17 +339,16 @@ mod tests {
     async fn test_ilst() {
         let src_year = DataBox {
             data_type: DataType::Text,
-            data: b"test_year".to_vec(),
+            data: b"test_year".to_vec().into(),
         };
 
         let src_box = IlstBox {
-            items: [
-                (MetadataKey::Title, DataBox::default()),
-                (MetadataKey::Year, src_year),
-                (MetadataKey::Poster, DataBox::default()),
-                (MetadataKey::Summary, DataBox::default()),
-            ]
-            .into(),
+            items: vec![
+                IlstItem::new(MetadataKey::Title, DataBox::default()),
+                IlstItem::new(MetadataKey::Year, src_year),
+                IlstItem::new(MetadataKey::Poster, DataBox::default()),
+                IlstItem::new(MetadataKey::Summary, DataBox::default()),
+            ],
         };
         let mut buf = Vec::new();
         src_box.write_box(&mut buf).unwrap();
@@ -191,4 +378,71 @@ mod tests {
         let dst_box = IlstBox::read_block(&mut reader).unwrap();
         assert_eq!(src_box, dst_box);
     }
+
+    #[tokio::test]
+    async fn test_ilst_tags() {
+        let text = |s: &str| DataBox {
+            data_type: DataType::Text,
+            data: s.as_bytes().to_vec().into(),
+        };
+
+        let mut src_box = IlstBox::default();
+        src_box.set(MetadataKey::Artist, text("artist"));
+        src_box.set(MetadataKey::Encoder, text("flowly"));
+        src_box.set(MetadataKey::Location, text("+48.8577+002.2950/"));
+        src_box.set(
+            MetadataKey::TrackNumber,
+            DataBox {
+                data_type: DataType::Binary,
+                data: vec![0, 0, 0, 3, 0, 12, 0, 0].into(),
+            },
+        );
+        src_box.set(
+            MetadataKey::Rating,
+            DataBox {
+                data_type: DataType::TempoCpil,
+                data: vec![1].into(),
+            },
+        );
+        src_box.set(
+            MetadataKey::Freeform {
+                mean: "com.apple.iTunes".to_string(),
+                name: "iTunNORM".to_string(),
+            },
+            text("norm"),
+        );
+        src_box.add(MetadataKey::Genre, text("Rock"));
+        src_box.add(MetadataKey::Genre, text("Pop"));
+
+        // an item with a child other than data is kept undecoded
+        src_box.items.push(IlstItem {
+            key: MetadataKey::Other(str::parse("xxxx").unwrap()),
+            values: Vec::new(),
+            raw: Some(Bytes::from_static(&[0, 0, 0, 9, b'f', b'r', b'e', b'e', 7])),
+        });
+
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.kind, BoxType::IlstBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = IlstBox::read_block(&mut reader).unwrap();
+        assert_eq!(src_box, dst_box);
+
+        assert_eq!(dst_box.artist().as_deref(), Some("artist"));
+        assert_eq!(dst_box.encoder().as_deref(), Some("flowly"));
+        assert_eq!(dst_box.location().as_deref(), Some("+48.8577+002.2950/"));
+        assert_eq!(dst_box.track_number(), Some((3, 12)));
+        assert_eq!(dst_box.disk_number(), None);
+        assert_eq!(dst_box.rating(), Some(1));
+        assert_eq!(
+            dst_box.freeform("com.apple.iTunes", "iTunNORM").as_deref(),
+            Some("norm")
+        );
+        assert_eq!(dst_box.get(&MetadataKey::Genre).unwrap().values.len(), 2);
+    }
 }
@@ -0,0 +1,160 @@
+use byteorder::{BigEndian, WriteBytesExt};
+use serde::Serialize;
+use std::io::Write;
+
+use crate::mp4box::meta::MDTA;
+use crate::mp4box::*;
+
+/// QuickTime metadata key table of a `mdta` meta box.
+///
+/// Items of the accompanying `ilst` are typed by the 1-based index into `entries`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct KeysBox {
+    pub version: u8,
+    pub flags: u32,
+    pub entries: Vec<KeyEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyEntry {
+    pub namespace: FourCC,
+    pub name: String,
+}
+
+impl KeysBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::KeysBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE
+            + HEADER_EXT_SIZE
+            + 4
+            + self
+                .entries
+                .iter()
+                .map(|e| 8 + e.name.len() as u64)
+                .sum::<u64>()
+    }
+
+    /// 1-based index of the key in the `mdta` namespace.
+    pub fn index_of(&self, name: &str) -> Option<u32> {
+        self.entries
+            .iter()
+            .position(|e| e.namespace == MDTA && e.name == name)
+            .map(|idx| idx as u32 + 1)
+    }
+
+    pub fn get(&self, index: u32) -> Option<&KeyEntry> {
+        self.entries.get(index.checked_sub(1)? as usize)
+    }
+}
+
+impl Mp4Box for KeysBox {
+    const TYPE: BoxType = BoxType::KeysBox;
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String, Error> {
+        let s = format!("entry_count={}", self.entries.len());
+        Ok(s)
+    }
+}
+
+impl BlockReader for KeysBox {
+    fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        let (version, flags) = read_box_header_ext(reader)?;
+
+        let entry_count = reader.try_get_u32()?;
+        if entry_count as usize > reader.remaining() / 8 {
+            return Err(Error::InvalidData(
+                "keys entry_count indicates more entries than could fit in the box",
+            ));
+        }
+
+        let mut entries = Vec::with_capacity(entry_count as usize);
+        for _ in 0..entry_count {
+            let size = reader.try_get_u32()? as usize;
+            if size < 8 || size - 4 > reader.remaining() {
+                return Err(Error::InvalidData("invalid keys entry size"));
+            }
+
+            let namespace = reader.try_get_u32()?.into();
+            let name = String::from_utf8(reader.collect(size - 8)?)
+                .map_err(|_| Error::InvalidData("keys entry is not valid UTF-8"))?;
+
+            entries.push(KeyEntry { namespace, name });
+        }
+
+        Ok(KeysBox {
+            version,
+            flags,
+            entries,
+        })
+    }
+
+    fn size_hint() -> usize {
+        8
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for KeysBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+        let size = self.box_size();
+        BoxHeader::new(Self::TYPE, size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        writer.write_u32::<BigEndian>(self.entries.len() as u32)?;
+        for entry in &self.entries {
+            writer.write_u32::<BigEndian>(8 + entry.name.len() as u32)?;
+            writer.write_u32::<BigEndian>(entry.namespace.into())?;
+            writer.write_all(entry.name.as_bytes())?;
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+
+    #[tokio::test]
+    async fn test_keys() {
+        let src_box = KeysBox {
+            version: 0,
+            flags: 0,
+            entries: vec![
+                KeyEntry {
+                    namespace: MDTA,
+                    name: "com.apple.quicktime.make".to_string(),
+                },
+                KeyEntry {
+                    namespace: MDTA,
+                    name: "com.apple.quicktime.model".to_string(),
+                },
+            ],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.kind, BoxType::KeysBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = KeysBox::read_block(&mut reader).unwrap();
+        assert_eq!(src_box, dst_box);
+        assert_eq!(dst_box.index_of("com.apple.quicktime.model"), Some(2));
+        assert_eq!(dst_box.get(0), None);
+    }
+}
@@ -70,27 +70,27 @@ impl Mp4Box for MdhdBox {
 
 impl BlockReader for MdhdBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
         let (creation_time, modification_time, timescale, duration) = if version == 1 {
             (
-                reader.get_u64(),
-                reader.get_u64(),
-                reader.get_u32(),
-                reader.get_u64(),
+                reader.try_get_u64()?,
+                reader.try_get_u64()?,
+                reader.try_get_u32()?,
+                reader.try_get_u64()?,
             )
         } else if version == 0 {
             (
-                reader.get_u32() as u64,
-                reader.get_u32() as u64,
-                reader.get_u32(),
-                reader.get_u32() as u64,
+                reader.try_get_u32()? as u64,
+                reader.try_get_u32()? as u64,
+                reader.try_get_u32()?,
+                reader.try_get_u32()? as u64,
             )
         } else {
             return Err(Error::InvalidData("version must be 0 or 1"));
         };
 
-        let language_code = reader.get_u16();
+        let language_code = reader.try_get_u16()?;
         let language = language_string(language_code);
 
         Ok(MdhdBox {
@@ -164,7 +164,6 @@ fn language_code(language: &str) -> u16 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::mp4box::BoxHeader;
 
     fn test_language_code(lang: &str) {
         let code = language_code(lang);
@@ -179,6 +178,7 @@ mod tests {
         test_language_code("kor");
     }
 
+    #[cfg(feature = "tokio")]
     #[tokio::test]
     async fn test_mdhd32() {
         let src_box = MdhdBox {
@@ -203,6 +203,7 @@ mod tests {
         assert_eq!(src_box, dst_box);
     }
 
+    #[cfg(feature = "tokio")]
     #[tokio::test]
     async fn test_mdhd64() {
         let src_box = MdhdBox {
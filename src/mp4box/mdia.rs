@@ -9,6 +9,9 @@ pub struct MdiaBox {
     pub mdhd: MdhdBox,
     pub hdlr: HdlrBox,
     pub minf: MinfBox,
+
+    #[serde(skip)]
+    pub unknown: UnknownBoxes,
 }
 
 impl MdiaBox {
@@ -17,7 +20,11 @@ impl MdiaBox {
     }
 
     pub fn get_size(&self) -> u64 {
-        HEADER_SIZE + self.mdhd.box_size() + self.hdlr.box_size() + self.minf.box_size()
+        HEADER_SIZE
+            + self.mdhd.box_size()
+            + self.hdlr.box_size()
+            + self.minf.box_size()
+            + unknown_boxes_size(&self.unknown)
     }
 }
 
@@ -40,8 +47,30 @@ impl Mp4Box for MdiaBox {
 
 impl BlockReader for MdiaBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (mdhd, hdlr, minf) = reader.find_box3()?;
-        Ok(MdiaBox { mdhd, hdlr, minf })
+        let mut mdhd = None;
+        let mut hdlr = None;
+        let mut minf = None;
+        let mut unknown = UnknownBoxes::new();
+
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
+
+            match bx.kind {
+                BoxType::MdhdBox => mdhd = Some(bx.read()?),
+                BoxType::HdlrBox => hdlr = Some(bx.read()?),
+                BoxType::MinfBox => minf = Some(bx.read()?),
+                _ => unknown.push_at(position, bx.read_raw()),
+            }
+        }
+
+        Ok(MdiaBox {
+            mdhd: mdhd.ok_or(Error::BoxNotFound(BoxType::MdhdBox))?,
+            hdlr: hdlr.ok_or(Error::BoxNotFound(BoxType::HdlrBox))?,
+            minf: minf.ok_or(Error::BoxNotFound(BoxType::MinfBox))?,
+            unknown,
+        })
     }
 
     fn size_hint() -> usize {
@@ -54,9 +83,12 @@ impl<W: Write> WriteBox<&mut W> for MdiaBox {
         let size = self.box_size();
         BoxHeader::new(Self::TYPE, size).write(writer)?;
 
-        self.mdhd.write_box(writer)?;
-        self.hdlr.write_box(writer)?;
-        self.minf.write_box(writer)?;
+        let mut children = self.unknown.writer(writer);
+
+        children.write(&self.mdhd)?;
+        children.write(&self.hdlr)?;
+        children.write(&self.minf)?;
+        children.finish()?;
 
         Ok(size)
     }
@@ -47,12 +47,12 @@ impl Mp4Box for MehdBox {
 
 impl BlockReader for MehdBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
         let fragment_duration = if version == 1 {
-            reader.get_u64()
+            reader.try_get_u64()?
         } else if version == 0 {
-            reader.get_u32() as u64
+            reader.try_get_u32()? as u64
         } else {
             return Err(Error::InvalidData("version must be 0 or 1"));
         };
@@ -88,7 +88,7 @@ impl<W: Write> WriteBox<&mut W> for MehdBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -1,9 +1,11 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
 
 use serde::Serialize;
 
+use crate::mp4box::data::DataBox;
 use crate::mp4box::hdlr::HdlrBox;
 use crate::mp4box::ilst::IlstBox;
+use crate::mp4box::keys::{KeyEntry, KeysBox};
 use crate::mp4box::*;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
@@ -13,6 +15,21 @@ pub enum MetaBox {
     Mdir {
         #[serde(skip_serializing_if = "Option::is_none")]
         ilst: Option<IlstBox>,
+
+        /// Other children, such as `free` padding.
+        #[serde(skip)]
+        unknown: UnknownBoxes,
+    },
+
+    /// QuickTime metadata, `ilst` items are typed by their index into `keys`.
+    Mdta {
+        keys: KeysBox,
+
+        #[serde(skip_serializing_if = "Option::is_none")]
+        ilst: Option<IlstBox>,
+
+        #[serde(skip)]
+        unknown: UnknownBoxes,
     },
 
     #[serde(skip)]
@@ -21,14 +38,28 @@ pub enum MetaBox {
         hdlr: HdlrBox,
 
         #[serde(skip)]
-        data: Vec<(BoxType, Vec<u8>)>,
+        data: Vec<(BoxType, Bytes)>,
     },
 }
 
 const MDIR: FourCC = FourCC { value: *b"mdir" };
-const MDTA: FourCC = FourCC { value: *b"mdta" };
+pub(crate) const MDTA: FourCC = FourCC { value: *b"mdta" };
 
 impl MetaBox {
+    pub const KEY_TITLE: &'static str = "com.apple.quicktime.title";
+    pub const KEY_DESCRIPTION: &'static str = "com.apple.quicktime.description";
+    pub const KEY_ARTIST: &'static str = "com.apple.quicktime.artist";
+    pub const KEY_ALBUM: &'static str = "com.apple.quicktime.album";
+    pub const KEY_GENRE: &'static str = "com.apple.quicktime.genre";
+    pub const KEY_COMMENT: &'static str = "com.apple.quicktime.comment";
+    pub const KEY_COPYRIGHT: &'static str = "com.apple.quicktime.copyright";
+    pub const KEY_ARTWORK: &'static str = "com.apple.quicktime.artwork";
+    pub const KEY_CREATION_DATE: &'static str = "com.apple.quicktime.creationdate";
+    pub const KEY_LOCATION: &'static str = "com.apple.quicktime.location.ISO6709";
+    pub const KEY_MAKE: &'static str = "com.apple.quicktime.make";
+    pub const KEY_MODEL: &'static str = "com.apple.quicktime.model";
+    pub const KEY_SOFTWARE: &'static str = "com.apple.quicktime.software";
+
     pub fn get_type(&self) -> BoxType {
         BoxType::MetaBox
     }
@@ -36,8 +67,19 @@ impl MetaBox {
     pub fn get_size(&self) -> u64 {
         let mut size = HEADER_SIZE + HEADER_EXT_SIZE;
         match self {
-            Self::Mdir { ilst } => {
-                size += HdlrBox::default().box_size();
+            Self::Mdir { ilst, unknown } => {
+                size += HdlrBox::default().box_size() + unknown_boxes_size(unknown);
+                if let Some(ilst) = ilst {
+                    size += ilst.box_size();
+                }
+            }
+            Self::Mdta {
+                keys,
+                ilst,
+                unknown,
+            } => {
+                size +=
+                    HdlrBox::default().box_size() + keys.box_size() + unknown_boxes_size(unknown);
                 if let Some(ilst) = ilst {
                     size += ilst.box_size();
                 }
@@ -52,6 +94,80 @@ impl MetaBox {
         }
         size
     }
+
+    /// First value of a `mdta` item, e.g. [`MetaBox::KEY_MAKE`].
+    pub fn mdta_value(&self, name: &str) -> Option<&DataBox> {
+        let Self::Mdta { keys, ilst, .. } = self else {
+            return None;
+        };
+
+        let index = keys.index_of(name)?;
+        ilst.as_ref()?.value(&mdta_item_key(index))
+    }
+
+    /// All `mdta` items as key name and value pairs, in file order.
+    pub fn mdta_items(&self) -> Vec<(&str, &DataBox)> {
+        let Self::Mdta {
+            keys,
+            ilst: Some(ilst),
+            ..
+        } = self
+        else {
+            return Vec::new();
+        };
+
+        ilst.items
+            .iter()
+            .filter_map(|item| {
+                let MetadataKey::Other(fourcc) = item.key else {
+                    return None;
+                };
+
+                let key = keys.get(u32::from(fourcc))?;
+                Some(item.values.iter().map(move |v| (key.name.as_str(), v)))
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Sets a `mdta` item, adding the key to the key table if needed.
+    ///
+    /// Does nothing unless this is a `mdta` meta box.
+    pub fn set_mdta_value(&mut self, name: &str, value: DataBox) {
+        let Self::Mdta { keys, ilst, .. } = self else {
+            return;
+        };
+
+        let index = keys.index_of(name).unwrap_or_else(|| {
+            keys.entries.push(KeyEntry {
+                namespace: MDTA,
+                name: name.to_string(),
+            });
+            keys.entries.len() as u32
+        });
+
+        ilst.get_or_insert_with(Default::default)
+            .set(mdta_item_key(index), value);
+    }
+
+    fn mdta_text(&self, name: &str) -> Option<Cow<'_, str>> {
+        self.mdta_value(name)
+            .map(|v| String::from_utf8_lossy(&v.data))
+    }
+}
+
+/// Children of a `mdir` or `mdta` meta box other than `hdlr`, `keys` and `ilst`.
+fn unknown_children(children: Vec<(usize, BoxType, Bytes)>) -> UnknownBoxes {
+    let mut unknown = UnknownBoxes::new();
+    for (position, kind, data) in children {
+        unknown.push_at(position, UnknownBox::Raw(kind, data));
+    }
+    unknown
+}
+
+/// `mdta` items are typed by the 1-based index of their key.
+fn mdta_item_key(index: u32) -> MetadataKey {
+    MetadataKey::Other(index.into())
 }
 
 impl Mp4Box for MetaBox {
@@ -68,6 +184,7 @@ impl Mp4Box for MetaBox {
     fn summary(&self) -> Result<String, Error> {
         let s = match self {
             Self::Mdir { .. } => "hdlr=ilst".to_string(),
+            Self::Mdta { keys, .. } => format!("hdlr=mdta key_count={}", keys.entries.len()),
             Self::Unknown { hdlr, data } => {
                 format!("hdlr={} data_len={}", hdlr.handler_type, data.len())
             }
@@ -87,30 +204,58 @@ impl Default for MetaBox {
 
 impl BlockReader for MetaBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let extended_header = reader.peek_u32();
-        if extended_header == 0 {
+        // ISO meta is a full box, QuickTime meta has no version and flags
+        if reader.remaining() >= 4 && reader.peek_u32() == 0 {
             reader.skip(4);
         }
 
-        let mut boxes = HashMap::new();
-        while let Some(mut bx) = reader.get_box()? {
-            boxes.insert(bx.kind, bx.inner.collect_remaining());
+        // the handler decides how the other children are read, and it may come last
+        let mut hdlr = None;
+        let mut children = Vec::new();
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
+
+            // slices of the parent buffer when reading from `Bytes`, so cover art isn't copied
+            let data = bx.inner.collect_remaining_bytes();
+            match bx.kind {
+                BoxType::HdlrBox if hdlr.is_none() => hdlr = Some(data),
+                kind => children.push((position, kind, data)),
+            }
         }
 
-        // find the hdlr box
-        let hdlr = HdlrBox::read_block(&mut boxes.remove(&BoxType::HdlrBox).unwrap().as_slice())?;
+        let mut hdlr = hdlr.ok_or(Error::BoxNotFound(BoxType::HdlrBox))?;
+        let hdlr = HdlrBox::read_block(&mut hdlr)?;
+
+        let mut take = |kind: BoxType| {
+            let idx = children.iter().position(|(_, k, _)| *k == kind)?;
+            Some(children.remove(idx).2)
+        };
 
         Ok(match hdlr.handler_type {
             MDIR => MetaBox::Mdir {
-                ilst: if let Some(inner) = boxes.remove(&BoxType::IlstBox) {
-                    Some(IlstBox::read_block(&mut inner.as_slice())?)
-                } else {
-                    None
+                ilst: take(BoxType::IlstBox)
+                    .map(|mut inner| IlstBox::read_block(&mut inner))
+                    .transpose()?,
+                unknown: unknown_children(children),
+            },
+            MDTA => MetaBox::Mdta {
+                keys: match take(BoxType::KeysBox) {
+                    Some(mut inner) => KeysBox::read_block(&mut inner)?,
+                    None => return Err(Error::BoxNotFound(BoxType::KeysBox)),
                 },
+                ilst: take(BoxType::IlstBox)
+                    .map(|mut inner| IlstBox::read_block(&mut inner))
+                    .transpose()?,
+                unknown: unknown_children(children),
             },
             _ => MetaBox::Unknown {
-                hdlr: hdlr.clone(),
-                data: boxes.into_iter().collect::<Vec<(BoxType, Vec<u8>)>>(),
+                hdlr,
+                data: children
+                    .into_iter()
+                    .map(|(_, kind, data)| (kind, data))
+                    .collect(),
             },
         })
     }
@@ -132,17 +277,37 @@ impl<W: Write> WriteBox<&mut W> for MetaBox {
                 handler_type: MDIR,
                 ..Default::default()
             },
+            Self::Mdta { .. } => HdlrBox {
+                handler_type: MDTA,
+                ..Default::default()
+            },
             Self::Unknown { hdlr, .. } => hdlr.clone(),
         };
-        hdlr.write_box(writer)?;
 
         match self {
-            Self::Mdir { ilst } => {
+            Self::Mdir { ilst, unknown } => {
+                let mut children = unknown.writer(writer);
+                children.write(&hdlr)?;
+                if let Some(ilst) = ilst {
+                    children.write(ilst)?;
+                }
+                children.finish()?;
+            }
+            Self::Mdta {
+                keys,
+                ilst,
+                unknown,
+            } => {
+                let mut children = unknown.writer(writer);
+                children.write(&hdlr)?;
+                children.write(keys)?;
                 if let Some(ilst) = ilst {
-                    ilst.write_box(writer)?;
+                    children.write(ilst)?;
                 }
+                children.finish()?;
             }
             Self::Unknown { data, .. } => {
+                hdlr.write_box(writer)?;
                 for (box_type, data) in data {
                     BoxHeader::new(*box_type, data.len() as u64 + HEADER_SIZE).write(writer)?;
                     writer.write_all(data)?;
@@ -153,14 +318,141 @@ impl<W: Write> WriteBox<&mut W> for MetaBox {
     }
 }
 
+impl<'a> Metadata<'a> for MetaBox {
+    fn title(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Self::Mdir { ilst, .. } => ilst.title(),
+            _ => self.mdta_text(Self::KEY_TITLE),
+        }
+    }
+
+    fn year(&self) -> Option<u32> {
+        match self {
+            Self::Mdir { ilst, .. } => ilst.year(),
+            _ => self
+                .mdta_text(Self::KEY_CREATION_DATE)
+                .and_then(|date| date.get(..4)?.parse().ok()),
+        }
+    }
+
+    fn poster(&self) -> Option<&[u8]> {
+        match self {
+            Self::Mdir { ilst, .. } => ilst.poster(),
+            _ => self.mdta_value(Self::KEY_ARTWORK).map(|v| v.data.as_ref()),
+        }
+    }
+
+    fn summary(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Self::Mdir { ilst, .. } => ilst.summary(),
+            _ => self.mdta_text(Self::KEY_DESCRIPTION),
+        }
+    }
+
+    fn artist(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Self::Mdir { ilst, .. } => ilst.artist(),
+            _ => self.mdta_text(Self::KEY_ARTIST),
+        }
+    }
+
+    fn album_artist(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Self::Mdir { ilst, .. } => ilst.album_artist(),
+            _ => None,
+        }
+    }
+
+    fn album(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Self::Mdir { ilst, .. } => ilst.album(),
+            _ => self.mdta_text(Self::KEY_ALBUM),
+        }
+    }
+
+    fn genre(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Self::Mdir { ilst, .. } => ilst.genre(),
+            _ => self.mdta_text(Self::KEY_GENRE),
+        }
+    }
+
+    fn comment(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Self::Mdir { ilst, .. } => ilst.comment(),
+            _ => self.mdta_text(Self::KEY_COMMENT),
+        }
+    }
+
+    fn composer(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Self::Mdir { ilst, .. } => ilst.composer(),
+            _ => None,
+        }
+    }
+
+    fn copyright(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Self::Mdir { ilst, .. } => ilst.copyright(),
+            _ => self.mdta_text(Self::KEY_COPYRIGHT),
+        }
+    }
+
+    fn encoder(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Self::Mdir { ilst, .. } => ilst.encoder(),
+            _ => self.mdta_text(Self::KEY_SOFTWARE),
+        }
+    }
+
+    fn track_number(&self) -> Option<(u16, u16)> {
+        match self {
+            Self::Mdir { ilst, .. } => ilst.track_number(),
+            _ => None,
+        }
+    }
+
+    fn disk_number(&self) -> Option<(u16, u16)> {
+        match self {
+            Self::Mdir { ilst, .. } => ilst.disk_number(),
+            _ => None,
+        }
+    }
+
+    fn location(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Self::Mdir { ilst, .. } => ilst.location(),
+            _ => self.mdta_text(Self::KEY_LOCATION),
+        }
+    }
+
+    fn rating(&self) -> Option<u8> {
+        match self {
+            Self::Mdir { ilst, .. } => ilst.rating(),
+            _ => None,
+        }
+    }
+
+    fn freeform(&self, mean: &str, name: &str) -> Option<Cow<'_, str>> {
+        match self {
+            Self::Mdir { ilst, .. } => ilst.freeform(mean, name),
+            _ if mean == "mdta" => self.mdta_text(name),
+            _ => None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::mp4box::BoxHeader;
 
+    #[cfg(feature = "tokio")]
     #[tokio::test]
     async fn test_meta_mdir_empty() {
-        let src_box = MetaBox::Mdir { ilst: None };
+        let src_box = MetaBox::Mdir {
+            ilst: None,
+            unknown: UnknownBoxes::new(),
+        };
 
         let mut buf = Vec::new();
         src_box.write_box(&mut buf).unwrap();
@@ -175,10 +467,22 @@ mod tests {
         assert_eq!(dst_box, src_box);
     }
 
+    #[test]
+    fn test_meta_without_hdlr() {
+        let mut buf = vec![0u8; 4];
+        buf.extend_from_slice(&[0, 0, 0, 8]);
+        buf.extend_from_slice(b"ilst");
+
+        let err = MetaBox::read_block(&mut buf.as_slice()).unwrap_err();
+        assert!(matches!(err, Error::BoxNotFound(BoxType::HdlrBox)));
+    }
+
+    #[cfg(feature = "tokio")]
     #[tokio::test]
     async fn test_meta_mdir() {
         let src_box = MetaBox::Mdir {
             ilst: Some(IlstBox::default()),
+            unknown: UnknownBoxes::new(),
         };
 
         let mut buf = Vec::new();
@@ -194,6 +498,7 @@ mod tests {
         assert_eq!(dst_box, src_box);
     }
 
+    #[cfg(feature = "tokio")]
     #[tokio::test]
     async fn test_meta_hdrl_non_first() {
         let data = b"\x00\x00\x00\x7fmeta\x00\x00\x00\x00\x00\x00\x00Qilst\x00\x00\x00I\xa9too\x00\x00\x00Adata\x00\x00\x00\x01\x00\x00\x00\x00TMPGEnc Video Mastering Works 7 Version 7.0.15.17\x00\x00\x00\"hdlr\x00\x00\x00\x00\x00\x00\x00\x00mdirappl\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
@@ -205,23 +510,34 @@ mod tests {
 
         let meta_box = MetaBox::read_block(&mut reader).unwrap();
 
-        // this contains \xa9too box in the ilst
-        // it designates the tool that created the file, but is not yet supported by this crate
+        // this contains \xa9too box in the ilst, the tool that created the file
+        let mut ilst = IlstBox::default();
+        ilst.set(
+            MetadataKey::Encoder,
+            DataBox {
+                data_type: DataType::Text,
+                data: b"TMPGEnc Video Mastering Works 7 Version 7.0.15.17"
+                    .to_vec()
+                    .into(),
+            },
+        );
         assert_eq!(
             meta_box,
             MetaBox::Mdir {
-                ilst: Some(IlstBox::default())
+                ilst: Some(ilst),
+                unknown: UnknownBoxes::new(),
             }
         );
     }
 
+    #[cfg(feature = "tokio")]
     #[tokio::test]
     async fn test_meta_unknown() {
         let src_hdlr = HdlrBox {
             handler_type: FourCC::from(*b"test"),
             ..Default::default()
         };
-        let src_data = (BoxType::UnknownBox(0x42494241), b"123".to_vec());
+        let src_data = (BoxType::UnknownBox(0x42494241), Bytes::from_static(b"123"));
         let src_box = MetaBox::Unknown {
             hdlr: src_hdlr,
             data: vec![src_data],
@@ -239,4 +555,118 @@ mod tests {
         let dst_box = MetaBox::read_block(&mut reader).unwrap();
         assert_eq!(dst_box, src_box);
     }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_meta_mdta() {
+        let text = |s: &str| DataBox {
+            data_type: DataType::Text,
+            data: s.as_bytes().to_vec().into(),
+        };
+
+        let mut src_box = MetaBox::Mdta {
+            keys: KeysBox::default(),
+            ilst: None,
+            unknown: UnknownBoxes::new(),
+        };
+        src_box.set_mdta_value(MetaBox::KEY_MAKE, text("Apple"));
+        src_box.set_mdta_value(MetaBox::KEY_MODEL, text("iPhone 15"));
+        src_box.set_mdta_value(MetaBox::KEY_LOCATION, text("+37.3349-122.0090+030.000/"));
+        src_box.set_mdta_value(MetaBox::KEY_CREATION_DATE, text("2024-05-01T10:00:00+0200"));
+        src_box.set_mdta_value(MetaBox::KEY_MODEL, text("iPhone 15 Pro"));
+
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.kind, BoxType::MetaBox);
+        assert_eq!(header.size, src_box.box_size());
+
+        let dst_box = MetaBox::read_block(&mut reader).unwrap();
+        assert_eq!(dst_box, src_box);
+
+        assert_eq!(dst_box.mdta_value(MetaBox::KEY_MAKE), Some(&text("Apple")));
+        assert_eq!(
+            dst_box.mdta_items().len(),
+            4,
+            "replacing a value must not add a key"
+        );
+        assert_eq!(dst_box.year(), Some(2024));
+        assert_eq!(
+            dst_box.location().as_deref(),
+            Some("+37.3349-122.0090+030.000/")
+        );
+        assert_eq!(
+            dst_box.freeform("mdta", MetaBox::KEY_MODEL).as_deref(),
+            Some("iPhone 15 Pro")
+        );
+        assert_eq!(dst_box.title(), None);
+    }
+
+    #[test]
+    fn test_meta_cover_zero_copy() {
+        use crate::mp4box::moov::MoovBox;
+        use crate::mp4box::udta::UdtaBox;
+
+        let mut ilst = IlstBox::default();
+        ilst.set(
+            MetadataKey::Poster,
+            DataBox {
+                data_type: DataType::Image,
+                data: vec![0xd8; 256].into(),
+            },
+        );
+        let src_box = MoovBox {
+            udta: Some(UdtaBox {
+                meta: Some(MetaBox::Mdir {
+                    ilst: Some(ilst),
+                    unknown: UnknownBoxes::new(),
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+
+        let buf = Bytes::from(buf);
+        let mut reader = buf.clone();
+        let mut bx = reader.get_box().unwrap().unwrap();
+        assert_eq!(bx.kind, BoxType::MoovBox);
+
+        let dst_box = MoovBox::read_block(&mut bx.inner).unwrap();
+        assert_eq!(dst_box, src_box);
+
+        let meta = dst_box.udta.unwrap().meta.unwrap();
+        let poster = meta.poster().unwrap();
+        assert_eq!(poster.len(), 256);
+        assert!(buf.as_ptr_range().contains(&poster.as_ptr()));
+    }
+
+    #[test]
+    fn test_meta_mdir_free() {
+        let mut unknown = UnknownBoxes::new();
+        unknown.push_at(
+            1,
+            UnknownBox::Raw(BoxType::FreeBox, Bytes::from_static(&[0; 16])),
+        );
+        let src_box = MetaBox::Mdir {
+            ilst: Some(IlstBox::default()),
+            unknown,
+        };
+
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let dst_box = MetaBox::read_block(&mut &buf[8..]).unwrap();
+        assert_eq!(dst_box, src_box);
+
+        let mut rewritten = Vec::new();
+        dst_box.write_box(&mut rewritten).unwrap();
+        assert_eq!(rewritten, buf);
+    }
 }
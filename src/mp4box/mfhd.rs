@@ -50,12 +50,12 @@ impl Mp4Box for MfhdBox {
 
 impl BlockReader for MfhdBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
         Ok(MfhdBox {
             version,
             flags,
-            sequence_number: reader.get_u32(),
+            sequence_number: reader.try_get_u32()?,
         })
     }
 
@@ -76,7 +76,7 @@ impl<W: Write> WriteBox<&mut W> for MfhdBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
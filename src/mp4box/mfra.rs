@@ -0,0 +1,144 @@
+use serde::Serialize;
+use std::io::Write;
+
+use crate::mp4box::*;
+use crate::mp4box::{mfro::MfroBox, tfra::TfraBox};
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct MfraBox {
+    #[serde(rename = "tfra")]
+    pub tfras: Vec<TfraBox>,
+    pub mfro: MfroBox,
+}
+
+impl MfraBox {
+    pub fn new(tfras: Vec<TfraBox>) -> Self {
+        let mut mfra = MfraBox {
+            tfras,
+            mfro: MfroBox::default(),
+        };
+        mfra.mfro.size = mfra.get_size() as u32;
+        mfra
+    }
+
+    pub fn get_type(&self) -> BoxType {
+        BoxType::MfraBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + self.mfro.box_size();
+        for tfra in self.tfras.iter() {
+            size += tfra.box_size();
+        }
+        size
+    }
+
+    pub fn tfra(&self, track_id: u32) -> Option<&TfraBox> {
+        self.tfras.iter().find(|x| x.track_id == track_id)
+    }
+}
+
+impl Mp4Box for MfraBox {
+    const TYPE: BoxType = BoxType::MfraBox;
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String, Error> {
+        let s = format!("tfras={}", self.tfras.len());
+        Ok(s)
+    }
+}
+
+impl BlockReader for MfraBox {
+    fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        let mut tfras = Vec::new();
+        let mut mfro = None;
+
+        while let Some(mut bx) = reader.get_box()? {
+            match bx.kind {
+                BoxType::TfraBox => {
+                    let idx = tfras.len();
+                    tfras.push(bx.read().map_err(|err| err.with_index(idx))?);
+                }
+
+                BoxType::MfroBox => {
+                    mfro = Some(bx.read()?);
+                }
+
+                _ => continue,
+            }
+        }
+
+        let Some(mfro) = mfro else {
+            return Err(Error::BoxNotFound(BoxType::MfroBox));
+        };
+
+        Ok(MfraBox { tfras, mfro })
+    }
+
+    fn size_hint() -> usize {
+        MfroBox::size_hint()
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for MfraBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+        let size = self.box_size();
+        BoxHeader::new(Self::TYPE, size).write(writer)?;
+
+        for tfra in self.tfras.iter() {
+            tfra.write_box(writer)?;
+        }
+        self.mfro.write_box(writer)?;
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::tfra::TfraEntry;
+    use crate::mp4box::BoxHeader;
+
+    #[tokio::test]
+    async fn test_mfra() {
+        let src_box = MfraBox::new(vec![
+            TfraBox {
+                track_id: 1,
+                entries: vec![TfraEntry {
+                    time: 0,
+                    moof_offset: 100,
+                    traf_number: 1,
+                    trun_number: 1,
+                    sample_number: 1,
+                }],
+                ..Default::default()
+            },
+            TfraBox {
+                track_id: 2,
+                ..Default::default()
+            },
+        ]);
+        assert_eq!(src_box.mfro.size as u64, src_box.box_size());
+
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.kind, BoxType::MfraBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = MfraBox::read_block(&mut reader).unwrap();
+        assert_eq!(src_box, dst_box);
+        assert_eq!(dst_box.tfra(1).unwrap().entries.len(), 1);
+    }
+}
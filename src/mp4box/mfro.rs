@@ -0,0 +1,97 @@
+use byteorder::{BigEndian, WriteBytesExt};
+use serde::Serialize;
+use std::io::Write;
+
+use crate::mp4box::*;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct MfroBox {
+    pub version: u8,
+    pub flags: u32,
+
+    /// Size of the enclosing `mfra` box, including this box.
+    pub size: u32,
+}
+
+impl MfroBox {
+    pub const SIZE: u64 = HEADER_SIZE + HEADER_EXT_SIZE + 4;
+
+    pub fn get_type(&self) -> BoxType {
+        BoxType::MfroBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        Self::SIZE
+    }
+}
+
+impl Mp4Box for MfroBox {
+    const TYPE: BoxType = BoxType::MfroBox;
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String, Error> {
+        let s = format!("size={}", self.size);
+        Ok(s)
+    }
+}
+
+impl BlockReader for MfroBox {
+    fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        let (version, flags) = read_box_header_ext(reader)?;
+
+        Ok(MfroBox {
+            version,
+            flags,
+            size: reader.try_get_u32()?,
+        })
+    }
+
+    fn size_hint() -> usize {
+        8
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for MfroBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+        let size = self.box_size();
+        BoxHeader::new(Self::TYPE, size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+        writer.write_u32::<BigEndian>(self.size)?;
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+
+    #[tokio::test]
+    async fn test_mfro() {
+        let src_box = MfroBox {
+            version: 0,
+            flags: 0,
+            size: 1234,
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.kind, BoxType::MfroBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = MfroBox::read_block(&mut reader).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+}
@@ -2,7 +2,9 @@ use serde::Serialize;
 use std::io::Write;
 
 use crate::mp4box::*;
-use crate::mp4box::{dinf::DinfBox, smhd::SmhdBox, stbl::StblBox, vmhd::VmhdBox};
+use crate::mp4box::{
+    dinf::DinfBox, nmhd::NmhdBox, smhd::SmhdBox, stbl::StblBox, sthd::SthdBox, vmhd::VmhdBox,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
 pub struct MinfBox {
@@ -12,8 +14,18 @@ pub struct MinfBox {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub smhd: Option<SmhdBox>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nmhd: Option<NmhdBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sthd: Option<SthdBox>,
+
     pub dinf: DinfBox,
     pub stbl: StblBox,
+
+    /// Other media headers such as `hmhd` or `gmhd` end up here as well.
+    #[serde(skip)]
+    pub unknown: UnknownBoxes,
 }
 
 impl MinfBox {
@@ -29,9 +41,15 @@ impl MinfBox {
         if let Some(ref smhd) = self.smhd {
             size += smhd.box_size();
         }
+        if let Some(ref nmhd) = self.nmhd {
+            size += nmhd.box_size();
+        }
+        if let Some(ref sthd) = self.sthd {
+            size += sthd.box_size();
+        }
         size += self.dinf.box_size();
         size += self.stbl.box_size();
-        size
+        size + unknown_boxes_size(&self.unknown)
     }
 }
 
@@ -54,7 +72,29 @@ impl Mp4Box for MinfBox {
 
 impl BlockReader for MinfBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (vmhd, smhd, dinf, stbl) = reader.try_find_box4()?;
+        let mut vmhd = None;
+        let mut smhd = None;
+        let mut nmhd = None;
+        let mut sthd = None;
+        let mut dinf = None;
+        let mut stbl = None;
+        let mut unknown = UnknownBoxes::new();
+
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
+
+            match bx.kind {
+                BoxType::VmhdBox => vmhd = Some(bx.read()?),
+                BoxType::SmhdBox => smhd = Some(bx.read()?),
+                BoxType::NmhdBox => nmhd = Some(bx.read()?),
+                BoxType::SthdBox => sthd = Some(bx.read()?),
+                BoxType::DinfBox => dinf = Some(bx.read()?),
+                BoxType::StblBox => stbl = Some(bx.read()?),
+                _ => unknown.push_at(position, bx.read_raw()),
+            }
+        }
 
         if dinf.is_none() {
             return Err(Error::BoxNotFound(BoxType::DinfBox));
@@ -67,8 +107,11 @@ impl BlockReader for MinfBox {
         Ok(MinfBox {
             vmhd,
             smhd,
+            nmhd,
+            sthd,
             dinf: dinf.unwrap(),
             stbl: stbl.unwrap(),
+            unknown,
         })
     }
 
@@ -82,14 +125,23 @@ impl<W: Write> WriteBox<&mut W> for MinfBox {
         let size = self.box_size();
         BoxHeader::new(Self::TYPE, size).write(writer)?;
 
+        let mut children = self.unknown.writer(writer);
+
         if let Some(ref vmhd) = self.vmhd {
-            vmhd.write_box(writer)?;
+            children.write(vmhd)?;
         }
         if let Some(ref smhd) = self.smhd {
-            smhd.write_box(writer)?;
+            children.write(smhd)?;
+        }
+        if let Some(ref nmhd) = self.nmhd {
+            children.write(nmhd)?;
+        }
+        if let Some(ref sthd) = self.sthd {
+            children.write(sthd)?;
         }
-        self.dinf.write_box(writer)?;
-        self.stbl.write_box(writer)?;
+        children.write(&self.dinf)?;
+        children.write(&self.stbl)?;
+        children.finish()?;
 
         Ok(size)
     }
@@ -17,8 +17,14 @@
 //!         meta
 //!             ilst
 //!                 data
+//!         chpl
+//!     meta (mdta)
+//!         hdlr
+//!         keys
+//!         ilst
 //!     trak
 //!         tkhd
+//!         tref
 //!         mdia
 //!             mdhd
 //!             hdlr
@@ -29,6 +35,15 @@
 //!                         hev1
 //!                         mp4a
 //!                         tx3g
+//!                         wvtt
+//!                         stpp
+//!                         c608
+//!                         encv / enca
+//!                             sinf
+//!                                 frma
+//!                                 schm
+//!                                 schi
+//!                                     tenc
 //!                     stts
 //!                     stsc
 //!                     stsz
@@ -40,31 +55,44 @@
 //!                     dref
 //!                 smhd
 //!                 vmhd
+//!                 nmhd
+//!                 sthd
 //!         edts
 //!             elst
 //!     mvex
 //!         mehd
 //!         trex
+//!     pssh
 //! emsg
 //! moof
 //!     mfhd
+//!     pssh
 //!     traf
 //!         tfhd
 //!         tfdt
 //!         trun
+//!         senc
+//!         saiz
+//!         saio
+//! mfra
+//!     tfra
+//!     mfro
 //! mdat
 //! free
 //!
 
 use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
-use bytes::Buf;
+use bytes::{Buf, Bytes};
+use std::any::Any;
 use std::io::Write;
+use std::sync::Arc;
 use std::{convert::TryInto, marker::PhantomData};
-use tokio::io::{AsyncRead, AsyncReadExt};
 
 use crate::*;
 
 pub(crate) mod avc1;
+pub(crate) mod c608;
+pub(crate) mod chpl;
 pub(crate) mod co64;
 pub(crate) mod ctts;
 pub(crate) mod data;
@@ -76,30 +104,45 @@ pub(crate) mod ftyp;
 pub(crate) mod hdlr;
 pub(crate) mod hev1;
 pub(crate) mod ilst;
+pub(crate) mod keys;
 pub(crate) mod mdhd;
 pub(crate) mod mdia;
 pub(crate) mod mehd;
 pub(crate) mod meta;
 pub(crate) mod mfhd;
+pub(crate) mod mfra;
+pub(crate) mod mfro;
 pub(crate) mod minf;
 pub(crate) mod moof;
 pub(crate) mod moov;
 pub(crate) mod mp4a;
 pub(crate) mod mvex;
 pub(crate) mod mvhd;
+pub(crate) mod nmhd;
+pub(crate) mod pssh;
+pub(crate) mod saio;
+pub(crate) mod saiz;
+pub(crate) mod schm;
+pub(crate) mod senc;
+pub(crate) mod sinf;
 pub(crate) mod smhd;
 pub(crate) mod stbl;
 pub(crate) mod stco;
+pub(crate) mod sthd;
+pub(crate) mod stpp;
 pub(crate) mod stsc;
 pub(crate) mod stsd;
 pub(crate) mod stss;
 pub(crate) mod stsz;
 pub(crate) mod stts;
+pub(crate) mod tenc;
 pub(crate) mod tfdt;
 pub(crate) mod tfhd;
+pub(crate) mod tfra;
 pub(crate) mod tkhd;
 pub(crate) mod traf;
 pub(crate) mod trak;
+pub(crate) mod tref;
 pub(crate) mod trex;
 pub(crate) mod trun;
 pub(crate) mod tx3g;
@@ -107,8 +150,11 @@ pub(crate) mod udta;
 pub(crate) mod vmhd;
 pub(crate) mod vp09;
 pub(crate) mod vpcc;
+pub(crate) mod wvtt;
 
 pub use avc1::Avc1Box;
+pub use c608::C608Box;
+pub use chpl::{ChplBox, ChplEntry};
 pub use co64::Co64Box;
 pub use ctts::CttsBox;
 pub use data::DataBox;
@@ -119,38 +165,54 @@ pub use emsg::EmsgBox;
 pub use ftyp::FtypBox;
 pub use hdlr::HdlrBox;
 pub use hev1::Hev1Box;
-pub use ilst::IlstBox;
+pub use ilst::{IlstBox, IlstItem};
+pub use keys::{KeyEntry, KeysBox};
 pub use mdhd::MdhdBox;
 pub use mdia::MdiaBox;
 pub use mehd::MehdBox;
 pub use meta::MetaBox;
 pub use mfhd::MfhdBox;
+pub use mfra::MfraBox;
+pub use mfro::MfroBox;
 pub use minf::MinfBox;
 pub use moof::MoofBox;
 pub use moov::MoovBox;
 pub use mp4a::Mp4aBox;
 pub use mvex::MvexBox;
 pub use mvhd::MvhdBox;
+pub use nmhd::NmhdBox;
+pub use pssh::PsshBox;
+pub use saio::SaioBox;
+pub use saiz::SaizBox;
+pub use schm::SchmBox;
+pub use senc::{SampleEncryption, SencBox, SubsampleEncryption};
+pub use sinf::{FrmaBox, SchiBox, SinfBox};
 pub use smhd::SmhdBox;
 pub use stbl::StblBox;
 pub use stco::StcoBox;
+pub use sthd::SthdBox;
+pub use stpp::StppBox;
 pub use stsc::StscBox;
 pub use stsd::StsdBox;
 pub use stss::StssBox;
 pub use stsz::StszBox;
 pub use stts::SttsBox;
+pub use tenc::TencBox;
 pub use tfdt::TfdtBox;
 pub use tfhd::TfhdBox;
+pub use tfra::{TfraBox, TfraEntry};
 pub use tkhd::TkhdBox;
 pub use traf::TrafBox;
 pub use trak::TrakBox;
+pub use tref::{ReferenceType, TrackReference, TrefBox};
 pub use trex::TrexBox;
 pub use trun::TrunBox;
-pub use tx3g::Tx3gBox;
+pub use tx3g::{Hyperlink, Karaoke, KaraokeEntry, RgbaColor, TextSample, TextStyle, Tx3gBox};
 pub use udta::UdtaBox;
 pub use vmhd::VmhdBox;
 pub use vp09::Vp09Box;
 pub use vpcc::VpccBox;
+pub use wvtt::{VttCue, VttSample, WvttBox};
 
 pub const HEADER_SIZE: u64 = 8;
 // const HEADER_LARGE_SIZE: u64 = 16;
@@ -205,6 +267,9 @@ boxtype! {
     TrexBox => 0x74726578,
     EmsgBox => 0x656d7367,
     MoofBox => 0x6d6f6f66,
+    MfraBox => 0x6d667261,
+    TfraBox => 0x74667261,
+    MfroBox => 0x6d66726f,
     TkhdBox => 0x746b6864,
     TfhdBox => 0x74666864,
     TfdtBox => 0x74666474,
@@ -225,14 +290,18 @@ boxtype! {
     StcoBox => 0x7374636F,
     Co64Box => 0x636F3634,
     TrakBox => 0x7472616b,
+    TrefBox => 0x74726566,
     TrafBox => 0x74726166,
     TrunBox => 0x7472756E,
     UdtaBox => 0x75647461,
+    ChplBox => 0x6368706c,
     MetaBox => 0x6d657461,
     DinfBox => 0x64696e66,
     DrefBox => 0x64726566,
     UrlBox  => 0x75726C20,
     SmhdBox => 0x736d6864,
+    NmhdBox => 0x6e6d6864,
+    SthdBox => 0x73746864,
     Avc1Box => 0x61766331,
     AvcCBox => 0x61766343,
     Hev1Box => 0x68657631,
@@ -240,16 +309,32 @@ boxtype! {
     Mp4aBox => 0x6d703461,
     EsdsBox => 0x65736473,
     Tx3gBox => 0x74783367,
+    WvttBox => 0x77767474,
+    StppBox => 0x73747070,
+    C608Box => 0x63363038,
     VpccBox => 0x76706343,
     Vp09Box => 0x76703039,
     DataBox => 0x64617461,
     IlstBox => 0x696c7374,
+    KeysBox => 0x6b657973,
     NameBox => 0xa96e616d,
     DayBox => 0xa9646179,
     CovrBox => 0x636f7672,
     DescBox => 0x64657363,
     WideBox => 0x77696465,
-    WaveBox => 0x77617665
+    WaveBox => 0x77617665,
+    EncvBox => 0x656e6376,
+    EncaBox => 0x656e6361,
+    SinfBox => 0x73696e66,
+    FrmaBox => 0x66726d61,
+    SchmBox => 0x7363686d,
+    SchiBox => 0x73636869,
+    TencBox => 0x74656e63,
+    PsshBox => 0x70737368,
+    SencBox => 0x73656e63,
+    SaizBox => 0x7361697a,
+    SaioBox => 0x7361696f,
+    Hvc1Box => 0x68766331
 }
 
 pub trait Mp4Box: Sized {
@@ -261,8 +346,11 @@ pub trait Mp4Box: Sized {
 }
 
 pub struct BoxReader<'a, R: Reader<'a>> {
-    kind: BoxType,
+    pub(crate) kind: BoxType,
     inner: R,
+
+    /// Bytes following the box in its parent, to locate errors.
+    after: usize,
     m: PhantomData<&'a ()>,
 }
 
@@ -270,7 +358,7 @@ impl<'a, R: Reader<'a>> BoxReader<'a, R> {
     #[inline]
     pub fn try_read<T: Mp4Box + BlockReader>(&mut self) -> Result<Option<T>, Error> {
         if T::TYPE == self.kind {
-            Ok(Some(T::read_block(&mut self.inner)?))
+            Ok(Some(self.read_as()?))
         } else {
             Ok(None)
         }
@@ -279,11 +367,277 @@ impl<'a, R: Reader<'a>> BoxReader<'a, R> {
     #[inline]
     pub fn read<T: Mp4Box + BlockReader>(&mut self) -> Result<T, Error> {
         if T::TYPE == self.kind {
-            T::read_block(&mut self.inner)
+            self.read_as()
         } else {
             Err(Error::BoxNotFound(T::TYPE))
         }
     }
+
+    /// Reads the body as `T` whatever the box type, errors get this box added to their
+    /// [`ErrorContext`](crate::ErrorContext).
+    #[inline]
+    pub(crate) fn read_as<T: BlockReader>(&mut self) -> Result<T, Error> {
+        T::read_block(&mut self.inner)
+            .map_err(|err| err.in_box(self.kind, self.inner.remaining(), self.after))
+    }
+
+    /// Body of a box that isn't modelled, to be written back unchanged.
+    #[inline]
+    pub fn read_raw(&mut self) -> UnknownBox {
+        UnknownBox::Raw(self.kind, self.inner.collect_remaining_bytes())
+    }
+}
+
+/// Box parsed by a type registered with a [`BoxRegistry`](crate::BoxRegistry).
+///
+/// Implemented for every box type with [`Mp4Box`] and [`WriteBox`] impls.
+pub trait CustomBox: Any + std::fmt::Debug + Send + Sync {
+    fn kind(&self) -> BoxType;
+    fn size(&self) -> u64;
+    fn write_to(&self, writer: &mut Vec<u8>) -> Result<u64, Error>;
+    fn as_any(&self) -> &dyn Any;
+    fn eq_box(&self, other: &dyn CustomBox) -> bool;
+}
+
+impl<T> CustomBox for T
+where
+    T: Mp4Box + for<'w> WriteBox<&'w mut Vec<u8>>,
+    T: std::fmt::Debug + PartialEq + Send + Sync + 'static,
+{
+    fn kind(&self) -> BoxType {
+        T::TYPE
+    }
+
+    fn size(&self) -> u64 {
+        self.box_size()
+    }
+
+    fn write_to(&self, writer: &mut Vec<u8>) -> Result<u64, Error> {
+        self.write_box(writer)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn eq_box(&self, other: &dyn CustomBox) -> bool {
+        other.as_any().downcast_ref::<T>() == Some(self)
+    }
+}
+
+/// Child box not modelled by its container, kept so it's written back unchanged.
+#[derive(Debug, Clone)]
+pub enum UnknownBox {
+    Raw(BoxType, Bytes),
+    Custom(Arc<dyn CustomBox>),
+}
+
+impl UnknownBox {
+    pub fn kind(&self) -> BoxType {
+        match self {
+            UnknownBox::Raw(kind, _) => *kind,
+            UnknownBox::Custom(bx) => bx.kind(),
+        }
+    }
+
+    pub fn box_size(&self) -> u64 {
+        match self {
+            UnknownBox::Raw(_, data) => HEADER_SIZE + data.len() as u64,
+            UnknownBox::Custom(bx) => bx.size(),
+        }
+    }
+
+    /// The parsed box if it was read by a registered parser of type `T`.
+    pub fn downcast_ref<T: CustomBox>(&self) -> Option<&T> {
+        match self {
+            UnknownBox::Raw(..) => None,
+            UnknownBox::Custom(bx) => bx.as_any().downcast_ref(),
+        }
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<u64, Error> {
+        match self {
+            UnknownBox::Raw(kind, data) => {
+                let size = BoxHeader::new(*kind, HEADER_SIZE + data.len() as u64).write(writer)?;
+                writer.write_all(data)?;
+                Ok(size + data.len() as u64)
+            }
+            UnknownBox::Custom(bx) => {
+                let mut buf = Vec::with_capacity(bx.size() as usize);
+                let size = bx.write_to(&mut buf)?;
+                writer.write_all(&buf)?;
+                Ok(size)
+            }
+        }
+    }
+}
+
+impl PartialEq for UnknownBox {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (UnknownBox::Raw(a_kind, a_data), UnknownBox::Raw(b_kind, b_data)) => {
+                a_kind == b_kind && a_data == b_data
+            }
+            (UnknownBox::Custom(a), UnknownBox::Custom(b)) => a.eq_box(b.as_ref()),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for UnknownBox {}
+
+/// Unknown children of a container, in the order they were read.
+///
+/// Dereferences to a slice of the boxes. Each box read from a file remembers its index
+/// among all children of the container, so a rewrite puts it back between the same
+/// known boxes. Boxes added with [`push`](Self::push) are written after the known ones.
+#[derive(Debug, Clone, Default)]
+pub struct UnknownBoxes {
+    boxes: Vec<UnknownBox>,
+    positions: Vec<Option<usize>>,
+}
+
+impl UnknownBoxes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, bx: UnknownBox) {
+        self.boxes.push(bx);
+        self.positions.push(None);
+    }
+
+    /// Adds a box read as the `position`-th child of its container.
+    pub(crate) fn push_at(&mut self, position: usize, bx: UnknownBox) {
+        self.boxes.push(bx);
+        self.positions.push(Some(position));
+    }
+
+    /// Starts writing the children of a container.
+    pub(crate) fn writer<'a, W: Write>(&'a self, writer: &'a mut W) -> ChildWriter<'a, W> {
+        ChildWriter {
+            writer,
+            unknown: self,
+            next: 0,
+            written: 0,
+        }
+    }
+}
+
+impl std::ops::Deref for UnknownBoxes {
+    type Target = [UnknownBox];
+
+    fn deref(&self) -> &Self::Target {
+        &self.boxes
+    }
+}
+
+impl std::ops::DerefMut for UnknownBoxes {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.boxes
+    }
+}
+
+impl From<Vec<UnknownBox>> for UnknownBoxes {
+    fn from(boxes: Vec<UnknownBox>) -> Self {
+        Self {
+            positions: vec![None; boxes.len()],
+            boxes,
+        }
+    }
+}
+
+impl FromIterator<UnknownBox> for UnknownBoxes {
+    fn from_iter<I: IntoIterator<Item = UnknownBox>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl IntoIterator for UnknownBoxes {
+    type Item = UnknownBox;
+    type IntoIter = std::vec::IntoIter<UnknownBox>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.boxes.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a UnknownBoxes {
+    type Item = &'a UnknownBox;
+    type IntoIter = std::slice::Iter<'a, UnknownBox>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.boxes.iter()
+    }
+}
+
+/// Positions only matter for writing, boxes with the same children are equal.
+impl PartialEq for UnknownBoxes {
+    fn eq(&self, other: &Self) -> bool {
+        self.boxes == other.boxes
+    }
+}
+
+impl Eq for UnknownBoxes {}
+
+impl PartialEq<Vec<UnknownBox>> for UnknownBoxes {
+    fn eq(&self, other: &Vec<UnknownBox>) -> bool {
+        &self.boxes == other
+    }
+}
+
+/// Writes the children of a container, putting each unknown box back at the index it
+/// was read at.
+pub(crate) struct ChildWriter<'a, W> {
+    writer: &'a mut W,
+    unknown: &'a UnknownBoxes,
+
+    /// Next unknown box to write.
+    next: usize,
+
+    /// Children written so far.
+    written: usize,
+}
+
+impl<W: Write> ChildWriter<'_, W> {
+    pub(crate) fn write<T>(&mut self, bx: &T) -> Result<(), Error>
+    where
+        T: for<'w> WriteBox<&'w mut W>,
+    {
+        self.write_unknown(false)?;
+        bx.write_box(&mut *self.writer)?;
+        self.written += 1;
+
+        Ok(())
+    }
+
+    /// Writes the remaining unknown boxes.
+    pub(crate) fn finish(mut self) -> Result<(), Error> {
+        self.write_unknown(true)
+    }
+
+    fn write_unknown(&mut self, all: bool) -> Result<(), Error> {
+        let UnknownBoxes { boxes, positions } = self.unknown;
+
+        while let Some(bx) = boxes.get(self.next) {
+            match positions[self.next] {
+                Some(position) if all || position <= self.written => {}
+                None if all => {}
+                _ => break,
+            }
+
+            bx.write(self.writer)?;
+            self.next += 1;
+            self.written += 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// Size of the unknown child boxes kept by a container.
+pub(crate) fn unknown_boxes_size(boxes: &[UnknownBox]) -> u64 {
+    boxes.iter().map(UnknownBox::box_size).sum()
 }
 
 pub trait Reader<'a> {
@@ -406,6 +760,11 @@ pub trait Reader<'a> {
     }
     fn get_null_terminated_string(&mut self) -> String;
     fn collect(&mut self, size: usize) -> Result<Vec<u8>, Error> {
+        // the size comes from the file, don't allocate it before it's known to be there
+        if self.remaining() < size {
+            return Err(Error::InvalidData("expected more bytes"));
+        }
+
         let mut buf = vec![0; size];
         self.copy_to_slice(&mut buf)?;
 
@@ -417,6 +776,17 @@ pub trait Reader<'a> {
         self.collect(self.remaining()).unwrap()
     }
 
+    /// Like [`Reader::collect`], but a reader over [`Bytes`] returns a slice of its buffer
+    /// instead of a copy.
+    fn collect_bytes(&mut self, size: usize) -> Result<Bytes, Error> {
+        self.collect(size).map(Bytes::from)
+    }
+
+    #[inline]
+    fn collect_remaining_bytes(&mut self) -> Bytes {
+        self.collect_bytes(self.remaining()).unwrap()
+    }
+
     fn copy_to_slice(&mut self, slice: &mut [u8]) -> Result<(), Error>;
     fn get_box(&mut self) -> Result<Option<BoxReader<'a, impl Reader<'a> + '_>>, Error>;
 
@@ -446,12 +816,13 @@ pub trait Reader<'a> {
                 }
             }
 
-            println!(" 1 unknown box {}", bx.kind);
+            log::trace!("skipping unknown box {}", bx.kind);
         }
 
         Ok((a, b))
     }
 
+    #[allow(clippy::type_complexity)]
     fn try_find_box3<A, B, C>(&mut self) -> Result<(Option<A>, Option<B>, Option<C>), Error>
     where
         A: Mp4Box + BlockReader,
@@ -483,7 +854,7 @@ pub trait Reader<'a> {
                 }
             }
 
-            println!(" 2 unknown box {}", bx.kind);
+            log::trace!("skipping unknown box {}", bx.kind);
         }
 
         Ok((a, b, c))
@@ -513,6 +884,7 @@ pub trait Reader<'a> {
         Ok((a, b, c))
     }
 
+    #[allow(clippy::type_complexity)]
     fn try_find_box4<A, B, C, D>(
         &mut self,
     ) -> Result<(Option<A>, Option<B>, Option<C>, Option<D>), Error>
@@ -556,7 +928,7 @@ pub trait Reader<'a> {
                 }
             }
 
-            println!(" 3 unknown box {}", bx.kind);
+            log::trace!("skipping unknown box {}", bx.kind);
         }
 
         Ok((a, b, c, d))
@@ -569,7 +941,7 @@ pub trait Reader<'a> {
                 return Ok(Some(inner));
             }
 
-            println!(" 4 unknown box {}", bx.kind);
+            log::trace!("skipping unknown box {}", bx.kind);
         }
 
         Ok(None)
@@ -591,7 +963,8 @@ impl<'a> Reader<'a> for &'a [u8] {
 
     #[inline]
     fn skip(&mut self, size: usize) {
-        Buf::advance(self, size)
+        // reads past the end fail on the next read instead
+        Buf::advance(self, size.min(Buf::remaining(self)))
     }
 
     #[inline]
@@ -649,7 +1022,9 @@ impl<'a> Reader<'a> for &'a [u8] {
 
     #[inline]
     fn get_i24(&mut self) -> i32 {
-        todo!()
+        let val = BigEndian::read_i24(self.chunk());
+        self.skip(3);
+        val
     }
 
     #[inline]
@@ -659,7 +1034,9 @@ impl<'a> Reader<'a> for &'a [u8] {
 
     #[inline]
     fn get_i48(&mut self) -> i64 {
-        todo!()
+        let val = BigEndian::read_i48(self.chunk());
+        self.skip(6);
+        val
     }
 
     #[inline]
@@ -706,9 +1083,152 @@ impl<'a> Reader<'a> for &'a [u8] {
             return Ok(None);
         };
 
+        let body_size = size
+            .checked_sub(offset)
+            .ok_or(Error::InvalidData("box size smaller than its header"))?
+            as usize;
+        let after = self.remaining().saturating_sub(body_size);
+
         Ok(Some(BoxReader {
             kind,
-            inner: Reader::take(self, (size - offset) as _)?,
+            inner: Reader::take(self, body_size)?,
+            after,
+            m: PhantomData,
+        }))
+    }
+}
+
+impl<'a> Reader<'a> for Bytes {
+    #[inline]
+    fn take(&mut self, size: usize) -> Result<impl Reader<'a> + '_, Error> {
+        if self.len() < size {
+            return Err(Error::InvalidData("no bytes left"));
+        }
+
+        Ok(self.split_to(size))
+    }
+
+    #[inline]
+    fn skip(&mut self, size: usize) {
+        // reads past the end fail on the next read instead
+        Buf::advance(self, size.min(Buf::remaining(self)))
+    }
+
+    #[inline]
+    fn remaining(&self) -> usize {
+        Buf::remaining(self)
+    }
+
+    fn peek_u32(&self) -> u32 {
+        BigEndian::read_u32(self.chunk())
+    }
+
+    #[inline]
+    fn get_u8(&mut self) -> u8 {
+        Buf::get_u8(self)
+    }
+
+    #[inline]
+    fn get_u16(&mut self) -> u16 {
+        Buf::get_u16(self)
+    }
+
+    #[inline]
+    fn get_u24(&mut self) -> u32 {
+        Buf::get_uint(self, 3) as u32
+    }
+
+    #[inline]
+    fn get_u32(&mut self) -> u32 {
+        Buf::get_u32(self)
+    }
+
+    #[inline]
+    fn get_u48(&mut self) -> u64 {
+        Buf::get_uint(self, 6)
+    }
+
+    #[inline]
+    fn get_u64(&mut self) -> u64 {
+        Buf::get_u64(self)
+    }
+
+    #[inline]
+    fn get_i8(&mut self) -> i8 {
+        Buf::get_i8(self)
+    }
+
+    #[inline]
+    fn get_i16(&mut self) -> i16 {
+        Buf::get_i16(self)
+    }
+
+    #[inline]
+    fn get_i24(&mut self) -> i32 {
+        Buf::get_int(self, 3) as i32
+    }
+
+    #[inline]
+    fn get_i32(&mut self) -> i32 {
+        Buf::get_i32(self)
+    }
+
+    #[inline]
+    fn get_i48(&mut self) -> i64 {
+        Buf::get_int(self, 6)
+    }
+
+    #[inline]
+    fn get_i64(&mut self) -> i64 {
+        Buf::get_i64(self)
+    }
+
+    #[inline]
+    fn copy_to_slice(&mut self, slice: &mut [u8]) -> Result<(), Error> {
+        if self.len() < slice.len() {
+            return Err(Error::InvalidData("expected more bytes"));
+        }
+
+        Buf::copy_to_slice(self, slice);
+
+        Ok(())
+    }
+
+    #[inline]
+    fn collect_bytes(&mut self, size: usize) -> Result<Bytes, Error> {
+        if self.len() < size {
+            return Err(Error::InvalidData("expected more bytes"));
+        }
+
+        Ok(self.split_to(size))
+    }
+
+    #[inline]
+    fn get_null_terminated_string(&mut self) -> String {
+        let mut slice = self.as_ref();
+        let val = Reader::get_null_terminated_string(&mut slice);
+        let eat = self.len() - slice.len();
+        self.advance(eat);
+        val
+    }
+
+    #[inline]
+    fn get_box(&mut self) -> Result<Option<BoxReader<'a, impl Reader<'a> + '_>>, Error> {
+        let mut offset = 0;
+        let Some(BoxHeader { kind, size }) = BoxHeader::read_sync(self, &mut offset)? else {
+            return Ok(None);
+        };
+
+        let body_size = size
+            .checked_sub(offset)
+            .ok_or(Error::InvalidData("box size smaller than its header"))?
+            as usize;
+        let after = self.remaining().saturating_sub(body_size);
+
+        Ok(Some(BoxReader {
+            kind,
+            inner: Reader::take(self, body_size)?,
+            after,
             m: PhantomData,
         }))
     }
@@ -748,44 +1268,28 @@ impl BoxHeader {
         *offset += 8;
 
         // Get largesize if size is 1
-        let size = if sz == 1 {
+        let largesize = if sz == 1 {
             if reader.remaining() < 8 {
                 return Err(Error::InvalidData("expected 8 bytes more"));
             }
 
             *offset += 8;
-
-            let largesize = reader.get_u64();
-            // Subtract the length of the serialized largesize, as callers assume `size - HEADER_SIZE` is the length
-            // of the box data. Disallow `largesize < 16`, or else a largesize of 8 will result in a BoxHeader::size
-            // of 0, incorrectly indicating that the box data extends to the end of the stream.
-            match largesize {
-                0 => 0,
-                1..=15 => return Err(Error::InvalidData("64-bit box size too small")),
-                16..=u64::MAX => largesize - 8,
-            }
+            Some(reader.get_u64())
         } else {
-            sz as _
+            None
         };
 
-        println!(
-            "{} box {} {}",
-            if sz == 1 { "big" } else { "small" },
-            BoxType::from(typ).as_str(),
-            size
-        );
-
-        Ok(Some(BoxHeader {
-            kind: BoxType::from(typ),
-            size,
-        }))
+        Self::from_fields(sz, typ, largesize).map(Some)
     }
 
     // TODO: if size is 0, then this box is the last one in the file
-    pub async fn read<R: AsyncRead + Unpin>(
+    #[cfg(feature = "tokio")]
+    pub async fn read<R: tokio::io::AsyncRead + Unpin>(
         reader: &mut R,
         offset: &mut u64,
     ) -> Result<Option<Self>, Error> {
+        use tokio::io::AsyncReadExt;
+
         // Create and read to buf.
         let mut buf = [0u8; 8]; // 8 bytes for box header.
         match reader.read_exact(&mut buf).await {
@@ -797,16 +1301,45 @@ impl BoxHeader {
         }
         *offset += 8;
 
-        // Get size.
-        let s = buf[0..4].try_into().unwrap();
-        let sz = u32::from_be_bytes(s);
-
-        // Get box type string.
-        let t = buf[4..8].try_into().unwrap();
-        let typ = u32::from_be_bytes(t);
+        let (sz, typ) = Self::split_fields(buf);
 
         // Get largesize if size is 1
-        let size = if sz == 1 {
+        let largesize = if sz == 1 {
+            match reader.read_exact(&mut buf).await {
+                Ok(_) => (),
+                Err(err) => match err.kind() {
+                    std::io::ErrorKind::UnexpectedEof => return Ok(None),
+                    _ => return Err(err.into()),
+                },
+            }
+
+            *offset += 8;
+            Some(u64::from_be_bytes(buf))
+        } else {
+            None
+        };
+
+        Self::from_fields(sz, typ, largesize).map(Some)
+    }
+
+    /// Same as [`BoxHeader::read`] over any [`ByteSource`].
+    pub(crate) async fn read_from<S: ByteSource>(
+        reader: &mut S,
+        offset: &mut u64,
+    ) -> Result<Option<Self>, Error> {
+        let mut buf = [0u8; 8];
+        match reader.read_exact(&mut buf).await {
+            Ok(_) => (),
+            Err(err) => match err.kind() {
+                std::io::ErrorKind::UnexpectedEof => return Ok(None),
+                _ => return Err(err.into()),
+            },
+        }
+        *offset += 8;
+
+        let (sz, typ) = Self::split_fields(buf);
+
+        let largesize = if sz == 1 {
             match reader.read_exact(&mut buf).await {
                 Ok(_) => (),
                 Err(err) => match err.kind() {
@@ -816,31 +1349,45 @@ impl BoxHeader {
             }
 
             *offset += 8;
-            let largesize = u64::from_be_bytes(buf);
+            Some(u64::from_be_bytes(buf))
+        } else {
+            None
+        };
 
+        Self::from_fields(sz, typ, largesize).map(Some)
+    }
+
+    /// Size and box type fields of the first 8 header bytes.
+    fn split_fields(buf: [u8; 8]) -> (u32, u32) {
+        let sz = u32::from_be_bytes(buf[0..4].try_into().unwrap());
+        let typ = u32::from_be_bytes(buf[4..8].try_into().unwrap());
+        (sz, typ)
+    }
+
+    fn from_fields(sz: u32, typ: u32, largesize: Option<u64>) -> Result<Self, Error> {
+        let size = match largesize {
             // Subtract the length of the serialized largesize, as callers assume `size - HEADER_SIZE` is the length
             // of the box data. Disallow `largesize < 16`, or else a largesize of 8 will result in a BoxHeader::size
             // of 0, incorrectly indicating that the box data extends to the end of the stream.
-            match largesize {
+            Some(largesize) => match largesize {
                 0 => 0,
                 1..=15 => return Err(Error::InvalidData("64-bit box size too small")),
                 16..=u64::MAX => largesize - 8,
-            }
-        } else {
-            sz as _
+            },
+            None => sz as _,
         };
 
-        println!(
+        log::trace!(
             "{} box {} {}",
             if sz == 1 { "big" } else { "small" },
-            BoxType::from(typ).as_str(),
+            BoxType::from(typ),
             size
         );
 
-        Ok(Some(BoxHeader {
+        Ok(BoxHeader {
             kind: BoxType::from(typ),
             size,
-        }))
+        })
     }
 
     pub fn write<W: Write>(&self, writer: &mut W) -> Result<u64, Error> {
@@ -858,8 +1405,8 @@ impl BoxHeader {
 }
 
 #[inline]
-pub fn read_box_header_ext<'a, R: Reader<'a>>(reader: &mut R) -> (u8, u32) {
-    (reader.get_u8(), reader.get_u24())
+pub fn read_box_header_ext<'a, R: Reader<'a>>(reader: &mut R) -> Result<(u8, u32), Error> {
+    Ok((reader.try_get_u8()?, reader.try_get_u24()?))
 }
 
 pub fn write_box_header_ext<W: Write>(w: &mut W, v: u8, f: u32) -> Result<u64, Error> {
@@ -924,6 +1471,50 @@ mod tests {
         assert_eq!(ftyp_fcc, ftyp_fcc2);
     }
 
+    #[test]
+    fn test_bytes_reader() {
+        let data = [
+            0xff, 0xff, 0xfe, 0x00, 0x00, 0x00, 0x00, 0x01, 0x02, b'a', b'b', 0, b'c',
+        ];
+        let mut slice = &data[..];
+        let mut bytes = Bytes::copy_from_slice(&data);
+
+        assert_eq!(bytes.get_i24(), -2);
+        assert_eq!(bytes.get_u48(), 0x0102);
+        assert_eq!(Reader::get_u24(&mut slice), 0xfffffe);
+        slice.skip(6);
+
+        assert_eq!(Reader::get_null_terminated_string(&mut slice), "ab");
+        assert_eq!(bytes.get_null_terminated_string(), "ab");
+        assert_eq!(bytes.collect_remaining_bytes(), &b"c"[..]);
+        assert!(bytes.collect_bytes(1).is_err());
+    }
+
+    #[test]
+    fn test_bytes_get_box() {
+        let mut bytes = Bytes::from_static(b"\0\0\0\x0afree\x01\x02\0\0\0\x08mdat");
+        {
+            let mut bx = bytes.get_box().unwrap().unwrap();
+            assert_eq!(bx.kind, BoxType::FreeBox);
+            assert_eq!(bx.inner.collect_remaining_bytes(), &[1u8, 2][..]);
+        }
+        assert_eq!(bytes.get_box().unwrap().unwrap().kind, BoxType::MdatBox);
+        assert!(bytes.get_box().unwrap().is_none());
+
+        // sizes smaller than the header or past the end of the buffer
+        let mut bytes = Bytes::from_static(b"\0\0\0\x04free");
+        assert!(bytes.get_box().is_err());
+
+        let mut bytes = Bytes::from_static(b"\0\0\0\x10free\x01\x02");
+        assert!(bytes.get_box().is_err());
+
+        // skipping past the end leaves the reader empty
+        let mut bytes = Bytes::from_static(b"\x01\x02");
+        Reader::skip(&mut bytes, 4);
+        assert_eq!(Reader::remaining(&bytes), 0);
+    }
+
+    #[cfg(feature = "tokio")]
     #[tokio::test]
     async fn test_largesize_too_small() {
         let error = BoxHeader::read(
@@ -934,6 +1525,7 @@ mod tests {
         assert!(matches!(error, Err(Error::InvalidData(_))));
     }
 
+    #[cfg(feature = "tokio")]
     #[tokio::test]
     async fn test_zero_largesize() {
         let error = BoxHeader::read(
@@ -944,6 +1536,7 @@ mod tests {
         assert!(matches!(error, Err(Error::InvalidData(_))));
     }
 
+    #[cfg(feature = "tokio")]
     #[tokio::test]
     async fn test_nonzero_largesize_too_small() {
         let error = BoxHeader::read(
@@ -954,6 +1547,7 @@ mod tests {
         assert!(matches!(error, Err(Error::InvalidData(_))));
     }
 
+    #[cfg(feature = "tokio")]
     #[tokio::test]
     async fn test_valid_largesize() {
         let header = BoxHeader::read(
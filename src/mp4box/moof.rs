@@ -8,8 +8,14 @@ use crate::mp4box::{mfhd::MfhdBox, traf::TrafBox};
 pub struct MoofBox {
     pub mfhd: MfhdBox,
 
+    #[serde(rename = "pssh", skip_serializing_if = "Vec::is_empty")]
+    pub psshs: Vec<PsshBox>,
+
     #[serde(rename = "traf")]
     pub trafs: Vec<TrafBox>,
+
+    #[serde(skip)]
+    pub unknown: UnknownBoxes,
 }
 
 impl MoofBox {
@@ -19,10 +25,13 @@ impl MoofBox {
 
     pub fn get_size(&self) -> u64 {
         let mut size = HEADER_SIZE + self.mfhd.box_size();
+        for pssh in self.psshs.iter() {
+            size += pssh.box_size();
+        }
         for traf in self.trafs.iter() {
             size += traf.box_size();
         }
-        size
+        size + unknown_boxes_size(&self.unknown)
     }
 }
 
@@ -46,19 +55,31 @@ impl Mp4Box for MoofBox {
 impl BlockReader for MoofBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
         let mut mfhd = None;
+        let mut psshs = Vec::new();
         let mut trafs = Vec::new();
+        let mut unknown = UnknownBoxes::new();
+
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
 
-        while let Some(mut bx) = reader.get_box()? {
             match bx.kind {
                 BoxType::MfhdBox => {
                     mfhd = Some(bx.read()?);
                 }
 
+                BoxType::PsshBox => {
+                    let idx = psshs.len();
+                    psshs.push(bx.read().map_err(|err| err.with_index(idx))?);
+                }
+
                 BoxType::TrafBox => {
-                    trafs.push(bx.read()?);
+                    let idx = trafs.len();
+                    trafs.push(bx.read().map_err(|err| err.with_index(idx))?);
                 }
 
-                _ => continue,
+                _ => unknown.push_at(position, bx.read_raw()),
             }
         }
 
@@ -68,7 +89,9 @@ impl BlockReader for MoofBox {
 
         Ok(MoofBox {
             mfhd: mfhd.unwrap(),
+            psshs,
             trafs,
+            unknown,
         })
     }
 
@@ -82,10 +105,16 @@ impl<W: Write> WriteBox<&mut W> for MoofBox {
         let size = self.box_size();
         BoxHeader::new(Self::TYPE, size).write(writer)?;
 
-        self.mfhd.write_box(writer)?;
+        let mut children = self.unknown.writer(writer);
+
+        children.write(&self.mfhd)?;
+        for pssh in self.psshs.iter() {
+            children.write(pssh)?;
+        }
         for traf in self.trafs.iter() {
-            traf.write_box(writer)?;
+            children.write(traf)?;
         }
+        children.finish()?;
         Ok(0)
     }
 }
@@ -20,6 +20,12 @@ pub struct MoovBox {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub udta: Option<UdtaBox>,
+
+    #[serde(rename = "pssh", skip_serializing_if = "Vec::is_empty")]
+    pub psshs: Vec<PsshBox>,
+
+    #[serde(skip)]
+    pub unknown: UnknownBoxes,
 }
 
 impl MoovBox {
@@ -32,13 +38,19 @@ impl MoovBox {
         for trak in self.traks.iter() {
             size += trak.box_size();
         }
+        if let Some(mvex) = &self.mvex {
+            size += mvex.box_size();
+        }
         if let Some(meta) = &self.meta {
             size += meta.box_size();
         }
         if let Some(udta) = &self.udta {
             size += udta.box_size();
         }
-        size
+        for pssh in self.psshs.iter() {
+            size += pssh.box_size();
+        }
+        size + unknown_boxes_size(&self.unknown)
     }
 }
 
@@ -66,8 +78,14 @@ impl BlockReader for MoovBox {
         let mut udta = None;
         let mut mvex = None;
         let mut traks = Vec::new();
+        let mut psshs = Vec::new();
+        let mut unknown = UnknownBoxes::new();
+
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
 
-        while let Some(mut bx) = reader.get_box()? {
             match bx.kind {
                 BoxType::MvhdBox => {
                     mvhd = Some(bx.read()?);
@@ -82,14 +100,20 @@ impl BlockReader for MoovBox {
                 }
 
                 BoxType::TrakBox => {
-                    traks.push(bx.read()?);
+                    let idx = traks.len();
+                    traks.push(bx.read().map_err(|err| err.with_index(idx))?);
                 }
 
                 BoxType::UdtaBox => {
                     udta = Some(bx.read()?);
                 }
 
-                _ => continue,
+                BoxType::PsshBox => {
+                    let idx = psshs.len();
+                    psshs.push(bx.read().map_err(|err| err.with_index(idx))?);
+                }
+
+                _ => unknown.push_at(position, bx.read_raw()),
             }
         }
 
@@ -103,6 +127,8 @@ impl BlockReader for MoovBox {
             udta,
             mvex,
             traks,
+            psshs,
+            unknown,
         })
     }
 
@@ -116,21 +142,30 @@ impl<W: Write> WriteBox<&mut W> for MoovBox {
         let size = self.box_size();
         BoxHeader::new(Self::TYPE, size).write(writer)?;
 
-        self.mvhd.write_box(writer)?;
+        let mut children = self.unknown.writer(writer);
+
+        children.write(&self.mvhd)?;
         for trak in self.traks.iter() {
-            trak.write_box(writer)?;
+            children.write(trak)?;
+        }
+        if let Some(mvex) = &self.mvex {
+            children.write(mvex)?;
         }
         if let Some(meta) = &self.meta {
-            meta.write_box(writer)?;
+            children.write(meta)?;
         }
         if let Some(udta) = &self.udta {
-            udta.write_box(writer)?;
+            children.write(udta)?;
+        }
+        for pssh in self.psshs.iter() {
+            children.write(pssh)?;
         }
+        children.finish()?;
         Ok(0)
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -139,10 +174,23 @@ mod tests {
     async fn test_moov() {
         let src_box = MoovBox {
             mvhd: MvhdBox::default(),
-            mvex: None, // XXX mvex is not written currently
+            mvex: Some(MvexBox {
+                mehd: None,
+                trexs: vec![TrexBox {
+                    track_id: 1,
+                    ..Default::default()
+                }],
+                unknown: UnknownBoxes::new(),
+            }),
             traks: vec![],
             meta: Some(MetaBox::default()),
             udta: Some(UdtaBox::default()),
+            psshs: vec![PsshBox {
+                system_id: [0xed; 16],
+                data: vec![1, 2, 3],
+                ..Default::default()
+            }],
+            unknown: UnknownBoxes::new(),
         };
 
         let mut buf = Vec::new();
@@ -174,4 +222,82 @@ mod tests {
         let dst_box = MoovBox::read_block(&mut reader).unwrap();
         assert_eq!(dst_box, src_box);
     }
+
+    #[tokio::test]
+    async fn test_moov_unknown() {
+        let src_box = MoovBox {
+            udta: Some(UdtaBox {
+                unknown: vec![UnknownBox::Raw(
+                    BoxType::from(u32::from_be_bytes(*b"\xa9too")),
+                    Bytes::from_static(b"\x00\x05\x00\x00encoder"),
+                )]
+                .into(),
+                ..Default::default()
+            }),
+            unknown: vec![UnknownBox::Raw(
+                BoxType::from(u32::from_be_bytes(*b"xtra")),
+                Bytes::from_static(&[1, 2, 3, 4]),
+            )]
+            .into(),
+            ..Default::default()
+        };
+
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.size, src_box.box_size());
+
+        let dst_box = MoovBox::read_block(&mut reader).unwrap();
+        assert_eq!(dst_box, src_box);
+
+        let mut rewritten = Vec::new();
+        dst_box.write_box(&mut rewritten).unwrap();
+        assert_eq!(rewritten, buf);
+    }
+
+    #[tokio::test]
+    async fn test_moov_unknown_order() {
+        let iods = UnknownBox::Raw(
+            BoxType::from(u32::from_be_bytes(*b"iods")),
+            Bytes::from_static(&[0, 0, 0, 0, 0x10, 0x80, 0x80, 0x80, 0x07]),
+        );
+        let xtra = UnknownBox::Raw(
+            BoxType::from(u32::from_be_bytes(*b"xtra")),
+            Bytes::from_static(&[1, 2, 3, 4]),
+        );
+        let mvex = MvexBox {
+            mehd: None,
+            trexs: vec![TrexBox {
+                track_id: 1,
+                ..Default::default()
+            }],
+            unknown: UnknownBoxes::new(),
+        };
+
+        // mvhd, iods, mvex, xtra, udta
+        let mut body = Vec::new();
+        MvhdBox::default().write_box(&mut body).unwrap();
+        iods.write(&mut body).unwrap();
+        mvex.write_box(&mut body).unwrap();
+        xtra.write(&mut body).unwrap();
+        UdtaBox::default().write_box(&mut body).unwrap();
+
+        let mut buf = Vec::new();
+        BoxHeader::new(BoxType::MoovBox, HEADER_SIZE + body.len() as u64)
+            .write(&mut buf)
+            .unwrap();
+        buf.extend_from_slice(&body);
+
+        let mut reader = buf.as_slice();
+        BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        let moov = MoovBox::read_block(&mut reader).unwrap();
+        assert_eq!(moov.unknown, vec![iods, xtra]);
+
+        let mut rewritten = Vec::new();
+        moov.write_box(&mut rewritten).unwrap();
+        assert_eq!(rewritten, buf);
+    }
 }
@@ -13,6 +13,13 @@ pub struct Mp4aBox {
     #[serde(with = "value_u32")]
     pub samplerate: FixedPointU16,
     pub esds: Option<EsdsBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sinf: Option<SinfBox>,
+
+    /// Children such as `btrt` or `chan`.
+    #[serde(skip)]
+    pub unknown: UnknownBoxes,
 }
 
 impl Default for Mp4aBox {
@@ -23,6 +30,8 @@ impl Default for Mp4aBox {
             samplesize: 16,
             samplerate: FixedPointU16::new(48000),
             esds: Some(EsdsBox::default()),
+            sinf: None,
+            unknown: UnknownBoxes::new(),
         }
     }
 }
@@ -35,11 +44,17 @@ impl Mp4aBox {
             samplesize: 16,
             samplerate: FixedPointU16::new(config.freq_index.freq() as u16),
             esds: Some(EsdsBox::new(config)),
+            sinf: None,
+            unknown: UnknownBoxes::new(),
         }
     }
 
     pub fn get_type(&self) -> BoxType {
-        BoxType::Mp4aBox
+        if self.sinf.is_some() {
+            BoxType::EncaBox
+        } else {
+            BoxType::Mp4aBox
+        }
     }
 
     pub fn get_size(&self) -> u64 {
@@ -47,7 +62,10 @@ impl Mp4aBox {
         if let Some(ref esds) = self.esds {
             size += esds.box_size();
         }
-        size
+        if let Some(ref sinf) = self.sinf {
+            size += sinf.box_size();
+        }
+        size + unknown_boxes_size(&self.unknown)
     }
 }
 
@@ -75,21 +93,21 @@ impl Mp4Box for Mp4aBox {
 
 impl BlockReader for Mp4aBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        reader.get_u32(); // reserved
-        reader.get_u16(); // reserved
+        reader.try_get_u32()?; // reserved
+        reader.try_get_u16()?; // reserved
 
-        let data_reference_index = reader.get_u16();
-        let version = reader.get_u16();
+        let data_reference_index = reader.try_get_u16()?;
+        let version = reader.try_get_u16()?;
 
-        reader.get_u16(); // reserved
-        reader.get_u32(); // reserved
+        reader.try_get_u16()?; // reserved
+        reader.try_get_u32()?; // reserved
 
-        let channelcount = reader.get_u16();
-        let samplesize = reader.get_u16();
+        let channelcount = reader.try_get_u16()?;
+        let samplesize = reader.try_get_u16()?;
 
-        reader.get_u32(); // pre-defined, reserved
+        reader.try_get_u32()?; // pre-defined, reserved
 
-        let samplerate = FixedPointU16::new_raw(reader.get_u32());
+        let samplerate = FixedPointU16::new_raw(reader.try_get_u32()?);
 
         if version == 1 {
             if reader.remaining() < 16 {
@@ -97,8 +115,24 @@ impl BlockReader for Mp4aBox {
             }
 
             // Skip QTFF
-            reader.get_u64();
-            reader.get_u64();
+            reader.try_get_u64()?;
+            reader.try_get_u64()?;
+        }
+
+        let mut esds = None;
+        let mut sinf = None;
+        let mut unknown = UnknownBoxes::new();
+
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
+
+            match bx.kind {
+                BoxType::EsdsBox => esds = Some(bx.read()?),
+                BoxType::SinfBox => sinf = Some(bx.read()?),
+                _ => unknown.push_at(position, bx.read_raw()),
+            }
         }
 
         Ok(Mp4aBox {
@@ -106,7 +140,9 @@ impl BlockReader for Mp4aBox {
             channelcount,
             samplesize,
             samplerate,
-            esds: reader.try_find_box::<EsdsBox>()?,
+            esds,
+            sinf,
+            unknown,
         })
     }
 
@@ -118,7 +154,7 @@ impl BlockReader for Mp4aBox {
 impl<W: Write> WriteBox<&mut W> for Mp4aBox {
     fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
         let size = self.box_size();
-        BoxHeader::new(Self::TYPE, size).write(writer)?;
+        BoxHeader::new(self.get_type(), size).write(writer)?;
 
         writer.write_u32::<BigEndian>(0)?; // reserved
         writer.write_u16::<BigEndian>(0)?; // reserved
@@ -130,9 +166,16 @@ impl<W: Write> WriteBox<&mut W> for Mp4aBox {
         writer.write_u32::<BigEndian>(0)?; // reserved
         writer.write_u32::<BigEndian>(self.samplerate.raw_value())?;
 
+        let mut children = self.unknown.writer(writer);
+
         if let Some(ref esds) = self.esds {
-            esds.write_box(writer)?;
+            children.write(esds)?;
+        }
+
+        if let Some(ref sinf) = self.sinf {
+            children.write(sinf)?;
         }
+        children.finish()?;
 
         Ok(size)
     }
@@ -177,7 +220,7 @@ impl Mp4Box for EsdsBox {
 
 impl BlockReader for EsdsBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
         let mut es_desc = None;
 
@@ -256,7 +299,7 @@ fn size_of_length(size: u32) -> u32 {
 fn write_desc<W: Write>(writer: &mut W, tag: u8, size: u32) -> Result<u64, Error> {
     writer.write_u8(tag)?;
 
-    if size as u64 > std::u32::MAX as u64 {
+    if size as u64 > u32::MAX as u64 {
         return Err(Error::InvalidData("invalid descriptor length range"));
     }
 
@@ -308,8 +351,8 @@ impl Descriptor for ESDescriptor {
 
 impl BlockReader for ESDescriptor {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let es_id = reader.get_u16();
-        reader.get_u8(); // XXX flags must be 0
+        let es_id = reader.try_get_u16()?;
+        reader.try_get_u8()?; // XXX flags must be 0
 
         let mut dec_config = None;
         let mut sl_config = None;
@@ -397,13 +440,13 @@ impl Descriptor for DecoderConfigDescriptor {
 
 impl BlockReader for DecoderConfigDescriptor {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let object_type_indication = reader.get_u8();
-        let byte_a = reader.get_u8();
+        let object_type_indication = reader.try_get_u8()?;
+        let byte_a = reader.try_get_u8()?;
         let stream_type = (byte_a & 0xFC) >> 2;
         let up_stream = byte_a & 0x02;
-        let buffer_size_db = reader.get_u24();
-        let max_bitrate = reader.get_u32();
-        let avg_bitrate = reader.get_u32();
+        let buffer_size_db = reader.try_get_u24()?;
+        let max_bitrate = reader.try_get_u32()?;
+        let avg_bitrate = reader.try_get_u32()?;
 
         let mut dec_specific = None;
 
@@ -511,8 +554,8 @@ fn get_chan_conf<'a, R: Reader<'a>>(
 
 impl BlockReader for DecoderSpecificDescriptor {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let byte_a = reader.get_u8();
-        let byte_b = reader.get_u8();
+        let byte_a = reader.try_get_u8()?;
+        let byte_b = reader.try_get_u8()?;
         let profile = get_audio_object_type(byte_a, byte_b);
 
         let freq_index;
@@ -570,7 +613,7 @@ impl Descriptor for SLConfigDescriptor {
 
 impl BlockReader for SLConfigDescriptor {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        reader.get_u8(); // pre-defined
+        reader.try_get_u8()?; // pre-defined
 
         Ok(SLConfigDescriptor {})
     }
@@ -590,7 +633,7 @@ impl<W: Write> WriteDesc<&mut W> for SLConfigDescriptor {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -623,6 +666,8 @@ mod tests {
                     sl_config: SLConfigDescriptor::default(),
                 },
             }),
+            sinf: None,
+            unknown: UnknownBoxes::new(),
         };
         let mut buf = Vec::new();
         src_box.write_box(&mut buf).unwrap();
@@ -645,6 +690,8 @@ mod tests {
             samplesize: 16,
             samplerate: FixedPointU16::new(48000),
             esds: None,
+            sinf: None,
+            unknown: UnknownBoxes::new(),
         };
         let mut buf = Vec::new();
         src_box.write_box(&mut buf).unwrap();
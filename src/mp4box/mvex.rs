@@ -7,7 +7,13 @@ use crate::mp4box::{mehd::MehdBox, trex::TrexBox};
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
 pub struct MvexBox {
     pub mehd: Option<MehdBox>,
-    pub trex: TrexBox,
+
+    /// One `trex` per track, formerly the single `trex` field.
+    #[serde(rename = "trex")]
+    pub trexs: Vec<TrexBox>,
+
+    #[serde(skip)]
+    pub unknown: UnknownBoxes,
 }
 
 impl MvexBox {
@@ -16,7 +22,14 @@ impl MvexBox {
     }
 
     pub fn get_size(&self) -> u64 {
-        HEADER_SIZE + self.mehd.as_ref().map(|x| x.box_size()).unwrap_or(0) + self.trex.box_size()
+        HEADER_SIZE
+            + self.mehd.as_ref().map(|x| x.box_size()).unwrap_or(0)
+            + self.trexs.iter().map(|x| x.box_size()).sum::<u64>()
+            + unknown_boxes_size(&self.unknown)
+    }
+
+    pub fn trex(&self, track_id: u32) -> Option<&TrexBox> {
+        self.trexs.iter().find(|x| x.track_id == track_id)
     }
 }
 
@@ -39,15 +52,37 @@ impl Mp4Box for MvexBox {
 
 impl BlockReader for MvexBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (mehd, trex) = reader.try_find_box2::<MehdBox, TrexBox>()?;
+        let mut mehd = None;
+        let mut trexs = Vec::new();
+        let mut unknown = UnknownBoxes::new();
+
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
+
+            match bx.kind {
+                BoxType::MehdBox => {
+                    mehd = Some(bx.read()?);
+                }
+
+                BoxType::TrexBox => {
+                    let idx = trexs.len();
+                    trexs.push(bx.read().map_err(|err| err.with_index(idx))?);
+                }
+
+                _ => unknown.push_at(position, bx.read_raw()),
+            }
+        }
 
-        if trex.is_none() {
+        if trexs.is_empty() {
             return Err(Error::BoxNotFound(BoxType::TrexBox));
         }
 
         Ok(MvexBox {
             mehd,
-            trex: trex.unwrap(),
+            trexs,
+            unknown,
         })
     }
 
@@ -61,11 +96,16 @@ impl<W: Write> WriteBox<&mut W> for MvexBox {
         let size = self.box_size();
         BoxHeader::new(Self::TYPE, size).write(writer)?;
 
+        let mut children = self.unknown.writer(writer);
+
         if let Some(mehd) = &self.mehd {
-            mehd.write_box(writer)?;
+            children.write(mehd)?;
         }
 
-        self.trex.write_box(writer)?;
+        for trex in self.trexs.iter() {
+            children.write(trex)?;
+        }
+        children.finish()?;
 
         Ok(size)
     }
@@ -85,7 +85,7 @@ impl Mp4Box for MvhdBox {
 
 impl BlockReader for MvhdBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
         let (creation_time, modification_time, timescale, duration) = if version == 1 {
             if reader.remaining() < Self::size_hint() - 4 + 12 {
@@ -93,43 +93,43 @@ impl BlockReader for MvhdBox {
             }
 
             (
-                reader.get_u64(),
-                reader.get_u64(),
-                reader.get_u32(),
-                reader.get_u64(),
+                reader.try_get_u64()?,
+                reader.try_get_u64()?,
+                reader.try_get_u32()?,
+                reader.try_get_u64()?,
             )
         } else if version == 0 {
             (
-                reader.get_u32() as u64,
-                reader.get_u32() as u64,
-                reader.get_u32(),
-                reader.get_u32() as u64,
+                reader.try_get_u32()? as u64,
+                reader.try_get_u32()? as u64,
+                reader.try_get_u32()?,
+                reader.try_get_u32()? as u64,
             )
         } else {
             return Err(Error::InvalidData("version must be 0 or 1"));
         };
 
-        let rate = FixedPointU16::new_raw(reader.get_u32());
-        let volume = FixedPointU8::new_raw(reader.get_u16());
+        let rate = FixedPointU16::new_raw(reader.try_get_u32()?);
+        let volume = FixedPointU8::new_raw(reader.try_get_u16()?);
 
-        reader.get_u16(); // reserved = 0
-        reader.get_u64(); // reserved = 0
+        reader.try_get_u16()?; // reserved = 0
+        reader.try_get_u64()?; // reserved = 0
 
         let matrix = tkhd::Matrix {
-            a: reader.get_i32(),
-            b: reader.get_i32(),
-            u: reader.get_i32(),
-            c: reader.get_i32(),
-            d: reader.get_i32(),
-            v: reader.get_i32(),
-            x: reader.get_i32(),
-            y: reader.get_i32(),
-            w: reader.get_i32(),
+            a: reader.try_get_i32()?,
+            b: reader.try_get_i32()?,
+            u: reader.try_get_i32()?,
+            c: reader.try_get_i32()?,
+            d: reader.try_get_i32()?,
+            v: reader.try_get_i32()?,
+            x: reader.try_get_i32()?,
+            y: reader.try_get_i32()?,
+            w: reader.try_get_i32()?,
         };
 
         reader.skip(24);
 
-        let next_track_id = reader.get_u32();
+        let next_track_id = reader.try_get_u32()?;
 
         Ok(MvhdBox {
             version,
@@ -196,7 +196,7 @@ impl<W: Write> WriteBox<&mut W> for MvhdBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -0,0 +1,82 @@
+use serde::Serialize;
+use std::io::Write;
+
+use crate::mp4box::*;
+
+/// Null media header, used by tracks such as WebVTT text tracks that have no specific media header.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct NmhdBox {
+    pub version: u8,
+    pub flags: u32,
+}
+
+impl NmhdBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::NmhdBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE + HEADER_EXT_SIZE
+    }
+}
+
+impl Mp4Box for NmhdBox {
+    const TYPE: BoxType = BoxType::NmhdBox;
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String, Error> {
+        Ok(String::new())
+    }
+}
+
+impl BlockReader for NmhdBox {
+    fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        let (version, flags) = read_box_header_ext(reader)?;
+
+        Ok(NmhdBox { version, flags })
+    }
+
+    fn size_hint() -> usize {
+        4
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for NmhdBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+        let size = self.box_size();
+        BoxHeader::new(Self::TYPE, size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+
+    #[tokio::test]
+    async fn test_nmhd() {
+        let src_box = NmhdBox::default();
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.kind, BoxType::NmhdBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = NmhdBox::read_block(&mut reader).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+}
@@ -0,0 +1,148 @@
+use byteorder::{BigEndian, WriteBytesExt};
+use serde::Serialize;
+use std::io::Write;
+
+use crate::mp4box::*;
+
+/// Protection system specific header.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct PsshBox {
+    pub version: u8,
+    pub flags: u32,
+    pub system_id: [u8; 16],
+
+    /// Key ids, version 1 only.
+    pub kids: Vec<[u8; 16]>,
+
+    #[serde(skip_serializing)]
+    pub data: Vec<u8>,
+}
+
+impl PsshBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::PsshBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + HEADER_EXT_SIZE + 16 + 4 + self.data.len() as u64;
+        if self.version > 0 {
+            size += 4 + 16 * self.kids.len() as u64;
+        }
+        size
+    }
+}
+
+impl Mp4Box for PsshBox {
+    const TYPE: BoxType = BoxType::PsshBox;
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String, Error> {
+        let system_id: String = self.system_id.iter().map(|b| format!("{b:02x}")).collect();
+        let s = format!(
+            "system_id={} kids={} data_size={}",
+            system_id,
+            self.kids.len(),
+            self.data.len()
+        );
+        Ok(s)
+    }
+}
+
+impl BlockReader for PsshBox {
+    fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        let (version, flags) = read_box_header_ext(reader)?;
+
+        let mut system_id = [0u8; 16];
+        reader.copy_to_slice(&mut system_id)?;
+
+        let mut kids = Vec::new();
+        if version > 0 {
+            let kid_count = reader.try_get_u32()?;
+            if kid_count as usize > reader.remaining() / 16 {
+                return Err(Error::InvalidData(
+                    "pssh kid_count indicates more entries than could fit in the box",
+                ));
+            }
+
+            for _ in 0..kid_count {
+                let mut kid = [0u8; 16];
+                reader.copy_to_slice(&mut kid)?;
+                kids.push(kid);
+            }
+        }
+
+        let data_size = reader.try_get_u32()?;
+
+        Ok(PsshBox {
+            version,
+            flags,
+            system_id,
+            kids,
+            data: reader.collect(data_size as usize)?,
+        })
+    }
+
+    fn size_hint() -> usize {
+        24
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for PsshBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+        let size = self.box_size();
+        BoxHeader::new(Self::TYPE, size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        writer.write_all(&self.system_id)?;
+        if self.version > 0 {
+            writer.write_u32::<BigEndian>(self.kids.len() as u32)?;
+            for kid in &self.kids {
+                writer.write_all(kid)?;
+            }
+        }
+
+        writer.write_u32::<BigEndian>(self.data.len() as u32)?;
+        writer.write_all(&self.data)?;
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+
+    #[tokio::test]
+    async fn test_pssh() {
+        let src_box = PsshBox {
+            version: 1,
+            flags: 0,
+            system_id: [
+                0x10, 0x77, 0xef, 0xec, 0xc0, 0xb2, 0x4d, 0x02, 0xac, 0xe3, 0x3c, 0x1e, 0x52, 0xe2,
+                0xfb, 0x4b,
+            ],
+            kids: vec![[1; 16], [2; 16]],
+            data: vec![0x08, 0x01, 0x12, 0x10],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.kind, BoxType::PsshBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = PsshBox::read_block(&mut reader).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+}
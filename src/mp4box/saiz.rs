@@ -0,0 +1,165 @@
+use byteorder::{BigEndian, WriteBytesExt};
+use serde::Serialize;
+use std::io::Write;
+
+use crate::mp4box::*;
+
+/// Sample auxiliary information sizes.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct SaizBox {
+    pub version: u8,
+    pub flags: u32,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aux_info_type: Option<FourCC>,
+    pub aux_info_type_parameter: u32,
+
+    pub default_sample_info_size: u8,
+    pub sample_count: u32,
+
+    #[serde(skip_serializing)]
+    pub sample_info_sizes: Vec<u8>,
+}
+
+impl SaizBox {
+    pub const FLAG_AUX_INFO_TYPE: u32 = 0x01;
+
+    pub fn get_type(&self) -> BoxType {
+        BoxType::SaizBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + HEADER_EXT_SIZE + 5;
+        if self.aux_info_type.is_some() {
+            size += 8;
+        }
+        if self.default_sample_info_size == 0 {
+            size += self.sample_info_sizes.len() as u64;
+        }
+        size
+    }
+
+    pub fn sample_info_size(&self, sample_idx: usize) -> Option<u8> {
+        if self.default_sample_info_size != 0 {
+            (sample_idx < self.sample_count as usize).then_some(self.default_sample_info_size)
+        } else {
+            self.sample_info_sizes.get(sample_idx).copied()
+        }
+    }
+}
+
+impl Mp4Box for SaizBox {
+    const TYPE: BoxType = BoxType::SaizBox;
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String, Error> {
+        let s = format!(
+            "default_sample_info_size={} sample_count={}",
+            self.default_sample_info_size, self.sample_count
+        );
+        Ok(s)
+    }
+}
+
+impl BlockReader for SaizBox {
+    fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        let (version, flags) = read_box_header_ext(reader)?;
+
+        let (aux_info_type, aux_info_type_parameter) = if flags & Self::FLAG_AUX_INFO_TYPE != 0 {
+            (Some(reader.try_get_u32()?.into()), reader.try_get_u32()?)
+        } else {
+            (None, 0)
+        };
+
+        let default_sample_info_size = reader.try_get_u8()?;
+        let sample_count = reader.try_get_u32()?;
+
+        let sample_info_sizes = if default_sample_info_size == 0 {
+            reader.collect(sample_count as usize)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(SaizBox {
+            version,
+            flags,
+            aux_info_type,
+            aux_info_type_parameter,
+            default_sample_info_size,
+            sample_count,
+            sample_info_sizes,
+        })
+    }
+
+    fn size_hint() -> usize {
+        9
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for SaizBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+        let size = self.box_size();
+        BoxHeader::new(Self::TYPE, size).write(writer)?;
+
+        let flags = if self.aux_info_type.is_some() {
+            self.flags | Self::FLAG_AUX_INFO_TYPE
+        } else {
+            self.flags & !Self::FLAG_AUX_INFO_TYPE
+        };
+
+        write_box_header_ext(writer, self.version, flags)?;
+
+        if let Some(aux_info_type) = self.aux_info_type {
+            writer.write_u32::<BigEndian>(aux_info_type.into())?;
+            writer.write_u32::<BigEndian>(self.aux_info_type_parameter)?;
+        }
+
+        writer.write_u8(self.default_sample_info_size)?;
+        writer.write_u32::<BigEndian>(self.sample_count)?;
+
+        if self.default_sample_info_size == 0 {
+            writer.write_all(&self.sample_info_sizes)?;
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+
+    #[tokio::test]
+    async fn test_saiz() {
+        let src_box = SaizBox {
+            version: 0,
+            flags: SaizBox::FLAG_AUX_INFO_TYPE,
+            aux_info_type: Some(str::parse("cenc").unwrap()),
+            aux_info_type_parameter: 0,
+            default_sample_info_size: 0,
+            sample_count: 3,
+            sample_info_sizes: vec![16, 22, 16],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.kind, BoxType::SaizBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = SaizBox::read_block(&mut reader).unwrap();
+        assert_eq!(src_box, dst_box);
+        assert_eq!(dst_box.sample_info_size(1), Some(22));
+        assert_eq!(dst_box.sample_info_size(3), None);
+    }
+}
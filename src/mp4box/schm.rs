@@ -0,0 +1,131 @@
+use byteorder::{BigEndian, WriteBytesExt};
+use serde::Serialize;
+use std::io::Write;
+
+use crate::mp4box::*;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct SchmBox {
+    pub version: u8,
+    pub flags: u32,
+    pub scheme_type: FourCC,
+    pub scheme_version: u32,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheme_uri: Option<String>,
+}
+
+impl SchmBox {
+    pub const FLAG_SCHEME_URI: u32 = 0x01;
+
+    pub fn get_type(&self) -> BoxType {
+        BoxType::SchmBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + HEADER_EXT_SIZE + 8;
+        if let Some(ref uri) = self.scheme_uri {
+            size += uri.len() as u64 + 1;
+        }
+        size
+    }
+}
+
+impl Mp4Box for SchmBox {
+    const TYPE: BoxType = BoxType::SchmBox;
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String, Error> {
+        let s = format!(
+            "scheme_type={} scheme_version={:#x}",
+            self.scheme_type, self.scheme_version
+        );
+        Ok(s)
+    }
+}
+
+impl BlockReader for SchmBox {
+    fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        let (version, flags) = read_box_header_ext(reader)?;
+
+        let scheme_type = reader.try_get_u32()?.into();
+        let scheme_version = reader.try_get_u32()?;
+        let scheme_uri = if flags & Self::FLAG_SCHEME_URI != 0 {
+            Some(reader.get_null_terminated_string())
+        } else {
+            None
+        };
+
+        Ok(SchmBox {
+            version,
+            flags,
+            scheme_type,
+            scheme_version,
+            scheme_uri,
+        })
+    }
+
+    fn size_hint() -> usize {
+        12
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for SchmBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+        let size = self.box_size();
+        BoxHeader::new(Self::TYPE, size).write(writer)?;
+
+        let flags = if self.scheme_uri.is_some() {
+            self.flags | Self::FLAG_SCHEME_URI
+        } else {
+            self.flags & !Self::FLAG_SCHEME_URI
+        };
+
+        write_box_header_ext(writer, self.version, flags)?;
+
+        writer.write_u32::<BigEndian>(self.scheme_type.into())?;
+        writer.write_u32::<BigEndian>(self.scheme_version)?;
+
+        if let Some(ref uri) = self.scheme_uri {
+            writer.write_all(uri.as_bytes())?;
+            writer.write_u8(0)?;
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+
+    #[tokio::test]
+    async fn test_schm() {
+        let src_box = SchmBox {
+            version: 0,
+            flags: SchmBox::FLAG_SCHEME_URI,
+            scheme_type: str::parse("cbcs").unwrap(),
+            scheme_version: 0x10000,
+            scheme_uri: Some(String::from("urn:example")),
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.kind, BoxType::SchmBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = SchmBox::read_block(&mut reader).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+}
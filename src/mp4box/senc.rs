@@ -0,0 +1,204 @@
+use byteorder::{BigEndian, WriteBytesExt};
+use serde::Serialize;
+use std::io::Write;
+
+use crate::mp4box::*;
+
+/// Sample encryption box.
+///
+/// The layout of the entries depends on the per-sample IV size which is signalled in
+/// `tenc`, so the entries are kept in their serialized form and decoded with
+/// [`SencBox::samples`].
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct SencBox {
+    pub version: u8,
+    pub flags: u32,
+    pub sample_count: u32,
+
+    #[serde(skip_serializing)]
+    pub data: Vec<u8>,
+}
+
+/// Per-sample encryption parameters.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct SampleEncryption {
+    pub iv: Vec<u8>,
+    pub subsamples: Vec<SubsampleEncryption>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct SubsampleEncryption {
+    pub bytes_of_clear_data: u16,
+    pub bytes_of_protected_data: u32,
+}
+
+impl SencBox {
+    pub const FLAG_USE_SUBSAMPLE_ENCRYPTION: u32 = 0x02;
+
+    pub fn new(samples: &[SampleEncryption]) -> Self {
+        let use_subsamples = samples.iter().any(|s| !s.subsamples.is_empty());
+        let mut data = Vec::new();
+
+        for sample in samples {
+            data.extend_from_slice(&sample.iv);
+
+            if use_subsamples {
+                data.extend_from_slice(&(sample.subsamples.len() as u16).to_be_bytes());
+                for sub in &sample.subsamples {
+                    data.extend_from_slice(&sub.bytes_of_clear_data.to_be_bytes());
+                    data.extend_from_slice(&sub.bytes_of_protected_data.to_be_bytes());
+                }
+            }
+        }
+
+        SencBox {
+            version: 0,
+            flags: if use_subsamples {
+                Self::FLAG_USE_SUBSAMPLE_ENCRYPTION
+            } else {
+                0
+            },
+            sample_count: samples.len() as u32,
+            data,
+        }
+    }
+
+    pub fn get_type(&self) -> BoxType {
+        BoxType::SencBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE + HEADER_EXT_SIZE + 4 + self.data.len() as u64
+    }
+
+    /// Decodes the entries using the per-sample IV size from `tenc`.
+    pub fn samples(&self, per_sample_iv_size: u8) -> Result<Vec<SampleEncryption>, Error> {
+        self.read_samples(&mut self.data.as_slice(), per_sample_iv_size)
+    }
+
+    fn read_samples<'a>(
+        &self,
+        reader: &mut impl Reader<'a>,
+        per_sample_iv_size: u8,
+    ) -> Result<Vec<SampleEncryption>, Error> {
+        let mut samples = Vec::with_capacity(self.sample_count.min(4096) as usize);
+
+        for _ in 0..self.sample_count {
+            let iv = reader.collect(per_sample_iv_size as usize)?;
+            let mut subsamples = Vec::new();
+
+            if self.flags & Self::FLAG_USE_SUBSAMPLE_ENCRYPTION != 0 {
+                let count = reader.try_get_u16()?;
+                if count as usize > reader.remaining() / 6 {
+                    return Err(Error::InvalidData(
+                        "senc subsample_count indicates more entries than could fit in the box",
+                    ));
+                }
+
+                subsamples.reserve(count as usize);
+                for _ in 0..count {
+                    subsamples.push(SubsampleEncryption {
+                        bytes_of_clear_data: reader.try_get_u16()?,
+                        bytes_of_protected_data: reader.try_get_u32()?,
+                    });
+                }
+            }
+
+            samples.push(SampleEncryption { iv, subsamples });
+        }
+
+        Ok(samples)
+    }
+}
+
+impl Mp4Box for SencBox {
+    const TYPE: BoxType = BoxType::SencBox;
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String, Error> {
+        let s = format!("sample_count={}", self.sample_count);
+        Ok(s)
+    }
+}
+
+impl BlockReader for SencBox {
+    fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        let (version, flags) = read_box_header_ext(reader)?;
+
+        Ok(SencBox {
+            version,
+            flags,
+            sample_count: reader.try_get_u32()?,
+            data: reader.collect_remaining(),
+        })
+    }
+
+    fn size_hint() -> usize {
+        8
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for SencBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+        let size = self.box_size();
+        BoxHeader::new(Self::TYPE, size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        writer.write_u32::<BigEndian>(self.sample_count)?;
+        writer.write_all(&self.data)?;
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+
+    #[tokio::test]
+    async fn test_senc() {
+        let samples = vec![
+            SampleEncryption {
+                iv: vec![1, 2, 3, 4, 5, 6, 7, 8],
+                subsamples: vec![
+                    SubsampleEncryption {
+                        bytes_of_clear_data: 5,
+                        bytes_of_protected_data: 1024,
+                    },
+                    SubsampleEncryption {
+                        bytes_of_clear_data: 3,
+                        bytes_of_protected_data: 16,
+                    },
+                ],
+            },
+            SampleEncryption {
+                iv: vec![8, 7, 6, 5, 4, 3, 2, 1],
+                subsamples: vec![],
+            },
+        ];
+
+        let src_box = SencBox::new(&samples);
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.kind, BoxType::SencBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = SencBox::read_block(&mut reader).unwrap();
+        assert_eq!(src_box, dst_box);
+        assert_eq!(dst_box.samples(8).unwrap(), samples);
+        assert!(dst_box.samples(16).is_err());
+    }
+}
@@ -0,0 +1,304 @@
+use byteorder::{BigEndian, WriteBytesExt};
+use serde::Serialize;
+use std::io::Write;
+
+use crate::mp4box::*;
+
+/// Protection scheme information, wraps the original sample entry of `encv`/`enca`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct SinfBox {
+    pub frma: FrmaBox,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schm: Option<SchmBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schi: Option<SchiBox>,
+
+    #[serde(skip)]
+    pub unknown: UnknownBoxes,
+}
+
+impl SinfBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::SinfBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + self.frma.box_size();
+        if let Some(ref schm) = self.schm {
+            size += schm.box_size();
+        }
+        if let Some(ref schi) = self.schi {
+            size += schi.box_size();
+        }
+        size + unknown_boxes_size(&self.unknown)
+    }
+
+    /// Protection scheme, e.g. `cenc` or `cbcs`.
+    pub fn scheme_type(&self) -> Option<FourCC> {
+        self.schm.as_ref().map(|schm| schm.scheme_type)
+    }
+
+    pub fn tenc(&self) -> Option<&TencBox> {
+        self.schi.as_ref().and_then(|schi| schi.tenc.as_ref())
+    }
+}
+
+impl Mp4Box for SinfBox {
+    const TYPE: BoxType = BoxType::SinfBox;
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String, Error> {
+        let s = format!("original_format={}", self.frma.original_format);
+        Ok(s)
+    }
+}
+
+impl BlockReader for SinfBox {
+    fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        let mut frma = None;
+        let mut schm = None;
+        let mut schi = None;
+        let mut unknown = UnknownBoxes::new();
+
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
+
+            match bx.kind {
+                BoxType::FrmaBox => frma = Some(bx.read()?),
+                BoxType::SchmBox => schm = Some(bx.read()?),
+                BoxType::SchiBox => schi = Some(bx.read()?),
+                _ => unknown.push_at(position, bx.read_raw()),
+            }
+        }
+
+        let Some(frma) = frma else {
+            return Err(Error::BoxNotFound(BoxType::FrmaBox));
+        };
+
+        Ok(SinfBox {
+            frma,
+            schm,
+            schi,
+            unknown,
+        })
+    }
+
+    fn size_hint() -> usize {
+        FrmaBox::size_hint()
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for SinfBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+        let size = self.box_size();
+        BoxHeader::new(Self::TYPE, size).write(writer)?;
+
+        let mut children = self.unknown.writer(writer);
+        children.write(&self.frma)?;
+        if let Some(ref schm) = self.schm {
+            children.write(schm)?;
+        }
+        if let Some(ref schi) = self.schi {
+            children.write(schi)?;
+        }
+        children.finish()?;
+
+        Ok(size)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct FrmaBox {
+    pub original_format: FourCC,
+}
+
+impl Mp4Box for FrmaBox {
+    const TYPE: BoxType = BoxType::FrmaBox;
+
+    fn box_size(&self) -> u64 {
+        HEADER_SIZE + 4
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String, Error> {
+        let s = format!("original_format={}", self.original_format);
+        Ok(s)
+    }
+}
+
+impl BlockReader for FrmaBox {
+    fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        Ok(FrmaBox {
+            original_format: reader.try_get_u32()?.into(),
+        })
+    }
+
+    fn size_hint() -> usize {
+        4
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for FrmaBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+        let size = self.box_size();
+        BoxHeader::new(Self::TYPE, size).write(writer)?;
+
+        writer.write_u32::<BigEndian>(self.original_format.into())?;
+
+        Ok(size)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct SchiBox {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenc: Option<TencBox>,
+
+    /// Boxes of other protection systems, such as `adkm`.
+    #[serde(skip)]
+    pub unknown: UnknownBoxes,
+}
+
+impl Mp4Box for SchiBox {
+    const TYPE: BoxType = BoxType::SchiBox;
+
+    fn box_size(&self) -> u64 {
+        HEADER_SIZE
+            + self.tenc.as_ref().map(|x| x.box_size()).unwrap_or(0)
+            + unknown_boxes_size(&self.unknown)
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String, Error> {
+        Ok(String::new())
+    }
+}
+
+impl BlockReader for SchiBox {
+    fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        let mut tenc = None;
+        let mut unknown = UnknownBoxes::new();
+
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
+
+            match bx.kind {
+                BoxType::TencBox => tenc = Some(bx.read()?),
+                _ => unknown.push_at(position, bx.read_raw()),
+            }
+        }
+
+        Ok(SchiBox { tenc, unknown })
+    }
+
+    fn size_hint() -> usize {
+        0
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for SchiBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+        let size = self.box_size();
+        BoxHeader::new(Self::TYPE, size).write(writer)?;
+
+        let mut children = self.unknown.writer(writer);
+        if let Some(ref tenc) = self.tenc {
+            children.write(tenc)?;
+        }
+        children.finish()?;
+
+        Ok(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_sinf() {
+        let src_box = SinfBox {
+            frma: FrmaBox {
+                original_format: BoxType::Avc1Box.into(),
+            },
+            schm: Some(SchmBox {
+                scheme_type: str::parse("cenc").unwrap(),
+                scheme_version: 0x10000,
+                ..Default::default()
+            }),
+            schi: Some(SchiBox {
+                tenc: Some(TencBox {
+                    default_is_protected: 1,
+                    default_per_sample_iv_size: 8,
+                    default_kid: [7; 16],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.kind, BoxType::SinfBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = SinfBox::read_block(&mut reader).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+
+    #[test]
+    fn test_sinf_unknown() {
+        let unknown = |kind: &[u8; 4], data: &'static [u8]| {
+            UnknownBox::Raw(
+                BoxType::from(u32::from_be_bytes(*kind)),
+                Bytes::from_static(data),
+            )
+        };
+
+        let src_box = SinfBox {
+            frma: FrmaBox {
+                original_format: BoxType::Mp4aBox.into(),
+            },
+            schi: Some(SchiBox {
+                unknown: vec![unknown(b"adkm", &[0, 0, 0, 0, 1, 2])].into(),
+                ..Default::default()
+            }),
+            unknown: vec![unknown(b"xtra", &[1, 2, 3, 4])].into(),
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let dst_box = SinfBox::read_block(&mut &buf[8..]).unwrap();
+        assert_eq!(src_box, dst_box);
+
+        let mut rewritten = Vec::new();
+        dst_box.write_box(&mut rewritten).unwrap();
+        assert_eq!(rewritten, buf);
+    }
+}
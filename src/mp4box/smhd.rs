@@ -52,12 +52,12 @@ impl Mp4Box for SmhdBox {
 
 impl BlockReader for SmhdBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
         Ok(SmhdBox {
             version,
             flags,
-            balance: FixedPointI8::new_raw(reader.get_i16()),
+            balance: FixedPointI8::new_raw(reader.try_get_i16()?),
         })
     }
 
@@ -80,7 +80,7 @@ impl<W: Write> WriteBox<&mut W> for SmhdBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
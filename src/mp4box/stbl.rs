@@ -25,6 +25,10 @@ pub struct StblBox {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub co64: Option<Co64Box>,
+
+    /// Children such as `sgpd`, `sbgp` or `sdtp`.
+    #[serde(skip)]
+    pub unknown: UnknownBoxes,
 }
 
 impl StblBox {
@@ -50,7 +54,7 @@ impl StblBox {
         if let Some(ref co64) = self.co64 {
             size += co64.box_size();
         }
-        size
+        size + unknown_boxes_size(&self.unknown)
     }
 }
 
@@ -81,8 +85,13 @@ impl BlockReader for StblBox {
         let mut stsz = None;
         let mut stco = None;
         let mut co64 = None;
+        let mut unknown = UnknownBoxes::new();
+
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
 
-        while let Some(mut bx) = reader.get_box()? {
             match bx.kind {
                 BoxType::StsdBox => {
                     stsd = Some(bx.read()?);
@@ -116,7 +125,7 @@ impl BlockReader for StblBox {
                     co64 = Some(bx.read()?);
                 }
 
-                _ => continue,
+                _ => unknown.push_at(position, bx.read_raw()),
             }
         }
 
@@ -149,6 +158,7 @@ impl BlockReader for StblBox {
             stsz: stsz.unwrap(),
             stco,
             co64,
+            unknown,
         })
     }
 
@@ -162,22 +172,25 @@ impl<W: Write> WriteBox<&mut W> for StblBox {
         let size = self.box_size();
         BoxHeader::new(Self::TYPE, size).write(writer)?;
 
-        self.stsd.write_box(writer)?;
-        self.stts.write_box(writer)?;
+        let mut children = self.unknown.writer(writer);
+
+        children.write(&self.stsd)?;
+        children.write(&self.stts)?;
         if let Some(ref ctts) = self.ctts {
-            ctts.write_box(writer)?;
+            children.write(ctts)?;
         }
         if let Some(ref stss) = self.stss {
-            stss.write_box(writer)?;
+            children.write(stss)?;
         }
-        self.stsc.write_box(writer)?;
-        self.stsz.write_box(writer)?;
+        children.write(&self.stsc)?;
+        children.write(&self.stsz)?;
         if let Some(ref stco) = self.stco {
-            stco.write_box(writer)?;
+            children.write(stco)?;
         }
         if let Some(ref co64) = self.co64 {
-            co64.write_box(writer)?;
+            children.write(co64)?;
         }
+        children.finish()?;
 
         Ok(size)
     }
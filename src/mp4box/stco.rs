@@ -63,10 +63,10 @@ impl Mp4Box for StcoBox {
 
 impl BlockReader for StcoBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
         let entry_size = size_of::<u32>(); // chunk_offset
-        let entry_count = reader.get_u32();
+        let entry_count = reader.try_get_u32()?;
         if entry_count as usize > reader.remaining() / entry_size {
             return Err(Error::InvalidData(
                 "stco entry_count indicates more entries than could fit in the box",
@@ -75,7 +75,7 @@ impl BlockReader for StcoBox {
 
         let mut entries = Vec::with_capacity(entry_count as usize);
         for _i in 0..entry_count {
-            let chunk_offset = reader.get_u32();
+            let chunk_offset = reader.try_get_u32()?;
             entries.push(chunk_offset);
         }
 
@@ -125,7 +125,7 @@ impl std::convert::TryFrom<&co64::Co64Box> for StcoBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -0,0 +1,82 @@
+use serde::Serialize;
+use std::io::Write;
+
+use crate::mp4box::*;
+
+/// Subtitle media header of `subt` tracks such as TTML.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct SthdBox {
+    pub version: u8,
+    pub flags: u32,
+}
+
+impl SthdBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::SthdBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE + HEADER_EXT_SIZE
+    }
+}
+
+impl Mp4Box for SthdBox {
+    const TYPE: BoxType = BoxType::SthdBox;
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String, Error> {
+        Ok(String::new())
+    }
+}
+
+impl BlockReader for SthdBox {
+    fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        let (version, flags) = read_box_header_ext(reader)?;
+
+        Ok(SthdBox { version, flags })
+    }
+
+    fn size_hint() -> usize {
+        4
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for SthdBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+        let size = self.box_size();
+        BoxHeader::new(Self::TYPE, size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+
+    #[tokio::test]
+    async fn test_sthd() {
+        let src_box = SthdBox::default();
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.kind, BoxType::SthdBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = SthdBox::read_block(&mut reader).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+}
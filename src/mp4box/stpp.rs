@@ -0,0 +1,205 @@
+use byteorder::{BigEndian, WriteBytesExt};
+use serde::Serialize;
+use std::io::Write;
+
+use crate::mp4box::*;
+
+/// XML subtitle sample entry (ISO/IEC 14496-30), used for TTML and IMSC.
+///
+/// Every sample is a complete TTML document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StppBox {
+    pub data_reference_index: u16,
+
+    /// Space separated XML namespaces of the documents.
+    pub namespace: String,
+    pub schema_location: String,
+
+    /// MIME types of images or fonts referenced by the documents.
+    pub auxiliary_mime_types: String,
+
+    /// `mime`, `btrt` and other child boxes.
+    #[serde(skip)]
+    pub unknown: UnknownBoxes,
+}
+
+impl Default for StppBox {
+    fn default() -> Self {
+        StppBox {
+            data_reference_index: 1,
+            namespace: "http://www.w3.org/ns/ttml".to_string(),
+            schema_location: String::new(),
+            auxiliary_mime_types: String::new(),
+            unknown: UnknownBoxes::new(),
+        }
+    }
+}
+
+impl StppBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::StppBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE
+            + 8
+            + self.namespace.len() as u64
+            + 1
+            + self.schema_location.len() as u64
+            + 1
+            + self.auxiliary_mime_types.len() as u64
+            + 1
+            + unknown_boxes_size(&self.unknown)
+    }
+}
+
+impl Mp4Box for StppBox {
+    const TYPE: BoxType = BoxType::StppBox;
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String, Error> {
+        let s = format!(
+            "data_reference_index={} namespace={}",
+            self.data_reference_index, self.namespace
+        );
+        Ok(s)
+    }
+}
+
+impl BlockReader for StppBox {
+    fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        reader.try_get_u32()?; // reserved
+        reader.try_get_u16()?; // reserved
+        let data_reference_index = reader.try_get_u16()?;
+
+        let namespace = reader.get_null_terminated_string();
+        let schema_location = reader.get_null_terminated_string();
+        let auxiliary_mime_types = reader.get_null_terminated_string();
+
+        // optional mime and btrt boxes follow
+        let mut unknown = UnknownBoxes::new();
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
+            unknown.push_at(position, bx.read_raw());
+        }
+
+        Ok(StppBox {
+            data_reference_index,
+            namespace,
+            schema_location,
+            auxiliary_mime_types,
+            unknown,
+        })
+    }
+
+    fn size_hint() -> usize {
+        8
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for StppBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+        let size = self.box_size();
+        BoxHeader::new(Self::TYPE, size).write(writer)?;
+
+        writer.write_u32::<BigEndian>(0)?; // reserved
+        writer.write_u16::<BigEndian>(0)?; // reserved
+        writer.write_u16::<BigEndian>(self.data_reference_index)?;
+
+        for s in [
+            &self.namespace,
+            &self.schema_location,
+            &self.auxiliary_mime_types,
+        ] {
+            writer.write_all(s.as_bytes())?;
+            writer.write_u8(0)?;
+        }
+
+        self.unknown.writer(writer).finish()?;
+
+        Ok(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_stpp() {
+        let src_box = StppBox {
+            data_reference_index: 1,
+            namespace: "http://www.w3.org/ns/ttml http://www.w3.org/ns/ttml#styling".to_string(),
+            schema_location: "http://www.w3.org/ns/ttml ttml.xsd".to_string(),
+            auxiliary_mime_types: "image/png".to_string(),
+            unknown: UnknownBoxes::new(),
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.kind, BoxType::StppBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = StppBox::read_block(&mut reader).unwrap();
+        assert_eq!(src_box, dst_box);
+
+        let mut rewritten = Vec::new();
+        dst_box.write_box(&mut rewritten).unwrap();
+        assert_eq!(rewritten, buf);
+    }
+
+    #[test]
+    fn test_stpp_empty_strings() {
+        let src_box = StppBox {
+            namespace: "http://www.w3.org/ns/ttml".to_string(),
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let dst_box = StppBox::read_block(&mut &buf[8..]).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+
+    #[test]
+    fn test_stpp_unknown() {
+        let src_box = StppBox {
+            unknown: vec![
+                UnknownBox::Raw(
+                    BoxType::from(u32::from_be_bytes(*b"mime")),
+                    Bytes::from_static(b"\0\0\0\0application/ttml+xml;codecs=im1t\0"),
+                ),
+                UnknownBox::Raw(
+                    BoxType::from(u32::from_be_bytes(*b"btrt")),
+                    Bytes::from_static(&[0, 0, 0, 0, 0, 0, 0x10, 0, 0, 0, 0x08, 0]),
+                ),
+            ]
+            .into(),
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let dst_box = StppBox::read_block(&mut &buf[8..]).unwrap();
+        assert_eq!(src_box, dst_box);
+
+        let mut rewritten = Vec::new();
+        dst_box.write_box(&mut rewritten).unwrap();
+        assert_eq!(rewritten, buf);
+    }
+}
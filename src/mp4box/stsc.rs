@@ -51,10 +51,10 @@ impl Mp4Box for StscBox {
 
 impl BlockReader for StscBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
         let entry_size = size_of::<u32>() + size_of::<u32>() + size_of::<u32>(); // first_chunk + samples_per_chunk + sample_description_index
-        let entry_count = reader.get_u32();
+        let entry_count = reader.try_get_u32()?;
         if entry_count as usize > reader.remaining() / entry_size {
             return Err(Error::InvalidData(
                 "stsc entry_count indicates more entries than could fit in the box",
@@ -63,9 +63,9 @@ impl BlockReader for StscBox {
         let mut entries = Vec::with_capacity(entry_count as usize);
         for _ in 0..entry_count {
             let entry = StscEntry {
-                first_chunk: reader.get_u32(),
-                samples_per_chunk: reader.get_u32(),
-                sample_description_index: reader.get_u32(),
+                first_chunk: reader.try_get_u32()?,
+                samples_per_chunk: reader.try_get_u32()?,
+                sample_description_index: reader.try_get_u32()?,
                 first_sample: 0,
             };
             entries.push(entry);
@@ -121,7 +121,7 @@ impl<W: Write> WriteBox<&mut W> for StscBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
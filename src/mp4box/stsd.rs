@@ -25,6 +25,19 @@ pub struct StsdBox {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tx3g: Option<Tx3gBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wvtt: Option<WvttBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stpp: Option<StppBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub c608: Option<C608Box>,
+
+    /// Sample entries of other formats, written back at their original position.
+    #[serde(skip)]
+    pub unknown: UnknownBoxes,
 }
 
 impl StsdBox {
@@ -44,12 +57,47 @@ impl StsdBox {
             size += mp4a.box_size();
         } else if let Some(ref tx3g) = self.tx3g {
             size += tx3g.box_size();
+        } else if let Some(ref wvtt) = self.wvtt {
+            size += wvtt.box_size();
+        } else if let Some(ref stpp) = self.stpp {
+            size += stpp.box_size();
+        } else if let Some(ref c608) = self.c608 {
+            size += c608.box_size();
         }
 
-        size
+        size + unknown_boxes_size(&self.unknown)
+    }
+
+    fn has_known_entry(&self) -> bool {
+        self.avc1.is_some()
+            || self.hev1.is_some()
+            || self.vp09.is_some()
+            || self.mp4a.is_some()
+            || self.tx3g.is_some()
+            || self.wvtt.is_some()
+            || self.stpp.is_some()
+            || self.c608.is_some()
+    }
+
+    /// Protection scheme information of an `encv`/`enca` sample entry.
+    pub fn sinf(&self) -> Option<&SinfBox> {
+        if let Some(ref avc1) = self.avc1 {
+            avc1.sinf.as_ref()
+        } else if let Some(ref hev1) = self.hev1 {
+            hev1.sinf.as_ref()
+        } else if let Some(ref vp09) = self.vp09 {
+            vp09.sinf.as_ref()
+        } else if let Some(ref mp4a) = self.mp4a {
+            mp4a.sinf.as_ref()
+        } else {
+            None
+        }
     }
 }
 
+/// Size of the `VisualSampleEntry` fields preceding the child boxes.
+const VISUAL_SAMPLE_ENTRY_SIZE: usize = 78;
+
 impl Mp4Box for StsdBox {
     const TYPE: BoxType = BoxType::StsdBox;
 
@@ -69,17 +117,25 @@ impl Mp4Box for StsdBox {
 
 impl BlockReader for StsdBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
-        reader.get_u32(); // XXX entry_count
+        reader.try_get_u32()?; // XXX entry_count
 
         let mut avc1 = None;
         let mut hev1 = None;
         let mut vp09 = None;
         let mut mp4a = None;
         let mut tx3g = None;
+        let mut wvtt = None;
+        let mut stpp = None;
+        let mut c608 = None;
+        let mut unknown = UnknownBoxes::new();
+
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
 
-        while let Some(mut bx) = reader.get_box()? {
             match bx.kind {
                 BoxType::Avc1Box => {
                     avc1 = Some(bx.read()?);
@@ -101,7 +157,56 @@ impl BlockReader for StsdBox {
                     tx3g = Some(bx.read()?);
                 }
 
-                _ => {}
+                BoxType::WvttBox => {
+                    wvtt = Some(bx.read()?);
+                }
+
+                BoxType::StppBox => {
+                    stpp = Some(bx.read()?);
+                }
+
+                BoxType::C608Box => {
+                    c608 = Some(bx.read()?);
+                }
+
+                BoxType::EncvBox => {
+                    // the original sample entry type is only known from sinf.frma
+                    let data = bx.inner.collect_remaining();
+                    let Some(sinf) = data
+                        .get(VISUAL_SAMPLE_ENTRY_SIZE..)
+                        .map(|mut children| children.try_find_box::<SinfBox>())
+                        .transpose()?
+                        .flatten()
+                    else {
+                        return Err(Error::BoxNotFound(BoxType::SinfBox));
+                    };
+
+                    let mut reader = data.as_slice();
+                    match BoxType::from(u32::from(sinf.frma.original_format)) {
+                        BoxType::Avc1Box => avc1 = Some(Avc1Box::read_block(&mut reader)?),
+                        BoxType::Hev1Box | BoxType::Hvc1Box => {
+                            hev1 = Some(Hev1Box::read_block(&mut reader)?)
+                        }
+                        BoxType::Vp09Box => vp09 = Some(Vp09Box::read_block(&mut reader)?),
+                        _ => unknown.push_at(position, UnknownBox::Raw(bx.kind, Bytes::from(data))),
+                    }
+                }
+
+                BoxType::EncaBox => {
+                    // mp4a is the only audio entry modelled, others are kept like encv
+                    let data = bx.inner.collect_remaining();
+                    let entry = Mp4aBox::read_block(&mut data.as_slice())?;
+                    let Some(ref sinf) = entry.sinf else {
+                        return Err(Error::BoxNotFound(BoxType::SinfBox));
+                    };
+
+                    match BoxType::from(u32::from(sinf.frma.original_format)) {
+                        BoxType::Mp4aBox => mp4a = Some(entry),
+                        _ => unknown.push_at(position, UnknownBox::Raw(bx.kind, Bytes::from(data))),
+                    }
+                }
+
+                _ => unknown.push_at(position, bx.read_raw()),
             }
         }
 
@@ -113,6 +218,10 @@ impl BlockReader for StsdBox {
             vp09,
             mp4a,
             tx3g,
+            wvtt,
+            stpp,
+            c608,
+            unknown,
         })
     }
 
@@ -128,20 +237,117 @@ impl<W: Write> WriteBox<&mut W> for StsdBox {
 
         write_box_header_ext(writer, self.version, self.flags)?;
 
-        writer.write_u32::<BigEndian>(1)?; // entry_count
+        let entry_count = self.has_known_entry() as usize + self.unknown.len();
+        writer.write_u32::<BigEndian>(entry_count as u32)?;
+
+        let mut children = self.unknown.writer(writer);
 
         if let Some(ref avc1) = self.avc1 {
-            avc1.write_box(writer)?;
+            children.write(avc1)?;
         } else if let Some(ref hev1) = self.hev1 {
-            hev1.write_box(writer)?;
+            children.write(hev1)?;
         } else if let Some(ref vp09) = self.vp09 {
-            vp09.write_box(writer)?;
+            children.write(vp09)?;
         } else if let Some(ref mp4a) = self.mp4a {
-            mp4a.write_box(writer)?;
+            children.write(mp4a)?;
         } else if let Some(ref tx3g) = self.tx3g {
-            tx3g.write_box(writer)?;
+            children.write(tx3g)?;
+        } else if let Some(ref wvtt) = self.wvtt {
+            children.write(wvtt)?;
+        } else if let Some(ref stpp) = self.stpp {
+            children.write(stpp)?;
+        } else if let Some(ref c608) = self.c608 {
+            children.write(c608)?;
         }
+        children.finish()?;
 
         Ok(size)
     }
 }
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::{avc1::AvcCBox, BoxHeader};
+
+    #[tokio::test]
+    async fn test_stsd_encv() {
+        let src_box = StsdBox {
+            avc1: Some(Avc1Box {
+                sinf: Some(SinfBox {
+                    frma: FrmaBox {
+                        original_format: BoxType::Avc1Box.into(),
+                    },
+                    schm: Some(SchmBox {
+                        scheme_type: str::parse("cenc").unwrap(),
+                        scheme_version: 0x10000,
+                        ..Default::default()
+                    }),
+                    schi: Some(SchiBox {
+                        tenc: Some(TencBox {
+                            default_is_protected: 1,
+                            default_per_sample_iv_size: 16,
+                            default_kid: [1; 16],
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                avcc: AvcCBox {
+                    length_size_minus_one: 3,
+                    ..AvcCBox::new(&[0x67, 0x64, 0x00, 0x1f], &[0x68, 0xeb])
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+        assert_eq!(&buf[20..24], b"encv");
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.kind, BoxType::StsdBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = StsdBox::read_block(&mut reader).unwrap();
+        assert_eq!(src_box, dst_box);
+        assert_eq!(
+            dst_box.sinf().unwrap().scheme_type(),
+            Some(str::parse("cenc").unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stsd_enca_unknown_format() {
+        let src_box = StsdBox {
+            mp4a: Some(Mp4aBox {
+                sinf: Some(SinfBox {
+                    frma: FrmaBox {
+                        original_format: str::parse("ac-3").unwrap(),
+                    },
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(&buf[20..24], b"enca");
+
+        let mut reader = buf.as_slice();
+        BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+
+        // kept as is, like an encv entry of an unknown format
+        let dst_box = StsdBox::read_block(&mut reader).unwrap();
+        assert_eq!(dst_box.mp4a, None);
+        assert_eq!(dst_box.unknown.len(), 1);
+
+        let mut rewritten = Vec::new();
+        dst_box.write_box(&mut rewritten).unwrap();
+        assert_eq!(rewritten, buf);
+    }
+}
@@ -43,10 +43,10 @@ impl Mp4Box for StssBox {
 
 impl BlockReader for StssBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
         let entry_size = size_of::<u32>(); // sample_number
-        let entry_count = reader.get_u32();
+        let entry_count = reader.try_get_u32()?;
         if entry_count as usize > reader.remaining() / entry_size {
             return Err(Error::InvalidData(
                 "stss entry_count indicates more entries than could fit in the box",
@@ -55,7 +55,7 @@ impl BlockReader for StssBox {
 
         let mut entries = Vec::with_capacity(entry_count as usize);
         for _i in 0..entry_count {
-            let sample_number = reader.get_u32();
+            let sample_number = reader.try_get_u32()?;
             entries.push(sample_number);
         }
 
@@ -87,7 +87,7 @@ impl<W: Write> WriteBox<&mut W> for StssBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
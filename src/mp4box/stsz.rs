@@ -50,15 +50,15 @@ impl Mp4Box for StszBox {
 
 impl BlockReader for StszBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
-        let sample_size = reader.get_u32();
+        let sample_size = reader.try_get_u32()?;
         let stsz_item_size = if sample_size == 0 {
             size_of::<u32>() // entry_size
         } else {
             0
         };
-        let sample_count = reader.get_u32();
+        let sample_count = reader.try_get_u32()?;
         let mut sample_sizes = Vec::new();
         if sample_size == 0 {
             if sample_count as usize > reader.remaining() / stsz_item_size {
@@ -68,7 +68,7 @@ impl BlockReader for StszBox {
             }
             sample_sizes.reserve(sample_count as usize);
             for _ in 0..sample_count {
-                let sample_number = reader.get_u32();
+                let sample_number = reader.try_get_u32()?;
                 sample_sizes.push(sample_number);
             }
         }
@@ -109,7 +109,7 @@ impl<W: Write> WriteBox<&mut W> for StszBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
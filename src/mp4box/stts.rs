@@ -49,10 +49,10 @@ impl Mp4Box for SttsBox {
 
 impl BlockReader for SttsBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
         let entry_size = size_of::<u32>() + size_of::<u32>(); // sample_count + sample_delta
-        let entry_count = reader.get_u32();
+        let entry_count = reader.try_get_u32()?;
 
         if entry_count as usize > reader.remaining() / entry_size {
             return Err(Error::InvalidData(
@@ -63,8 +63,8 @@ impl BlockReader for SttsBox {
         let mut entries = Vec::with_capacity(entry_count as usize);
         for _i in 0..entry_count {
             let entry = SttsEntry {
-                sample_count: reader.get_u32(),
-                sample_delta: reader.get_u32(),
+                sample_count: reader.try_get_u32()?,
+                sample_delta: reader.try_get_u32()?,
             };
             entries.push(entry);
         }
@@ -98,7 +98,7 @@ impl<W: Write> WriteBox<&mut W> for SttsBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
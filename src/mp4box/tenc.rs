@@ -0,0 +1,173 @@
+use byteorder::WriteBytesExt;
+use serde::Serialize;
+use std::io::Write;
+
+use crate::mp4box::*;
+
+/// Track encryption defaults (ISO/IEC 23001-7).
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct TencBox {
+    pub version: u8,
+    pub flags: u32,
+
+    /// Pattern encryption, version 1 only.
+    pub default_crypt_byte_block: u8,
+    pub default_skip_byte_block: u8,
+
+    pub default_is_protected: u8,
+    pub default_per_sample_iv_size: u8,
+    pub default_kid: [u8; 16],
+
+    /// Present if the track is protected and `default_per_sample_iv_size` is 0.
+    pub default_constant_iv: Vec<u8>,
+}
+
+impl TencBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::TencBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + HEADER_EXT_SIZE + 20;
+        if self.has_constant_iv() {
+            size += 1 + self.default_constant_iv.len() as u64;
+        }
+        size
+    }
+
+    #[inline]
+    fn has_constant_iv(&self) -> bool {
+        self.default_is_protected == 1 && self.default_per_sample_iv_size == 0
+    }
+}
+
+impl Mp4Box for TencBox {
+    const TYPE: BoxType = BoxType::TencBox;
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String, Error> {
+        let s = format!(
+            "is_protected={} per_sample_iv_size={}",
+            self.default_is_protected, self.default_per_sample_iv_size
+        );
+        Ok(s)
+    }
+}
+
+impl BlockReader for TencBox {
+    fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        let (version, flags) = read_box_header_ext(reader)?;
+
+        if reader.remaining() < 20 {
+            return Err(Error::InvalidData("tenc box is too small"));
+        }
+
+        reader.try_get_u8()?; // reserved
+
+        let (default_crypt_byte_block, default_skip_byte_block) = if version == 0 {
+            reader.try_get_u8()?; // reserved
+            (0, 0)
+        } else {
+            let pattern = reader.try_get_u8()?;
+            (pattern >> 4, pattern & 0x0f)
+        };
+
+        let default_is_protected = reader.try_get_u8()?;
+        let default_per_sample_iv_size = reader.try_get_u8()?;
+
+        let mut default_kid = [0u8; 16];
+        reader.copy_to_slice(&mut default_kid)?;
+
+        let mut tenc = TencBox {
+            version,
+            flags,
+            default_crypt_byte_block,
+            default_skip_byte_block,
+            default_is_protected,
+            default_per_sample_iv_size,
+            default_kid,
+            default_constant_iv: Vec::new(),
+        };
+
+        if tenc.has_constant_iv() {
+            let size = reader.try_get_u8()?;
+            tenc.default_constant_iv = reader.collect(size as usize)?;
+        }
+
+        Ok(tenc)
+    }
+
+    fn size_hint() -> usize {
+        24
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for TencBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+        let size = self.box_size();
+        BoxHeader::new(Self::TYPE, size).write(writer)?;
+
+        write_box_header_ext(writer, self.version, self.flags)?;
+
+        writer.write_u8(0)?; // reserved
+        if self.version == 0 {
+            writer.write_u8(0)?; // reserved
+        } else {
+            writer.write_u8(
+                (self.default_crypt_byte_block << 4) | (self.default_skip_byte_block & 0x0f),
+            )?;
+        }
+
+        writer.write_u8(self.default_is_protected)?;
+        writer.write_u8(self.default_per_sample_iv_size)?;
+        writer.write_all(&self.default_kid)?;
+
+        if self.has_constant_iv() {
+            writer.write_u8(self.default_constant_iv.len() as u8)?;
+            writer.write_all(&self.default_constant_iv)?;
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+
+    #[tokio::test]
+    async fn test_tenc_v1_constant_iv() {
+        let src_box = TencBox {
+            version: 1,
+            flags: 0,
+            default_crypt_byte_block: 1,
+            default_skip_byte_block: 9,
+            default_is_protected: 1,
+            default_per_sample_iv_size: 0,
+            default_kid: [
+                0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d,
+                0x1e, 0x1f,
+            ],
+            default_constant_iv: vec![0xaa; 16],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.kind, BoxType::TencBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = TencBox::read_block(&mut reader).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+}
@@ -46,12 +46,12 @@ impl Mp4Box for TfdtBox {
 
 impl BlockReader for TfdtBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
         let base_media_decode_time = if version == 1 {
-            reader.get_u64()
+            reader.try_get_u64()?
         } else if version == 0 {
-            reader.get_u32() as u64
+            reader.try_get_u32()? as u64
         } else {
             return Err(Error::InvalidData("version must be 0 or 1"));
         };
@@ -87,7 +87,7 @@ impl<W: Write> WriteBox<&mut W> for TfdtBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -69,35 +69,35 @@ impl Mp4Box for TfhdBox {
 
 impl BlockReader for TfhdBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
-        let track_id = reader.get_u32();
+        let (version, flags) = read_box_header_ext(reader)?;
+        let track_id = reader.try_get_u32()?;
 
         let base_data_offset = if TfhdBox::FLAG_BASE_DATA_OFFSET & flags > 0 {
-            Some(reader.get_u64())
+            Some(reader.try_get_u64()?)
         } else {
             None
         };
 
         let sample_description_index = if TfhdBox::FLAG_SAMPLE_DESCRIPTION_INDEX & flags > 0 {
-            Some(reader.get_u32())
+            Some(reader.try_get_u32()?)
         } else {
             None
         };
 
         let default_sample_duration = if TfhdBox::FLAG_DEFAULT_SAMPLE_DURATION & flags > 0 {
-            Some(reader.get_u32())
+            Some(reader.try_get_u32()?)
         } else {
             None
         };
 
         let default_sample_size = if TfhdBox::FLAG_DEFAULT_SAMPLE_SIZE & flags > 0 {
-            Some(reader.get_u32())
+            Some(reader.try_get_u32()?)
         } else {
             None
         };
 
         let default_sample_flags = if TfhdBox::FLAG_DEFAULT_SAMPLE_FLAGS & flags > 0 {
-            Some(reader.get_u32())
+            Some(reader.try_get_u32()?)
         } else {
             None
         };
@@ -146,7 +146,7 @@ impl<W: Write> WriteBox<&mut W> for TfhdBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
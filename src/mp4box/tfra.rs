@@ -0,0 +1,332 @@
+use byteorder::{BigEndian, WriteBytesExt};
+use serde::Serialize;
+use std::io::Write;
+
+use crate::mp4box::*;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct TfraBox {
+    /// Written as 1 when an entry's time or offset doesn't fit in 32 bits.
+    pub version: u8,
+    pub flags: u32,
+    pub track_id: u32,
+    pub length_size_of_traf_num: u8,
+    pub length_size_of_trun_num: u8,
+    pub length_size_of_sample_num: u8,
+
+    #[serde(skip_serializing)]
+    pub entries: Vec<TfraEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct TfraEntry {
+    pub time: u64,
+    pub moof_offset: u64,
+    pub traf_number: u32,
+    pub trun_number: u32,
+    pub sample_number: u32,
+}
+
+impl TfraBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::TfraBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE + HEADER_EXT_SIZE + 12 + self.entry_size() * self.entries.len() as u64
+    }
+
+    fn written_version(&self) -> u8 {
+        let fits = |v: u64| v <= u32::MAX as u64;
+        if self.version == 0
+            && self
+                .entries
+                .iter()
+                .all(|e| fits(e.time) && fits(e.moof_offset))
+        {
+            0
+        } else {
+            1
+        }
+    }
+
+    fn entry_size(&self) -> u64 {
+        let time_size = if self.written_version() == 1 { 16 } else { 8 };
+
+        time_size
+            + (self.length_size_of_traf_num & 0b11) as u64
+            + 1
+            + (self.length_size_of_trun_num & 0b11) as u64
+            + 1
+            + (self.length_size_of_sample_num & 0b11) as u64
+            + 1
+    }
+
+    /// Returns the entry of the last random access point at or before `time`,
+    /// or `None` if `time` precedes every entry.
+    pub fn find_entry(&self, time: u64) -> Option<&TfraEntry> {
+        let idx = self.entries.partition_point(|e| e.time <= time);
+
+        idx.checked_sub(1).and_then(|idx| self.entries.get(idx))
+    }
+}
+
+impl Mp4Box for TfraBox {
+    const TYPE: BoxType = BoxType::TfraBox;
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String, Error> {
+        let s = format!("track_id={} entries={}", self.track_id, self.entries.len());
+        Ok(s)
+    }
+}
+
+impl BlockReader for TfraBox {
+    fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        let (version, flags) = read_box_header_ext(reader)?;
+
+        if version > 1 {
+            return Err(Error::InvalidData("version must be 0 or 1"));
+        }
+
+        let track_id = reader.try_get_u32()?;
+        let sizes = reader.try_get_u32()?;
+        let length_size_of_traf_num = ((sizes >> 4) & 0b11) as u8;
+        let length_size_of_trun_num = ((sizes >> 2) & 0b11) as u8;
+        let length_size_of_sample_num = (sizes & 0b11) as u8;
+
+        let mut tfra = TfraBox {
+            version,
+            flags,
+            track_id,
+            length_size_of_traf_num,
+            length_size_of_trun_num,
+            length_size_of_sample_num,
+            entries: Vec::new(),
+        };
+
+        let entry_count = reader.try_get_u32()?;
+        if entry_count as usize > reader.remaining() / tfra.entry_size() as usize {
+            return Err(Error::InvalidData(
+                "tfra entry_count indicates more entries than could fit in the box",
+            ));
+        }
+
+        tfra.entries.reserve(entry_count as usize);
+        for _ in 0..entry_count {
+            let (time, moof_offset) = if version == 1 {
+                (reader.try_get_u64()?, reader.try_get_u64()?)
+            } else {
+                (reader.try_get_u32()? as u64, reader.try_get_u32()? as u64)
+            };
+
+            tfra.entries.push(TfraEntry {
+                time,
+                moof_offset,
+                traf_number: read_sized_u32(reader, length_size_of_traf_num)?,
+                trun_number: read_sized_u32(reader, length_size_of_trun_num)?,
+                sample_number: read_sized_u32(reader, length_size_of_sample_num)?,
+            });
+        }
+
+        Ok(tfra)
+    }
+
+    fn size_hint() -> usize {
+        16
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for TfraBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+        let size = self.box_size();
+        BoxHeader::new(Self::TYPE, size).write(writer)?;
+
+        let version = self.written_version();
+        write_box_header_ext(writer, version, self.flags)?;
+
+        writer.write_u32::<BigEndian>(self.track_id)?;
+        writer.write_u32::<BigEndian>(
+            ((self.length_size_of_traf_num as u32 & 0b11) << 4)
+                | ((self.length_size_of_trun_num as u32 & 0b11) << 2)
+                | (self.length_size_of_sample_num as u32 & 0b11),
+        )?;
+        writer.write_u32::<BigEndian>(self.entries.len() as u32)?;
+
+        for entry in &self.entries {
+            if version == 1 {
+                writer.write_u64::<BigEndian>(entry.time)?;
+                writer.write_u64::<BigEndian>(entry.moof_offset)?;
+            } else {
+                writer.write_u32::<BigEndian>(entry.time as u32)?;
+                writer.write_u32::<BigEndian>(entry.moof_offset as u32)?;
+            }
+
+            write_sized_u32(writer, self.length_size_of_traf_num, entry.traf_number)?;
+            write_sized_u32(writer, self.length_size_of_trun_num, entry.trun_number)?;
+            write_sized_u32(writer, self.length_size_of_sample_num, entry.sample_number)?;
+        }
+
+        Ok(size)
+    }
+}
+
+fn read_sized_u32<'a>(reader: &mut impl Reader<'a>, length_size: u8) -> Result<u32, Error> {
+    Ok(match length_size & 0b11 {
+        0 => reader.try_get_u8()? as u32,
+        1 => reader.try_get_u16()? as u32,
+        2 => reader.try_get_u24()?,
+        _ => reader.try_get_u32()?,
+    })
+}
+
+fn write_sized_u32<W: Write>(writer: &mut W, length_size: u8, value: u32) -> Result<(), Error> {
+    match length_size & 0b11 {
+        0 => writer.write_u8(value as u8)?,
+        1 => writer.write_u16::<BigEndian>(value as u16)?,
+        2 => writer.write_u24::<BigEndian>(value)?,
+        _ => writer.write_u32::<BigEndian>(value)?,
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_tfra32() {
+        let src_box = TfraBox {
+            version: 0,
+            flags: 0,
+            track_id: 1,
+            length_size_of_traf_num: 0,
+            length_size_of_trun_num: 1,
+            length_size_of_sample_num: 2,
+            entries: vec![
+                TfraEntry {
+                    time: 0,
+                    moof_offset: 1024,
+                    traf_number: 1,
+                    trun_number: 1,
+                    sample_number: 1,
+                },
+                TfraEntry {
+                    time: 90000,
+                    moof_offset: 524288,
+                    traf_number: 1,
+                    trun_number: 2,
+                    sample_number: 70000,
+                },
+            ],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.kind, BoxType::TfraBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = TfraBox::read_block(&mut reader).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_tfra64() {
+        let src_box = TfraBox {
+            version: 1,
+            flags: 0,
+            track_id: 2,
+            length_size_of_traf_num: 3,
+            length_size_of_trun_num: 3,
+            length_size_of_sample_num: 3,
+            entries: vec![TfraEntry {
+                time: 0x1_0000_0000,
+                moof_offset: 0x2_0000_0000,
+                traf_number: 2,
+                trun_number: 1,
+                sample_number: 1,
+            }],
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.kind, BoxType::TfraBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = TfraBox::read_block(&mut reader).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+
+    #[test]
+    fn test_tfra_find_entry() {
+        let tfra = TfraBox {
+            entries: [0, 100, 200]
+                .into_iter()
+                .map(|time| TfraEntry {
+                    time,
+                    moof_offset: time * 10,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        assert_eq!(tfra.find_entry(0).unwrap().time, 0);
+        assert_eq!(tfra.find_entry(99).unwrap().time, 0);
+        assert_eq!(tfra.find_entry(150).unwrap().time, 100);
+        assert_eq!(tfra.find_entry(200).unwrap().time, 200);
+        assert_eq!(tfra.find_entry(u64::MAX).unwrap().time, 200);
+        assert!(TfraBox::default().find_entry(0).is_none());
+
+        let late = TfraBox {
+            entries: tfra.entries[1..].to_vec(),
+            ..Default::default()
+        };
+        assert!(late.find_entry(99).is_none());
+        assert_eq!(late.find_entry(100).unwrap().time, 100);
+    }
+
+    #[test]
+    fn test_tfra_version_bump() {
+        let src_box = TfraBox {
+            version: 0,
+            track_id: 1,
+            entries: vec![
+                TfraEntry {
+                    time: 0,
+                    moof_offset: 1024,
+                    ..Default::default()
+                },
+                TfraEntry {
+                    time: 90000,
+                    moof_offset: 0x1_0000_0000,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let dst_box = TfraBox::read_block(&mut &buf[8..]).unwrap();
+        assert_eq!(dst_box.version, 1);
+        assert_eq!(dst_box.entries, src_box.entries);
+    }
+}
@@ -145,50 +145,50 @@ impl Mp4Box for TkhdBox {
 
 impl BlockReader for TkhdBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
         let (creation_time, modification_time, track_id, _, duration) = if version == 1 {
             (
-                reader.get_u64(),
-                reader.get_u64(),
-                reader.get_u32(),
-                reader.get_u32(),
-                reader.get_u64(),
+                reader.try_get_u64()?,
+                reader.try_get_u64()?,
+                reader.try_get_u32()?,
+                reader.try_get_u32()?,
+                reader.try_get_u64()?,
             )
         } else if version == 0 {
             (
-                reader.get_u32() as u64,
-                reader.get_u32() as u64,
-                reader.get_u32(),
-                reader.get_u32(),
-                reader.get_u32() as u64,
+                reader.try_get_u32()? as u64,
+                reader.try_get_u32()? as u64,
+                reader.try_get_u32()?,
+                reader.try_get_u32()?,
+                reader.try_get_u32()? as u64,
             )
         } else {
             return Err(Error::InvalidData("version must be 0 or 1"));
         };
 
-        reader.get_u64(); // reserved
+        reader.try_get_u64()?; // reserved
 
-        let layer = reader.get_u16();
-        let alternate_group = reader.get_u16();
-        let volume = FixedPointU8::new_raw(reader.get_u16());
+        let layer = reader.try_get_u16()?;
+        let alternate_group = reader.try_get_u16()?;
+        let volume = FixedPointU8::new_raw(reader.try_get_u16()?);
 
-        reader.get_u16(); // reserved
+        reader.try_get_u16()?; // reserved
 
         let matrix = Matrix {
-            a: reader.get_i32(),
-            b: reader.get_i32(),
-            u: reader.get_i32(),
-            c: reader.get_i32(),
-            d: reader.get_i32(),
-            v: reader.get_i32(),
-            x: reader.get_i32(),
-            y: reader.get_i32(),
-            w: reader.get_i32(),
+            a: reader.try_get_i32()?,
+            b: reader.try_get_i32()?,
+            u: reader.try_get_i32()?,
+            c: reader.try_get_i32()?,
+            d: reader.try_get_i32()?,
+            v: reader.try_get_i32()?,
+            x: reader.try_get_i32()?,
+            y: reader.try_get_i32()?,
+            w: reader.try_get_i32()?,
         };
 
-        let width = FixedPointU16::new_raw(reader.get_u32());
-        let height = FixedPointU16::new_raw(reader.get_u32());
+        let width = FixedPointU16::new_raw(reader.try_get_u32()?);
+        let height = FixedPointU16::new_raw(reader.try_get_u32()?);
 
         Ok(TkhdBox {
             version,
@@ -258,7 +258,7 @@ impl<W: Write> WriteBox<&mut W> for TkhdBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
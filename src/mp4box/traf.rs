@@ -9,6 +9,21 @@ pub struct TrafBox {
     pub tfhd: TfhdBox,
     pub tfdt: Option<TfdtBox>,
     pub trun: Option<TrunBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub senc: Option<SencBox>,
+
+    /// Sizes of the `senc` entries, checked against them when the samples are read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saiz: Option<SaizBox>,
+
+    /// Offset of the `senc` entries. Auxiliary info stored elsewhere is not read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saio: Option<SaioBox>,
+
+    /// Children such as `sgpd`, `sbgp` or `sdtp`.
+    #[serde(skip)]
+    pub unknown: UnknownBoxes,
 }
 
 impl TrafBox {
@@ -21,7 +36,16 @@ impl TrafBox {
         if let Some(ref trun) = self.trun {
             size += trun.box_size();
         }
-        size
+        if let Some(ref senc) = self.senc {
+            size += senc.box_size();
+        }
+        if let Some(ref saiz) = self.saiz {
+            size += saiz.box_size();
+        }
+        if let Some(ref saio) = self.saio {
+            size += saio.box_size();
+        }
+        size + unknown_boxes_size(&self.unknown)
     }
 }
 
@@ -44,12 +68,61 @@ impl Mp4Box for TrafBox {
 
 impl BlockReader for TrafBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (tfhd, tfdt, trun) = reader.try_find_box3()?;
+        let mut tfhd = None;
+        let mut tfdt = None;
+        let mut trun = None;
+        let mut senc = None;
+        let mut saiz = None;
+        let mut saio = None;
+        let mut unknown = UnknownBoxes::new();
+
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
+
+            match bx.kind {
+                BoxType::TfhdBox => {
+                    tfhd = Some(bx.read()?);
+                }
+
+                BoxType::TfdtBox => {
+                    tfdt = Some(bx.read()?);
+                }
+
+                BoxType::TrunBox => {
+                    trun = Some(bx.read()?);
+                }
+
+                BoxType::SencBox => {
+                    senc = Some(bx.read()?);
+                }
+
+                BoxType::SaizBox => {
+                    saiz = Some(bx.read()?);
+                }
+
+                BoxType::SaioBox => {
+                    saio = Some(bx.read()?);
+                }
+
+                _ => unknown.push_at(position, bx.read_raw()),
+            }
+        }
+
         let Some(tfhd) = tfhd else {
             return Err(Error::BoxNotFound(BoxType::TfhdBox));
         };
 
-        Ok(TrafBox { tfhd, tfdt, trun })
+        Ok(TrafBox {
+            tfhd,
+            tfdt,
+            trun,
+            senc,
+            saiz,
+            saio,
+            unknown,
+        })
     }
 
     fn size_hint() -> usize {
@@ -62,15 +135,30 @@ impl<W: Write> WriteBox<&mut W> for TrafBox {
         let size = self.box_size();
         BoxHeader::new(Self::TYPE, size).write(writer)?;
 
-        self.tfhd.write_box(writer)?;
+        let mut children = self.unknown.writer(writer);
+
+        children.write(&self.tfhd)?;
 
         if let Some(ref tfdt) = self.tfdt {
-            tfdt.write_box(writer)?;
+            children.write(tfdt)?;
         }
 
         if let Some(ref trun) = self.trun {
-            trun.write_box(writer)?;
+            children.write(trun)?;
+        }
+
+        if let Some(ref senc) = self.senc {
+            children.write(senc)?;
+        }
+
+        if let Some(ref saiz) = self.saiz {
+            children.write(saiz)?;
+        }
+
+        if let Some(ref saio) = self.saio {
+            children.write(saio)?;
         }
+        children.finish()?;
 
         Ok(size)
     }
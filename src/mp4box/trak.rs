@@ -10,14 +10,79 @@ pub struct TrakBox {
     pub tkhd: TkhdBox,
     pub mdia: MdiaBox,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tref: Option<TrefBox>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub edts: Option<EdtsBox>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub meta: Option<MetaBox>,
+
+    #[serde(skip)]
+    pub unknown: UnknownBoxes,
 }
 
 impl TrakBox {
+    pub fn new(track_id: u32, config: &TrackConfig) -> Self {
+        let mut trak = TrakBox::default();
+        trak.tkhd.track_id = track_id;
+        trak.mdia.mdhd.timescale = config.timescale;
+        trak.mdia.mdhd.language = config.language.to_owned();
+        trak.mdia.hdlr.handler_type = config.track_type.into();
+        trak.mdia.minf.stbl.stco = Some(StcoBox::default());
+
+        match &config.media_conf {
+            MediaConfig::AvcConfig(avc_config) => {
+                trak.tkhd.set_width(avc_config.width);
+                trak.tkhd.set_height(avc_config.height);
+                trak.mdia.minf.vmhd = Some(VmhdBox::default());
+                trak.mdia.minf.stbl.stsd.avc1 = Some(Avc1Box::new(avc_config));
+            }
+            MediaConfig::HevcConfig(hevc_config) => {
+                trak.tkhd.set_width(hevc_config.width);
+                trak.tkhd.set_height(hevc_config.height);
+                trak.mdia.minf.vmhd = Some(VmhdBox::default());
+                trak.mdia.minf.stbl.stsd.hev1 = Some(Hev1Box::new(hevc_config));
+            }
+            MediaConfig::Vp9Config(vp9_config) => {
+                trak.tkhd.set_width(vp9_config.width);
+                trak.tkhd.set_height(vp9_config.height);
+                trak.mdia.minf.vmhd = Some(VmhdBox::default());
+                trak.mdia.minf.stbl.stsd.vp09 = Some(Vp09Box::new(vp9_config));
+            }
+            MediaConfig::AacConfig(aac_config) => {
+                trak.mdia.minf.smhd = Some(SmhdBox::default());
+                trak.mdia.minf.stbl.stsd.mp4a = Some(Mp4aBox::new(aac_config));
+            }
+            MediaConfig::TtxtConfig(_) => {
+                trak.mdia.minf.stbl.stsd.tx3g = Some(Tx3gBox::default());
+            }
+            MediaConfig::WebVttConfig(vtt_config) => {
+                trak.mdia.minf.nmhd = Some(NmhdBox::default());
+                trak.mdia.minf.stbl.stsd.wvtt = Some(WvttBox {
+                    config: vtt_config.config.clone(),
+                    label: vtt_config.label.clone(),
+                    ..Default::default()
+                });
+            }
+            MediaConfig::TtmlConfig(ttml_config) => {
+                trak.mdia.minf.sthd = Some(SthdBox::default());
+                trak.mdia.minf.stbl.stsd.stpp = Some(StppBox {
+                    namespace: ttml_config.namespace.clone(),
+                    schema_location: ttml_config.schema_location.clone(),
+                    auxiliary_mime_types: ttml_config.auxiliary_mime_types.clone(),
+                    ..Default::default()
+                });
+            }
+            MediaConfig::C608Config(_) => {
+                trak.mdia.minf.stbl.stsd.c608 = Some(C608Box::default());
+            }
+        }
+
+        trak
+    }
+
     pub fn get_type(&self) -> BoxType {
         BoxType::TrakBox
     }
@@ -25,138 +90,17 @@ impl TrakBox {
     pub fn get_size(&self) -> u64 {
         let mut size = HEADER_SIZE;
         size += self.tkhd.box_size();
+        if let Some(ref tref) = self.tref {
+            size += tref.box_size();
+        }
         if let Some(ref edts) = self.edts {
             size += edts.box_size();
         }
         size += self.mdia.box_size();
-        size
-    }
-
-    pub(crate) fn stsc_index(&self, sample_id: u32) -> Result<usize, Error> {
-        if self.mdia.minf.stbl.stsc.entries.is_empty() {
-            return Err(Error::InvalidData("no stsc entries"));
-        }
-
-        for (i, entry) in self.mdia.minf.stbl.stsc.entries.iter().enumerate() {
-            if sample_id < entry.first_sample {
-                return if i == 0 {
-                    Err(Error::InvalidData("sample not found"))
-                } else {
-                    Ok(i - 1)
-                };
-            }
-        }
-
-        Ok(self.mdia.minf.stbl.stsc.entries.len() - 1)
-    }
-
-    pub(crate) fn chunk_offset(&self, chunk_id: u32) -> Result<u64, Error> {
-        if self.mdia.minf.stbl.stco.is_none() && self.mdia.minf.stbl.co64.is_none() {
-            return Err(Error::InvalidData("must have either stco or co64 boxes"));
-        }
-
-        if let Some(ref stco) = self.mdia.minf.stbl.stco {
-            if let Some(offset) = stco.entries.get(chunk_id as usize - 1) {
-                return Ok(*offset as u64);
-            } else {
-                return Err(Error::EntryInStblNotFound(
-                    self.tkhd.track_id,
-                    BoxType::StcoBox,
-                    chunk_id,
-                ));
-            }
-        } else if let Some(ref co64) = self.mdia.minf.stbl.co64 {
-            if let Some(offset) = co64.entries.get(chunk_id as usize - 1) {
-                return Ok(*offset);
-            } else {
-                return Err(Error::EntryInStblNotFound(
-                    self.tkhd.track_id,
-                    BoxType::Co64Box,
-                    chunk_id,
-                ));
-            }
-        }
-
-        Err(Error::Box2NotFound(BoxType::StcoBox, BoxType::Co64Box))
-    }
-
-    pub(crate) fn sample_size(&self, sample_id: u32) -> Result<u32, Error> {
-        let stsz = &self.mdia.minf.stbl.stsz;
-
-        if stsz.sample_size > 0 {
-            return Ok(stsz.sample_size);
-        }
-
-        if let Some(size) = stsz.sample_sizes.get(sample_id as usize - 1) {
-            Ok(*size)
-        } else {
-            Err(Error::EntryInStblNotFound(
-                self.tkhd.track_id,
-                BoxType::StszBox,
-                sample_id,
-            ))
-        }
-    }
-
-    pub(crate) fn sample_offset(&self, sample_id: u32) -> Result<u64, Error> {
-        let stsc_index = self.stsc_index(sample_id)?;
-
-        let stsc = &self.mdia.minf.stbl.stsc;
-        let stsc_entry = stsc.entries.get(stsc_index).unwrap();
-
-        let first_chunk = stsc_entry.first_chunk;
-        let first_sample = stsc_entry.first_sample;
-        let samples_per_chunk = stsc_entry.samples_per_chunk;
-
-        let chunk_id = sample_id
-            .checked_sub(first_sample)
-            .map(|n| n / samples_per_chunk)
-            .and_then(|n| n.checked_add(first_chunk))
-            .ok_or(Error::InvalidData(
-                "attempt to calculate stsc chunk_id with overflow",
-            ))?;
-
-        let chunk_offset = self.chunk_offset(chunk_id)?;
-
-        let first_sample_in_chunk = sample_id - (sample_id - first_sample) % samples_per_chunk;
-
-        let mut sample_offset = 0;
-        for i in first_sample_in_chunk..sample_id {
-            sample_offset += self.sample_size(i)?;
-        }
-
-        Ok(chunk_offset + sample_offset as u64)
-    }
-
-    pub(crate) fn sample_time(&self, sample_id: u32) -> Result<(u64, u32), Error> {
-        let stts = &self.mdia.minf.stbl.stts;
-
-        let mut sample_count: u32 = 1;
-        let mut elapsed = 0;
-
-        for entry in stts.entries.iter() {
-            let new_sample_count =
-                sample_count
-                    .checked_add(entry.sample_count)
-                    .ok_or(Error::InvalidData(
-                        "attempt to sum stts entries sample_count with overflow",
-                    ))?;
-
-            if sample_id < new_sample_count {
-                let start_time =
-                    (sample_id - sample_count) as u64 * entry.sample_delta as u64 + elapsed;
-                return Ok((start_time, entry.sample_delta));
-            }
-
-            sample_count = new_sample_count;
-            elapsed += entry.sample_count as u64 * entry.sample_delta as u64;
+        if let Some(ref meta) = self.meta {
+            size += meta.box_size();
         }
-
-        Err(Error::EntryInStblNotFound(
-            self.tkhd.track_id,
-            BoxType::SttsBox,
-            sample_id,
-        ))
+        size + unknown_boxes_size(&self.unknown)
     }
 
     pub(crate) fn ctts_index(&self, sample_id: u32) -> Result<(usize, u32), Error> {
@@ -222,21 +166,58 @@ impl Mp4Box for TrakBox {
 
 impl BlockReader for TrakBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (tkhd, edts, meta, mdia) = reader.try_find_box4()?;
+        let mut tkhd = None;
+        let mut tref = None;
+        let mut edts = None;
+        let mut meta = None;
+        let mut mdia = None;
+        let mut unknown = UnknownBoxes::new();
+
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
+
+            match bx.kind {
+                BoxType::TkhdBox => {
+                    tkhd = Some(bx.read()?);
+                }
+
+                BoxType::TrefBox => {
+                    tref = Some(bx.read()?);
+                }
+
+                BoxType::EdtsBox => {
+                    edts = Some(bx.read()?);
+                }
+
+                BoxType::MetaBox => {
+                    meta = Some(bx.read()?);
+                }
+
+                BoxType::MdiaBox => {
+                    mdia = Some(bx.read()?);
+                }
+
+                _ => unknown.push_at(position, bx.read_raw()),
+            }
+        }
 
-        if tkhd.is_none() {
+        let Some(tkhd) = tkhd else {
             return Err(Error::BoxNotFound(BoxType::TkhdBox));
-        }
+        };
 
-        if mdia.is_none() {
+        let Some(mdia) = mdia else {
             return Err(Error::BoxNotFound(BoxType::MdiaBox));
-        }
+        };
 
         Ok(TrakBox {
-            tkhd: tkhd.unwrap(),
+            tkhd,
+            tref,
             edts,
             meta,
-            mdia: mdia.unwrap(),
+            mdia,
+            unknown,
         })
     }
 
@@ -250,11 +231,20 @@ impl<W: Write> WriteBox<&mut W> for TrakBox {
         let size = self.box_size();
         BoxHeader::new(Self::TYPE, size).write(writer)?;
 
-        self.tkhd.write_box(writer)?;
+        let mut children = self.unknown.writer(writer);
+
+        children.write(&self.tkhd)?;
+        if let Some(ref tref) = self.tref {
+            children.write(tref)?;
+        }
         if let Some(ref edts) = self.edts {
-            edts.write_box(writer)?;
+            children.write(edts)?;
+        }
+        children.write(&self.mdia)?;
+        if let Some(ref meta) = self.meta {
+            children.write(meta)?;
         }
-        self.mdia.write_box(writer)?;
+        children.finish()?;
 
         Ok(size)
     }
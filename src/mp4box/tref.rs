@@ -0,0 +1,208 @@
+use byteorder::{BigEndian, WriteBytesExt};
+use serde::Serialize;
+use std::io::Write;
+
+use crate::mp4box::*;
+
+/// Track reference box.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct TrefBox {
+    pub references: Vec<TrackReference>,
+}
+
+/// Track reference type box, e.g. `chap`, listing the referenced track IDs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TrackReference {
+    pub reference_type: ReferenceType,
+    pub track_ids: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum ReferenceType {
+    /// `chap`, QuickTime chapter text track
+    Chapter,
+    /// `hint`, original media of a hint track
+    Hint,
+    /// `cdsc`, timed metadata describing the referenced track
+    ContentDescribes,
+    /// `font`, track carrying fonts used by the referencing track
+    Font,
+    /// `sync`, synchronization source
+    Sync,
+    /// `vdep`, auxiliary depth video
+    VideoDepth,
+    /// `subt`, subtitle or alternate text track
+    Subtitle,
+    /// `thmb`, thumbnail track
+    Thumbnail,
+    Other(FourCC),
+}
+
+impl From<FourCC> for ReferenceType {
+    fn from(fourcc: FourCC) -> Self {
+        match &fourcc.value {
+            b"chap" => Self::Chapter,
+            b"hint" => Self::Hint,
+            b"cdsc" => Self::ContentDescribes,
+            b"font" => Self::Font,
+            b"sync" => Self::Sync,
+            b"vdep" => Self::VideoDepth,
+            b"subt" => Self::Subtitle,
+            b"thmb" => Self::Thumbnail,
+            _ => Self::Other(fourcc),
+        }
+    }
+}
+
+impl From<ReferenceType> for FourCC {
+    fn from(t: ReferenceType) -> FourCC {
+        let value = match t {
+            ReferenceType::Chapter => b"chap",
+            ReferenceType::Hint => b"hint",
+            ReferenceType::ContentDescribes => b"cdsc",
+            ReferenceType::Font => b"font",
+            ReferenceType::Sync => b"sync",
+            ReferenceType::VideoDepth => b"vdep",
+            ReferenceType::Subtitle => b"subt",
+            ReferenceType::Thumbnail => b"thmb",
+            ReferenceType::Other(fourcc) => return fourcc,
+        };
+
+        FourCC { value: *value }
+    }
+}
+
+impl TrefBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::TrefBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        HEADER_SIZE
+            + self
+                .references
+                .iter()
+                .map(|r| HEADER_SIZE + 4 * r.track_ids.len() as u64)
+                .sum::<u64>()
+    }
+
+    /// Track IDs referenced with the given type.
+    pub fn track_ids(&self, reference_type: ReferenceType) -> &[u32] {
+        self.references
+            .iter()
+            .find(|r| r.reference_type == reference_type)
+            .map_or(&[], |r| r.track_ids.as_slice())
+    }
+
+    /// Adds a reference, merging it into an existing entry of the same type.
+    pub fn add(&mut self, reference_type: ReferenceType, track_id: u32) {
+        match self
+            .references
+            .iter_mut()
+            .find(|r| r.reference_type == reference_type)
+        {
+            Some(r) => {
+                if !r.track_ids.contains(&track_id) {
+                    r.track_ids.push(track_id);
+                }
+            }
+            None => self.references.push(TrackReference {
+                reference_type,
+                track_ids: vec![track_id],
+            }),
+        }
+    }
+}
+
+impl Mp4Box for TrefBox {
+    const TYPE: BoxType = BoxType::TrefBox;
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String, Error> {
+        let s = self
+            .references
+            .iter()
+            .map(|r| format!("{}={:?}", FourCC::from(r.reference_type), r.track_ids))
+            .collect::<Vec<_>>()
+            .join(" ");
+        Ok(s)
+    }
+}
+
+impl BlockReader for TrefBox {
+    fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        let mut references = Vec::new();
+
+        while let Some(mut bx) = reader.get_box()? {
+            let count = bx.inner.remaining() / 4;
+            let track_ids = (0..count).map(|_| bx.inner.get_u32()).collect();
+
+            references.push(TrackReference {
+                reference_type: FourCC::from(bx.kind).into(),
+                track_ids,
+            });
+        }
+
+        Ok(TrefBox { references })
+    }
+
+    fn size_hint() -> usize {
+        0
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for TrefBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+        let size = self.box_size();
+        BoxHeader::new(Self::TYPE, size).write(writer)?;
+
+        for reference in &self.references {
+            let kind = u32::from(FourCC::from(reference.reference_type)).into();
+            BoxHeader::new(kind, HEADER_SIZE + 4 * reference.track_ids.len() as u64)
+                .write(writer)?;
+
+            for &track_id in &reference.track_ids {
+                writer.write_u32::<BigEndian>(track_id)?;
+            }
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::mp4box::BoxHeader;
+
+    #[tokio::test]
+    async fn test_tref() {
+        let mut src_box = TrefBox::default();
+        src_box.add(ReferenceType::Chapter, 3);
+        src_box.add(ReferenceType::Thumbnail, 4);
+        src_box.add(ReferenceType::Other(str::parse("auxl").unwrap()), 5);
+        src_box.add(ReferenceType::Thumbnail, 6);
+        src_box.add(ReferenceType::Thumbnail, 4);
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.kind, BoxType::TrefBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = TrefBox::read_block(&mut reader).unwrap();
+        assert_eq!(src_box, dst_box);
+        assert_eq!(dst_box.track_ids(ReferenceType::Chapter), &[3]);
+        assert_eq!(dst_box.track_ids(ReferenceType::Thumbnail), &[4, 6]);
+        assert_eq!(dst_box.track_ids(ReferenceType::Hint), &[] as &[u32]);
+    }
+}
@@ -47,13 +47,13 @@ impl Mp4Box for TrexBox {
 
 impl BlockReader for TrexBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
-        let track_id = reader.get_u32();
-        let default_sample_description_index = reader.get_u32();
-        let default_sample_duration = reader.get_u32();
-        let default_sample_size = reader.get_u32();
-        let default_sample_flags = reader.get_u32();
+        let track_id = reader.try_get_u32()?;
+        let default_sample_description_index = reader.try_get_u32()?;
+        let default_sample_duration = reader.try_get_u32()?;
+        let default_sample_size = reader.try_get_u32()?;
+        let default_sample_flags = reader.try_get_u32()?;
 
         Ok(TrexBox {
             version,
@@ -88,7 +88,7 @@ impl<W: Write> WriteBox<&mut W> for TrexBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
@@ -81,14 +81,14 @@ impl Mp4Box for TrunBox {
 
 impl BlockReader for TrunBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
         let sample_size = if TrunBox::FLAG_SAMPLE_DURATION & flags > 0 { size_of::<u32>() } else { 0 } // sample_duration
             + if TrunBox::FLAG_SAMPLE_SIZE & flags > 0 { size_of::<u32>() } else { 0 } // sample_size
             + if TrunBox::FLAG_SAMPLE_FLAGS & flags > 0 { size_of::<u32>() } else { 0 } // sample_flags
             + if TrunBox::FLAG_SAMPLE_CTS & flags > 0 { size_of::<u32>() } else { 0 }; // sample_composition_time_offset
 
-        let sample_count = reader.get_u32();
+        let sample_count = reader.try_get_u32()?;
 
         let data_offset = if TrunBox::FLAG_DATA_OFFSET & flags > 0 {
             Some(reader.try_get_i32()?)
@@ -131,22 +131,22 @@ impl BlockReader for TrunBox {
 
         for _ in 0..sample_count {
             if TrunBox::FLAG_SAMPLE_DURATION & flags > 0 {
-                let duration = reader.get_u32();
+                let duration = reader.try_get_u32()?;
                 sample_durations.push(duration);
             }
 
             if TrunBox::FLAG_SAMPLE_SIZE & flags > 0 {
-                let sample_size = reader.get_u32();
+                let sample_size = reader.try_get_u32()?;
                 sample_sizes.push(sample_size);
             }
 
             if TrunBox::FLAG_SAMPLE_FLAGS & flags > 0 {
-                let sample_flag = reader.get_u32();
+                let sample_flag = reader.try_get_u32()?;
                 sample_flags.push(sample_flag);
             }
 
             if TrunBox::FLAG_SAMPLE_CTS & flags > 0 {
-                let cts = reader.get_u32();
+                let cts = reader.try_get_u32()?;
                 sample_cts.push(cts);
             }
         }
@@ -205,7 +205,7 @@ impl<W: Write> WriteBox<&mut W> for TrunBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
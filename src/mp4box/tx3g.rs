@@ -13,6 +13,10 @@ pub struct Tx3gBox {
     pub bg_color_rgba: RgbaColor,
     pub box_record: [i16; 4],
     pub style_record: [u8; 12],
+
+    /// `ftab` font table and other child boxes.
+    #[serde(skip)]
+    pub unknown: UnknownBoxes,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
@@ -38,6 +42,7 @@ impl Default for Tx3gBox {
             },
             box_record: [0, 0, 0, 0],
             style_record: [0, 0, 0, 0, 0, 1, 0, 16, 255, 255, 255, 255],
+            unknown: UnknownBoxes::new(),
         }
     }
 }
@@ -48,7 +53,7 @@ impl Tx3gBox {
     }
 
     pub fn get_size(&self) -> u64 {
-        HEADER_SIZE + 6 + 32
+        HEADER_SIZE + 6 + 32 + unknown_boxes_size(&self.unknown)
     }
 }
 
@@ -74,40 +79,48 @@ impl Mp4Box for Tx3gBox {
 
 impl BlockReader for Tx3gBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        reader.get_u32(); // reserved
-        reader.get_u16(); // reserved
-        let data_reference_index = reader.get_u16();
+        reader.try_get_u32()?; // reserved
+        reader.try_get_u16()?; // reserved
+        let data_reference_index = reader.try_get_u16()?;
 
-        let display_flags = reader.get_u32();
-        let horizontal_justification = reader.get_i8();
-        let vertical_justification = reader.get_i8();
+        let display_flags = reader.try_get_u32()?;
+        let horizontal_justification = reader.try_get_i8()?;
+        let vertical_justification = reader.try_get_i8()?;
         let bg_color_rgba = RgbaColor {
-            red: reader.get_u8(),
-            green: reader.get_u8(),
-            blue: reader.get_u8(),
-            alpha: reader.get_u8(),
+            red: reader.try_get_u8()?,
+            green: reader.try_get_u8()?,
+            blue: reader.try_get_u8()?,
+            alpha: reader.try_get_u8()?,
         };
         let box_record: [i16; 4] = [
-            reader.get_i16(),
-            reader.get_i16(),
-            reader.get_i16(),
-            reader.get_i16(),
+            reader.try_get_i16()?,
+            reader.try_get_i16()?,
+            reader.try_get_i16()?,
+            reader.try_get_i16()?,
         ];
         let style_record: [u8; 12] = [
-            reader.get_u8(),
-            reader.get_u8(),
-            reader.get_u8(),
-            reader.get_u8(),
-            reader.get_u8(),
-            reader.get_u8(),
-            reader.get_u8(),
-            reader.get_u8(),
-            reader.get_u8(),
-            reader.get_u8(),
-            reader.get_u8(),
-            reader.get_u8(),
+            reader.try_get_u8()?,
+            reader.try_get_u8()?,
+            reader.try_get_u8()?,
+            reader.try_get_u8()?,
+            reader.try_get_u8()?,
+            reader.try_get_u8()?,
+            reader.try_get_u8()?,
+            reader.try_get_u8()?,
+            reader.try_get_u8()?,
+            reader.try_get_u8()?,
+            reader.try_get_u8()?,
+            reader.try_get_u8()?,
         ];
 
+        let mut unknown = UnknownBoxes::new();
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
+            unknown.push_at(position, bx.read_raw());
+        }
+
         Ok(Tx3gBox {
             data_reference_index,
             display_flags,
@@ -116,6 +129,7 @@ impl BlockReader for Tx3gBox {
             bg_color_rgba,
             box_record,
             style_record,
+            unknown,
         })
     }
 
@@ -146,15 +160,317 @@ impl<W: Write> WriteBox<&mut W> for Tx3gBox {
             writer.write_u8(self.style_record[n])?;
         }
 
+        self.unknown.writer(writer).finish()?;
+
         Ok(size)
     }
 }
 
+/// Decoded 3GPP timed text sample (3GPP TS 26.245): the text followed by optional
+/// modifier boxes. Character offsets of the modifiers are kept as stored.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct TextSample {
+    pub text: String,
+
+    /// `styl`
+    pub styles: Vec<TextStyle>,
+
+    /// `hlit`, start and end character offsets of the highlighted text
+    pub highlight: Option<(u16, u16)>,
+
+    /// `hclr`
+    pub highlight_color: Option<RgbaColor>,
+
+    /// `krok`
+    pub karaoke: Option<Karaoke>,
+
+    /// `dlay`, in track timescale units
+    pub scroll_delay: Option<u32>,
+
+    /// `href`
+    pub hyperlinks: Vec<Hyperlink>,
+
+    /// `tbox`, top, left, bottom and right of the text box
+    pub text_box: Option<[i16; 4]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct TextStyle {
+    pub start_char: u16,
+    pub end_char: u16,
+    pub font_id: u16,
+    pub face_style_flags: u8,
+    pub font_size: u8,
+    pub text_color: RgbaColor,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct Karaoke {
+    pub start_time: u32,
+    pub entries: Vec<KaraokeEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct KaraokeEntry {
+    pub end_time: u32,
+    pub start_char: u16,
+    pub end_char: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct Hyperlink {
+    pub start_char: u16,
+    pub end_char: u16,
+    pub url: String,
+    pub alt: String,
+}
+
+impl TextSample {
+    pub fn new(text: impl Into<String>) -> Self {
+        TextSample {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Decodes a sample: a 16-bit length followed by UTF-8 text, or UTF-16 if the text
+    /// starts with a byte order mark. Unknown modifiers are skipped.
+    pub fn read(mut data: &[u8]) -> Result<Self, Error> {
+        Self::read_from(&mut data)
+    }
+
+    fn read_from<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        let len = reader.try_get_u16()? as usize;
+        let text = reader.collect(len)?;
+
+        let text = match text.as_slice() {
+            [0xfe, 0xff, rest @ ..] => {
+                let units = rest
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect::<Vec<_>>();
+
+                String::from_utf16(&units)
+                    .map_err(|_| Error::InvalidData("text sample is not valid UTF-16"))?
+            }
+            _ => String::from_utf8_lossy(&text).into_owned(),
+        };
+
+        let mut sample = TextSample::new(text);
+
+        // some encoders pad samples with zeroes after the text
+        while reader.remaining() >= 8 && reader.peek_u32() != 0 {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
+
+            let modifier = &mut bx.inner;
+            match &FourCC::from(bx.kind).value {
+                b"styl" => {
+                    let count = modifier.try_get_u16()?;
+                    if count as usize > modifier.remaining() / 12 {
+                        return Err(Error::InvalidData(
+                            "styl entry_count indicates more entries than could fit in the box",
+                        ));
+                    }
+
+                    for _ in 0..count {
+                        sample.styles.push(TextStyle {
+                            start_char: modifier.try_get_u16()?,
+                            end_char: modifier.try_get_u16()?,
+                            font_id: modifier.try_get_u16()?,
+                            face_style_flags: modifier.try_get_u8()?,
+                            font_size: modifier.try_get_u8()?,
+                            text_color: read_color(modifier)?,
+                        });
+                    }
+                }
+                b"hlit" => {
+                    sample.highlight = Some((modifier.try_get_u16()?, modifier.try_get_u16()?));
+                }
+                b"hclr" => {
+                    sample.highlight_color = Some(read_color(modifier)?);
+                }
+                b"krok" => {
+                    let start_time = modifier.try_get_u32()?;
+                    let count = modifier.try_get_u16()?;
+                    if count as usize > modifier.remaining() / 8 {
+                        return Err(Error::InvalidData(
+                            "krok entry_count indicates more entries than could fit in the box",
+                        ));
+                    }
+
+                    let entries = (0..count)
+                        .map(|_| {
+                            Ok(KaraokeEntry {
+                                end_time: modifier.try_get_u32()?,
+                                start_char: modifier.try_get_u16()?,
+                                end_char: modifier.try_get_u16()?,
+                            })
+                        })
+                        .collect::<Result<_, Error>>()?;
+
+                    sample.karaoke = Some(Karaoke {
+                        start_time,
+                        entries,
+                    });
+                }
+                b"dlay" => {
+                    sample.scroll_delay = Some(modifier.try_get_u32()?);
+                }
+                b"href" => {
+                    let start_char = modifier.try_get_u16()?;
+                    let end_char = modifier.try_get_u16()?;
+                    let url = read_pascal_string(modifier)?;
+                    let alt = read_pascal_string(modifier)?;
+
+                    sample.hyperlinks.push(Hyperlink {
+                        start_char,
+                        end_char,
+                        url,
+                        alt,
+                    });
+                }
+                b"tbox" => {
+                    sample.text_box = Some([
+                        modifier.try_get_i16()?,
+                        modifier.try_get_i16()?,
+                        modifier.try_get_i16()?,
+                        modifier.try_get_i16()?,
+                    ]);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(sample)
+    }
+
+    /// Encodes the sample, the text is truncated to 65535 bytes of UTF-8.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut end = self.text.len().min(u16::MAX as usize);
+        while !self.text.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        let mut buf = Vec::with_capacity(2 + end);
+        buf.extend_from_slice(&(end as u16).to_be_bytes());
+        buf.extend_from_slice(&self.text.as_bytes()[..end]);
+
+        if !self.styles.is_empty() {
+            let mut body = (self.styles.len() as u16).to_be_bytes().to_vec();
+            for style in &self.styles {
+                body.extend_from_slice(&style.start_char.to_be_bytes());
+                body.extend_from_slice(&style.end_char.to_be_bytes());
+                body.extend_from_slice(&style.font_id.to_be_bytes());
+                body.push(style.face_style_flags);
+                body.push(style.font_size);
+                write_color(&mut body, &style.text_color);
+            }
+            write_modifier(&mut buf, b"styl", &body);
+        }
+
+        if let Some((start_char, end_char)) = self.highlight {
+            let mut body = start_char.to_be_bytes().to_vec();
+            body.extend_from_slice(&end_char.to_be_bytes());
+            write_modifier(&mut buf, b"hlit", &body);
+        }
+
+        if let Some(ref color) = self.highlight_color {
+            let mut body = Vec::with_capacity(4);
+            write_color(&mut body, color);
+            write_modifier(&mut buf, b"hclr", &body);
+        }
+
+        if let Some(ref karaoke) = self.karaoke {
+            let mut body = karaoke.start_time.to_be_bytes().to_vec();
+            body.extend_from_slice(&(karaoke.entries.len() as u16).to_be_bytes());
+            for entry in &karaoke.entries {
+                body.extend_from_slice(&entry.end_time.to_be_bytes());
+                body.extend_from_slice(&entry.start_char.to_be_bytes());
+                body.extend_from_slice(&entry.end_char.to_be_bytes());
+            }
+            write_modifier(&mut buf, b"krok", &body);
+        }
+
+        if let Some(delay) = self.scroll_delay {
+            write_modifier(&mut buf, b"dlay", &delay.to_be_bytes());
+        }
+
+        for link in &self.hyperlinks {
+            let mut body = link.start_char.to_be_bytes().to_vec();
+            body.extend_from_slice(&link.end_char.to_be_bytes());
+            write_pascal_string(&mut body, &link.url);
+            write_pascal_string(&mut body, &link.alt);
+            write_modifier(&mut buf, b"href", &body);
+        }
+
+        if let Some(text_box) = self.text_box {
+            let body = text_box
+                .iter()
+                .flat_map(|v| v.to_be_bytes())
+                .collect::<Vec<_>>();
+            write_modifier(&mut buf, b"tbox", &body);
+        }
+
+        buf
+    }
+}
+
+fn read_color<'a>(reader: &mut impl Reader<'a>) -> Result<RgbaColor, Error> {
+    if reader.remaining() < 4 {
+        return Err(Error::InvalidData("expected at least 4 byte more"));
+    }
+
+    Ok(RgbaColor {
+        red: reader.try_get_u8()?,
+        green: reader.try_get_u8()?,
+        blue: reader.try_get_u8()?,
+        alpha: reader.try_get_u8()?,
+    })
+}
+
+fn read_pascal_string<'a>(reader: &mut impl Reader<'a>) -> Result<String, Error> {
+    let len = reader.try_get_u8()?;
+    let bytes = reader.collect(len as usize)?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn write_color(buf: &mut Vec<u8>, color: &RgbaColor) {
+    buf.extend_from_slice(&[color.red, color.green, color.blue, color.alpha]);
+}
+
+fn write_pascal_string(buf: &mut Vec<u8>, s: &str) {
+    let mut end = s.len().min(u8::MAX as usize);
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    buf.push(end as u8);
+    buf.extend_from_slice(&s.as_bytes()[..end]);
+}
+
+fn write_modifier(buf: &mut Vec<u8>, kind: &[u8; 4], body: &[u8]) {
+    buf.extend_from_slice(&(HEADER_SIZE as u32 + body.len() as u32).to_be_bytes());
+    buf.extend_from_slice(kind);
+    buf.extend_from_slice(body);
+}
+
+/// Text of a 3GPP timed text sample, ignoring its modifiers.
+pub(crate) fn sample_text(data: &[u8]) -> Option<String> {
+    TextSample::read(data).ok().map(|sample| sample.text)
+}
+
+/// Encodes `text` as a 3GPP timed text sample without modifiers.
+pub(crate) fn text_sample(text: &str) -> Vec<u8> {
+    TextSample::new(text).to_bytes()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::mp4box::BoxHeader;
 
+    #[cfg(feature = "tokio")]
     #[tokio::test]
     async fn test_tx3g() {
         let src_box = Tx3gBox {
@@ -170,6 +486,7 @@ mod tests {
             },
             box_record: [0, 0, 0, 0],
             style_record: [0, 0, 0, 0, 0, 1, 0, 16, 255, 255, 255, 255],
+            unknown: UnknownBoxes::new(),
         };
         let mut buf = Vec::new();
         src_box.write_box(&mut buf).unwrap();
@@ -183,4 +500,95 @@ mod tests {
         let dst_box = Tx3gBox::read_block(&mut reader).unwrap();
         assert_eq!(src_box, dst_box);
     }
+
+    #[test]
+    fn test_tx3g_unknown() {
+        // a font table with font 1 named "Serif"
+        let src_box = Tx3gBox {
+            unknown: vec![UnknownBox::Raw(
+                BoxType::from(u32::from_be_bytes(*b"ftab")),
+                Bytes::from_static(b"\x00\x01\x00\x01\x05Serif"),
+            )]
+            .into(),
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let dst_box = Tx3gBox::read_block(&mut &buf[8..]).unwrap();
+        assert_eq!(src_box, dst_box);
+
+        let mut rewritten = Vec::new();
+        dst_box.write_box(&mut rewritten).unwrap();
+        assert_eq!(rewritten, buf);
+    }
+
+    #[test]
+    fn test_text_sample_modifiers() {
+        let src = TextSample {
+            text: "Hello world".to_string(),
+            styles: vec![TextStyle {
+                start_char: 0,
+                end_char: 5,
+                font_id: 1,
+                face_style_flags: 1,
+                font_size: 18,
+                text_color: RgbaColor {
+                    red: 255,
+                    green: 255,
+                    blue: 0,
+                    alpha: 255,
+                },
+            }],
+            highlight: Some((6, 11)),
+            highlight_color: Some(RgbaColor {
+                red: 255,
+                green: 0,
+                blue: 0,
+                alpha: 255,
+            }),
+            karaoke: Some(Karaoke {
+                start_time: 0,
+                entries: vec![
+                    KaraokeEntry {
+                        end_time: 500,
+                        start_char: 0,
+                        end_char: 5,
+                    },
+                    KaraokeEntry {
+                        end_time: 1000,
+                        start_char: 6,
+                        end_char: 11,
+                    },
+                ],
+            }),
+            scroll_delay: Some(100),
+            hyperlinks: vec![Hyperlink {
+                start_char: 6,
+                end_char: 11,
+                url: "https://example.com".to_string(),
+                alt: "world".to_string(),
+            }],
+            text_box: Some([10, 20, 110, 320]),
+        };
+
+        let mut data = src.to_bytes();
+        assert_eq!(TextSample::read(&data).unwrap(), src);
+
+        // trailing zero padding is not a modifier
+        data.extend_from_slice(&[0; 8]);
+        assert_eq!(TextSample::read(&data).unwrap(), src);
+    }
+
+    #[test]
+    fn test_text_sample_utf16() {
+        let mut data = vec![0, 6, 0xfe, 0xff, 0x00, 0x48, 0x00, 0xe9];
+        // unknown modifier
+        data.extend_from_slice(&[0, 0, 0, 9, b'b', b'l', b'n', b'k', 0]);
+
+        let sample = TextSample::read(&data).unwrap();
+        assert_eq!(sample, TextSample::new("Hé"));
+        assert_eq!(sample_text(&text_sample("Hé")).unwrap(), "Hé");
+    }
 }
@@ -7,6 +7,13 @@ use crate::mp4box::*;
 pub struct UdtaBox {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub meta: Option<MetaBox>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chpl: Option<ChplBox>,
+
+    /// QuickTime `©xyz` style user data and other children.
+    #[serde(skip)]
+    pub unknown: UnknownBoxes,
 }
 
 impl UdtaBox {
@@ -19,7 +26,10 @@ impl UdtaBox {
         if let Some(meta) = &self.meta {
             size += meta.box_size();
         }
-        size
+        if let Some(chpl) = &self.chpl {
+            size += chpl.box_size();
+        }
+        size + unknown_boxes_size(&self.unknown)
     }
 }
 
@@ -41,8 +51,26 @@ impl Mp4Box for UdtaBox {
 
 impl BlockReader for UdtaBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        let mut meta = None;
+        let mut chpl = None;
+        let mut unknown = UnknownBoxes::new();
+
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
+
+            match bx.kind {
+                BoxType::MetaBox => meta = Some(bx.read()?),
+                BoxType::ChplBox => chpl = Some(bx.read()?),
+                _ => unknown.push_at(position, bx.read_raw()),
+            }
+        }
+
         Ok(UdtaBox {
-            meta: reader.try_find_box()?,
+            meta,
+            chpl,
+            unknown,
         })
     }
 
@@ -56,21 +84,27 @@ impl<W: Write> WriteBox<&mut W> for UdtaBox {
         let size = self.box_size();
         BoxHeader::new(Self::TYPE, size).write(writer)?;
 
+        let mut children = self.unknown.writer(writer);
+
         if let Some(meta) = &self.meta {
-            meta.write_box(writer)?;
+            children.write(meta)?;
+        }
+        if let Some(chpl) = &self.chpl {
+            children.write(chpl)?;
         }
+        children.finish()?;
         Ok(size)
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
 
     #[tokio::test]
     async fn test_udta_empty() {
-        let src_box = UdtaBox { meta: None };
+        let src_box = UdtaBox::default();
 
         let mut buf = Vec::new();
         src_box.write_box(&mut buf).unwrap();
@@ -89,6 +123,8 @@ mod tests {
     async fn test_udta() {
         let src_box = UdtaBox {
             meta: Some(MetaBox::default()),
+            chpl: None,
+            unknown: UnknownBoxes::new(),
         };
 
         let mut buf = Vec::new();
@@ -51,12 +51,12 @@ impl Mp4Box for VmhdBox {
 
 impl BlockReader for VmhdBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
-        let graphics_mode = reader.get_u16();
+        let (version, flags) = read_box_header_ext(reader)?;
+        let graphics_mode = reader.try_get_u16()?;
         let op_color = RgbColor {
-            red: reader.get_u16(),
-            green: reader.get_u16(),
-            blue: reader.get_u16(),
+            red: reader.try_get_u16()?,
+            green: reader.try_get_u16()?,
+            blue: reader.try_get_u16()?,
         };
 
         Ok(VmhdBox {
@@ -88,7 +88,7 @@ impl<W: Write> WriteBox<&mut W> for VmhdBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
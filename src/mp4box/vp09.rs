@@ -20,6 +20,13 @@ pub struct Vp09Box {
     pub depth: u16,
     pub end_code: u16,
     pub vpcc: VpccBox,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sinf: Option<SinfBox>,
+
+    /// Children such as `colr`, `pasp` or `btrt`.
+    #[serde(skip)]
+    pub unknown: UnknownBoxes,
 }
 
 impl Vp09Box {
@@ -61,6 +68,16 @@ impl Vp09Box {
                 matrix_coefficients: 0,
                 codec_initialization_data_size: 0,
             },
+            sinf: None,
+            unknown: UnknownBoxes::new(),
+        }
+    }
+
+    pub fn get_type(&self) -> BoxType {
+        if self.sinf.is_some() {
+            BoxType::EncvBox
+        } else {
+            BoxType::Vp09Box
         }
     }
 }
@@ -69,7 +86,7 @@ impl Mp4Box for Vp09Box {
     const TYPE: BoxType = BoxType::Vp09Box;
 
     fn box_size(&self) -> u64 {
-        0x6A
+        0x6A + self.sinf.as_ref().map_or(0, |x| x.box_size()) + unknown_boxes_size(&self.unknown)
     }
 
     fn to_json(&self) -> Result<String, Error> {
@@ -83,35 +100,54 @@ impl Mp4Box for Vp09Box {
 
 impl BlockReader for Vp09Box {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
-        let start_code: u16 = reader.get_u16();
-        let data_reference_index: u16 = reader.get_u16();
+        let start_code: u16 = reader.try_get_u16()?;
+        let data_reference_index: u16 = reader.try_get_u16()?;
         let reserved0: [u8; 16] = {
             let mut buf = [0u8; 16];
             reader.copy_to_slice(&mut buf)?;
             buf
         };
 
-        let width: u16 = reader.get_u16();
-        let height: u16 = reader.get_u16();
-        let horizresolution: (u16, u16) = (reader.get_u16(), reader.get_u16());
-        let vertresolution: (u16, u16) = (reader.get_u16(), reader.get_u16());
+        let width: u16 = reader.try_get_u16()?;
+        let height: u16 = reader.try_get_u16()?;
+        let horizresolution: (u16, u16) = (reader.try_get_u16()?, reader.try_get_u16()?);
+        let vertresolution: (u16, u16) = (reader.try_get_u16()?, reader.try_get_u16()?);
         let reserved1: [u8; 4] = {
             let mut buf = [0u8; 4];
             reader.copy_to_slice(&mut buf)?;
             buf
         };
 
-        let frame_count: u16 = reader.get_u16();
+        let frame_count: u16 = reader.try_get_u16()?;
         let compressorname: [u8; 32] = {
             let mut buf = [0u8; 32];
             reader.copy_to_slice(&mut buf)?;
             buf
         };
 
-        let depth: u16 = reader.get_u16();
-        let end_code: u16 = reader.get_u16();
+        let depth: u16 = reader.try_get_u16()?;
+        let end_code: u16 = reader.try_get_u16()?;
+
+        let mut vpcc = None;
+        let mut sinf = None;
+        let mut unknown = UnknownBoxes::new();
+
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
+
+            match bx.kind {
+                BoxType::VpccBox => vpcc = Some(bx.read()?),
+                BoxType::SinfBox => sinf = Some(bx.read()?),
+                _ => unknown.push_at(position, bx.read_raw()),
+            }
+        }
+        let Some(vpcc) = vpcc else {
+            return Err(Error::BoxNotFound(BoxType::VpccBox));
+        };
 
         Ok(Self {
             version,
@@ -128,7 +164,9 @@ impl BlockReader for Vp09Box {
             compressorname,
             depth,
             end_code,
-            vpcc: reader.find_box::<VpccBox>()?,
+            vpcc,
+            sinf,
+            unknown,
         })
     }
 
@@ -140,7 +178,7 @@ impl BlockReader for Vp09Box {
 impl<W: Write> WriteBox<&mut W> for Vp09Box {
     fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
         let size = self.box_size();
-        BoxHeader::new(Self::TYPE, size).write(writer)?;
+        BoxHeader::new(self.get_type(), size).write(writer)?;
 
         write_box_header_ext(writer, self.version, self.flags)?;
 
@@ -158,13 +196,20 @@ impl<W: Write> WriteBox<&mut W> for Vp09Box {
         writer.write_all(&self.compressorname)?;
         writer.write_u16::<BigEndian>(self.depth)?;
         writer.write_u16::<BigEndian>(self.end_code)?;
-        VpccBox::write_box(&self.vpcc, writer)?;
+
+        let mut children = self.unknown.writer(writer);
+        children.write(&self.vpcc)?;
+
+        if let Some(ref sinf) = self.sinf {
+            children.write(sinf)?;
+        }
+        children.finish()?;
 
         Ok(size)
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
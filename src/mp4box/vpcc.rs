@@ -40,18 +40,18 @@ impl Mp4Box for VpccBox {
 
 impl BlockReader for VpccBox {
     fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
-        let (version, flags) = read_box_header_ext(reader);
+        let (version, flags) = read_box_header_ext(reader)?;
 
-        let profile = reader.get_u8();
-        let level = reader.get_u8();
+        let profile = reader.try_get_u8()?;
+        let level = reader.try_get_u8()?;
         let (bit_depth, chroma_subsampling, video_full_range_flag) = {
-            let b = reader.get_u8();
+            let b = reader.try_get_u8()?;
             (b >> 4, b << 4 >> 5, b & 0x01 == 1)
         };
 
-        let transfer_characteristics = reader.get_u8();
-        let matrix_coefficients = reader.get_u8();
-        let codec_initialization_data_size = reader.get_u16();
+        let transfer_characteristics = reader.try_get_u8()?;
+        let matrix_coefficients = reader.try_get_u8()?;
+        let codec_initialization_data_size = reader.try_get_u16()?;
 
         Ok(Self {
             version,
@@ -96,7 +96,7 @@ impl<W: Write> WriteBox<&mut W> for VpccBox {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "tokio"))]
 mod tests {
     use super::*;
     use crate::mp4box::BoxHeader;
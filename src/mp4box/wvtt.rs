@@ -0,0 +1,331 @@
+use byteorder::{BigEndian, WriteBytesExt};
+use serde::Serialize;
+use std::io::Write;
+
+use crate::mp4box::*;
+
+/// WebVTT sample entry (ISO/IEC 14496-30).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WvttBox {
+    pub data_reference_index: u16,
+
+    /// `vttC`, the WebVTT file header up to the first cue, e.g. `WEBVTT` and any
+    /// STYLE or REGION blocks.
+    pub config: String,
+
+    /// `vlab`, source label of the track.
+    pub label: Option<String>,
+
+    /// `btrt` and other child boxes.
+    #[serde(skip)]
+    pub unknown: UnknownBoxes,
+}
+
+impl Default for WvttBox {
+    fn default() -> Self {
+        WvttBox {
+            data_reference_index: 1,
+            config: "WEBVTT".to_string(),
+            label: None,
+            unknown: UnknownBoxes::new(),
+        }
+    }
+}
+
+/// A cue stored in a WebVTT sample, the timing is taken from the sample.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct VttCue {
+    /// `iden`
+    pub id: Option<String>,
+
+    /// `ctim`, the cue's original start time if the payload contains timestamps.
+    pub current_time: Option<String>,
+
+    /// `sttg`
+    pub settings: Option<String>,
+
+    /// `payl`
+    pub payload: String,
+}
+
+/// Decoded WebVTT sample: the cues active over the sample duration. A sample without
+/// cues is written as an empty cue (`vtte`).
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct VttSample {
+    /// `vttc`
+    pub cues: Vec<VttCue>,
+
+    /// `vttx`, comments from the source file.
+    pub additional_text: Vec<String>,
+}
+
+impl WvttBox {
+    pub fn get_type(&self) -> BoxType {
+        BoxType::WvttBox
+    }
+
+    pub fn get_size(&self) -> u64 {
+        let mut size = HEADER_SIZE + 8 + HEADER_SIZE + self.config.len() as u64;
+        if let Some(ref label) = self.label {
+            size += HEADER_SIZE + label.len() as u64;
+        }
+        size + unknown_boxes_size(&self.unknown)
+    }
+}
+
+impl Mp4Box for WvttBox {
+    const TYPE: BoxType = BoxType::WvttBox;
+
+    fn box_size(&self) -> u64 {
+        self.get_size()
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(&self).unwrap())
+    }
+
+    fn summary(&self) -> Result<String, Error> {
+        let s = format!(
+            "data_reference_index={} label={}",
+            self.data_reference_index,
+            self.label.as_deref().unwrap_or_default()
+        );
+        Ok(s)
+    }
+}
+
+impl BlockReader for WvttBox {
+    fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        reader.try_get_u32()?; // reserved
+        reader.try_get_u16()?; // reserved
+        let data_reference_index = reader.try_get_u16()?;
+
+        let mut config = None;
+        let mut label = None;
+        let mut unknown = UnknownBoxes::new();
+
+        for position in 0.. {
+            let Some(mut bx) = reader.get_box()? else {
+                break;
+            };
+
+            match &FourCC::from(bx.kind).value {
+                b"vttC" => config = Some(read_string(&mut bx.inner)),
+                b"vlab" => label = Some(read_string(&mut bx.inner)),
+                _ => unknown.push_at(position, bx.read_raw()),
+            }
+        }
+
+        Ok(WvttBox {
+            data_reference_index,
+            config: config.ok_or(Error::InvalidData("wvtt is missing its vttC box"))?,
+            label,
+            unknown,
+        })
+    }
+
+    fn size_hint() -> usize {
+        8
+    }
+}
+
+impl<W: Write> WriteBox<&mut W> for WvttBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+        let size = self.box_size();
+        BoxHeader::new(Self::TYPE, size).write(writer)?;
+
+        writer.write_u32::<BigEndian>(0)?; // reserved
+        writer.write_u16::<BigEndian>(0)?; // reserved
+        writer.write_u16::<BigEndian>(self.data_reference_index)?;
+
+        let mut children = self.unknown.writer(writer);
+        children.write(&StringBox(b"vttC", &self.config))?;
+        if let Some(ref label) = self.label {
+            children.write(&StringBox(b"vlab", label))?;
+        }
+        children.finish()?;
+
+        Ok(size)
+    }
+}
+
+/// Child box holding only UTF-8 text, such as `vttC`.
+struct StringBox<'a>(&'a [u8; 4], &'a str);
+
+impl<W: Write> WriteBox<&mut W> for StringBox<'_> {
+    fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+        write_string_box(writer, self.0, self.1)
+    }
+}
+
+impl VttCue {
+    pub fn new(payload: impl Into<String>) -> Self {
+        VttCue {
+            payload: payload.into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl VttSample {
+    pub fn read(mut data: &[u8]) -> Result<Self, Error> {
+        Self::read_from(&mut data)
+    }
+
+    fn read_from<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        let mut sample = VttSample::default();
+
+        while let Some(mut bx) = reader.get_box()? {
+            match &FourCC::from(bx.kind).value {
+                b"vttc" => {
+                    let mut cue = VttCue::default();
+
+                    while let Some(mut child) = bx.inner.get_box()? {
+                        let value = read_string(&mut child.inner);
+                        match &FourCC::from(child.kind).value {
+                            b"iden" => cue.id = Some(value),
+                            b"ctim" => cue.current_time = Some(value),
+                            b"sttg" => cue.settings = Some(value),
+                            b"payl" => cue.payload = value,
+                            _ => {}
+                        }
+                    }
+
+                    sample.cues.push(cue);
+                }
+                b"vttx" => sample.additional_text.push(read_string(&mut bx.inner)),
+                _ => {}
+            }
+        }
+
+        Ok(sample)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+
+        if self.cues.is_empty() && self.additional_text.is_empty() {
+            // writing to a Vec can't fail
+            let _ =
+                BoxHeader::new(u32::from_be_bytes(*b"vtte").into(), HEADER_SIZE).write(&mut buf);
+            return buf;
+        }
+
+        for cue in &self.cues {
+            let mut body = Vec::new();
+            let children = [
+                (b"iden", cue.id.as_deref()),
+                (b"ctim", cue.current_time.as_deref()),
+                (b"sttg", cue.settings.as_deref()),
+                (b"payl", Some(cue.payload.as_str())),
+            ];
+
+            for (kind, value) in children {
+                if let Some(value) = value {
+                    let _ = write_string_box(&mut body, kind, value);
+                }
+            }
+
+            let _ = BoxHeader::new(
+                u32::from_be_bytes(*b"vttc").into(),
+                HEADER_SIZE + body.len() as u64,
+            )
+            .write(&mut buf);
+            buf.extend_from_slice(&body);
+        }
+
+        for text in &self.additional_text {
+            let _ = write_string_box(&mut buf, b"vttx", text);
+        }
+
+        buf
+    }
+}
+
+/// Reads the remaining bytes of a box holding only UTF-8 text.
+pub(crate) fn read_string<'a>(reader: &mut impl Reader<'a>) -> String {
+    String::from_utf8_lossy(&reader.collect_remaining()).into_owned()
+}
+
+pub(crate) fn write_string_box<W: Write>(
+    writer: &mut W,
+    kind: &[u8; 4],
+    value: &str,
+) -> Result<u64, Error> {
+    let size = HEADER_SIZE + value.len() as u64;
+    BoxHeader::new(u32::from_be_bytes(*kind).into(), size).write(writer)?;
+    writer.write_all(value.as_bytes())?;
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_wvtt() {
+        let src_box = WvttBox {
+            data_reference_index: 1,
+            config: "WEBVTT\n\nSTYLE\n::cue { color: yellow }".to_string(),
+            label: Some("English".to_string()),
+            unknown: UnknownBoxes::new(),
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let mut reader = buf.as_slice();
+        let header = BoxHeader::read(&mut reader, &mut 0).await.unwrap().unwrap();
+        assert_eq!(header.kind, BoxType::WvttBox);
+        assert_eq!(src_box.box_size(), header.size);
+
+        let dst_box = WvttBox::read_block(&mut reader).unwrap();
+        assert_eq!(src_box, dst_box);
+    }
+
+    #[test]
+    fn test_wvtt_unknown() {
+        let src_box = WvttBox {
+            unknown: vec![UnknownBox::Raw(
+                BoxType::from(u32::from_be_bytes(*b"btrt")),
+                Bytes::from_static(&[0, 0, 0, 0, 0, 0, 0x10, 0, 0, 0, 0x08, 0]),
+            )]
+            .into(),
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        src_box.write_box(&mut buf).unwrap();
+        assert_eq!(buf.len(), src_box.box_size() as usize);
+
+        let dst_box = WvttBox::read_block(&mut &buf[8..]).unwrap();
+        assert_eq!(src_box, dst_box);
+
+        let mut rewritten = Vec::new();
+        dst_box.write_box(&mut rewritten).unwrap();
+        assert_eq!(rewritten, buf);
+    }
+
+    #[test]
+    fn test_vtt_sample() {
+        let src = VttSample {
+            cues: vec![
+                VttCue {
+                    id: Some("intro".to_string()),
+                    settings: Some("align:start line:0".to_string()),
+                    ..VttCue::new("Hello\nworld")
+                },
+                VttCue::new("<v Bob>Hi"),
+            ],
+            additional_text: vec!["NOTE translated".to_string()],
+        };
+
+        let data = src.to_bytes();
+        assert_eq!(&data[4..8], b"vttc");
+        assert_eq!(VttSample::read(&data).unwrap(), src);
+
+        let empty = VttSample::default().to_bytes();
+        assert_eq!(empty, [0, 0, 0, 8, b'v', b't', b't', b'e']);
+        assert_eq!(VttSample::read(&empty).unwrap(), VttSample::default());
+    }
+}
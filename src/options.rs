@@ -0,0 +1,98 @@
+use crate::Error;
+
+/// How [`Mp4File`](crate::Mp4File) reacts to malformed input.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Rejects inconsistent sample tables and truncated boxes.
+    Strict,
+
+    /// Fails on boxes that can't be parsed, tolerates a truncated `mdat` and sample
+    /// tables that disagree on the sample count.
+    #[default]
+    Standard,
+
+    /// Skips tracks, fragments and boxes that can't be parsed, fills missing boxes with
+    /// defaults and records a warning for each in [`Mp4File::warnings`](crate::Mp4File::warnings).
+    ///
+    /// Meant for damaged files, such as recordings cut by a power loss, to yield the
+    /// tracks that are still intact.
+    Lenient,
+}
+
+/// Options of [`Mp4File`](crate::Mp4File), see [`set_parse_options`](crate::Mp4File::set_parse_options).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    pub mode: ParseMode,
+    pub limits: Limits,
+}
+
+impl ParseOptions {
+    pub fn strict() -> Self {
+        Self {
+            mode: ParseMode::Strict,
+            ..Default::default()
+        }
+    }
+
+    pub fn lenient() -> Self {
+        Self {
+            mode: ParseMode::Lenient,
+            ..Default::default()
+        }
+    }
+}
+
+/// Bounds on what a file may make the parser allocate, so hostile input fails with
+/// [`Error::LimitExceeded`](crate::Error::LimitExceeded) instead of exhausting memory.
+///
+/// The defaults are well above what real recordings need.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Limits {
+    /// Largest box read into memory as a whole, such as `moov` or `moof`.
+    pub max_box_size: u64,
+
+    /// Most samples of a single track, counting all fragments.
+    pub max_sample_count: u64,
+    pub max_tracks: usize,
+
+    /// Deepest nesting of boxes walked by [`BoxTree`](crate::BoxTree).
+    ///
+    /// Only applies to the tree walker, [`Mp4File`](crate::Mp4File) parses the boxes it
+    /// knows, whose nesting is fixed, and keeps unknown children as raw bytes without
+    /// descending into them.
+    pub max_depth: usize,
+
+    /// Total bytes held by a file: box bodies, sample lists and `mdat` boxes kept in
+    /// memory. Data blocks that don't fit are read from the source instead.
+    pub max_allocation: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_box_size: 512 * 1024 * 1024,
+            max_sample_count: 16 * 1024 * 1024,
+            max_tracks: 1024,
+            max_depth: 32,
+            max_allocation: 2 * 1024 * 1024 * 1024,
+        }
+    }
+}
+
+impl Limits {
+    pub(crate) fn check_box_size(&self, size: u64) -> Result<(), Error> {
+        if size > self.max_box_size {
+            return Err(Error::LimitExceeded("max_box_size"));
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn check_sample_count(&self, count: u64) -> Result<(), Error> {
+        if count > self.max_sample_count {
+            return Err(Error::LimitExceeded("max_sample_count"));
+        }
+
+        Ok(())
+    }
+}
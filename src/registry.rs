@@ -0,0 +1,433 @@
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::mp4box::*;
+use crate::Error;
+
+type ParseFn = fn(&Bytes) -> Result<Arc<dyn CustomBox>, Error>;
+
+/// Parsers for boxes the crate doesn't model, such as vendor boxes in `udta`.
+///
+/// Each type is registered under the path of its parent box, e.g. `[moov, udta]`.
+/// Sample entries appear in the path with the type they have in the file, so the
+/// children of an encrypted `avc1` entry live under `.../stsd/encv`. Entries the
+/// crate doesn't model, such as `hvc1`, are kept whole as children of `stsd`.
+/// Matching children are parsed into [`UnknownBox::Custom`] values, which are written
+/// back with the type's [`WriteBox`] impl and retrieved with [`UnknownBox::downcast_ref`].
+#[derive(Debug, Clone, Default)]
+pub struct BoxRegistry {
+    parsers: HashMap<(Vec<BoxType>, BoxType), ParseFn>,
+}
+
+impl BoxRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<T>(&mut self, parent: &[BoxType]) -> &mut Self
+    where
+        T: CustomBox + Mp4Box + BlockReader,
+    {
+        self.parsers.insert((parent.to_vec(), T::TYPE), |data| {
+            Ok(Arc::new(T::read_block(&mut data.clone())?))
+        });
+
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parsers.is_empty()
+    }
+
+    /// Parses the raw children of the box at `parent` that have a registered type.
+    pub fn parse(&self, parent: &[BoxType], boxes: &mut [UnknownBox]) -> Result<(), Error> {
+        for bx in boxes.iter_mut() {
+            let UnknownBox::Raw(kind, data) = bx else {
+                continue;
+            };
+
+            if let Some(parse) = self.parsers.get(&(parent.to_vec(), *kind)) {
+                *bx = UnknownBox::Custom(parse(data)?);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn parse_moov(&self, moov: &mut MoovBox) -> Result<(), Error> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        use BoxType::*;
+
+        self.parse(&[MoovBox], &mut moov.unknown)?;
+        self.parse_meta(&[MoovBox], moov.meta.as_mut())?;
+        if let Some(udta) = &mut moov.udta {
+            self.parse(&[MoovBox, UdtaBox], &mut udta.unknown)?;
+            self.parse_meta(&[MoovBox, UdtaBox], udta.meta.as_mut())?;
+        }
+        if let Some(mvex) = &mut moov.mvex {
+            self.parse(&[MoovBox, MvexBox], &mut mvex.unknown)?;
+        }
+
+        for trak in moov.traks.iter_mut() {
+            self.parse(&[MoovBox, TrakBox], &mut trak.unknown)?;
+            self.parse_meta(&[MoovBox, TrakBox], trak.meta.as_mut())?;
+            if let Some(edts) = &mut trak.edts {
+                self.parse(&[MoovBox, TrakBox, EdtsBox], &mut edts.unknown)?;
+            }
+
+            let mdia = &mut trak.mdia;
+            self.parse(&[MoovBox, TrakBox, MdiaBox], &mut mdia.unknown)?;
+
+            let minf = &mut mdia.minf;
+            self.parse(&[MoovBox, TrakBox, MdiaBox, MinfBox], &mut minf.unknown)?;
+
+            let stbl = &mut minf.stbl;
+            self.parse(
+                &[MoovBox, TrakBox, MdiaBox, MinfBox, StblBox],
+                &mut stbl.unknown,
+            )?;
+
+            let stsd = &mut stbl.stsd;
+            let path = [MoovBox, TrakBox, MdiaBox, MinfBox, StblBox, StsdBox];
+            self.parse(&path, &mut stsd.unknown)?;
+
+            // entries are addressed by the type written to the file, e.g. `encv`
+            let entry_path = |kind| [MoovBox, TrakBox, MdiaBox, MinfBox, StblBox, StsdBox, kind];
+            if let Some(avc1) = &mut stsd.avc1 {
+                let path = entry_path(avc1.get_type());
+                self.parse(&path, &mut avc1.unknown)?;
+                self.parse_sinf(&path, avc1.sinf.as_mut())?;
+            }
+            if let Some(hev1) = &mut stsd.hev1 {
+                let path = entry_path(hev1.get_type());
+                self.parse(&path, &mut hev1.unknown)?;
+                self.parse_sinf(&path, hev1.sinf.as_mut())?;
+            }
+            if let Some(vp09) = &mut stsd.vp09 {
+                let path = entry_path(vp09.get_type());
+                self.parse(&path, &mut vp09.unknown)?;
+                self.parse_sinf(&path, vp09.sinf.as_mut())?;
+            }
+            if let Some(mp4a) = &mut stsd.mp4a {
+                let path = entry_path(mp4a.get_type());
+                self.parse(&path, &mut mp4a.unknown)?;
+                self.parse_sinf(&path, mp4a.sinf.as_mut())?;
+            }
+            if let Some(tx3g) = &mut stsd.tx3g {
+                self.parse(&entry_path(tx3g.get_type()), &mut tx3g.unknown)?;
+            }
+            if let Some(wvtt) = &mut stsd.wvtt {
+                self.parse(&entry_path(wvtt.get_type()), &mut wvtt.unknown)?;
+            }
+            if let Some(stpp) = &mut stsd.stpp {
+                self.parse(&entry_path(stpp.get_type()), &mut stpp.unknown)?;
+            }
+            if let Some(c608) = &mut stsd.c608 {
+                self.parse(&entry_path(c608.get_type()), &mut c608.unknown)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_meta(&self, parent: &[BoxType], meta: Option<&mut MetaBox>) -> Result<(), Error> {
+        let path = [parent, &[BoxType::MetaBox]].concat();
+        match meta {
+            Some(MetaBox::Mdir { unknown, .. }) | Some(MetaBox::Mdta { unknown, .. }) => {
+                self.parse(&path, unknown)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn parse_sinf(&self, parent: &[BoxType], sinf: Option<&mut SinfBox>) -> Result<(), Error> {
+        let Some(sinf) = sinf else {
+            return Ok(());
+        };
+
+        let path = [parent, &[BoxType::SinfBox]].concat();
+        self.parse(&path, &mut sinf.unknown)?;
+        if let Some(schi) = &mut sinf.schi {
+            let path = [&path[..], &[BoxType::SchiBox]].concat();
+            self.parse(&path, &mut schi.unknown)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn parse_moof(&self, moof: &mut MoofBox) -> Result<(), Error> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        use BoxType::*;
+
+        self.parse(&[MoofBox], &mut moof.unknown)?;
+        for traf in moof.trafs.iter_mut() {
+            self.parse(&[MoofBox, TrafBox], &mut traf.unknown)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byteorder::{BigEndian, WriteBytesExt};
+    use std::io::Write;
+
+    #[derive(Debug, PartialEq)]
+    struct GpsBox {
+        latitude: i32,
+        longitude: i32,
+    }
+
+    impl Mp4Box for GpsBox {
+        const TYPE: BoxType = BoxType::UnknownBox(u32::from_be_bytes(*b"xgps"));
+
+        fn box_size(&self) -> u64 {
+            HEADER_SIZE + 8
+        }
+
+        fn to_json(&self) -> Result<String, Error> {
+            Ok(String::new())
+        }
+
+        fn summary(&self) -> Result<String, Error> {
+            Ok(String::new())
+        }
+    }
+
+    impl BlockReader for GpsBox {
+        fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+            Ok(GpsBox {
+                latitude: reader.get_i32(),
+                longitude: reader.get_i32(),
+            })
+        }
+
+        fn size_hint() -> usize {
+            8
+        }
+    }
+
+    impl<W: Write> WriteBox<&mut W> for GpsBox {
+        fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+            let size = self.box_size();
+            BoxHeader::new(Self::TYPE, size).write(writer)?;
+
+            writer.write_i32::<BigEndian>(self.latitude)?;
+            writer.write_i32::<BigEndian>(self.longitude)?;
+            Ok(size)
+        }
+    }
+
+    #[test]
+    fn test_parse_moov() {
+        let gps = UnknownBox::Raw(
+            GpsBox::TYPE,
+            Bytes::from_static(&[0, 0, 0, 1, 0xff, 0xff, 0xff, 0xfe]),
+        );
+        let mut moov = MoovBox {
+            udta: Some(UdtaBox {
+                unknown: vec![gps.clone()].into(),
+                ..Default::default()
+            }),
+            unknown: vec![gps.clone()].into(),
+            ..Default::default()
+        };
+
+        let mut src = Vec::new();
+        moov.write_box(&mut src).unwrap();
+
+        let mut registry = BoxRegistry::new();
+        registry.register::<GpsBox>(&[BoxType::MoovBox, BoxType::UdtaBox]);
+        registry.parse_moov(&mut moov).unwrap();
+
+        // only registered under udta
+        assert_eq!(moov.unknown, vec![gps]);
+
+        let udta = moov.udta.as_ref().unwrap();
+        assert_eq!(
+            udta.unknown[0].downcast_ref::<GpsBox>(),
+            Some(&GpsBox {
+                latitude: 1,
+                longitude: -2,
+            })
+        );
+
+        let mut dst = Vec::new();
+        moov.write_box(&mut dst).unwrap();
+        assert_eq!(dst, src);
+    }
+
+    fn meta_unknown(meta: &mut Option<MetaBox>) -> &mut UnknownBoxes {
+        match meta.as_mut().unwrap() {
+            MetaBox::Mdir { unknown, .. } | MetaBox::Mdta { unknown, .. } => unknown,
+            MetaBox::Unknown { .. } => unreachable!(),
+        }
+    }
+
+    fn stsd(moov: &mut MoovBox) -> &mut StsdBox {
+        &mut moov.traks[0].mdia.minf.stbl.stsd
+    }
+
+    #[test]
+    fn test_parse_moov_containers() {
+        use BoxType as B;
+
+        let mdir = || MetaBox::Mdir {
+            ilst: None,
+            unknown: UnknownBoxes::new(),
+        };
+        let sinf = || SinfBox {
+            schi: Some(Default::default()),
+            ..Default::default()
+        };
+        let mut trak = TrakBox {
+            edts: Some(EdtsBox::default()),
+            meta: Some(mdir()),
+            ..Default::default()
+        };
+        trak.mdia.minf.stbl.stsd = StsdBox {
+            avc1: Some(Avc1Box {
+                sinf: Some(sinf()),
+                ..Default::default()
+            }),
+            hev1: Some(Hev1Box::default()),
+            mp4a: Some(Mp4aBox {
+                sinf: Some(sinf()),
+                ..Default::default()
+            }),
+            tx3g: Some(Tx3gBox::default()),
+            wvtt: Some(WvttBox::default()),
+            stpp: Some(StppBox::default()),
+            c608: Some(C608Box::default()),
+            ..Default::default()
+        };
+        let base = MoovBox {
+            meta: Some(mdir()),
+            udta: Some(UdtaBox {
+                meta: Some(mdir()),
+                ..Default::default()
+            }),
+            traks: vec![trak],
+            ..Default::default()
+        };
+
+        let stsd_path = [
+            B::MoovBox,
+            B::TrakBox,
+            B::MdiaBox,
+            B::MinfBox,
+            B::StblBox,
+            B::StsdBox,
+        ];
+        let entry = |tail: &[BoxType]| [&stsd_path[..], tail].concat();
+
+        type Get = fn(&mut MoovBox) -> &mut UnknownBoxes;
+        let cases: Vec<(Vec<BoxType>, Get)> = vec![
+            (vec![B::MoovBox, B::MetaBox], |m| meta_unknown(&mut m.meta)),
+            (vec![B::MoovBox, B::UdtaBox, B::MetaBox], |m| {
+                meta_unknown(&mut m.udta.as_mut().unwrap().meta)
+            }),
+            (vec![B::MoovBox, B::TrakBox, B::MetaBox], |m| {
+                meta_unknown(&mut m.traks[0].meta)
+            }),
+            (vec![B::MoovBox, B::TrakBox, B::EdtsBox], |m| {
+                &mut m.traks[0].edts.as_mut().unwrap().unknown
+            }),
+            (entry(&[B::EncvBox]), |m| {
+                &mut stsd(m).avc1.as_mut().unwrap().unknown
+            }),
+            (entry(&[B::EncvBox, B::SinfBox]), |m| {
+                &mut stsd(m)
+                    .avc1
+                    .as_mut()
+                    .unwrap()
+                    .sinf
+                    .as_mut()
+                    .unwrap()
+                    .unknown
+            }),
+            (entry(&[B::EncvBox, B::SinfBox, B::SchiBox]), |m| {
+                let sinf = stsd(m).avc1.as_mut().unwrap().sinf.as_mut().unwrap();
+                &mut sinf.schi.as_mut().unwrap().unknown
+            }),
+            (entry(&[B::Hev1Box]), |m| {
+                &mut stsd(m).hev1.as_mut().unwrap().unknown
+            }),
+            (entry(&[B::EncaBox]), |m| {
+                &mut stsd(m).mp4a.as_mut().unwrap().unknown
+            }),
+            (entry(&[B::EncaBox, B::SinfBox, B::SchiBox]), |m| {
+                let sinf = stsd(m).mp4a.as_mut().unwrap().sinf.as_mut().unwrap();
+                &mut sinf.schi.as_mut().unwrap().unknown
+            }),
+            (entry(&[B::Tx3gBox]), |m| {
+                &mut stsd(m).tx3g.as_mut().unwrap().unknown
+            }),
+            (entry(&[B::WvttBox]), |m| {
+                &mut stsd(m).wvtt.as_mut().unwrap().unknown
+            }),
+            (entry(&[B::StppBox]), |m| {
+                &mut stsd(m).stpp.as_mut().unwrap().unknown
+            }),
+            (entry(&[B::C608Box]), |m| {
+                &mut stsd(m).c608.as_mut().unwrap().unknown
+            }),
+        ];
+
+        let gps = UnknownBox::Raw(GpsBox::TYPE, Bytes::from_static(&[0; 8]));
+        for (path, get) in cases {
+            let mut moov = base.clone();
+            get(&mut moov).push(gps.clone());
+
+            let mut registry = BoxRegistry::new();
+            registry.register::<GpsBox>(&path);
+            registry.parse_moov(&mut moov).unwrap();
+
+            assert!(
+                get(&mut moov)[0].downcast_ref::<GpsBox>().is_some(),
+                "{path:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_moov_entry_type() {
+        use BoxType as B;
+
+        let gps = UnknownBox::Raw(GpsBox::TYPE, Bytes::from_static(&[0; 8]));
+        let mut moov = MoovBox {
+            traks: vec![TrakBox::default()],
+            ..Default::default()
+        };
+        stsd(&mut moov).avc1 = Some(Avc1Box {
+            sinf: Some(SinfBox::default()),
+            unknown: vec![gps.clone()].into(),
+            ..Default::default()
+        });
+
+        // the entry is written as `encv`, not `avc1`
+        let path = [
+            B::MoovBox,
+            B::TrakBox,
+            B::MdiaBox,
+            B::MinfBox,
+            B::StblBox,
+            B::StsdBox,
+            B::Avc1Box,
+        ];
+        let mut registry = BoxRegistry::new();
+        registry.register::<GpsBox>(&path);
+        registry.parse_moov(&mut moov).unwrap();
+
+        assert_eq!(stsd(&mut moov).avc1.as_ref().unwrap().unknown, vec![gps]);
+    }
+}
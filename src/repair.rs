@@ -0,0 +1,663 @@
+//! Recovery of recordings whose `moov` is missing, in the manner of `untrunc`.
+//!
+//! A camera that loses power leaves the `mdat` it was writing without the `moov`
+//! that indexes it. The samples are still there, back to back, so [`repair`] walks
+//! the `mdat` and splits it with the codec configuration of a healthy file from the
+//! same device:
+//!
+//! - AVC and HEVC samples are runs of length prefixed NAL units, an access unit ends
+//!   where the next one starts (a parameter set, SEI or delimiter, or the first slice
+//!   of a picture).
+//! - AAC frames carry no length, a frame ends with the `ID_END` element and starts
+//!   with the element the `esds` channel configuration calls for, with a valid
+//!   `ics_info`. A frame is only taken when the next ones are frames as well, within
+//!   the frame sizes seen in the reference.
+//!
+//! The samples are indexed by new sample tables in a `moov` written before them.
+
+use std::collections::BTreeMap;
+use std::io::{SeekFrom, Write};
+use std::ops::Range;
+
+use crate::file::nal_units;
+use crate::mp4a::DecoderSpecificDescriptor;
+use crate::writer::SampleTable;
+use crate::{
+    BoxHeader, BoxTree, BoxType, ByteSource, Error, Limits, MoovBox, Mp4Box, Mp4File, Mp4Track,
+    ReadSampleFormat, TrackType, TrakBox, WriteBox, HEADER_SIZE,
+};
+
+/// Outcome of a [`repair`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairReport {
+    /// Recovered samples per track ID of the reference file.
+    pub samples: BTreeMap<u32, usize>,
+
+    /// Bytes of the `mdat` that matched no track, such as the sample cut by the
+    /// power loss.
+    pub skipped_bytes: u64,
+}
+
+/// Rebuilds the samples of the `mdat` boxes of `damaged` and writes them with the
+/// tracks of `reference` as `ftyp`, a `moov` with new sample tables and one `mdat`.
+///
+/// `reference` must have its header read. Only its AVC, HEVC and AAC tracks are
+/// recovered, with the most common sample duration of each track since the timing
+/// is lost with the `moov`. The `mdat` boxes are scanned a window at a time, so
+/// `damaged` is read twice: once to find the samples and once to copy them.
+pub async fn repair<R, F, S, W>(
+    reference: &Mp4File<R, F>,
+    damaged: &mut S,
+    mut writer: W,
+) -> Result<(W, RepairReport), Error>
+where
+    R: ByteSource,
+    F: ReadSampleFormat,
+    S: ByteSource,
+    W: Write,
+{
+    let limits = Limits::default();
+
+    let mut tracks = reference.tracks.values().collect::<Vec<_>>();
+    tracks.sort_by_key(|t| t.track_id);
+    let tracks = tracks
+        .into_iter()
+        .filter_map(RepairTrack::new)
+        .collect::<Vec<_>>();
+
+    if tracks.is_empty() {
+        return Err(Error::InvalidData(
+            "reference file has no AVC, HEVC or AAC track",
+        ));
+    }
+
+    let file_size = damaged.seek(SeekFrom::End(0)).await?;
+    let (tree, _) = BoxTree::read_lenient(damaged, &limits).await?;
+
+    let mut mdats = Vec::new();
+    for (idx, node) in tree.boxes.iter().enumerate() {
+        if node.kind != BoxType::MdatBox {
+            continue;
+        }
+
+        // a recording cut short often leaves the placeholder size of the mdat header
+        let end = if idx + 1 == tree.boxes.len() {
+            file_size
+        } else {
+            node.end().min(file_size)
+        };
+        mdats.push(node.data_offset()..end);
+    }
+
+    if mdats.is_empty() {
+        return Err(Error::BoxNotFound(BoxType::MdatBox));
+    }
+
+    // a sample and whatever follows it must fit in the window to be recognized
+    let max_size = tracks.iter().map(|t| t.max_size).max().unwrap_or(0);
+    let window = 2 * max_size + AAC_MAX_FRAME_SIZE;
+
+    let mut report = RepairReport::default();
+    let mut samples = Vec::new();
+    for range in mdats {
+        report.skipped_bytes += scan_mdat(&tracks, damaged, range, window, &mut samples).await?;
+    }
+
+    let mut tables = tracks
+        .iter()
+        .map(|_| SampleTable::default())
+        .collect::<Vec<_>>();
+    let mut size = 0;
+    for sample in &samples {
+        let track = &tracks[sample.track];
+        tables[sample.track].push(size, sample.size, track.duration, sample.is_sync);
+        size += sample.size as u64;
+
+        let count = report.samples.entry(track.track.track_id).or_default();
+        *count += 1;
+        limits.check_sample_count(*count as u64)?;
+    }
+
+    let ftyp = reference.ftyp.clone().unwrap_or_default();
+    // a 64-bit size when the samples don't fit in a 32-bit one
+    let header_size = if HEADER_SIZE + size > u32::MAX as u64 {
+        HEADER_SIZE + 8
+    } else {
+        HEADER_SIZE
+    };
+    let mdat = BoxHeader::new(BoxType::MdatBox, header_size + size);
+
+    // the moov grows if large offsets need co64, which in turn moves the samples
+    let mut data_offset = ftyp.box_size() + header_size;
+    let moov = loop {
+        let moov = build_moov(&tracks, &tables, data_offset);
+        let offset = ftyp.box_size() + moov.box_size() + header_size;
+        if offset == data_offset {
+            break moov;
+        }
+
+        data_offset = offset;
+    };
+
+    ftyp.write_box(&mut writer)?;
+    moov.write_box(&mut writer)?;
+    mdat.write(&mut writer)?;
+
+    let mut buf = Vec::new();
+    for sample in &samples {
+        buf.resize(sample.size as usize, 0);
+        damaged.seek(SeekFrom::Start(sample.offset)).await?;
+        damaged.read_exact(&mut buf).await?;
+        writer.write_all(&buf)?;
+    }
+    writer.flush()?;
+
+    Ok((writer, report))
+}
+
+/// Sample found in the damaged file.
+struct FoundSample {
+    /// Index in the repaired tracks.
+    track: usize,
+    offset: u64,
+    size: u32,
+    is_sync: bool,
+}
+
+/// Finds the samples of `tracks` in `range` of `source`, keeping at least `window`
+/// bytes ahead of the current position in memory. Returns the number of bytes no
+/// track claimed.
+async fn scan_mdat<S: ByteSource>(
+    tracks: &[RepairTrack<'_>],
+    source: &mut S,
+    range: Range<u64>,
+    window: usize,
+    samples: &mut Vec<FoundSample>,
+) -> Result<u64, Error> {
+    let mut buf = Vec::new();
+    let mut buf_start = range.start;
+    let mut pos = range.start;
+    let mut skipped = 0;
+
+    source.seek(SeekFrom::Start(range.start)).await?;
+    while pos < range.end {
+        let buf_end = buf_start + buf.len() as u64;
+        if buf_end < range.end && buf_end - pos < window as u64 {
+            buf.drain(..(pos - buf_start) as usize);
+            buf_start = pos;
+
+            let len = (range.end - buf_end).min((2 * window - buf.len()) as u64) as usize;
+            let filled = buf.len();
+            buf.resize(filled + len, 0);
+            source.read_exact(&mut buf[filled..]).await?;
+        }
+
+        match find_sample(tracks, &buf[(pos - buf_start) as usize..]) {
+            Some((track, size, is_sync)) => {
+                samples.push(FoundSample {
+                    track,
+                    offset: pos,
+                    size: size as u32,
+                    is_sync,
+                });
+                pos += size as u64;
+            }
+            None => {
+                skipped += 1;
+                pos += 1;
+            }
+        }
+    }
+
+    Ok(skipped)
+}
+
+/// Track index, size and sync flag of the sample at the start of `data`.
+fn find_sample(tracks: &[RepairTrack<'_>], data: &[u8]) -> Option<(usize, usize, bool)> {
+    tracks.iter().enumerate().find_map(|(idx, track)| {
+        track
+            .sample_at(tracks, data)
+            .map(|(size, is_sync)| (idx, size, is_sync))
+    })
+}
+
+/// `moov` of the repaired file, with the sample data at `data_offset`.
+fn build_moov(tracks: &[RepairTrack<'_>], tables: &[SampleTable], data_offset: u64) -> MoovBox {
+    let mut moov = MoovBox::default();
+    let movie_timescale = moov.mvhd.timescale as u64;
+
+    for (idx, (track, table)) in tracks.iter().zip(tables).enumerate() {
+        let timescale = track.track.timescale().max(1) as u64;
+        let duration = table.duration() * movie_timescale / timescale;
+
+        let mut trak = TrakBox {
+            tkhd: track.track.tkhd.clone(),
+            mdia: track.track.mdia.clone(),
+            ..Default::default()
+        };
+        trak.tkhd.track_id = idx as u32 + 1;
+        trak.tkhd.duration = duration;
+        trak.mdia.mdhd.duration = table.duration();
+        trak.mdia.minf.stbl = table.stbl(track.track.mdia.minf.stbl.stsd.clone(), data_offset);
+
+        moov.mvhd.duration = moov.mvhd.duration.max(duration);
+        moov.traks.push(trak);
+    }
+
+    moov.mvhd.next_track_id = tracks.len() as u32 + 1;
+    moov
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Codec {
+    Avc { length_size: usize },
+    Hevc { length_size: usize },
+
+    Aac(AacSyntax),
+}
+
+struct RepairTrack<'a> {
+    track: &'a Mp4Track,
+    codec: Codec,
+    duration: u32,
+    min_size: usize,
+    max_size: usize,
+}
+
+impl<'a> RepairTrack<'a> {
+    fn new(track: &'a Mp4Track) -> Option<Self> {
+        if track.protection().is_some() {
+            return None;
+        }
+
+        let stsd = &track.mdia.minf.stbl.stsd;
+        let codec = if let Some(ref avc1) = stsd.avc1 {
+            Codec::Avc {
+                length_size: (avc1.avcc.length_size_minus_one & 0x03) as usize + 1,
+            }
+        } else if let Some(ref hev1) = stsd.hev1 {
+            Codec::Hevc {
+                length_size: (hev1.hvcc.length_size_minus_one & 0x03) as usize + 1,
+            }
+        } else if let Some(ref mp4a) = stsd.mp4a {
+            let config = &mp4a.esds.as_ref()?.es_desc.dec_config.dec_specific;
+            Codec::Aac(AacSyntax::new(config))
+        } else {
+            return None;
+        };
+
+        let mut durations = BTreeMap::<u32, usize>::new();
+        for sample in &track.samples {
+            *durations.entry(sample.duration).or_default() += 1;
+        }
+
+        let duration = durations
+            .into_iter()
+            .max_by_key(|&(_, count)| count)
+            .map(|(duration, _)| duration)
+            .filter(|&d| d > 0)
+            .unwrap_or(match codec {
+                Codec::Aac(_) => 1024,
+                _ => track.timescale().max(30) / 30,
+            });
+
+        // leave some room around the sizes seen in the reference
+        let min_size = track.samples.iter().map(|s| s.size as usize).min();
+        let max_size = track.samples.iter().map(|s| s.size as usize).max();
+        let (min_size, max_size) = match (codec, min_size, max_size) {
+            (_, Some(min), Some(max)) => ((min - min / 4).max(1), max + max / 4 + 1),
+            (Codec::Aac(_), _, _) => (1, AAC_MAX_FRAME_SIZE),
+            _ => (1, MAX_ACCESS_UNIT_SIZE),
+        };
+        let max_size = max_size.min(MAX_ACCESS_UNIT_SIZE);
+
+        Some(Self {
+            track,
+            codec,
+            duration,
+            min_size,
+            max_size,
+        })
+    }
+
+    fn is_video(&self) -> bool {
+        self.track.track_type() == TrackType::Video
+    }
+
+    /// Size and sync flag of the sample of this track at the start of `data`.
+    fn sample_at(&self, tracks: &[RepairTrack<'_>], data: &[u8]) -> Option<(usize, bool)> {
+        match self.codec {
+            Codec::Avc { length_size } => access_unit(data, length_size, self.max_size, &AVC),
+            Codec::Hevc { length_size } => access_unit(data, length_size, self.max_size, &HEVC),
+            Codec::Aac(ref syntax) => self
+                .aac_frame(tracks, data, syntax, AAC_LOOKAHEAD)
+                .map(|size| (size, true)),
+        }
+    }
+
+    /// Size of the AAC frame at the start of `data`. The frame must be followed by the
+    /// end of the data, a video sample or `depth` more frames, or only the start of one
+    /// close to the end of the data.
+    fn aac_frame(
+        &self,
+        tracks: &[RepairTrack<'_>],
+        data: &[u8],
+        syntax: &AacSyntax,
+        depth: usize,
+    ) -> Option<usize> {
+        if !syntax.frame_start(data) {
+            return None;
+        }
+
+        let max = self.max_size.min(data.len());
+        (self.min_size..=max).find(|&size| {
+            let rest = &data[size..];
+            aac_frame_end(&data[..size])
+                && (rest.is_empty()
+                    || tracks
+                        .iter()
+                        .filter(|t| t.is_video())
+                        .any(|t| t.sample_at(tracks, rest).is_some())
+                    || if depth == 0 || rest.len() <= self.max_size {
+                        syntax.frame_start(rest)
+                    } else {
+                        self.aac_frame(tracks, rest, syntax, depth - 1).is_some()
+                    })
+        })
+    }
+}
+
+/// Walks the NAL units of one access unit at the start of `data`, returning its size
+/// and whether it is a random access point.
+fn access_unit(
+    data: &[u8],
+    length_size: usize,
+    max_size: usize,
+    syntax: &NalSyntax,
+) -> Option<(usize, bool)> {
+    let mut size = 0;
+    let mut has_vcl = false;
+    let mut is_sync = false;
+
+    // the data runs on past the access unit, so a length running past it ends the walk
+    for nal in nal_units(data, length_size).map_while(Result::ok) {
+        if nal.len() <= syntax.header_size || !(syntax.is_valid)(nal) {
+            break;
+        }
+
+        let vcl = (syntax.is_vcl)(nal);
+        if has_vcl && !(syntax.continues)(nal) {
+            break;
+        }
+
+        size += length_size + nal.len();
+        if size > max_size {
+            return None;
+        }
+
+        has_vcl |= vcl;
+        is_sync |= vcl && (syntax.is_sync)(nal);
+    }
+
+    has_vcl.then_some((size, is_sync))
+}
+
+/// NAL unit header checks of a codec.
+struct NalSyntax {
+    header_size: usize,
+    is_valid: fn(&[u8]) -> bool,
+    is_vcl: fn(&[u8]) -> bool,
+    is_sync: fn(&[u8]) -> bool,
+
+    /// Whether a unit after a slice belongs to the same access unit.
+    continues: fn(&[u8]) -> bool,
+}
+
+const AVC: NalSyntax = NalSyntax {
+    header_size: 1,
+    is_valid: |nal| {
+        let ref_idc = nal[0] >> 5 & 0x03;
+        nal[0] & 0x80 == 0
+            && match nal[0] & 0x1f {
+                1..=4 => true,
+                5 | 7 | 8 => ref_idc != 0,
+                6 | 9..=12 => ref_idc == 0,
+                _ => false,
+            }
+    },
+    is_vcl: |nal| matches!(nal[0] & 0x1f, 1..=5),
+
+    // an IDR picture
+    is_sync: |nal| nal[0] & 0x1f == 5,
+
+    // further slices of the picture (first_mb_in_slice != 0) and end of sequence,
+    // end of stream and filler data
+    continues: |nal| match nal[0] & 0x1f {
+        1..=5 => nal[1] & 0x80 == 0,
+        10..=12 => true,
+        _ => false,
+    },
+};
+
+const HEVC: NalSyntax = NalSyntax {
+    header_size: 2,
+    is_valid: |nal| {
+        let layer_id = (nal[0] & 0x01) << 5 | nal[1] >> 3;
+        nal[0] & 0x80 == 0
+            && layer_id == 0
+            && nal[1] & 0x07 != 0
+            && matches!(nal[0] >> 1 & 0x3f, 0..=9 | 16..=21 | 32..=40)
+    },
+    is_vcl: |nal| nal[0] >> 1 & 0x3f < 32,
+
+    // an IRAP picture
+    is_sync: |nal| matches!(nal[0] >> 1 & 0x3f, 16..=21),
+
+    // further slice segments (first_slice_segment_in_pic_flag == 0) and end of
+    // sequence, end of bitstream, filler data and suffix SEI
+    continues: |nal| match nal[0] >> 1 & 0x3f {
+        0..=31 => nal[2] & 0x80 == 0,
+        36..=38 | 40 => true,
+        _ => false,
+    },
+};
+
+/// Single channel element, the first element of mono frames and of layouts with a
+/// center channel.
+const AAC_ID_SCE: u8 = 0;
+
+/// Channel pair element, the first element of stereo frames.
+const AAC_ID_CPE: u8 = 1;
+
+/// 6144 bits per channel for up to 8 channels.
+const AAC_MAX_FRAME_SIZE: usize = 768 * 8;
+
+/// Largest video sample looked for, when the reference has no larger ones.
+const MAX_ACCESS_UNIT_SIZE: usize = 32 * 1024 * 1024;
+
+/// `EIGHT_SHORT_SEQUENCE` window sequence of `ics_info`.
+const AAC_EIGHT_SHORT_SEQUENCE: u32 = 2;
+
+/// Frames that must follow an AAC frame for it to be recognized.
+const AAC_LOOKAHEAD: usize = 2;
+
+/// Start of the raw AAC frames of a track, from the `esds` decoder config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct AacSyntax {
+    /// Syntax element that starts a frame, `None` if the channel configuration
+    /// doesn't say.
+    first_element: Option<u8>,
+
+    /// Scale factor bands of long and short windows at the sample rate.
+    num_swb: (u32, u32),
+
+    /// Whether `predictor_data_present` may be set, only in AAC Main.
+    prediction: bool,
+}
+
+impl AacSyntax {
+    fn new(config: &DecoderSpecificDescriptor) -> Self {
+        Self {
+            first_element: match config.chan_conf {
+                0 => None,
+                2 => Some(AAC_ID_CPE),
+                _ => Some(AAC_ID_SCE),
+            },
+            num_swb: match config.freq_index {
+                0 | 1 => (41, 12),
+                2 => (47, 12),
+                3 | 4 => (49, 14),
+                5 => (51, 14),
+                6 | 7 => (47, 15),
+                8..=10 => (43, 15),
+                11 | 12 => (40, 15),
+                _ => (51, 15),
+            },
+            prediction: config.profile == 1,
+        }
+    }
+
+    /// Whether `data` starts with the first element of a raw AAC frame, with an
+    /// element instance tag of 0 and a valid `ics_info`.
+    fn frame_start(&self, data: &[u8]) -> bool {
+        let Some(head) = data.get(..4) else {
+            return false;
+        };
+
+        let mut bits = u32::from_be_bytes(head.try_into().unwrap());
+        let mut read = |count: u32| {
+            let value = bits >> (32 - count);
+            bits <<= count;
+            value
+        };
+
+        let id = read(3) as u8;
+        let valid_id = match self.first_element {
+            Some(first) => id == first,
+            None => id == AAC_ID_SCE || id == AAC_ID_CPE,
+        };
+        if !valid_id || read(4) != 0 {
+            return false;
+        }
+
+        let common_window = id == AAC_ID_CPE && read(1) == 1;
+        if !common_window {
+            read(8); // global_gain
+        }
+
+        // ics_reserved_bit
+        if read(1) != 0 {
+            return false;
+        }
+
+        let window_sequence = read(2);
+        read(1); // window_shape
+
+        if window_sequence == AAC_EIGHT_SHORT_SEQUENCE {
+            let max_sfb = read(4);
+            read(7); // scale_factor_grouping
+            max_sfb <= self.num_swb.1 && !(common_window && read(2) == 3)
+        } else {
+            let max_sfb = read(6);
+            let predictor_data_present = read(1) == 1;
+            if predictor_data_present {
+                return self.prediction && max_sfb <= self.num_swb.0;
+            }
+
+            // ms_mask_present of 3 is reserved
+            max_sfb <= self.num_swb.0 && !(common_window && read(2) == 3)
+        }
+    }
+}
+
+/// Whether `frame` ends with the `ID_END` element (`0b111`) and byte alignment zeros.
+fn aac_frame_end(frame: &[u8]) -> bool {
+    let Some(&last) = frame.last() else {
+        return false;
+    };
+
+    let prev = frame.len().checked_sub(2).map_or(0, |idx| frame[idx]);
+    let word = (prev as u16) << 8 | last as u16;
+    let padding = word.trailing_zeros();
+
+    padding < 8 && word >> padding & 0x07 == 0x07
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nal(bytes: &[u8]) -> Vec<u8> {
+        let mut data = (bytes.len() as u32).to_be_bytes().to_vec();
+        data.extend_from_slice(bytes);
+        data
+    }
+
+    #[test]
+    fn test_access_unit() {
+        // AUD, IDR slice in two parts, then the first slice of the next picture
+        let mut data = nal(&[0x09, 0xf0]);
+        data.extend(nal(&[0x65, 0x88, 0x12, 0x34]));
+        data.extend(nal(&[0x65, 0x40, 0x56]));
+        let first = data.len();
+        data.extend(nal(&[0x41, 0x9a, 0x78]));
+
+        assert_eq!(access_unit(&data, 4, usize::MAX, &AVC), Some((first, true)));
+        assert_eq!(
+            access_unit(&data[first..], 4, usize::MAX, &AVC),
+            Some((data.len() - first, false))
+        );
+        assert_eq!(access_unit(&data, 4, first - 1, &AVC), None);
+
+        // a length running past the data
+        assert_eq!(
+            access_unit(&data[..first - 1], 4, usize::MAX, &AVC).map(|x| x.0),
+            Some(14)
+        );
+        assert_eq!(access_unit(&[0, 0, 1, 0, 0x65], 4, usize::MAX, &AVC), None);
+
+        // VPS then a CRA picture
+        let mut data = nal(&[0x40, 0x01, 0x0c]);
+        data.extend(nal(&[0x2a, 0x01, 0xaf, 0x00]));
+        assert_eq!(
+            access_unit(&data, 4, usize::MAX, &HEVC),
+            Some((data.len(), true))
+        );
+        assert_eq!(
+            access_unit(&nal(&[0x40, 0x09, 0x0c]), 4, usize::MAX, &HEVC),
+            None
+        );
+    }
+
+    #[test]
+    fn test_aac_frame() {
+        let stereo = AacSyntax::new(&DecoderSpecificDescriptor {
+            profile: 2,
+            freq_index: 4,
+            chan_conf: 2,
+        });
+
+        // CPE without common window, long window with 12 bands
+        assert!(stereo.frame_start(&[0x20, 0x40, 0x03, 0x10]));
+        assert!(!stereo.frame_start(&[0x20, 0x40, 0x03, 0x20]));
+        assert!(!stereo.frame_start(&[0x20, 0x40, 0x83, 0x10]));
+
+        // CPE with common window, eight short windows with 14 bands
+        assert!(stereo.frame_start(&[0x21, 0x4e, 0x00, 0x00]));
+        assert!(!stereo.frame_start(&[0x21, 0x4f, 0x00, 0x00]));
+        assert!(!stereo.frame_start(&[0x21, 0x4e, 0x01, 0x80]));
+
+        let mono = AacSyntax {
+            first_element: Some(AAC_ID_SCE),
+            ..stereo
+        };
+        assert!(!mono.frame_start(&[0x20, 0x40, 0x03, 0x10]));
+        assert!(mono.frame_start(&[0x00, 0x40, 0x31, 0x00]));
+
+        assert!(aac_frame_end(&[0x21, 0x0e]));
+        assert!(aac_frame_end(&[0x21, 0xe0]));
+        assert!(aac_frame_end(&[0x03, 0x80]));
+        assert!(!aac_frame_end(&[0x21, 0x00]));
+        assert!(!aac_frame_end(&[0x21, 0x0c]));
+    }
+}
@@ -0,0 +1,290 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::Error;
+
+/// Text format of exported or imported subtitles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleFormat {
+    Srt,
+    WebVtt,
+}
+
+/// A single subtitle with its display interval.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SubtitleCue {
+    pub start: Duration,
+    pub end: Duration,
+    pub text: String,
+
+    /// WebVTT cue identifier.
+    pub id: Option<String>,
+
+    /// WebVTT cue settings, e.g. `align:start line:0`.
+    pub settings: Option<String>,
+}
+
+impl SubtitleFormat {
+    /// Formats the cues as a SubRip or WebVTT document.
+    pub fn write(self, cues: &[SubtitleCue]) -> String {
+        let mut out = String::new();
+        if self == SubtitleFormat::WebVtt {
+            out.push_str("WEBVTT\n\n");
+        }
+
+        for (idx, cue) in cues.iter().enumerate() {
+            match (self, &cue.id) {
+                (SubtitleFormat::Srt, _) => {
+                    let _ = writeln!(out, "{}", idx + 1);
+                }
+                (SubtitleFormat::WebVtt, Some(id)) => {
+                    let _ = writeln!(out, "{id}");
+                }
+                (SubtitleFormat::WebVtt, None) => {}
+            }
+
+            let _ = write!(
+                out,
+                "{} --> {}",
+                self.timestamp(cue.start),
+                self.timestamp(cue.end)
+            );
+            match (self, &cue.settings) {
+                (SubtitleFormat::WebVtt, Some(settings)) => {
+                    let _ = writeln!(out, " {settings}");
+                }
+                _ => out.push('\n'),
+            }
+
+            let text = cue.text.replace("\r\n", "\n");
+            for line in text.lines().filter(|line| !line.trim().is_empty()) {
+                out.push_str(line);
+                out.push('\n');
+            }
+            out.push('\n');
+        }
+
+        out
+    }
+
+    fn timestamp(self, time: Duration) -> String {
+        let millis = time.as_millis();
+        let separator = match self {
+            SubtitleFormat::Srt => ',',
+            SubtitleFormat::WebVtt => '.',
+        };
+
+        format!(
+            "{:02}:{:02}:{:02}{}{:03}",
+            millis / 3_600_000,
+            millis / 60_000 % 60,
+            millis / 1000 % 60,
+            separator,
+            millis % 1000
+        )
+    }
+}
+
+/// Parses a SubRip document, cue numbers are optional and cues are kept in file order.
+pub fn parse_srt(input: &str) -> Result<Vec<SubtitleCue>, Error> {
+    let input = input.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+
+    blocks(&input)
+        .map(|block| {
+            let mut cue = parse_cue(block)?;
+            cue.id = None;
+            cue.settings = None;
+            Ok(cue)
+        })
+        .collect()
+}
+
+/// Parses a WebVTT document into its header, as stored in `vttC` (the `WEBVTT` line
+/// followed by any STYLE and REGION blocks), and its cues. Comments are dropped.
+pub fn parse_webvtt(input: &str) -> Result<(String, Vec<SubtitleCue>), Error> {
+    let input = input.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    let mut blocks = blocks(&input);
+
+    let header = blocks
+        .next()
+        .filter(|block| block.starts_with("WEBVTT"))
+        .ok_or(Error::InvalidData("webvtt document must start with WEBVTT"))?;
+
+    let mut header = header.to_string();
+    let mut cues = Vec::new();
+
+    for block in blocks {
+        if block.starts_with("NOTE") {
+            continue;
+        }
+
+        if cues.is_empty() && (block.starts_with("STYLE") || block.starts_with("REGION")) {
+            header.push_str("\n\n");
+            header.push_str(block);
+            continue;
+        }
+
+        cues.push(parse_cue(block)?);
+    }
+
+    Ok((header, cues))
+}
+
+/// Non-empty blocks of a document separated by blank lines.
+fn blocks(input: &str) -> impl Iterator<Item = &str> {
+    input
+        .split("\n\n")
+        .map(|block| block.trim_matches('\n'))
+        .filter(|block| !block.trim().is_empty())
+}
+
+/// Parses an optional identifier line, the timing line and the cue text.
+fn parse_cue(block: &str) -> Result<SubtitleCue, Error> {
+    const MISSING_TIMING: Error = Error::InvalidData("cue is missing its timing line");
+
+    let mut lines = block.lines();
+    let mut line = lines.next().ok_or(MISSING_TIMING)?;
+
+    let mut id = None;
+    if !line.contains("-->") {
+        id = Some(line.trim().to_string());
+        line = lines.next().ok_or(MISSING_TIMING)?;
+    }
+
+    let (start, rest) = line.split_once("-->").ok_or(MISSING_TIMING)?;
+    let rest = rest.trim();
+    let (end, settings) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+
+    let start = parse_timestamp(start)?;
+    let end = parse_timestamp(end)?;
+    let settings = settings.trim();
+
+    Ok(SubtitleCue {
+        start,
+        end: end.max(start),
+        text: lines.collect::<Vec<_>>().join("\n"),
+        id,
+        settings: (!settings.is_empty()).then(|| settings.to_string()),
+    })
+}
+
+/// Parses `HH:MM:SS,mmm`, also accepting `.` as the fraction separator and a missing
+/// hours field as WebVTT does.
+fn parse_timestamp(s: &str) -> Result<Duration, Error> {
+    const INVALID: Error = Error::InvalidData("invalid subtitle timestamp");
+
+    let (hms, millis) = s.trim().split_once([',', '.']).ok_or(INVALID)?;
+    let fields = hms
+        .split(':')
+        .map(|p| p.trim().parse::<u64>().map_err(|_| INVALID))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (hours, minutes, seconds) = match fields[..] {
+        [hours, minutes, seconds] => (hours, minutes, seconds),
+        [minutes, seconds] => (0, minutes, seconds),
+        _ => return Err(INVALID),
+    };
+
+    if minutes >= 60 || seconds >= 60 {
+        return Err(INVALID);
+    }
+
+    let millis = millis.get(..3).unwrap_or(millis);
+    let millis = millis.parse::<u64>().map_err(|_| INVALID)? * 10u64.pow(3 - millis.len() as u32);
+
+    Ok(Duration::from_millis(
+        (hours * 3600 + minutes * 60 + seconds) * 1000 + millis,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRT: &str = "\u{feff}1\r\n00:00:01,500 --> 00:00:03,000\r\nHello\r\nworld\r\n\r\n2\r\n01:02:03,004 --> 01:02:04,000\r\n<i>Bye</i>\r\n";
+
+    #[test]
+    fn test_srt_round_trip() {
+        let cues = parse_srt(SRT).unwrap();
+        assert_eq!(
+            cues,
+            vec![
+                SubtitleCue {
+                    start: Duration::from_millis(1500),
+                    end: Duration::from_secs(3),
+                    text: "Hello\nworld".to_string(),
+                    ..Default::default()
+                },
+                SubtitleCue {
+                    start: Duration::from_millis(3_723_004),
+                    end: Duration::from_secs(3724),
+                    text: "<i>Bye</i>".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+
+        let srt = SubtitleFormat::Srt.write(&cues);
+        assert_eq!(
+            srt,
+            "1\n00:00:01,500 --> 00:00:03,000\nHello\nworld\n\n2\n01:02:03,004 --> 01:02:04,000\n<i>Bye</i>\n\n"
+        );
+        assert_eq!(parse_srt(&srt).unwrap(), cues);
+
+        let vtt = SubtitleFormat::WebVtt.write(&cues[..1]);
+        assert_eq!(
+            vtt,
+            "WEBVTT\n\n00:00:01.500 --> 00:00:03.000\nHello\nworld\n\n"
+        );
+    }
+
+    #[test]
+    fn test_srt_invalid() {
+        assert!(parse_srt("1\n00:00:01 --> 00:00:02,000\nText\n").is_err());
+        assert!(parse_srt("1\nText\n").is_err());
+        assert_eq!(
+            parse_srt("00:00:01.5 --> 00:00:02.25 align:start\nText\n").unwrap()[0],
+            SubtitleCue {
+                start: Duration::from_millis(1500),
+                end: Duration::from_millis(2250),
+                text: "Text".to_string(),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_webvtt() {
+        let vtt = "WEBVTT - Example\n\nSTYLE\n::cue { color: yellow }\n\nNOTE a comment\n\n\
+                   intro\n00:01.000 --> 00:04.000 align:start line:0\n<v Bob>Hello\n\n\
+                   01:00:00.000 --> 01:00:01.000\nBye\n";
+
+        let (header, cues) = parse_webvtt(vtt).unwrap();
+        assert_eq!(header, "WEBVTT - Example\n\nSTYLE\n::cue { color: yellow }");
+        assert_eq!(
+            cues,
+            vec![
+                SubtitleCue {
+                    start: Duration::from_secs(1),
+                    end: Duration::from_secs(4),
+                    text: "<v Bob>Hello".to_string(),
+                    id: Some("intro".to_string()),
+                    settings: Some("align:start line:0".to_string()),
+                },
+                SubtitleCue {
+                    start: Duration::from_secs(3600),
+                    end: Duration::from_secs(3601),
+                    text: "Bye".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+
+        assert_eq!(
+            SubtitleFormat::WebVtt.write(&cues),
+            "WEBVTT\n\nintro\n00:00:01.000 --> 00:00:04.000 align:start line:0\n<v Bob>Hello\n\n\
+             01:00:00.000 --> 01:00:01.000\nBye\n\n"
+        );
+        assert!(parse_webvtt("1\n00:01.000 --> 00:02.000\nText\n").is_err());
+    }
+}
@@ -0,0 +1,90 @@
+//! Files and configs shared by the unit tests, `tests/common` has the same helpers
+//! for the integration tests.
+
+use crate::{
+    AacConfig, AvcConfig, MediaConfig, Mp4Config, Mp4FragmentWriter, Mp4Sample, TrackConfig,
+    TrackType,
+};
+
+/// Movie config with `brands[0]` as the major brand and a 1 ms timescale.
+pub(crate) fn config(brands: &[&str]) -> Mp4Config {
+    Mp4Config {
+        major_brand: str::parse(brands[0]).unwrap(),
+        minor_version: 512,
+        compatible_brands: brands.iter().map(|b| str::parse(b).unwrap()).collect(),
+        timescale: 1000,
+    }
+}
+
+/// 320x240 H.264 track.
+pub(crate) fn avc_track(timescale: u32) -> TrackConfig {
+    TrackConfig {
+        track_type: TrackType::Video,
+        timescale,
+        language: "und".to_string(),
+        media_conf: MediaConfig::AvcConfig(AvcConfig {
+            width: 320,
+            height: 240,
+            seq_param_set: vec![0x67, 0x64, 0x00, 0x1f, 0xac, 0xd9, 0x40, 0x50],
+            pic_param_set: vec![0x68, 0xeb, 0xe3, 0xcb],
+        }),
+    }
+}
+
+/// AAC LC track.
+pub(crate) fn aac_track(timescale: u32) -> TrackConfig {
+    TrackConfig {
+        track_type: TrackType::Audio,
+        timescale,
+        language: "und".to_string(),
+        media_conf: MediaConfig::AacConfig(AacConfig::default()),
+    }
+}
+
+pub(crate) fn sample(start_time: u64, duration: u32, is_sync: bool, bytes: Vec<u8>) -> Mp4Sample {
+    Mp4Sample {
+        start_time,
+        duration,
+        rendering_offset: 0,
+        is_sync,
+        bytes: bytes.into(),
+    }
+}
+
+/// Video and audio track with four 100 ms samples each, the third video sample
+/// isn't a sync sample so the file has three fragments.
+pub(crate) fn fragmented_file() -> Vec<u8> {
+    let mut writer = Mp4FragmentWriter::write_start(Vec::new(), &config(&["isom"])).unwrap();
+    let video = writer.add_track(&avc_track(1000)).unwrap();
+    let audio = writer.add_track(&aac_track(1000)).unwrap();
+
+    for idx in 0..4u64 {
+        for track_id in [video, audio] {
+            let sample = sample(idx * 100, 100, idx != 2, vec![idx as u8; 6]);
+            writer.write_sample(track_id, &sample).unwrap();
+        }
+    }
+
+    writer.write_end().unwrap()
+}
+
+/// Two audio tracks with 40 samples of 100 ms each, a sync sample every second.
+///
+/// Samples hold the track id and their index followed by `[0, 1, 2, 3]`.
+pub(crate) fn two_track_file() -> Vec<u8> {
+    let mut writer = Mp4FragmentWriter::write_start(Vec::new(), &config(&["isom"])).unwrap();
+    let track_ids = [
+        writer.add_track(&aac_track(1000)).unwrap(),
+        writer.add_track(&aac_track(1000)).unwrap(),
+    ];
+
+    for idx in 0..40u64 {
+        for track_id in track_ids {
+            let bytes = vec![track_id as u8, idx as u8, 0, 1, 2, 3];
+            let sample = sample(idx * 100, 100, idx % 10 == 0, bytes);
+            writer.write_sample(track_id, &sample).unwrap();
+        }
+    }
+
+    writer.write_end().unwrap()
+}
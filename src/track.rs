@@ -1,12 +1,14 @@
 use bytes::{BufMut, Bytes, BytesMut};
 use flowly::Fourcc;
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
 
 use crate::ctts::CttsEntry;
 use crate::error::Error;
 use crate::stsc::StscEntry;
 use crate::stts::SttsEntry;
-use crate::{BoxType, TrackType};
+use crate::{
+    BoxType, Limits, ParseMode, ParseOptions, ReferenceType, SampleEncryption, SinfBox, TrackType,
+};
 
 #[derive(Clone)]
 pub struct Mp4SampleOffset {
@@ -17,6 +19,9 @@ pub struct Mp4SampleOffset {
     pub rendering_offset: i32,
     pub is_sync: bool,
     pub chunk_id: u32,
+
+    /// IV and subsample map of a protected sample, see [`Mp4Track::protection`].
+    pub encryption: Option<SampleEncryption>,
 }
 
 #[derive(Clone)]
@@ -26,10 +31,32 @@ pub struct Mp4Track {
     pub samples: Vec<Mp4SampleOffset>,
     pub tkhd: crate::TkhdBox,
     pub mdia: crate::MdiaBox,
+    pub tref: Option<crate::TrefBox>,
 }
 
 impl Mp4Track {
     pub fn new(trak: crate::TrakBox, offsets: &mut BTreeSet<u64>) -> Result<Mp4Track, Error> {
+        Self::with_options(trak, offsets, &ParseOptions::default()).map(|(track, _)| track)
+    }
+
+    /// Builds the sample list with the checks of the parse mode, within the limits.
+    ///
+    /// In lenient mode samples without a chunk are dropped, and the returned messages
+    /// say so, as well as when protected samples have no encryption info.
+    pub(crate) fn with_options(
+        trak: crate::TrakBox,
+        offsets: &mut BTreeSet<u64>,
+        options: &ParseOptions,
+    ) -> Result<(Mp4Track, Vec<String>), Error> {
+        let mode = options.mode;
+        options
+            .limits
+            .check_sample_count(trak.mdia.minf.stbl.stsz.sample_count as u64)?;
+
+        if mode == ParseMode::Strict {
+            check_sample_tables(&trak.mdia.minf.stbl)?;
+        }
+
         let default_sample_duration = 1024;
         let mut total_duration = 0;
         let mut samples = Vec::with_capacity(trak.mdia.minf.stbl.stsz.sample_count as _);
@@ -103,10 +130,23 @@ impl Mp4Track {
         let mut sample_offset = 0;
         let mut curr_chunk_index = 0;
         let mut prev_size = 0;
+        let mut warnings = Vec::new();
+
+        let sample_count = trak.mdia.minf.stbl.stsz.sample_count as usize;
+        for sample_idx in 0..sample_count {
+            let Some(chunk) = sample_chunk_iter.next() else {
+                if mode != ParseMode::Lenient {
+                    return Err(Error::InvalidData("stsz has more samples than the chunks"));
+                }
 
-        for sample_idx in 0..trak.mdia.minf.stbl.stsz.sample_count as usize {
-            let (start_time, duration) = ts_deltas.next().unwrap();
-            let chunk = sample_chunk_iter.next().unwrap();
+                warnings.push(format!(
+                    "chunks hold {sample_idx} of the {sample_count} samples, the rest is dropped"
+                ));
+                break;
+            };
+
+            // the deltas are extended with a default duration, so never run out
+            let (start_time, duration) = ts_deltas.next().unwrap_or_default();
             let size = *trak
                 .mdia
                 .minf
@@ -133,16 +173,44 @@ impl Mp4Track {
                 start_time,
                 rendering_offset: rend_offset_iter.next().unwrap_or(0),
                 is_sync: sync_iter.next().unwrap_or(true),
+                encryption: None,
             })
         }
 
-        Ok(Self {
+        let mut track = Self {
             track_id: trak.tkhd.track_id,
             tkhd: trak.tkhd,
             mdia: trak.mdia,
+            tref: trak.tref,
             samples,
             duration: total_duration,
-        })
+        };
+
+        // senc is only found in fragments
+        let (encryption, warning) =
+            track.sample_encryption(None, None, track.samples.len() as u32)?;
+        for (sample, encryption) in track.samples.iter_mut().zip(encryption) {
+            sample.encryption = Some(encryption);
+        }
+        warnings.extend(warning);
+
+        Ok((track, warnings))
+    }
+
+    /// Referenced track IDs by reference type, from the track's `tref` box.
+    pub fn references(&self) -> HashMap<ReferenceType, Vec<u32>> {
+        self.tref
+            .iter()
+            .flat_map(|tref| tref.references.iter())
+            .map(|r| (r.reference_type, r.track_ids.clone()))
+            .collect()
+    }
+
+    /// Track IDs referenced with the given type.
+    pub fn referenced_tracks(&self, reference_type: ReferenceType) -> &[u32] {
+        self.tref
+            .as_ref()
+            .map_or(&[], |tref| tref.track_ids(reference_type))
     }
 
     #[inline]
@@ -162,23 +230,39 @@ impl Mp4Track {
             Fourcc::AUDIO_AAC
         } else if self.mdia.minf.stbl.stsd.tx3g.is_some() {
             Fourcc::from_static("TTXT")
+        } else if self.mdia.minf.stbl.stsd.wvtt.is_some() {
+            Fourcc::from_static("wvtt")
+        } else if self.mdia.minf.stbl.stsd.stpp.is_some() {
+            Fourcc::from_static("stpp")
+        } else if self.mdia.minf.stbl.stsd.c608.is_some() {
+            Fourcc::from_static("c608")
         } else {
             Default::default()
         }
     }
 
+    /// Protection scheme information if the track is encrypted.
+    #[inline]
+    pub fn protection(&self) -> Option<&SinfBox> {
+        self.mdia.minf.stbl.stsd.sinf()
+    }
+
+    /// Adds the samples of a fragment, the returned message says when protected
+    /// samples have no encryption info.
     pub(crate) fn add_traf(
         &mut self,
         base_moof_offset: u64,
         chunk_index: u32,
         traf: crate::TrafBox,
         offsets: &mut BTreeSet<u64>,
-    ) {
+        limits: &Limits,
+    ) -> Result<Option<String>, Error> {
         let base_data_offset = traf.tfhd.base_data_offset.unwrap_or(base_moof_offset);
         offsets.insert(base_data_offset);
 
         let default_sample_size = traf.tfhd.default_sample_size.unwrap_or(0);
         let default_sample_duration = traf.tfhd.default_sample_duration.unwrap_or(0);
+        let default_sample_flags = traf.tfhd.default_sample_flags;
         let base_start_time = traf
             .tfdt
             .map(|x| x.base_media_decode_time)
@@ -190,9 +274,16 @@ impl Mp4Track {
             .unwrap_or(0);
 
         let Some(trun) = traf.trun else {
-            return;
+            return Ok(None);
         };
 
+        limits.check_sample_count(self.samples.len() as u64 + trun.sample_count as u64)?;
+
+        let (encryption, warning) =
+            self.sample_encryption(traf.senc.as_ref(), traf.saiz.as_ref(), trun.sample_count)?;
+        let mut encryption = encryption.into_iter();
+
+        let first_new = self.samples.len();
         let mut sample_offset = 0u64;
         let mut start_time_offset = 0u64;
         for sample_idx in 0..trun.sample_count as usize {
@@ -210,6 +301,17 @@ impl Mp4Track {
 
             let rendering_offset = trun.sample_cts.get(sample_idx).copied().unwrap_or(0) as i32;
 
+            let sample_flags = trun
+                .first_sample_flags
+                .filter(|_| sample_idx == 0)
+                .or_else(|| trun.sample_flags.get(sample_idx).copied())
+                .or(default_sample_flags);
+
+            // sample_is_non_sync_sample bit
+            let is_sync = sample_flags
+                .map(|flags| flags & 0x10000 == 0)
+                .unwrap_or(sample_idx == 0);
+
             self.samples.push(Mp4SampleOffset {
                 chunk_id: chunk_index,
                 offset: (base_data_offset as i64
@@ -219,12 +321,73 @@ impl Mp4Track {
                 duration,
                 start_time: base_start_time + start_time_offset,
                 rendering_offset,
-                is_sync: sample_idx == 0,
+                is_sync,
+                encryption: encryption.next(),
             });
 
             sample_offset += size as u64;
             start_time_offset += duration as u64;
         }
+
+        // fragments may be loaded out of order (random access), keep samples sorted by time
+        if let Some(first) = self.samples.get(first_new) {
+            let start_time = first.start_time;
+            let pos = self.samples[..first_new].partition_point(|s| s.start_time <= start_time);
+            self.samples[pos..].rotate_left(first_new - pos);
+        }
+
+        Ok(warning)
+    }
+
+    /// Encryption info of `sample_count` protected samples.
+    ///
+    /// Entries come from `senc` and must match the sizes in `saiz`. Without `senc` only
+    /// a constant IV from `tenc` can be used, otherwise the samples are left without
+    /// encryption info and the returned message says so.
+    fn sample_encryption(
+        &self,
+        senc: Option<&crate::SencBox>,
+        saiz: Option<&crate::SaizBox>,
+        sample_count: u32,
+    ) -> Result<(Vec<SampleEncryption>, Option<String>), Error> {
+        let Some(tenc) = self.protection().and_then(SinfBox::tenc) else {
+            return Ok((Vec::new(), None));
+        };
+
+        let mut samples = match senc {
+            Some(senc) => {
+                let samples = senc.samples(tenc.default_per_sample_iv_size)?;
+                if let Some(saiz) = saiz {
+                    check_aux_info_sizes(senc, saiz, &samples)?;
+                }
+
+                samples
+            }
+            None if sample_count == 0 || tenc.default_is_protected != 1 => Vec::new(),
+            None if tenc.default_per_sample_iv_size == 0
+                && !tenc.default_constant_iv.is_empty() =>
+            {
+                // the whole sample is encrypted with the constant IV
+                vec![SampleEncryption::default(); sample_count as usize]
+            }
+            None => {
+                let message = if saiz.is_some() {
+                    "sample auxiliary info outside of senc is not supported"
+                } else {
+                    "protected samples have no senc and tenc has no constant IV"
+                };
+
+                return Ok((Vec::new(), Some(message.to_string())));
+            }
+        };
+
+        if tenc.default_per_sample_iv_size == 0 {
+            for sample in samples.iter_mut() {
+                sample.iv.clone_from(&tenc.default_constant_iv);
+            }
+        }
+
+        Ok((samples, None))
     }
 
     pub fn sequence_parameter_set(&self) -> Result<&[u8], Error> {
@@ -262,12 +425,12 @@ impl Mp4Track {
             Fourcc::VIDEO_AVC => {
                 let mut buf = BytesMut::new();
 
-                let sps = self.sequence_parameter_set().unwrap();
+                let sps = self.sequence_parameter_set().ok()?;
                 buf.put_u32(sps.len() as u32 + 4);
                 buf.put_slice(&[0, 0, 0, 1]);
                 buf.put_slice(sps);
 
-                let pps = self.picture_parameter_set().unwrap();
+                let pps = self.picture_parameter_set().ok()?;
                 buf.put_u32(pps.len() as u32 + 4);
                 buf.put_slice(&[0, 0, 0, 1]);
                 buf.put_slice(pps);
@@ -277,7 +440,7 @@ impl Mp4Track {
 
             Fourcc::VIDEO_HEVC => {
                 let mut buf = BytesMut::new();
-                let x = self.mdia.minf.stbl.stsd.hev1.as_ref().unwrap();
+                let x = self.mdia.minf.stbl.stsd.hev1.as_ref()?;
                 for arr in &x.hvcc.arrays {
                     for nalu in &arr.nalus {
                         buf.put_u32(nalu.data.len() as u32 + 4);
@@ -298,6 +461,104 @@ impl Mp4Track {
     }
 }
 
+/// Checks that the `saiz` sizes describe the `senc` entries.
+fn check_aux_info_sizes(
+    senc: &crate::SencBox,
+    saiz: &crate::SaizBox,
+    samples: &[SampleEncryption],
+) -> Result<(), Error> {
+    let use_subsamples = senc.flags & crate::SencBox::FLAG_USE_SUBSAMPLE_ENCRYPTION != 0;
+
+    for (sample_idx, sample) in samples.iter().enumerate() {
+        let mut size = sample.iv.len();
+        if use_subsamples {
+            size += 2 + 6 * sample.subsamples.len();
+        }
+
+        if saiz.sample_info_size(sample_idx).map(usize::from) != Some(size) {
+            return Err(Error::InvalidData(
+                "saiz sizes don't match the senc entries",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that the sample tables agree on the sample count and chunks.
+fn check_sample_tables(stbl: &crate::StblBox) -> Result<(), Error> {
+    let sample_count = stbl.stsz.sample_count as u64;
+
+    let stts_count: u64 = stbl
+        .stts
+        .entries
+        .iter()
+        .map(|e| e.sample_count as u64)
+        .sum();
+    if stts_count != sample_count {
+        return Err(Error::InvalidData("stts and stsz sample counts differ"));
+    }
+
+    if let Some(ctts) = &stbl.ctts {
+        let ctts_count: u64 = ctts.entries.iter().map(|e| e.sample_count as u64).sum();
+        if ctts_count != sample_count {
+            return Err(Error::InvalidData("ctts and stsz sample counts differ"));
+        }
+    }
+
+    if stbl.stsz.sample_size == 0 && stbl.stsz.sample_sizes.len() as u64 != sample_count {
+        return Err(Error::InvalidData(
+            "stsz sample sizes don't match its count",
+        ));
+    }
+
+    if let Some(stss) = &stbl.stss {
+        if stss.entries.windows(2).any(|w| w[0] >= w[1])
+            || stss
+                .entries
+                .iter()
+                .any(|&s| s == 0 || s as u64 > sample_count)
+        {
+            return Err(Error::InvalidData("stss entries out of order or range"));
+        }
+    }
+
+    let chunk_count = match (&stbl.stco, &stbl.co64) {
+        (Some(stco), None) => stco.entries.len() as u64,
+        (None, Some(co64)) => co64.entries.len() as u64,
+        _ if sample_count == 0 => 0,
+        _ => {
+            return Err(Error::InvalidData(
+                "stbl needs exactly one of stco and co64",
+            ))
+        }
+    };
+
+    let entries = &stbl.stsc.entries;
+    let mut covered = 0u64;
+    for (idx, entry) in entries.iter().enumerate() {
+        let next = entries
+            .get(idx + 1)
+            .map_or(chunk_count + 1, |e| e.first_chunk as u64);
+
+        if (idx == 0 && entry.first_chunk != 1) || next <= entry.first_chunk as u64 {
+            return Err(Error::InvalidData("stsc entries out of order"));
+        }
+
+        if entry.first_chunk as u64 > chunk_count {
+            return Err(Error::InvalidData("stsc refers to a missing chunk"));
+        }
+
+        covered += (next - entry.first_chunk as u64) * entry.samples_per_chunk as u64;
+    }
+
+    if covered != sample_count {
+        return Err(Error::InvalidData("stsc and stsz sample counts differ"));
+    }
+
+    Ok(())
+}
+
 trait RunLenghtItem {
     type Value: Clone;
 
@@ -0,0 +1,498 @@
+use bytes::Bytes;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::io::SeekFrom;
+
+use crate::mp4box::avc1::AvcCBox;
+use crate::mp4box::hev1::HvcCBox;
+use crate::mp4box::mp4a::EsdsBox;
+use crate::mp4box::*;
+use crate::{ByteSource, Error, Limits};
+
+/// Box found by [`BoxTree::read`] or [`BoxTree::parse`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BoxNode {
+    #[serde(rename = "type", serialize_with = "serialize_kind")]
+    pub kind: BoxType,
+
+    /// Offset of the box header from the start of the file.
+    pub offset: u64,
+    pub header_size: u64,
+
+    /// Size of the box including its header.
+    pub size: u64,
+
+    /// Output of [`Mp4Box::summary`] for the box types the crate parses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+
+    /// Output of [`Mp4Box::to_json`] for the box types the crate parses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<serde_json::Value>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<BoxNode>,
+}
+
+impl BoxNode {
+    fn new(kind: BoxType, offset: u64, header_size: u64, size: u64) -> Self {
+        Self {
+            kind,
+            offset,
+            header_size,
+            size,
+            summary: None,
+            fields: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// Offset of the box body from the start of the file.
+    pub fn data_offset(&self) -> u64 {
+        self.offset + self.header_size
+    }
+
+    pub fn end(&self) -> u64 {
+        self.offset + self.size
+    }
+
+    /// First descendant matching `path`, see [`BoxTree::find_all`].
+    pub fn find(&self, path: &str) -> Option<&BoxNode> {
+        self.find_all(path).into_iter().next()
+    }
+
+    /// Descendants matching `path`, see [`BoxTree::find_all`].
+    pub fn find_all(&self, path: &str) -> Vec<&BoxNode> {
+        find_all(&self.children, path)
+    }
+
+    fn read_body(
+        &mut self,
+        parent: Option<BoxType>,
+        body: Bytes,
+        depth: usize,
+        limits: &Limits,
+    ) -> Result<(), Error> {
+        if let Some(skip) = children_offset(self.kind, parent, &body) {
+            if depth >= limits.max_depth {
+                return Err(Error::LimitExceeded("max_depth"));
+            }
+
+            self.children = parse_boxes(
+                Some(self.kind),
+                body.slice(skip..),
+                self.data_offset() + skip as u64,
+                depth + 1,
+                limits,
+            )?;
+        }
+
+        if let Some((summary, fields)) = describe(self.kind, &body) {
+            self.summary = Some(summary).filter(|s| !s.is_empty());
+            self.fields = Some(fields);
+        }
+
+        Ok(())
+    }
+
+    fn fmt_indented(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        write!(
+            f,
+            "{:indent$}{} @{} size={}",
+            "",
+            self.kind,
+            self.offset,
+            self.size,
+            indent = depth * 2
+        )?;
+
+        if let Some(summary) = &self.summary {
+            write!(f, " {summary}")?;
+        }
+        writeln!(f)?;
+
+        for child in &self.children {
+            child.fmt_indented(f, depth + 1)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Layout of the boxes in a file, including boxes the crate doesn't parse.
+///
+/// Containers are walked recursively, the other boxes are listed with their offset and
+/// size and described with [`Mp4Box::summary`] and [`Mp4Box::to_json`] when the crate
+/// can parse them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct BoxTree {
+    pub boxes: Vec<BoxNode>,
+}
+
+impl BoxTree {
+    /// Walks the boxes of a file, only the containers and the boxes to be described are
+    /// read into memory, `mdat` and unknown top level boxes are skipped.
+    pub async fn read<S: ByteSource>(reader: &mut S) -> Result<Self, Error> {
+        Self::read_with_limits(reader, &Limits::default()).await
+    }
+
+    /// Same as [`BoxTree::read`] with the box size and nesting depth bounded by `limits`.
+    pub async fn read_with_limits<S: ByteSource>(
+        reader: &mut S,
+        limits: &Limits,
+    ) -> Result<Self, Error> {
+        let (tree, errors) = Self::read_lenient(reader, limits).await?;
+        match errors.into_iter().next() {
+            Some((_, err)) => Err(err),
+            None => Ok(tree),
+        }
+    }
+
+    /// Same as [`BoxTree::read`], but a top level box whose children fail to parse is
+    /// kept without them and the error is returned along with the box offset.
+    pub(crate) async fn read_lenient<S: ByteSource>(
+        reader: &mut S,
+        limits: &Limits,
+    ) -> Result<(Self, Vec<(u64, Error)>), Error> {
+        let file_size = reader.seek(SeekFrom::End(0)).await?;
+        reader.seek(SeekFrom::Start(0)).await?;
+
+        let mut boxes = Vec::new();
+        let mut errors = Vec::new();
+        let mut offset = 0;
+
+        while offset < file_size {
+            let mut data_offset = offset;
+            let Some(header) = BoxHeader::read_from(reader, &mut data_offset).await? else {
+                break;
+            };
+
+            let mut node = match box_node(header, offset, data_offset, file_size) {
+                Ok(node) => node,
+                Err(err) => {
+                    errors.push((offset, err));
+                    break;
+                }
+            };
+
+            if children_offset(node.kind, None, &[]).is_some() || is_described(node.kind) {
+                // a truncated last box is walked as far as it was written
+                let body_size = node.end().min(file_size) - data_offset;
+                let result = match limits.check_box_size(body_size) {
+                    Ok(()) => {
+                        let mut body = vec![0u8; body_size as usize];
+                        reader.read_exact(&mut body).await?;
+                        node.read_body(None, Bytes::from(body), 0, limits)
+                    }
+                    Err(err) => Err(err),
+                };
+
+                if let Err(err) = result {
+                    errors.push((offset, err));
+                }
+            }
+
+            offset = node.end();
+            reader.seek(SeekFrom::Start(offset)).await?;
+            boxes.push(node);
+        }
+
+        Ok((Self { boxes }, errors))
+    }
+
+    /// Walks the boxes of a file held in memory.
+    pub fn parse(data: Bytes) -> Result<Self, Error> {
+        Self::parse_with_limits(data, &Limits::default())
+    }
+
+    /// Same as [`BoxTree::parse`] with the nesting depth bounded by `limits`.
+    pub fn parse_with_limits(data: Bytes, limits: &Limits) -> Result<Self, Error> {
+        Ok(Self {
+            boxes: parse_boxes(None, data, 0, 0, limits)?,
+        })
+    }
+
+    /// First box matching `path`, see [`BoxTree::find_all`].
+    pub fn find(&self, path: &str) -> Option<&BoxNode> {
+        self.find_all(path).into_iter().next()
+    }
+
+    /// Boxes matching a `/` separated path of box types such as
+    /// `moov/trak[1]/mdia/minf/stbl/stsd`.
+    ///
+    /// An index selects the n-th box of that type among its siblings, counting from 0,
+    /// a segment without an index matches all of them.
+    pub fn find_all(&self, path: &str) -> Vec<&BoxNode> {
+        find_all(&self.boxes, path)
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(&self).unwrap())
+    }
+}
+
+impl fmt::Display for BoxTree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for node in &self.boxes {
+            node.fmt_indented(f, 0)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn serialize_kind<S: Serializer>(kind: &BoxType, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(kind)
+}
+
+fn box_node(header: BoxHeader, offset: u64, data_offset: u64, end: u64) -> Result<BoxNode, Error> {
+    let header_size = data_offset - offset;
+    let size = match header.size {
+        // the box extends to the end of its parent
+        0 => end - offset,
+        size => size + header_size - HEADER_SIZE,
+    };
+
+    if size < header_size {
+        return Err(Error::InvalidData("box size smaller than its header"));
+    }
+
+    Ok(BoxNode::new(header.kind, offset, header_size, size))
+}
+
+fn parse_boxes(
+    parent: Option<BoxType>,
+    mut data: Bytes,
+    offset: u64,
+    depth: usize,
+    limits: &Limits,
+) -> Result<Vec<BoxNode>, Error> {
+    let end = offset + data.len() as u64;
+    let mut boxes = Vec::new();
+    let mut pos = offset;
+
+    while pos < end {
+        let mut data_offset = pos;
+        let Some(header) = BoxHeader::read_sync(&mut data, &mut data_offset)? else {
+            break;
+        };
+
+        let mut node = box_node(header, pos, data_offset, end)?;
+        let body_size = (node.size - node.header_size) as usize;
+        if body_size > data.len() {
+            if parent.is_some() {
+                return Err(Error::InvalidData("box extends past its parent"));
+            }
+
+            // a truncated last box is walked as far as it was written
+            node.read_body(parent, data.split_off(0), depth, limits)?;
+        } else {
+            node.read_body(parent, data.split_to(body_size), depth, limits)?;
+        }
+
+        pos = node.end();
+        boxes.push(node);
+    }
+
+    Ok(boxes)
+}
+
+/// Offset of the first child box in the body of a container.
+fn children_offset(kind: BoxType, parent: Option<BoxType>, body: &[u8]) -> Option<usize> {
+    let offset = match kind {
+        BoxType::MoovBox
+        | BoxType::TrakBox
+        | BoxType::MdiaBox
+        | BoxType::MinfBox
+        | BoxType::StblBox
+        | BoxType::UdtaBox
+        | BoxType::MvexBox
+        | BoxType::MoofBox
+        | BoxType::TrafBox
+        | BoxType::EdtsBox
+        | BoxType::DinfBox
+        | BoxType::MfraBox
+        | BoxType::SinfBox
+        | BoxType::SchiBox
+        | BoxType::IlstBox => 0,
+
+        // QuickTime `meta` boxes are not full boxes
+        BoxType::MetaBox if body.get(..4) == Some(&[0; 4]) => 4,
+        BoxType::MetaBox => 0,
+
+        BoxType::StsdBox | BoxType::DrefBox => 8,
+
+        BoxType::Avc1Box
+        | BoxType::Hev1Box
+        | BoxType::Hvc1Box
+        | BoxType::Vp09Box
+        | BoxType::EncvBox => 78,
+
+        // QuickTime sound sample descriptions version 1 and 2 are longer
+        BoxType::Mp4aBox | BoxType::EncaBox => match body.get(8..10) {
+            Some([0, 1]) => 44,
+            Some([0, 2]) => 64,
+            _ => 28,
+        },
+
+        BoxType::Tx3gBox => 38,
+        BoxType::WvttBox => 8,
+
+        // `ilst` items hold `data` boxes
+        _ if parent == Some(BoxType::IlstBox) => 0,
+        _ => return None,
+    };
+
+    Some(offset.min(body.len()))
+}
+
+macro_rules! described_boxes {
+    ($( $name:ident ),*) => {
+        /// Summary and JSON fields of a box the crate parses.
+        fn describe(kind: BoxType, body: &Bytes) -> Option<(String, serde_json::Value)> {
+            match kind {
+                $( BoxType::$name => describe_box::<$name>(body), )*
+                BoxType::Hvc1Box => describe_box::<Hev1Box>(body),
+                _ => None,
+            }
+        }
+
+        fn is_described(kind: BoxType) -> bool {
+            matches!(kind, $( BoxType::$name )|* | BoxType::Hvc1Box)
+        }
+    };
+}
+
+described_boxes!(
+    FtypBox, MvhdBox, MfhdBox, MehdBox, TrexBox, EmsgBox, MfroBox, TfraBox, TkhdBox, TrefBox,
+    ElstBox, MdhdBox, HdlrBox, VmhdBox, SmhdBox, SttsBox, CttsBox, StssBox, StscBox, StszBox,
+    StcoBox, Co64Box, Avc1Box, AvcCBox, Hev1Box, HvcCBox, Vp09Box, VpccBox, Mp4aBox, EsdsBox,
+    Tx3gBox, WvttBox, StppBox, C608Box, FrmaBox, SchmBox, TencBox, PsshBox, TfhdBox, TfdtBox,
+    TrunBox, SaizBox, SaioBox, ChplBox, KeysBox, DataBox, NmhdBox, SthdBox
+);
+
+fn describe_box<T: Mp4Box + BlockReader>(body: &Bytes) -> Option<(String, serde_json::Value)> {
+    if body.len() < T::size_hint() {
+        return None;
+    }
+
+    let bx = T::read_block(&mut body.clone()).ok()?;
+    let fields = serde_json::from_str(&bx.to_json().ok()?).ok()?;
+
+    Some((bx.summary().ok()?, fields))
+}
+
+fn find_all<'a>(nodes: &'a [BoxNode], path: &str) -> Vec<&'a BoxNode> {
+    let Some((segment, rest)) = path
+        .trim_start_matches('/')
+        .split_once('/')
+        .or(Some((path.trim_start_matches('/'), "")))
+        .filter(|(segment, _)| !segment.is_empty())
+    else {
+        return Vec::new();
+    };
+
+    let (name, index) = match segment.strip_suffix(']').and_then(|s| s.split_once('[')) {
+        Some((name, index)) => match index.parse::<usize>() {
+            Ok(index) => (name, Some(index)),
+            Err(_) => return Vec::new(),
+        },
+        None => (segment, None),
+    };
+
+    let matching = nodes.iter().filter(|node| node.kind.to_string() == name);
+    let selected: Vec<_> = match index {
+        Some(index) => matching.skip(index).take(1).collect(),
+        None => matching.collect(),
+    };
+
+    if rest.is_empty() {
+        return selected;
+    }
+
+    selected
+        .into_iter()
+        .flat_map(|node| find_all(&node.children, rest))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "tokio")]
+    use crate::test_util::fragmented_file;
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_box_tree() {
+        let data = fragmented_file();
+        let tree = BoxTree::read(&mut std::io::Cursor::new(data.clone()))
+            .await
+            .unwrap();
+        assert_eq!(BoxTree::parse(Bytes::from(data.clone())).unwrap(), tree);
+
+        let last = tree.boxes.last().unwrap();
+        assert_eq!(last.end(), data.len() as u64);
+        assert_eq!(tree.boxes[0].kind, BoxType::FtypBox);
+        assert!(tree.boxes[0].summary.is_some());
+        assert_eq!(tree.find_all("moof").len(), 3);
+        assert_eq!(tree.find_all("moov/trak").len(), 2);
+
+        let stsd = tree.find("moov/trak[0]/mdia/minf/stbl/stsd").unwrap();
+        let avc1 = &stsd.children[0];
+        assert_eq!(avc1.kind, BoxType::Avc1Box);
+        assert_eq!(avc1.offset, stsd.data_offset() + 8);
+        assert_eq!(avc1.fields.as_ref().unwrap()["width"], 320);
+        assert_eq!(avc1.children[0].offset, avc1.data_offset() + 78);
+        assert_eq!(
+            stsd.find("avc1/avcC").unwrap().summary.as_deref(),
+            Some("avc_profile_indication=100")
+        );
+
+        let esds = tree.find("moov/trak[1]/mdia/minf/stbl/stsd/mp4a/esds");
+        assert!(esds.is_some());
+        assert!(tree.find("moov/trak[2]").is_none());
+        assert!(tree.find("moov/trak[x]").is_none());
+
+        let json: serde_json::Value = serde_json::from_str(&tree.to_json().unwrap()).unwrap();
+        assert_eq!(json[1]["type"], "moov");
+        assert_eq!(json[1]["children"][0]["type"], "mvhd");
+    }
+
+    #[test]
+    fn test_box_tree_depth() {
+        // moov boxes nested 40 deep
+        let mut data = Vec::new();
+        for depth in 0..40u32 {
+            data.extend_from_slice(&((40 - depth) * 8).to_be_bytes());
+            data.extend_from_slice(b"moov");
+        }
+
+        let tree = BoxTree::parse(Bytes::from(data.clone()));
+        assert!(matches!(tree, Err(Error::LimitExceeded("max_depth"))));
+
+        let limits = Limits {
+            max_depth: 64,
+            ..Default::default()
+        };
+        let tree = BoxTree::parse_with_limits(Bytes::from(data), &limits).unwrap();
+        assert_eq!(tree.find_all("moov/moov/moov").len(), 1);
+    }
+
+    #[test]
+    fn test_box_tree_largesize() {
+        let mut data = vec![0, 0, 0, 1, b'f', b'r', b'e', b'e', 0, 0, 0, 0, 0, 0, 0, 20];
+        data.extend_from_slice(&[0; 4]);
+        // extends to the end of the file
+        data.extend_from_slice(&[0, 0, 0, 0, b'm', b'd', b'a', b't', 1, 2]);
+
+        let tree = BoxTree::parse(Bytes::from(data)).unwrap();
+        assert_eq!(tree.boxes[0].header_size, 16);
+        assert_eq!(tree.boxes[0].size, 20);
+        assert_eq!(tree.boxes[1].kind, BoxType::MdatBox);
+        assert_eq!(tree.boxes[1].offset, 20);
+        assert_eq!(tree.boxes[1].size, 10);
+        assert_eq!(tree.to_string(), "free @0 size=20\nmdat @20 size=10\n");
+    }
+}
@@ -86,7 +86,7 @@ impl fmt::Display for BoxType {
     }
 }
 
-#[derive(Default, PartialEq, Eq, Clone, Copy, Serialize)]
+#[derive(Default, PartialEq, Eq, Hash, Clone, Copy, Serialize)]
 pub struct FourCC {
     pub value: [u8; 4],
 }
@@ -155,6 +155,8 @@ impl fmt::Display for FourCC {
 const DISPLAY_TYPE_VIDEO: &str = "Video";
 const DISPLAY_TYPE_AUDIO: &str = "Audio";
 const DISPLAY_TYPE_SUBTITLE: &str = "Subtitle";
+const DISPLAY_TYPE_TEXT: &str = "Text";
+const DISPLAY_TYPE_ISO_SUBTITLE: &str = "ISO Subtitle";
 
 const HANDLER_TYPE_VIDEO: &str = "vide";
 const HANDLER_TYPE_VIDEO_FOURCC: [u8; 4] = [b'v', b'i', b'd', b'e'];
@@ -165,11 +167,22 @@ const HANDLER_TYPE_AUDIO_FOURCC: [u8; 4] = [b's', b'o', b'u', b'n'];
 const HANDLER_TYPE_SUBTITLE: &str = "sbtl";
 const HANDLER_TYPE_SUBTITLE_FOURCC: [u8; 4] = [b's', b'b', b't', b'l'];
 
+const HANDLER_TYPE_TEXT: &str = "text";
+const HANDLER_TYPE_TEXT_FOURCC: [u8; 4] = [b't', b'e', b'x', b't'];
+
+const HANDLER_TYPE_ISO_SUBTITLE: &str = "subt";
+const HANDLER_TYPE_ISO_SUBTITLE_FOURCC: [u8; 4] = [b's', b'u', b'b', b't'];
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TrackType {
     Video,
     Audio,
+    /// QuickTime `sbtl` subtitles, such as `tx3g`.
     Subtitle,
+    /// Timed text with a `nmhd` media header, such as WebVTT (`wvtt`) or chapter titles.
+    Text,
+    /// ISO `subt` subtitles with a `sthd` media header, such as TTML (`stpp`).
+    IsoSubtitle,
     Other(FourCC),
 }
 
@@ -179,6 +192,8 @@ impl fmt::Display for TrackType {
             TrackType::Video => write!(f, "{DISPLAY_TYPE_VIDEO}"),
             TrackType::Audio => write!(f, "{DISPLAY_TYPE_AUDIO}"),
             TrackType::Subtitle => write!(f, "{DISPLAY_TYPE_SUBTITLE}"),
+            TrackType::Text => write!(f, "{DISPLAY_TYPE_TEXT}"),
+            TrackType::IsoSubtitle => write!(f, "{DISPLAY_TYPE_ISO_SUBTITLE}"),
             TrackType::Other(other) => write!(f, "Other({})", other),
         }
     }
@@ -191,6 +206,8 @@ impl TryFrom<&str> for TrackType {
             HANDLER_TYPE_VIDEO => Ok(TrackType::Video),
             HANDLER_TYPE_AUDIO => Ok(TrackType::Audio),
             HANDLER_TYPE_SUBTITLE => Ok(TrackType::Subtitle),
+            HANDLER_TYPE_TEXT => Ok(TrackType::Text),
+            HANDLER_TYPE_ISO_SUBTITLE => Ok(TrackType::IsoSubtitle),
             _ => Err(Error::InvalidData("unsupported handler type")),
         }
     }
@@ -202,6 +219,8 @@ impl From<&FourCC> for TrackType {
             HANDLER_TYPE_VIDEO_FOURCC => TrackType::Video,
             HANDLER_TYPE_AUDIO_FOURCC => TrackType::Audio,
             HANDLER_TYPE_SUBTITLE_FOURCC => TrackType::Subtitle,
+            HANDLER_TYPE_TEXT_FOURCC => TrackType::Text,
+            HANDLER_TYPE_ISO_SUBTITLE_FOURCC => TrackType::IsoSubtitle,
             other => TrackType::Other(other.into()),
         }
     }
@@ -213,6 +232,8 @@ impl From<TrackType> for FourCC {
             TrackType::Video => HANDLER_TYPE_VIDEO_FOURCC.into(),
             TrackType::Audio => HANDLER_TYPE_AUDIO_FOURCC.into(),
             TrackType::Subtitle => HANDLER_TYPE_SUBTITLE_FOURCC.into(),
+            TrackType::Text => HANDLER_TYPE_TEXT_FOURCC.into(),
+            TrackType::IsoSubtitle => HANDLER_TYPE_ISO_SUBTITLE_FOURCC.into(),
             TrackType::Other(inner) => inner,
         }
     }
@@ -607,6 +628,42 @@ impl Default for AacConfig {
 #[derive(Debug, PartialEq, Eq, Clone, Default)]
 pub struct TtxtConfig {}
 
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct C608Config {}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct WebVttConfig {
+    /// WebVTT file header stored in `vttC`.
+    pub config: String,
+    pub label: Option<String>,
+}
+
+impl Default for WebVttConfig {
+    fn default() -> Self {
+        Self {
+            config: "WEBVTT".to_string(),
+            label: None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TtmlConfig {
+    pub namespace: String,
+    pub schema_location: String,
+    pub auxiliary_mime_types: String,
+}
+
+impl Default for TtmlConfig {
+    fn default() -> Self {
+        Self {
+            namespace: "http://www.w3.org/ns/ttml".to_string(),
+            schema_location: String::new(),
+            auxiliary_mime_types: String::new(),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum MediaConfig {
     AvcConfig(AvcConfig),
@@ -614,9 +671,35 @@ pub enum MediaConfig {
     Vp9Config(Vp9Config),
     AacConfig(AacConfig),
     TtxtConfig(TtxtConfig),
+    WebVttConfig(WebVttConfig),
+    TtmlConfig(TtmlConfig),
+    C608Config(C608Config),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mp4Config {
+    pub major_brand: FourCC,
+    pub minor_version: u32,
+    pub compatible_brands: Vec<FourCC>,
+    pub timescale: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackConfig {
+    pub track_type: TrackType,
+    pub timescale: u32,
+    pub language: String,
+    pub media_conf: MediaConfig,
+}
+
+/// A chapter marker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chapter {
+    pub start: std::time::Duration,
+    pub title: String,
+}
+
+#[derive(Debug, Clone)]
 pub struct Mp4Sample {
     pub start_time: u64,
     pub duration: u32,
@@ -662,8 +745,14 @@ pub fn creation_time(creation_time: u64) -> u64 {
 pub enum DataType {
     Binary = 0x000000,
     Text = 0x000001,
+    Utf16 = 0x000002,
     Image = 0x00000D,
+    Png = 0x00000E,
     TempoCpil = 0x000015,
+    BeUnsigned = 0x000016,
+    Float32 = 0x000017,
+    Float64 = 0x000018,
+    Bmp = 0x00001B,
 }
 
 #[allow(clippy::derivable_impls)]
@@ -679,8 +768,14 @@ impl TryFrom<u32> for DataType {
         match value {
             0x000000 => Ok(DataType::Binary),
             0x000001 => Ok(DataType::Text),
+            0x000002 => Ok(DataType::Utf16),
             0x00000D => Ok(DataType::Image),
+            0x00000E => Ok(DataType::Png),
             0x000015 => Ok(DataType::TempoCpil),
+            0x000016 => Ok(DataType::BeUnsigned),
+            0x000017 => Ok(DataType::Float32),
+            0x000018 => Ok(DataType::Float64),
+            0x00001B => Ok(DataType::Bmp),
             _ => Err(Error::InvalidData("invalid data type")),
         }
     }
@@ -692,6 +787,100 @@ pub enum MetadataKey {
     Year,
     Poster,
     Summary,
+    Artist,
+    AlbumArtist,
+    Album,
+    Genre,
+    /// ID3v1 genre index + 1 (`gnre`)
+    GenreId,
+    Comment,
+    Composer,
+    Grouping,
+    Lyrics,
+    Copyright,
+    /// Encoding tool (`©too`)
+    Encoder,
+    /// Person or company that encoded the file (`©enc`)
+    EncodedBy,
+    /// ISO 6709 location string (`©xyz`)
+    Location,
+    /// Content rating (`rtng`), 0 = none, 1 = explicit, 2 = clean
+    Rating,
+    TrackNumber,
+    DiskNumber,
+    Tempo,
+    Compilation,
+    LongDescription,
+    /// Freeform `----` item identified by its `mean` and `name` atoms
+    Freeform {
+        mean: String,
+        name: String,
+    },
+    /// Any other item
+    Other(FourCC),
+}
+
+impl MetadataKey {
+    pub fn from_fourcc(fourcc: FourCC) -> Self {
+        match &fourcc.value {
+            b"\xa9nam" => Self::Title,
+            b"\xa9day" => Self::Year,
+            b"covr" => Self::Poster,
+            b"desc" => Self::Summary,
+            b"\xa9ART" => Self::Artist,
+            b"aART" => Self::AlbumArtist,
+            b"\xa9alb" => Self::Album,
+            b"\xa9gen" => Self::Genre,
+            b"gnre" => Self::GenreId,
+            b"\xa9cmt" => Self::Comment,
+            b"\xa9wrt" => Self::Composer,
+            b"\xa9grp" => Self::Grouping,
+            b"\xa9lyr" => Self::Lyrics,
+            b"cprt" => Self::Copyright,
+            b"\xa9too" => Self::Encoder,
+            b"\xa9enc" => Self::EncodedBy,
+            b"\xa9xyz" => Self::Location,
+            b"rtng" => Self::Rating,
+            b"trkn" => Self::TrackNumber,
+            b"disk" => Self::DiskNumber,
+            b"tmpo" => Self::Tempo,
+            b"cpil" => Self::Compilation,
+            b"ldes" => Self::LongDescription,
+            _ => Self::Other(fourcc),
+        }
+    }
+
+    pub fn fourcc(&self) -> FourCC {
+        let value = match self {
+            Self::Title => b"\xa9nam",
+            Self::Year => b"\xa9day",
+            Self::Poster => b"covr",
+            Self::Summary => b"desc",
+            Self::Artist => b"\xa9ART",
+            Self::AlbumArtist => b"aART",
+            Self::Album => b"\xa9alb",
+            Self::Genre => b"\xa9gen",
+            Self::GenreId => b"gnre",
+            Self::Comment => b"\xa9cmt",
+            Self::Composer => b"\xa9wrt",
+            Self::Grouping => b"\xa9grp",
+            Self::Lyrics => b"\xa9lyr",
+            Self::Copyright => b"cprt",
+            Self::Encoder => b"\xa9too",
+            Self::EncodedBy => b"\xa9enc",
+            Self::Location => b"\xa9xyz",
+            Self::Rating => b"rtng",
+            Self::TrackNumber => b"trkn",
+            Self::DiskNumber => b"disk",
+            Self::Tempo => b"tmpo",
+            Self::Compilation => b"cpil",
+            Self::LongDescription => b"ldes",
+            Self::Freeform { .. } => b"----",
+            Self::Other(fourcc) => return *fourcc,
+        };
+
+        FourCC { value: *value }
+    }
 }
 
 pub trait Metadata<'a> {
@@ -703,6 +892,71 @@ pub trait Metadata<'a> {
     fn poster(&self) -> Option<&[u8]>;
     /// The video's summary
     fn summary(&self) -> Option<Cow<'_, str>>;
+
+    /// The performing artist
+    fn artist(&self) -> Option<Cow<'_, str>> {
+        None
+    }
+
+    /// The album artist
+    fn album_artist(&self) -> Option<Cow<'_, str>> {
+        None
+    }
+
+    /// The album name
+    fn album(&self) -> Option<Cow<'_, str>> {
+        None
+    }
+
+    /// The genre name
+    fn genre(&self) -> Option<Cow<'_, str>> {
+        None
+    }
+
+    /// Free text comment
+    fn comment(&self) -> Option<Cow<'_, str>> {
+        None
+    }
+
+    /// The composer
+    fn composer(&self) -> Option<Cow<'_, str>> {
+        None
+    }
+
+    /// Copyright notice
+    fn copyright(&self) -> Option<Cow<'_, str>> {
+        None
+    }
+
+    /// The encoding tool
+    fn encoder(&self) -> Option<Cow<'_, str>> {
+        None
+    }
+
+    /// Track number and total track count
+    fn track_number(&self) -> Option<(u16, u16)> {
+        None
+    }
+
+    /// Disk number and total disk count
+    fn disk_number(&self) -> Option<(u16, u16)> {
+        None
+    }
+
+    /// ISO 6709 location, e.g. `+48.8577+002.2950/`
+    fn location(&self) -> Option<Cow<'_, str>> {
+        None
+    }
+
+    /// Content rating, 0 = none, 1 = explicit, 2 = clean
+    fn rating(&self) -> Option<u8> {
+        None
+    }
+
+    /// Value of a freeform `----` item
+    fn freeform(&self, _mean: &str, _name: &str) -> Option<Cow<'_, str>> {
+        None
+    }
 }
 
 impl<'a, T: Metadata<'a>> Metadata<'a> for &'a T {
@@ -721,6 +975,58 @@ impl<'a, T: Metadata<'a>> Metadata<'a> for &'a T {
     fn summary(&self) -> Option<Cow<'_, str>> {
         (**self).summary()
     }
+
+    fn artist(&self) -> Option<Cow<'_, str>> {
+        (**self).artist()
+    }
+
+    fn album_artist(&self) -> Option<Cow<'_, str>> {
+        (**self).album_artist()
+    }
+
+    fn album(&self) -> Option<Cow<'_, str>> {
+        (**self).album()
+    }
+
+    fn genre(&self) -> Option<Cow<'_, str>> {
+        (**self).genre()
+    }
+
+    fn comment(&self) -> Option<Cow<'_, str>> {
+        (**self).comment()
+    }
+
+    fn composer(&self) -> Option<Cow<'_, str>> {
+        (**self).composer()
+    }
+
+    fn copyright(&self) -> Option<Cow<'_, str>> {
+        (**self).copyright()
+    }
+
+    fn encoder(&self) -> Option<Cow<'_, str>> {
+        (**self).encoder()
+    }
+
+    fn track_number(&self) -> Option<(u16, u16)> {
+        (**self).track_number()
+    }
+
+    fn disk_number(&self) -> Option<(u16, u16)> {
+        (**self).disk_number()
+    }
+
+    fn location(&self) -> Option<Cow<'_, str>> {
+        (**self).location()
+    }
+
+    fn rating(&self) -> Option<u8> {
+        (**self).rating()
+    }
+
+    fn freeform(&self, mean: &str, name: &str) -> Option<Cow<'_, str>> {
+        (**self).freeform(mean, name)
+    }
 }
 
 impl<'a, T: Metadata<'a>> Metadata<'a> for Option<T> {
@@ -739,4 +1045,56 @@ impl<'a, T: Metadata<'a>> Metadata<'a> for Option<T> {
     fn summary(&self) -> Option<Cow<'_, str>> {
         self.as_ref().and_then(|t| t.summary())
     }
+
+    fn artist(&self) -> Option<Cow<'_, str>> {
+        self.as_ref().and_then(|t| t.artist())
+    }
+
+    fn album_artist(&self) -> Option<Cow<'_, str>> {
+        self.as_ref().and_then(|t| t.album_artist())
+    }
+
+    fn album(&self) -> Option<Cow<'_, str>> {
+        self.as_ref().and_then(|t| t.album())
+    }
+
+    fn genre(&self) -> Option<Cow<'_, str>> {
+        self.as_ref().and_then(|t| t.genre())
+    }
+
+    fn comment(&self) -> Option<Cow<'_, str>> {
+        self.as_ref().and_then(|t| t.comment())
+    }
+
+    fn composer(&self) -> Option<Cow<'_, str>> {
+        self.as_ref().and_then(|t| t.composer())
+    }
+
+    fn copyright(&self) -> Option<Cow<'_, str>> {
+        self.as_ref().and_then(|t| t.copyright())
+    }
+
+    fn encoder(&self) -> Option<Cow<'_, str>> {
+        self.as_ref().and_then(|t| t.encoder())
+    }
+
+    fn track_number(&self) -> Option<(u16, u16)> {
+        self.as_ref().and_then(|t| t.track_number())
+    }
+
+    fn disk_number(&self) -> Option<(u16, u16)> {
+        self.as_ref().and_then(|t| t.disk_number())
+    }
+
+    fn location(&self) -> Option<Cow<'_, str>> {
+        self.as_ref().and_then(|t| t.location())
+    }
+
+    fn rating(&self) -> Option<u8> {
+        self.as_ref().and_then(|t| t.rating())
+    }
+
+    fn freeform(&self, mean: &str, name: &str) -> Option<Cow<'_, str>> {
+        self.as_ref().and_then(|t| t.freeform(mean, name))
+    }
 }
@@ -0,0 +1,676 @@
+use bytes::Bytes;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::SeekFrom;
+use std::ops::Range;
+
+use crate::file::parse_body;
+use crate::mp4box::*;
+use crate::tree::{BoxNode, BoxTree};
+use crate::{ByteSource, Error, Limits, TrackType};
+
+/// Brands whose files are made of movie fragments.
+const FRAGMENTED_BRANDS: [&[u8; 4]; 3] = [b"dash", b"msdh", b"cmfc"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Allowed by the format, but likely to trip players or to be a muxer bug.
+    Warning,
+    Error,
+}
+
+/// Issue found by [`validate`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+
+    /// Path of the box in the syntax of [`BoxTree::find`], such as
+    /// `moov/trak[0]/mdia/minf/stbl/stsz`.
+    pub path: String,
+
+    /// Offset of the box from the start of the file.
+    pub offset: u64,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+
+        write!(
+            f,
+            "{severity}: {} @{}: {}",
+            self.path, self.offset, self.message
+        )
+    }
+}
+
+/// Checks a file against the ISO BMFF rules and reports every issue found.
+///
+/// Covers the box structure, the consistency of the sample tables and of the sample
+/// data offsets with the `mdat` boxes, decode times and durations, and a few brand
+/// requirements. Only I/O failures are returned as an [`Error`], boxes that fail to parse
+/// are reported and skipped.
+pub async fn validate<S: ByteSource>(reader: &mut S) -> Result<Vec<Diagnostic>, Error> {
+    let (tree, errors) = BoxTree::read_lenient(reader, &Limits::default()).await?;
+    let file_size = reader.seek(SeekFrom::End(0)).await?;
+
+    let mut validator = Validator::new(tree, file_size);
+    for (offset, err) in errors {
+        validator.diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            path: validator.top_level_path(offset),
+            offset,
+            message: format!("invalid box: {err}"),
+        });
+    }
+
+    validator.check_structure();
+
+    let moov = match validator.tree.find("moov") {
+        Some(node) => {
+            let body = read_body(reader, node, file_size).await?;
+            match parse_body(BoxType::MoovBox, body, node.end().min(file_size)) {
+                Ok(moov) => Some(moov),
+                Err(err) => {
+                    validator.report_invalid("moov".to_string(), err);
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+
+    let ftyp = match validator.tree.find("ftyp") {
+        Some(node) => FtypBox::read_block(&mut read_body(reader, node, file_size).await?).ok(),
+        None => None,
+    };
+
+    if let Some(moov) = &moov {
+        if let Some(ftyp) = &ftyp {
+            validator.check_brands(ftyp, moov);
+        }
+
+        for (idx, trak) in moov.traks.iter().enumerate() {
+            validator.check_trak(&moov.mvhd, trak, &format!("moov/trak[{idx}]"));
+        }
+    }
+
+    let moofs: Vec<_> = validator
+        .tree
+        .find_all("moof")
+        .into_iter()
+        .cloned()
+        .collect();
+    let mut fragments = FragmentState::default();
+    for (idx, node) in moofs.iter().enumerate() {
+        let path = format!("moof[{idx}]");
+        let body = read_body(reader, node, file_size).await?;
+        match parse_body(BoxType::MoofBox, body, node.end().min(file_size)) {
+            Ok(moof) => validator.check_moof(moov.as_ref(), &moof, node, &path, &mut fragments),
+            Err(err) => validator.report_invalid(path, err.with_index(idx)),
+        }
+    }
+
+    Ok(validator.diagnostics)
+}
+
+async fn read_body<S: ByteSource>(
+    reader: &mut S,
+    node: &BoxNode,
+    file_size: u64,
+) -> Result<Bytes, Error> {
+    let mut body = vec![0u8; (node.end().min(file_size) - node.data_offset()) as usize];
+    reader.seek(SeekFrom::Start(node.data_offset())).await?;
+    reader.read_exact(&mut body).await?;
+    Ok(Bytes::from(body))
+}
+
+#[derive(Default)]
+struct FragmentState {
+    sequence_number: Option<u32>,
+
+    /// Decode time at the end of the last fragment of each track.
+    decode_end: HashMap<u32, u64>,
+}
+
+struct Validator {
+    tree: BoxTree,
+    file_size: u64,
+    mdats: Vec<Range<u64>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Validator {
+    fn new(tree: BoxTree, file_size: u64) -> Self {
+        let mdats = tree
+            .boxes
+            .iter()
+            .filter(|node| node.kind == BoxType::MdatBox)
+            .map(|node| node.data_offset()..node.end().min(file_size))
+            .collect();
+
+        Self {
+            tree,
+            file_size,
+            mdats,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn report(&mut self, severity: Severity, path: impl Into<String>, message: String) {
+        let path = path.into();
+        let offset = self.tree.find(&path).map_or(0, |node| node.offset);
+
+        self.diagnostics.push(Diagnostic {
+            severity,
+            path,
+            offset,
+            message,
+        });
+    }
+
+    /// Reports a box that failed to parse, at the location of the error if it has one.
+    fn report_invalid(&mut self, path: String, err: Error) {
+        match err {
+            Error::Context(ctx, err) if ctx.offset.is_some() => {
+                self.diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    path: ctx.box_path(),
+                    offset: ctx.offset.unwrap_or_default(),
+                    message: format!("invalid box: {err}"),
+                });
+            }
+            err => self.report(Severity::Error, path, format!("invalid box: {err}")),
+        }
+    }
+
+    /// Path of the top level box at `offset`, or of the position after the last box.
+    fn top_level_path(&self, offset: u64) -> String {
+        let Some(idx) = self.tree.boxes.iter().position(|n| n.offset == offset) else {
+            return String::new();
+        };
+
+        let kind = self.tree.boxes[idx].kind;
+        let nth = self.tree.boxes[..idx]
+            .iter()
+            .filter(|n| n.kind == kind)
+            .count();
+
+        format!("{kind}[{nth}]")
+    }
+
+    fn in_mdat(&self, range: &Range<u64>) -> bool {
+        range.is_empty()
+            || self
+                .mdats
+                .iter()
+                .any(|mdat| mdat.start <= range.start && range.end <= mdat.end)
+    }
+
+    fn check_structure(&mut self) {
+        let file_size = self.file_size;
+        for idx in 0..self.tree.boxes.len() {
+            let node = &self.tree.boxes[idx];
+            if node.end() > file_size {
+                let (offset, end) = (node.offset, node.end());
+                self.report(
+                    Severity::Error,
+                    self.top_level_path(offset),
+                    format!("box ends at {end}, past the end of the file at {file_size}"),
+                );
+            }
+        }
+
+        match self
+            .tree
+            .boxes
+            .iter()
+            .position(|n| n.kind == BoxType::FtypBox)
+        {
+            None => self.report(Severity::Warning, "", "missing ftyp box".to_string()),
+            Some(0) => {}
+            Some(_) => self.report(
+                Severity::Warning,
+                "ftyp",
+                "ftyp is not the first box of the file".to_string(),
+            ),
+        }
+
+        match self.tree.find_all("moov").len() {
+            0 => self.report(Severity::Error, "", "missing moov box".to_string()),
+            1 => {}
+            count => self.report(Severity::Error, "moov[1]", format!("{count} moov boxes")),
+        }
+
+        if self.tree.find("moof").is_some() && self.tree.find("moov/mvex").is_none() {
+            self.report(
+                Severity::Error,
+                "moov",
+                "file has movie fragments but moov has no mvex".to_string(),
+            );
+        }
+    }
+
+    fn check_brands(&mut self, ftyp: &FtypBox, moov: &MoovBox) {
+        let has_brand = |brand: &[u8; 4]| {
+            ftyp.major_brand.value == *brand
+                || ftyp.compatible_brands.iter().any(|b| b.value == *brand)
+        };
+
+        let has_avc = moov
+            .traks
+            .iter()
+            .any(|t| t.mdia.minf.stbl.stsd.avc1.is_some());
+        if has_brand(b"avc1") && !has_avc {
+            self.report(
+                Severity::Warning,
+                "ftyp",
+                "avc1 brand without an AVC track".to_string(),
+            );
+        }
+
+        let fragmented = self.tree.find("moof").is_some();
+        for brand in FRAGMENTED_BRANDS {
+            if has_brand(brand) && !fragmented {
+                self.report(
+                    Severity::Warning,
+                    "ftyp",
+                    format!(
+                        "{} brand without movie fragments",
+                        String::from_utf8_lossy(brand)
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_trak(&mut self, mvhd: &MvhdBox, trak: &TrakBox, path: &str) {
+        let stbl = &trak.mdia.minf.stbl;
+        let stbl_path = format!("{path}/mdia/minf/stbl");
+        let sample_count = stbl.stsz.sample_count as u64;
+
+        let stts_count: u64 = stbl
+            .stts
+            .entries
+            .iter()
+            .map(|e| e.sample_count as u64)
+            .sum();
+        if stts_count != sample_count {
+            self.report(
+                Severity::Error,
+                format!("{stbl_path}/stts"),
+                format!("stts covers {stts_count} samples, stsz has {sample_count}"),
+            );
+        }
+
+        let zero_durations: u64 = stbl
+            .stts
+            .entries
+            .iter()
+            .filter(|e| e.sample_delta == 0)
+            .map(|e| e.sample_count as u64)
+            .sum();
+        // the last sample may have no duration
+        if zero_durations > 1 {
+            self.report(
+                Severity::Warning,
+                format!("{stbl_path}/stts"),
+                format!("{zero_durations} samples have the same decode time as the next one"),
+            );
+        }
+
+        self.check_chunks(stbl, &stbl_path);
+        self.check_sync_samples(trak, &stbl_path);
+
+        if sample_count > 0 {
+            self.check_durations(mvhd, trak, path, stts_count);
+        }
+    }
+
+    fn check_chunks(&mut self, stbl: &StblBox, stbl_path: &str) {
+        let (offsets, offsets_path): (Vec<u64>, _) = match (&stbl.stco, &stbl.co64) {
+            (Some(_), Some(_)) => {
+                self.report(
+                    Severity::Error,
+                    format!("{stbl_path}/co64"),
+                    "both stco and co64 are present".to_string(),
+                );
+                return;
+            }
+            (Some(stco), None) => (
+                stco.entries.iter().map(|&o| o as u64).collect(),
+                format!("{stbl_path}/stco"),
+            ),
+            (None, Some(co64)) => (co64.entries.clone(), format!("{stbl_path}/co64")),
+            (None, None) => {
+                self.report(
+                    Severity::Error,
+                    stbl_path,
+                    "missing stco or co64 box".to_string(),
+                );
+                return;
+            }
+        };
+
+        let stsc_path = format!("{stbl_path}/stsc");
+        let entries = &stbl.stsc.entries;
+        let chunk_count = offsets.len() as u64;
+
+        if entries.first().is_some_and(|e| e.first_chunk != 1) {
+            self.report(
+                Severity::Error,
+                &stsc_path,
+                "first stsc entry doesn't start at chunk 1".to_string(),
+            );
+            return;
+        }
+
+        for (idx, entry) in entries.iter().enumerate() {
+            let message = if idx > 0 && entry.first_chunk <= entries[idx - 1].first_chunk {
+                format!("stsc entry {idx} doesn't start after the previous one")
+            } else if entry.first_chunk as u64 > chunk_count {
+                format!(
+                    "stsc entry {idx} starts at chunk {}, there are {chunk_count}",
+                    entry.first_chunk
+                )
+            } else if entry.samples_per_chunk == 0 {
+                format!("stsc entry {idx} has chunks without samples")
+            } else {
+                continue;
+            };
+
+            self.report(Severity::Error, &stsc_path, message);
+            return;
+        }
+
+        // samples of each chunk
+        let mut chunk_samples = Vec::with_capacity(offsets.len());
+        for (idx, entry) in entries.iter().enumerate() {
+            let next = entries
+                .get(idx + 1)
+                .map_or(chunk_count + 1, |e| e.first_chunk as u64);
+            for _ in entry.first_chunk as u64..next {
+                chunk_samples.push(entry.samples_per_chunk as u64);
+            }
+        }
+
+        let sample_count = stbl.stsz.sample_count as u64;
+        let covered: u64 = chunk_samples.iter().sum();
+        if covered != sample_count {
+            self.report(
+                Severity::Error,
+                stsc_path,
+                format!("stsc covers {covered} samples, stsz has {sample_count}"),
+            );
+            return;
+        }
+
+        let sample_size = |idx: usize| match stbl.stsz.sample_size {
+            0 => stbl.stsz.sample_sizes.get(idx).copied().unwrap_or(0) as u64,
+            size => size as u64,
+        };
+
+        let mut past_eof = Vec::new();
+        let mut outside_mdat = Vec::new();
+        let mut sample = 0;
+        for (chunk, (&offset, &samples)) in offsets.iter().zip(&chunk_samples).enumerate() {
+            let size: u64 = (sample..sample + samples as usize).map(sample_size).sum();
+            sample += samples as usize;
+
+            let range = offset..offset.saturating_add(size);
+            if range.end > self.file_size {
+                past_eof.push((chunk + 1, offset));
+            } else if !self.in_mdat(&range) {
+                outside_mdat.push((chunk + 1, offset));
+            }
+        }
+
+        for (chunks, problem) in [
+            (past_eof, "extend past the end of the file"),
+            (outside_mdat, "lie outside any mdat"),
+        ] {
+            if let Some(&(chunk, offset)) = chunks.first() {
+                self.report(
+                    Severity::Error,
+                    &offsets_path,
+                    format!(
+                        "{} chunks {problem}, the first is chunk {chunk} at offset {offset}",
+                        chunks.len()
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_sync_samples(&mut self, trak: &TrakBox, stbl_path: &str) {
+        let stbl = &trak.mdia.minf.stbl;
+        let sample_count = stbl.stsz.sample_count;
+
+        let Some(stss) = &stbl.stss else {
+            let is_video = TrackType::from(&trak.mdia.hdlr.handler_type) == TrackType::Video;
+            if is_video && sample_count > 1 {
+                self.report(
+                    Severity::Warning,
+                    stbl_path,
+                    "video track without stss, every sample is a sync sample".to_string(),
+                );
+            }
+            return;
+        };
+
+        let mut previous = 0;
+        for &sample in &stss.entries {
+            if sample <= previous || sample > sample_count {
+                self.report(
+                    Severity::Error,
+                    format!("{stbl_path}/stss"),
+                    format!(
+                        "sync sample {sample} is out of order or beyond the {sample_count} samples"
+                    ),
+                );
+                return;
+            }
+            previous = sample;
+        }
+    }
+
+    fn check_durations(&mut self, mvhd: &MvhdBox, trak: &TrakBox, path: &str, stts_count: u64) {
+        let stbl = &trak.mdia.minf.stbl;
+        let mdhd = &trak.mdia.mdhd;
+        if mvhd.timescale == 0 || mdhd.timescale == 0 {
+            self.report(
+                Severity::Error,
+                format!("{path}/mdia/mdhd"),
+                "zero timescale".to_string(),
+            );
+            return;
+        }
+
+        if stts_count == stbl.stsz.sample_count as u64 {
+            let media_duration: u64 = stbl
+                .stts
+                .entries
+                .iter()
+                .map(|e| e.sample_count as u64 * e.sample_delta as u64)
+                .sum();
+
+            if media_duration != mdhd.duration {
+                self.report(
+                    Severity::Warning,
+                    format!("{path}/mdia/mdhd"),
+                    format!(
+                        "mdhd duration {} doesn't match the {media_duration} of the samples",
+                        mdhd.duration
+                    ),
+                );
+            }
+        }
+
+        // with an edit list the track duration is the sum of the edits
+        let expected = match trak.edts.as_ref().and_then(|edts| edts.elst.as_ref()) {
+            Some(elst) => elst.entries.iter().map(|e| e.segment_duration).sum(),
+            None => {
+                (mdhd.duration as u128 * mvhd.timescale as u128 / mdhd.timescale as u128) as u64
+            }
+        };
+
+        if trak.tkhd.duration.abs_diff(expected) > 1 {
+            self.report(
+                Severity::Warning,
+                format!("{path}/tkhd"),
+                format!(
+                    "tkhd duration {} doesn't match the media duration {expected}",
+                    trak.tkhd.duration
+                ),
+            );
+        }
+    }
+
+    fn check_moof(
+        &mut self,
+        moov: Option<&MoovBox>,
+        moof: &MoofBox,
+        node: &BoxNode,
+        path: &str,
+        state: &mut FragmentState,
+    ) {
+        let sequence_number = moof.mfhd.sequence_number;
+        if state.sequence_number.is_some_and(|n| sequence_number <= n) {
+            self.report(
+                Severity::Warning,
+                format!("{path}/mfhd"),
+                format!("sequence number {sequence_number} doesn't increase"),
+            );
+        }
+        state.sequence_number = Some(sequence_number);
+
+        let mut data_end = node.offset;
+        for (idx, traf) in moof.trafs.iter().enumerate() {
+            let traf_path = format!("{path}/traf[{idx}]");
+            let tfhd = &traf.tfhd;
+            let track_id = tfhd.track_id;
+
+            let trex = moov
+                .and_then(|m| m.mvex.as_ref())
+                .and_then(|mvex| mvex.trexs.iter().find(|t| t.track_id == track_id));
+            let has_track =
+                moov.is_some_and(|m| m.traks.iter().any(|t| t.tkhd.track_id == track_id));
+            if moov.is_some() && !has_track {
+                self.report(
+                    Severity::Error,
+                    format!("{traf_path}/tfhd"),
+                    format!("track {track_id} is not declared in moov"),
+                );
+                continue;
+            }
+
+            let base = match tfhd.base_data_offset {
+                Some(offset) => offset,
+                None if idx == 0 || tfhd.flags & TfhdBox::FLAG_DEFAULT_BASE_IS_MOOF != 0 => {
+                    node.offset
+                }
+                None => data_end,
+            };
+
+            let default_size = tfhd
+                .default_sample_size
+                .or(trex.map(|t| t.default_sample_size))
+                .unwrap_or(0) as u64;
+            let default_duration = tfhd
+                .default_sample_duration
+                .or(trex.map(|t| t.default_sample_duration))
+                .unwrap_or(0) as u64;
+
+            let mut start = base;
+            let mut duration = 0;
+            if let Some(trun) = &traf.trun {
+                if let Some(data_offset) = trun.data_offset {
+                    start = base.saturating_add_signed(data_offset as i64);
+                }
+
+                let size: u64 = if trun.sample_sizes.is_empty() {
+                    default_size * trun.sample_count as u64
+                } else {
+                    trun.sample_sizes.iter().map(|&s| s as u64).sum()
+                };
+                duration = if trun.sample_durations.is_empty() {
+                    default_duration * trun.sample_count as u64
+                } else {
+                    trun.sample_durations.iter().map(|&d| d as u64).sum()
+                };
+
+                let range = start..start.saturating_add(size);
+                if !self.in_mdat(&range) {
+                    self.report(
+                        Severity::Error,
+                        format!("{traf_path}/trun"),
+                        format!(
+                            "sample data at {}..{} lies outside any mdat",
+                            range.start, range.end
+                        ),
+                    );
+                }
+                data_end = range.end;
+            }
+
+            let previous_end = state.decode_end.get(&track_id).copied();
+            let decode_time = match &traf.tfdt {
+                Some(tfdt) => tfdt.base_media_decode_time,
+                None => previous_end.unwrap_or(0),
+            };
+
+            if let Some(previous_end) = previous_end.filter(|&end| decode_time < end) {
+                self.report(
+                    Severity::Error,
+                    format!("{traf_path}/tfdt"),
+                    format!(
+                        "decode time {decode_time} goes back before the end of the previous fragment at {previous_end}"
+                    ),
+                );
+            }
+            state.decode_end.insert(track_id, decode_time + duration);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::test_util::fragmented_file;
+    use std::io::Cursor;
+
+    #[tokio::test]
+    async fn test_validate() {
+        let mut data = fragmented_file();
+        let diagnostics = validate(&mut Cursor::new(data.clone())).await.unwrap();
+        assert_eq!(diagnostics, vec![]);
+
+        let tree = BoxTree::parse(Bytes::from(data.clone())).unwrap();
+        assert!(tree.find_all("moof").len() >= 2);
+
+        // repeat the first sequence number and decode time in the second fragment
+        let mfhd = tree.find("moof[1]/mfhd").unwrap().data_offset() as usize + 4;
+        data[mfhd..mfhd + 4].copy_from_slice(&1u32.to_be_bytes());
+
+        let tfdt = tree.find("moof[1]/traf/tfdt").unwrap().data_offset() as usize;
+        let len = if data[tfdt] == 1 { 8 } else { 4 };
+        data[tfdt + 4..tfdt + 4 + len].fill(0);
+
+        let diagnostics = validate(&mut Cursor::new(data)).await.unwrap();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].path, "moof[1]/mfhd");
+        assert_eq!(
+            diagnostics[0].offset,
+            tree.find("moof[1]/mfhd").unwrap().offset
+        );
+        assert_eq!(diagnostics[1].severity, Severity::Error);
+        assert_eq!(diagnostics[1].path, "moof[1]/traf[0]/tfdt");
+    }
+}
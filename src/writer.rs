@@ -0,0 +1,914 @@
+use std::io::Write;
+
+use crate::cenc::nal_subsamples;
+use crate::stsc::StscEntry;
+use crate::stts::SttsEntry;
+use crate::tx3g::text_sample;
+use crate::{
+    c608_sample, parse_srt, parse_webvtt, C608Config, CaptionPacket, SubtitleCue, VttCue,
+    VttSample, WebVttConfig,
+};
+use crate::{
+    BoxHeader, BoxType, CencConfig, CencEncryptor, Chapter, ChplBox, ChplEntry, Co64Box, Error,
+    FourCC, FtypBox, MediaConfig, MfhdBox, MfraBox, MoofBox, MoovBox, Mp4Box, Mp4Config, Mp4Sample,
+    MvexBox, MvhdBox, PsshBox, ReferenceType, SaioBox, SaizBox, SampleEncryption, SencBox, StblBox,
+    StcoBox, StscBox, StsdBox, StssBox, StszBox, SttsBox, TfdtBox, TfhdBox, TfraBox, TfraEntry,
+    TrackConfig, TrackType, TrafBox, TrakBox, TrexBox, TrunBox, TtxtConfig, UdtaBox, UnknownBoxes,
+    WriteBox, HEADER_EXT_SIZE, HEADER_SIZE,
+};
+
+/// `sample_depends_on = 2` (does not depend on others)
+const SAMPLE_FLAGS_SYNC: u32 = 0x0200_0000;
+
+/// `sample_depends_on = 1`, `sample_is_non_sync_sample = 1`
+const SAMPLE_FLAGS_NON_SYNC: u32 = 0x0101_0000;
+
+/// Timescale of the QuickTime chapter track.
+const CHAPTER_TIMESCALE: u32 = 1000;
+const SUBTITLE_TIMESCALE: u32 = 1000;
+
+struct FragmentTrack {
+    trak: TrakBox,
+    samples: Vec<Mp4Sample>,
+    tfra: TfraBox,
+    protection: Option<TrackProtection>,
+
+    /// End of the last written sample, in the track timescale.
+    end_time: u64,
+}
+
+struct TrackProtection {
+    encryptor: CencEncryptor,
+
+    /// NAL unit length size and whether the track is HEVC, for subsample encryption.
+    nal_format: Option<(usize, bool)>,
+    samples: Vec<SampleEncryption>,
+}
+
+impl FragmentTrack {
+    fn set_protection(&mut self, config: &CencConfig) -> Result<(), Error> {
+        let track_type = TrackType::from(&self.trak.mdia.hdlr.handler_type);
+        let encryptor = CencEncryptor::new(config, track_type)?;
+        let stsd = &mut self.trak.mdia.minf.stbl.stsd;
+
+        let nal_format = if let Some(ref mut avc1) = stsd.avc1 {
+            avc1.sinf = Some(encryptor.sinf(BoxType::Avc1Box.into()));
+            Some(((avc1.avcc.length_size_minus_one & 0x03) as usize + 1, false))
+        } else if let Some(ref mut hev1) = stsd.hev1 {
+            hev1.sinf = Some(encryptor.sinf(BoxType::Hev1Box.into()));
+            Some(((hev1.hvcc.length_size_minus_one & 0x03) as usize + 1, true))
+        } else if let Some(ref mut mp4a) = stsd.mp4a {
+            mp4a.sinf = Some(encryptor.sinf(BoxType::Mp4aBox.into()));
+            None
+        } else {
+            return Err(Error::InvalidData(
+                "encryption is only supported for avc1, hev1 and mp4a tracks",
+            ));
+        };
+
+        self.protection = Some(TrackProtection {
+            encryptor,
+            nal_format,
+            samples: Vec::new(),
+        });
+
+        Ok(())
+    }
+
+    fn push_sample(&mut self, sample: &Mp4Sample) -> Result<(), Error> {
+        self.end_time = self
+            .end_time
+            .max(sample.start_time + sample.duration as u64);
+
+        let Some(protection) = self.protection.as_mut() else {
+            self.samples.push(sample.clone());
+            return Ok(());
+        };
+
+        let mut bytes = sample.bytes.to_vec();
+        let subsamples = match protection.nal_format {
+            Some((length_size, hevc)) => {
+                let scheme = protection.encryptor.scheme();
+                nal_subsamples(&bytes, length_size, hevc, scheme)?
+            }
+            None => Vec::new(),
+        };
+
+        let info = protection
+            .encryptor
+            .encrypt_sample(&mut bytes, subsamples)?;
+        protection.samples.push(info);
+
+        self.samples.push(Mp4Sample {
+            bytes: bytes.into(),
+            ..sample.clone()
+        });
+
+        Ok(())
+    }
+
+    fn build_traf(&self) -> Result<TrafBox, Error> {
+        let track_id = self.trak.tkhd.track_id;
+        let negative_cts = self.samples.iter().any(|s| s.rendering_offset < 0);
+        let has_cts = self.samples.iter().any(|s| s.rendering_offset != 0);
+
+        let mut flags = TrunBox::FLAG_DATA_OFFSET
+            | TrunBox::FLAG_SAMPLE_DURATION
+            | TrunBox::FLAG_SAMPLE_SIZE
+            | TrunBox::FLAG_SAMPLE_FLAGS;
+
+        if has_cts {
+            flags |= TrunBox::FLAG_SAMPLE_CTS;
+        }
+
+        let (senc, saiz, saio) = match self.protection {
+            Some(ref protection) => {
+                let senc = SencBox::new(&protection.samples);
+                let sizes = protection
+                    .samples
+                    .iter()
+                    .map(|s| {
+                        let subsamples = if senc.flags & SencBox::FLAG_USE_SUBSAMPLE_ENCRYPTION != 0
+                        {
+                            2 + 6 * s.subsamples.len()
+                        } else {
+                            0
+                        };
+                        u8::try_from(s.iv.len() + subsamples).map_err(|_| {
+                            Error::InvalidData("sample auxiliary info larger than 255 bytes")
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let default_sample_info_size = match sizes.first() {
+                    Some(&size) if size != 0 && sizes.iter().all(|&s| s == size) => size,
+                    _ => 0,
+                };
+
+                let saiz = SaizBox {
+                    default_sample_info_size,
+                    sample_count: sizes.len() as u32,
+                    sample_info_sizes: if default_sample_info_size == 0 {
+                        sizes
+                    } else {
+                        Vec::new()
+                    },
+                    ..Default::default()
+                };
+
+                // the offset is filled in once the layout of the moof is known
+                let saio = SaioBox {
+                    offsets: vec![0],
+                    ..Default::default()
+                };
+
+                (Some(senc), Some(saiz), Some(saio))
+            }
+            None => (None, None, None),
+        };
+
+        Ok(TrafBox {
+            tfhd: TfhdBox {
+                flags: TfhdBox::FLAG_DEFAULT_BASE_IS_MOOF,
+                track_id,
+                ..Default::default()
+            },
+            tfdt: Some(TfdtBox {
+                version: 1,
+                flags: 0,
+                base_media_decode_time: self.samples[0].start_time,
+            }),
+            trun: Some(TrunBox {
+                version: u8::from(negative_cts),
+                flags,
+                sample_count: self.samples.len() as u32,
+                data_offset: Some(0),
+                first_sample_flags: None,
+                sample_durations: self.samples.iter().map(|s| s.duration).collect(),
+                sample_sizes: self.samples.iter().map(|s| s.bytes.len() as u32).collect(),
+                sample_flags: self
+                    .samples
+                    .iter()
+                    .map(|s| {
+                        if s.is_sync {
+                            SAMPLE_FLAGS_SYNC
+                        } else {
+                            SAMPLE_FLAGS_NON_SYNC
+                        }
+                    })
+                    .collect(),
+                sample_cts: if has_cts {
+                    self.samples
+                        .iter()
+                        .map(|s| s.rendering_offset as u32)
+                        .collect()
+                } else {
+                    Vec::new()
+                },
+            }),
+            senc,
+            saiz,
+            saio,
+            unknown: UnknownBoxes::new(),
+        })
+    }
+}
+
+/// Sample tables of a track indexed by the `moov`, for samples stored outside of
+/// fragments.
+#[derive(Debug, Default)]
+pub(crate) struct SampleTable {
+    stts: Vec<SttsEntry>,
+    stss: Vec<u32>,
+    sizes: Vec<u32>,
+
+    /// Offset in the sample data and sample count of each chunk.
+    chunks: Vec<(u64, u32)>,
+    end: u64,
+    duration: u64,
+}
+
+impl SampleTable {
+    /// Adds a sample stored `offset` bytes into the sample data, a sample right after
+    /// the previous one continues its chunk.
+    pub(crate) fn push(&mut self, offset: u64, size: u32, duration: u32, is_sync: bool) {
+        match self.chunks.last_mut() {
+            Some((_, count)) if offset == self.end => *count += 1,
+            _ => self.chunks.push((offset, 1)),
+        }
+        self.end = offset + size as u64;
+
+        match self.stts.last_mut() {
+            Some(entry) if entry.sample_delta == duration => entry.sample_count += 1,
+            _ => self.stts.push(SttsEntry {
+                sample_count: 1,
+                sample_delta: duration,
+            }),
+        }
+
+        self.sizes.push(size);
+        if is_sync {
+            self.stss.push(self.sizes.len() as u32);
+        }
+        self.duration += duration as u64;
+    }
+
+    /// Sum of the sample durations, in the track timescale.
+    pub(crate) fn duration(&self) -> u64 {
+        self.duration
+    }
+
+    /// Tables of the samples described by `stsd`, with the sample data at `base` in
+    /// the file.
+    pub(crate) fn stbl(&self, stsd: StsdBox, base: u64) -> StblBox {
+        let mut stsc = StscBox::default();
+        let mut first_sample = 1;
+        for (idx, &(_, count)) in self.chunks.iter().enumerate() {
+            if stsc.entries.last().map(|e| e.samples_per_chunk) != Some(count) {
+                stsc.entries.push(StscEntry {
+                    first_chunk: idx as u32 + 1,
+                    samples_per_chunk: count,
+                    sample_description_index: 1,
+                    first_sample,
+                });
+            }
+            first_sample += count;
+        }
+
+        let offsets = self.chunks.iter().map(|&(offset, _)| base + offset);
+        let (stco, co64) = if base + self.end > u32::MAX as u64 {
+            let co64 = Co64Box {
+                entries: offsets.collect(),
+                ..Default::default()
+            };
+            (None, Some(co64))
+        } else {
+            let stco = StcoBox {
+                entries: offsets.map(|offset| offset as u32).collect(),
+                ..Default::default()
+            };
+            (Some(stco), None)
+        };
+
+        StblBox {
+            stsd,
+            stts: SttsBox {
+                entries: self.stts.clone(),
+                ..Default::default()
+            },
+            // without stss every sample is a sync sample
+            stss: (self.stss.len() != self.sizes.len()).then(|| StssBox {
+                entries: self.stss.clone(),
+                ..Default::default()
+            }),
+            stsc,
+            stsz: StszBox {
+                sample_count: self.sizes.len() as u32,
+                sample_sizes: self.sizes.clone(),
+                ..Default::default()
+            },
+            stco,
+            co64,
+            ..Default::default()
+        }
+    }
+}
+
+/// Fragmented MP4 muxer.
+///
+/// Writes `ftyp` on start, `moov` (with `mvex`) before the first fragment and one
+/// `moof` + `mdat` pair per fragment. A new fragment is started on every sync sample
+/// of the leading track (the first video track, or the first track if there is no
+/// video). On [`write_end`](Self::write_end) a `mfra` random access index is appended.
+///
+/// Tracks can be encrypted with [`set_track_protection`](Self::set_track_protection), in
+/// which case the sample entries are written as `encv`/`enca` and every `traf` carries
+/// `senc`/`saiz`/`saio` boxes.
+///
+/// Chapters set with [`set_chapters`](Self::set_chapters) are written both as a Nero
+/// `chpl` box and as a QuickTime chapter text track. Subtitle tracks added with
+/// [`add_srt_track`](Self::add_srt_track) or [`add_webvtt_track`](Self::add_webvtt_track)
+/// are written as a single fragment at the end.
+pub struct Mp4FragmentWriter<W> {
+    writer: W,
+    timescale: u32,
+    tracks: Vec<FragmentTrack>,
+    psshs: Vec<PsshBox>,
+    chapters: Vec<Chapter>,
+    chapter_track: Option<usize>,
+
+    /// Text tracks imported from subtitle files with their queued samples.
+    subtitle_tracks: Vec<(usize, Vec<Mp4Sample>)>,
+    sequence_number: u32,
+    offset: u64,
+    init_written: bool,
+}
+
+impl<W: Write> Mp4FragmentWriter<W> {
+    pub fn write_start(mut writer: W, config: &Mp4Config) -> Result<Self, Error> {
+        let ftyp = FtypBox {
+            major_brand: config.major_brand,
+            minor_version: config.minor_version,
+            compatible_brands: config.compatible_brands.clone(),
+        };
+        ftyp.write_box(&mut writer)?;
+
+        Ok(Self {
+            writer,
+            timescale: config.timescale,
+            tracks: Vec::new(),
+            psshs: Vec::new(),
+            chapters: Vec::new(),
+            chapter_track: None,
+            subtitle_tracks: Vec::new(),
+            sequence_number: 0,
+            offset: ftyp.box_size(),
+            init_written: false,
+        })
+    }
+
+    pub fn add_track(&mut self, config: &TrackConfig) -> Result<u32, Error> {
+        if self.init_written {
+            return Err(Error::InvalidData(
+                "tracks must be added before the first fragment",
+            ));
+        }
+
+        let track_id = self.tracks.len() as u32 + 1;
+        self.tracks.push(FragmentTrack {
+            trak: TrakBox::new(track_id, config),
+            samples: Vec::new(),
+            tfra: TfraBox {
+                version: 1,
+                track_id,
+                ..Default::default()
+            },
+            protection: None,
+            end_time: 0,
+        });
+
+        Ok(track_id)
+    }
+
+    /// Adds a `tref` reference from `track_id` to `referenced_track_id`, e.g. to link a
+    /// thumbnail or timed metadata track to its video track.
+    pub fn add_track_reference(
+        &mut self,
+        track_id: u32,
+        reference_type: ReferenceType,
+        referenced_track_id: u32,
+    ) -> Result<(), Error> {
+        if self.init_written {
+            return Err(Error::InvalidData(
+                "track references must be added before the first fragment",
+            ));
+        }
+
+        self.track_index(referenced_track_id)?;
+        let idx = self.track_index(track_id)?;
+        self.tracks[idx]
+            .trak
+            .tref
+            .get_or_insert_with(Default::default)
+            .add(reference_type, referenced_track_id);
+
+        Ok(())
+    }
+
+    /// Sets the chapter markers of the movie, at most 255 as `chpl` can't hold more.
+    ///
+    /// Adds a disabled text track carrying the chapter titles, referenced from all
+    /// other tracks with `tref/chap`, and a `chpl` box in `moov/udta`. The titles are
+    /// stored in an `mdat` ahead of the `moov` and indexed by the track's sample
+    /// tables, as QuickTime ignores chapter samples in fragments. The first title
+    /// starts at zero and the last one has a nominal duration, the length of the
+    /// movie isn't known yet when the `moov` is written.
+    pub fn set_chapters(&mut self, chapters: &[Chapter]) -> Result<(), Error> {
+        if self.init_written {
+            return Err(Error::InvalidData(
+                "chapters must be set before the first fragment",
+            ));
+        }
+
+        if chapters.len() > u8::MAX as usize {
+            return Err(Error::InvalidData("chpl can hold at most 255 chapters"));
+        }
+
+        if self.chapter_track.is_none() {
+            let track_id = self.add_track(&TrackConfig {
+                track_type: TrackType::Text,
+                timescale: CHAPTER_TIMESCALE,
+                language: "und".to_string(),
+                media_conf: MediaConfig::TtxtConfig(TtxtConfig {}),
+            })?;
+
+            let idx = track_id as usize - 1;
+            self.tracks[idx].trak.tkhd.flags = 0;
+            self.chapter_track = Some(idx);
+        }
+
+        self.chapters = chapters.to_vec();
+        self.chapters.sort_by_key(|c| c.start);
+
+        Ok(())
+    }
+
+    /// Adds a `tx3g` subtitle track from a SubRip document.
+    pub fn add_srt_track(&mut self, srt: &str, language: &str) -> Result<u32, Error> {
+        self.add_subtitle_track(&parse_srt(srt)?, language)
+    }
+
+    /// Adds a `tx3g` subtitle track with the given cues.
+    ///
+    /// The samples are written with the last fragment, gaps between cues are filled with
+    /// empty samples and overlapping cues are shortened.
+    pub fn add_subtitle_track(
+        &mut self,
+        cues: &[SubtitleCue],
+        language: &str,
+    ) -> Result<u32, Error> {
+        let track_id = self.add_track(&TrackConfig {
+            track_type: TrackType::Subtitle,
+            timescale: SUBTITLE_TIMESCALE,
+            language: language.to_string(),
+            media_conf: MediaConfig::TtxtConfig(TtxtConfig {}),
+        })?;
+
+        let scale = |d: std::time::Duration| {
+            (d.as_nanos() * SUBTITLE_TIMESCALE as u128 / 1_000_000_000) as u64
+        };
+
+        let mut cues = cues.iter().collect::<Vec<_>>();
+        cues.sort_by_key(|cue| cue.start);
+
+        let mut samples = Vec::with_capacity(cues.len() * 2);
+        let mut end_time = 0;
+        for cue in cues {
+            let start = scale(cue.start).max(end_time);
+            let end = scale(cue.end);
+            if end <= start {
+                continue;
+            }
+
+            if start > end_time {
+                samples.push(Mp4Sample {
+                    start_time: end_time,
+                    duration: (start - end_time) as u32,
+                    rendering_offset: 0,
+                    is_sync: true,
+                    bytes: text_sample("").into(),
+                });
+            }
+
+            samples.push(Mp4Sample {
+                start_time: start,
+                duration: (end - start) as u32,
+                rendering_offset: 0,
+                is_sync: true,
+                bytes: text_sample(&cue.text).into(),
+            });
+            end_time = end;
+        }
+
+        self.subtitle_tracks.push((track_id as usize - 1, samples));
+
+        Ok(track_id)
+    }
+
+    /// Adds a `wvtt` subtitle track from a WebVTT document.
+    ///
+    /// The samples are written with the last fragment. Overlapping cues are split so
+    /// every sample carries the cues active over its whole duration, gaps are filled
+    /// with empty cues.
+    pub fn add_webvtt_track(&mut self, vtt: &str, language: &str) -> Result<u32, Error> {
+        let (header, cues) = parse_webvtt(vtt)?;
+
+        let track_id = self.add_track(&TrackConfig {
+            track_type: TrackType::Text,
+            timescale: SUBTITLE_TIMESCALE,
+            language: language.to_string(),
+            media_conf: MediaConfig::WebVttConfig(WebVttConfig {
+                config: header,
+                label: None,
+            }),
+        })?;
+
+        let scale = |d: std::time::Duration| {
+            (d.as_nanos() * SUBTITLE_TIMESCALE as u128 / 1_000_000_000) as u64
+        };
+
+        let mut bounds = cues
+            .iter()
+            .flat_map(|cue| [scale(cue.start), scale(cue.end)])
+            .collect::<Vec<_>>();
+        bounds.push(0);
+        bounds.sort_unstable();
+        bounds.dedup();
+
+        let samples = bounds
+            .windows(2)
+            .map(|w| {
+                let (start, end) = (w[0], w[1]);
+                let cues = cues
+                    .iter()
+                    .filter(|cue| scale(cue.start) <= start && scale(cue.end) >= end)
+                    .map(|cue| VttCue {
+                        id: cue.id.clone(),
+                        settings: cue.settings.clone(),
+                        ..VttCue::new(cue.text.as_str())
+                    })
+                    .collect();
+
+                let sample = VttSample {
+                    cues,
+                    additional_text: Vec::new(),
+                };
+
+                Mp4Sample {
+                    start_time: start,
+                    duration: (end - start) as u32,
+                    rendering_offset: 0,
+                    is_sync: true,
+                    bytes: sample.to_bytes().into(),
+                }
+            })
+            .collect();
+
+        self.subtitle_tracks.push((track_id as usize - 1, samples));
+
+        Ok(track_id)
+    }
+
+    /// Adds a `c608` closed caption track, e.g. with the captions extracted from the SEI
+    /// messages of a video track.
+    ///
+    /// The samples are written with the last fragment, each packet lasts until the next
+    /// one.
+    pub fn add_c608_track(
+        &mut self,
+        packets: &[CaptionPacket],
+        timescale: u32,
+    ) -> Result<u32, Error> {
+        let mut sorted = packets.iter().collect::<Vec<_>>();
+        sorted.sort_by_key(|p| p.pts);
+
+        // packets sharing a timestamp go into the same sample
+        let mut merged: Vec<CaptionPacket> = Vec::new();
+        for packet in sorted {
+            match merged.last_mut() {
+                Some(last) if last.pts == packet.pts => {
+                    last.cc_data.extend_from_slice(&packet.cc_data)
+                }
+                _ => merged.push(packet.clone()),
+            }
+        }
+        merged.retain(|p| p.cc_data.iter().any(|cc| cc.cc_type < 2));
+
+        let samples = merged
+            .iter()
+            .enumerate()
+            .map(|(idx, packet)| {
+                let end = merged.get(idx + 1).map_or(packet.pts + 1, |next| next.pts);
+
+                Ok(Mp4Sample {
+                    start_time: packet.pts,
+                    duration: u32::try_from(end - packet.pts)
+                        .map_err(|_| Error::InvalidData("caption packet is too long"))?,
+                    rendering_offset: 0,
+                    is_sync: true,
+                    bytes: c608_sample(&packet.cc_data).into(),
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let track_id = self.add_track(&TrackConfig {
+            track_type: TrackType::Other(FourCC { value: *b"clcp" }),
+            timescale,
+            language: "und".to_string(),
+            media_conf: MediaConfig::C608Config(C608Config {}),
+        })?;
+
+        self.subtitle_tracks.push((track_id as usize - 1, samples));
+
+        Ok(track_id)
+    }
+
+    /// Encrypts all samples of the track written from now on.
+    ///
+    /// AVC and HEVC samples are expected in length-prefixed form and get subsample
+    /// encryption leaving NAL headers in clear, audio samples are encrypted whole.
+    pub fn set_track_protection(
+        &mut self,
+        track_id: u32,
+        config: &CencConfig,
+    ) -> Result<(), Error> {
+        if self.init_written {
+            return Err(Error::InvalidData(
+                "track protection must be set before the first fragment",
+            ));
+        }
+
+        let idx = self.track_index(track_id)?;
+        self.tracks[idx].set_protection(config)?;
+
+        for pssh in &config.psshs {
+            if !self.psshs.contains(pssh) {
+                self.psshs.push(pssh.clone());
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn write_sample(&mut self, track_id: u32, sample: &Mp4Sample) -> Result<(), Error> {
+        let idx = self.track_index(track_id)?;
+
+        if sample.is_sync && idx == self.leading_track() && !self.tracks[idx].samples.is_empty() {
+            self.flush_fragment()?;
+        }
+
+        self.tracks[idx].push_sample(sample)
+    }
+
+    /// Writes all buffered samples as a single fragment.
+    pub fn flush_fragment(&mut self) -> Result<(), Error> {
+        if !self.init_written {
+            self.write_init()?;
+        }
+
+        if self.tracks.iter().all(|t| t.samples.is_empty()) {
+            return Ok(());
+        }
+
+        self.sequence_number += 1;
+
+        let mut moof = MoofBox {
+            mfhd: MfhdBox {
+                sequence_number: self.sequence_number,
+                ..Default::default()
+            },
+            psshs: Vec::new(),
+            trafs: Vec::new(),
+            unknown: UnknownBoxes::new(),
+        };
+
+        let mut fragment_tracks = Vec::new();
+        for (idx, track) in self.tracks.iter().enumerate() {
+            if !track.samples.is_empty() {
+                moof.trafs.push(track.build_traf()?);
+                fragment_tracks.push(idx);
+            }
+        }
+
+        let data_size = fragment_tracks
+            .iter()
+            .flat_map(|&idx| self.tracks[idx].samples.iter())
+            .map(|s| s.bytes.len() as u64)
+            .sum::<u64>();
+
+        let mdat = BoxHeader::new(BoxType::MdatBox, HEADER_SIZE + data_size);
+        let mdat_header_size = if mdat.size > u32::MAX as u64 { 16 } else { 8 };
+
+        // data offsets are relative to the first byte of the moof box
+        let moof_size = moof.box_size();
+        let mut data_offset = moof_size + mdat_header_size;
+        for traf in moof.trafs.iter_mut() {
+            if let Some(trun) = traf.trun.as_mut() {
+                trun.data_offset = Some(data_offset as i32);
+                data_offset += trun.sample_sizes.iter().map(|&x| x as u64).sum::<u64>();
+            }
+        }
+
+        // saio offsets point at the first senc entry, relative to the moof as well
+        let mut traf_offset = HEADER_SIZE + moof.mfhd.box_size();
+        for traf in moof.trafs.iter_mut() {
+            let mut senc_offset = traf_offset + HEADER_SIZE + traf.tfhd.box_size();
+            if let Some(ref tfdt) = traf.tfdt {
+                senc_offset += tfdt.box_size();
+            }
+            if let Some(ref trun) = traf.trun {
+                senc_offset += trun.box_size();
+            }
+
+            if let Some(saio) = traf.saio.as_mut() {
+                saio.offsets = vec![senc_offset + HEADER_SIZE + HEADER_EXT_SIZE + 4];
+            }
+
+            traf_offset += traf.box_size();
+        }
+
+        for (traf_idx, &idx) in fragment_tracks.iter().enumerate() {
+            let track = &mut self.tracks[idx];
+            if track.samples[0].is_sync {
+                track.tfra.entries.push(TfraEntry {
+                    time: track.samples[0].start_time,
+                    moof_offset: self.offset,
+                    traf_number: traf_idx as u32 + 1,
+                    trun_number: 1,
+                    sample_number: 1,
+                });
+            }
+        }
+
+        moof.write_box(&mut self.writer)?;
+
+        BoxHeader::new(BoxType::MdatBox, mdat_header_size + data_size).write(&mut self.writer)?;
+        for &idx in &fragment_tracks {
+            let track = &mut self.tracks[idx];
+            for sample in track.samples.drain(..) {
+                self.writer.write_all(&sample.bytes)?;
+            }
+
+            if let Some(protection) = track.protection.as_mut() {
+                protection.samples.clear();
+            }
+        }
+
+        self.offset += moof_size + mdat_header_size + data_size;
+
+        Ok(())
+    }
+
+    /// Flushes pending samples, writes the `mfra` index and returns the inner writer.
+    pub fn write_end(mut self) -> Result<W, Error> {
+        for (idx, samples) in std::mem::take(&mut self.subtitle_tracks) {
+            for sample in &samples {
+                self.tracks[idx].push_sample(sample)?;
+            }
+        }
+
+        self.flush_fragment()?;
+
+        let mfra = MfraBox::new(self.tracks.iter().map(|t| t.tfra.clone()).collect());
+        mfra.write_box(&mut self.writer)?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+
+    pub fn into_writer(self) -> W {
+        self.writer
+    }
+
+    /// Writes the chapter titles in their own `mdat` and indexes them in the `stbl`
+    /// of the chapter track.
+    fn write_chapter_samples(&mut self, chapter_idx: usize) -> Result<(), Error> {
+        let scale = |d: std::time::Duration| {
+            (d.as_nanos() * CHAPTER_TIMESCALE as u128 / 1_000_000_000) as u64
+        };
+
+        let mut table = SampleTable::default();
+        let mut data = Vec::new();
+        for (idx, chapter) in self.chapters.iter().enumerate() {
+            let start = if idx == 0 { 0 } else { scale(chapter.start) };
+            let duration = match self.chapters.get(idx + 1) {
+                Some(next) => u32::try_from(scale(next.start).saturating_sub(start))
+                    .map_err(|_| Error::InvalidData("chapter is too long"))?,
+                None => 1,
+            };
+
+            let bytes = text_sample(&chapter.title);
+            table.push(data.len() as u64, bytes.len() as u32, duration.max(1), true);
+            data.extend_from_slice(&bytes);
+        }
+
+        let trak = &mut self.tracks[chapter_idx].trak;
+        let stsd = std::mem::take(&mut trak.mdia.minf.stbl.stsd);
+        trak.mdia.minf.stbl = table.stbl(stsd, self.offset + HEADER_SIZE);
+        trak.mdia.mdhd.duration = table.duration();
+        trak.tkhd.duration = table.duration() * self.timescale as u64 / CHAPTER_TIMESCALE as u64;
+
+        let mdat_size = HEADER_SIZE + data.len() as u64;
+        BoxHeader::new(BoxType::MdatBox, mdat_size).write(&mut self.writer)?;
+        self.writer.write_all(&data)?;
+        self.offset += mdat_size;
+
+        Ok(())
+    }
+
+    fn write_init(&mut self) -> Result<(), Error> {
+        if let Some(chapter_idx) = self.chapter_track {
+            self.write_chapter_samples(chapter_idx)?;
+
+            let chapter_track_id = chapter_idx as u32 + 1;
+            for (idx, track) in self.tracks.iter_mut().enumerate() {
+                if idx != chapter_idx {
+                    track
+                        .trak
+                        .tref
+                        .get_or_insert_with(Default::default)
+                        .add(ReferenceType::Chapter, chapter_track_id);
+                }
+            }
+        }
+
+        let udta = self.chapter_track.map(|_| UdtaBox {
+            meta: None,
+            chpl: Some(ChplBox {
+                chapters: self
+                    .chapters
+                    .iter()
+                    .map(|c| ChplEntry {
+                        start: (c.start.as_nanos() / 100) as u64,
+                        title: c.title.clone(),
+                    })
+                    .collect(),
+                ..Default::default()
+            }),
+            unknown: UnknownBoxes::new(),
+        });
+
+        let moov = MoovBox {
+            mvhd: MvhdBox {
+                timescale: self.timescale,
+                next_track_id: self.tracks.len() as u32 + 1,
+                ..Default::default()
+            },
+            meta: None,
+            mvex: Some(MvexBox {
+                mehd: None,
+                trexs: self
+                    .tracks
+                    .iter()
+                    .map(|t| TrexBox {
+                        track_id: t.trak.tkhd.track_id,
+                        default_sample_description_index: 1,
+                        ..Default::default()
+                    })
+                    .collect(),
+                unknown: UnknownBoxes::new(),
+            }),
+            traks: self.tracks.iter().map(|t| t.trak.clone()).collect(),
+            udta,
+            psshs: self.psshs.clone(),
+            unknown: UnknownBoxes::new(),
+        };
+
+        moov.write_box(&mut self.writer)?;
+        self.offset += moov.box_size();
+        self.init_written = true;
+
+        Ok(())
+    }
+
+    fn track_index(&self, track_id: u32) -> Result<usize, Error> {
+        if track_id == 0 || track_id as usize > self.tracks.len() {
+            return Err(Error::TrakNotFound(track_id));
+        }
+
+        Ok(track_id as usize - 1)
+    }
+
+    fn leading_track(&self) -> usize {
+        self.tracks
+            .iter()
+            .position(|t| TrackType::from(&t.trak.mdia.hdlr.handler_type) == TrackType::Video)
+            .or_else(|| {
+                (0..self.tracks.len()).find(|&idx| {
+                    Some(idx) != self.chapter_track
+                        && !self.subtitle_tracks.iter().any(|(i, _)| *i == idx)
+                })
+            })
+            .unwrap_or(0)
+    }
+}
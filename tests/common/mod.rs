@@ -0,0 +1,48 @@
+//! Configs shared by the integration tests, the unit tests use `src/test_util.rs`.
+
+use flowly_mp4::{AacConfig, AvcConfig, MediaConfig, Mp4Config, Mp4Sample, TrackConfig, TrackType};
+
+/// Movie config with `brands[0]` as the major brand and a 1 ms timescale.
+pub fn config(brands: &[&str]) -> Mp4Config {
+    Mp4Config {
+        major_brand: str::parse(brands[0]).unwrap(),
+        minor_version: 512,
+        compatible_brands: brands.iter().map(|b| str::parse(b).unwrap()).collect(),
+        timescale: 1000,
+    }
+}
+
+/// 320x240 H.264 track.
+pub fn avc_track(timescale: u32) -> TrackConfig {
+    TrackConfig {
+        track_type: TrackType::Video,
+        timescale,
+        language: "und".to_string(),
+        media_conf: MediaConfig::AvcConfig(AvcConfig {
+            width: 320,
+            height: 240,
+            seq_param_set: vec![0x67, 0x64, 0x00, 0x1f, 0xac, 0xd9, 0x40, 0x50],
+            pic_param_set: vec![0x68, 0xeb, 0xe3, 0xcb],
+        }),
+    }
+}
+
+/// AAC LC track.
+pub fn aac_track(timescale: u32) -> TrackConfig {
+    TrackConfig {
+        track_type: TrackType::Audio,
+        timescale,
+        language: "und".to_string(),
+        media_conf: MediaConfig::AacConfig(AacConfig::default()),
+    }
+}
+
+pub fn sample(start_time: u64, duration: u32, is_sync: bool, bytes: Vec<u8>) -> Mp4Sample {
+    Mp4Sample {
+        start_time,
+        duration,
+        rendering_offset: 0,
+        is_sync,
+        bytes: bytes.into(),
+    }
+}
@@ -1,8 +1,24 @@
-use flowly_mp4::{Mp4File, TrackType};
+use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::Aes128;
+use bytes::Bytes;
+use flowly_mp4::{
+    BlockReader, BoxHeader, BoxRegistry, BoxTree, BoxType, CaptionChannel, CencConfig, Chapter,
+    Error, FrmaBox, MediaConfig, MoofBox, MoovBox, Mp4Box, Mp4File, Mp4FragmentWriter, MvexBox,
+    PositionalReader, ProtectionScheme, PsshBox, Reader, ReferenceType, SaizBox, SampleEncryption,
+    SchiBox, SchmBox, SencBox, Severity, SinfBox, SubsampleEncryption, SubtitleFormat, TencBox,
+    TfhdBox, TrackConfig, TrackType, TrafBox, TrakBox, TrexBox, TrunBox, TtmlConfig, UdtaBox,
+    UnknownBox, UnknownBoxes, WriteBox, HEADER_SIZE,
+};
+use futures::StreamExt;
 use glob::glob;
+use std::io::Cursor;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::fs::File;
 use tokio::io::BufReader;
 
+mod common;
+
 #[tokio::test]
 async fn test_read_mp4() {
     let paths = glob("./assets/videos/*.mp4").expect("Failed to read glob pattern");
@@ -50,3 +66,1052 @@ async fn test_read_mp4() {
         }
     }
 }
+
+#[tokio::test]
+async fn test_fragmented_mp4_index() {
+    let config = common::config(&["iso6", "mp41"]);
+
+    let mut writer = Mp4FragmentWriter::write_start(Vec::new(), &config).unwrap();
+    let track_id = writer.add_track(&common::avc_track(1000)).unwrap();
+
+    for idx in 0..30u64 {
+        let payload = vec![idx as u8; 16 + idx as usize];
+        let mut bytes = (payload.len() as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(&payload);
+
+        writer
+            .write_sample(
+                track_id,
+                &common::sample(idx * 40, 40, idx % 10 == 0, bytes),
+            )
+            .unwrap();
+    }
+
+    let data = writer.write_end().unwrap();
+
+    let mut mp4_file = Mp4File::new(Cursor::new(data.clone()));
+    assert!(mp4_file.read_header_indexed().await.unwrap());
+
+    let tfra = mp4_file.mfra.as_ref().unwrap().tfra(track_id).unwrap();
+    assert_eq!(tfra.entries.len(), 3);
+    assert!(mp4_file.tracks[&track_id].samples.is_empty());
+
+    let idx = mp4_file
+        .read_fragment_at(track_id, 850)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let track = &mp4_file.tracks[&track_id];
+    assert_eq!(track.samples.len(), 10);
+    assert_eq!(track.samples[idx].start_time, 800);
+    assert!(track.samples[idx].is_sync);
+    assert!(!track.samples[idx + 1].is_sync);
+
+    let sample = mp4_file
+        .read_sample_data(track_id, idx + 3)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(&sample[4..], &[23u8; 39][..]);
+
+    let idx = mp4_file
+        .read_fragment_at(track_id, 0)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(idx, 0);
+    assert_eq!(mp4_file.tracks[&track_id].samples.len(), 20);
+    assert_eq!(mp4_file.tracks[&track_id].samples[10].start_time, 800);
+
+    // a full scan produces the same samples
+    let mut full = Mp4File::new(Cursor::new(data));
+    assert!(full.read_header().await.unwrap());
+    assert!(full.mfra.is_some());
+    assert_eq!(full.tracks[&track_id].samples.len(), 30);
+    assert_eq!(full.tracks[&track_id].samples[29].start_time, 29 * 40);
+}
+
+#[tokio::test]
+async fn test_read_mfra() {
+    let f = File::open("./assets/videos/cosmoc.mp4f").await.unwrap();
+    let mut mp4_file = Mp4File::new(BufReader::new(f));
+    assert!(mp4_file.read_header_indexed().await.unwrap());
+
+    let tfra = mp4_file.mfra.as_ref().unwrap().tfra(1).unwrap();
+    assert_eq!(tfra.entries.len(), 11);
+
+    let last = *tfra.entries.last().unwrap();
+    let idx = mp4_file
+        .read_fragment_at(1, u64::MAX)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let sample = &mp4_file.tracks[&1].samples[idx];
+    assert_eq!(sample.start_time, last.time);
+    assert!(mp4_file.read_sample_data(1, idx).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_read_shared() {
+    let f = std::fs::File::open("./assets/videos/cosmoc.mp4f").unwrap();
+    let mut shared = Mp4File::new(PositionalReader::new(f));
+    assert!(shared.read_header().await.unwrap());
+    let shared = Arc::new(shared);
+
+    let f = File::open("./assets/videos/cosmoc.mp4f").await.unwrap();
+    let mut mp4_file = Mp4File::new(BufReader::new(f));
+    assert!(mp4_file.read_header().await.unwrap());
+
+    let mut tasks = Vec::new();
+    for (&track_id, track) in &shared.tracks {
+        let shared = shared.clone();
+        let count = track.samples.len();
+        tasks.push(tokio::spawn(async move {
+            let mut samples = Vec::with_capacity(count);
+            for idx in 0..count {
+                let data = shared.spawn_sample_data(track_id, idx).await.unwrap();
+                samples.push(data.unwrap());
+            }
+            samples
+        }));
+    }
+
+    for (task, &track_id) in tasks.into_iter().zip(shared.tracks.keys()) {
+        for (idx, data) in task.await.unwrap().into_iter().enumerate() {
+            let expected = mp4_file.read_sample_data(track_id, idx).await.unwrap();
+            assert_eq!(Some(data), expected);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_read_blocking() {
+    let f = std::fs::File::open("./assets/videos/cosmoc.mp4f").unwrap();
+    let mut blocking = flowly_mp4::blocking::Mp4File::new(std::io::BufReader::new(f));
+    assert!(blocking.read_header_indexed().unwrap());
+    assert_eq!(
+        blocking
+            .mfra
+            .as_ref()
+            .unwrap()
+            .tfra(1)
+            .unwrap()
+            .entries
+            .len(),
+        11
+    );
+
+    let f = File::open("./assets/videos/cosmoc.mp4f").await.unwrap();
+    let mut mp4_file = Mp4File::new(BufReader::new(f));
+    assert!(mp4_file.read_header_indexed().await.unwrap());
+
+    let idx = blocking.read_fragment_at(1, u64::MAX).unwrap().unwrap();
+    assert_eq!(
+        mp4_file.read_fragment_at(1, u64::MAX).await.unwrap(),
+        Some(idx)
+    );
+    assert_eq!(
+        blocking.read_sample_data(1, idx).unwrap(),
+        mp4_file.read_sample_data(1, idx).await.unwrap()
+    );
+
+    let samples = blocking
+        .samples(&[1])
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(samples.len(), blocking.tracks[&1].samples.len());
+}
+
+#[tokio::test]
+async fn test_read_cenc_fragment() {
+    let mut trak = TrakBox::new(1, &common::avc_track(1000));
+
+    trak.mdia.minf.stbl.stsd.avc1.as_mut().unwrap().sinf = Some(SinfBox {
+        frma: FrmaBox {
+            original_format: BoxType::Avc1Box.into(),
+        },
+        schm: Some(SchmBox {
+            scheme_type: str::parse("cenc").unwrap(),
+            scheme_version: 0x10000,
+            ..Default::default()
+        }),
+        schi: Some(SchiBox {
+            tenc: Some(TencBox {
+                default_is_protected: 1,
+                default_per_sample_iv_size: 8,
+                default_kid: [0x42; 16],
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+
+    let moov = MoovBox {
+        traks: vec![trak],
+        mvex: Some(MvexBox {
+            mehd: None,
+            trexs: vec![TrexBox {
+                track_id: 1,
+                default_sample_description_index: 1,
+                ..Default::default()
+            }],
+            unknown: UnknownBoxes::new(),
+        }),
+        psshs: vec![PsshBox {
+            version: 1,
+            system_id: [0x10; 16],
+            kids: vec![[0x42; 16]],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let encryption = vec![
+        SampleEncryption {
+            iv: vec![1; 8],
+            subsamples: vec![SubsampleEncryption {
+                bytes_of_clear_data: 5,
+                bytes_of_protected_data: 11,
+            }],
+        },
+        SampleEncryption {
+            iv: vec![2; 8],
+            subsamples: vec![SubsampleEncryption {
+                bytes_of_clear_data: 5,
+                bytes_of_protected_data: 27,
+            }],
+        },
+    ];
+
+    let mut moof = MoofBox {
+        trafs: vec![TrafBox {
+            tfhd: TfhdBox {
+                flags: TfhdBox::FLAG_DEFAULT_BASE_IS_MOOF,
+                track_id: 1,
+                ..Default::default()
+            },
+            trun: Some(TrunBox {
+                flags: TrunBox::FLAG_DATA_OFFSET | TrunBox::FLAG_SAMPLE_SIZE,
+                sample_count: 2,
+                data_offset: Some(0),
+                sample_sizes: vec![16, 32],
+                ..Default::default()
+            }),
+            senc: Some(SencBox::new(&encryption)),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    fn write_file(moov: &MoovBox, moof: &mut MoofBox) -> Vec<u8> {
+        moof.trafs[0].trun.as_mut().unwrap().data_offset = Some(moof.box_size() as i32 + 8);
+
+        let mut data = Vec::new();
+        moov.write_box(&mut data).unwrap();
+        moof.write_box(&mut data).unwrap();
+        BoxHeader::new(BoxType::MdatBox, 8 + 48)
+            .write(&mut data)
+            .unwrap();
+        data.extend_from_slice(&[0; 48]);
+        data
+    }
+
+    let mut mp4_file = Mp4File::new(Cursor::new(write_file(&moov, &mut moof)));
+    assert!(mp4_file.read_header().await.unwrap());
+
+    let track = &mp4_file.tracks[&1];
+    let sinf = track.protection().unwrap();
+    assert_eq!(sinf.scheme_type(), Some(str::parse("cenc").unwrap()));
+    assert_eq!(sinf.tenc().unwrap().default_kid, [0x42; 16]);
+
+    assert_eq!(track.samples.len(), 2);
+    assert_eq!(track.samples[0].encryption.as_ref(), Some(&encryption[0]));
+    assert_eq!(track.samples[1].encryption.as_ref(), Some(&encryption[1]));
+
+    // without keys the samples are returned as stored
+    let sample = mp4_file.read_sample_data(1, 0).await.unwrap().unwrap();
+    assert_eq!(sample.as_ref(), &[0; 16]);
+
+    // the mdat holds zeroes, so the decrypted payload is the raw CTR keystream
+    let key = [0x2b; 16];
+    mp4_file.add_decryption_key([0x42; 16], key);
+
+    let sample = mp4_file.read_sample_data(1, 1).await.unwrap().unwrap();
+    let aes = Aes128::new(&key.into());
+    let mut keystream = Vec::new();
+    for counter in 0u64..2 {
+        let mut block = [0u8; 16];
+        block[..8].copy_from_slice(&[2; 8]);
+        block[8..].copy_from_slice(&counter.to_be_bytes());
+        let mut block = block.into();
+        aes.encrypt_block(&mut block);
+        keystream.extend_from_slice(&block);
+    }
+    assert_eq!(&sample[..5], &[0; 5]);
+    assert_eq!(&sample[5..], &keystream[..27]);
+
+    // saiz must describe the senc entries
+    moof.trafs[0].saiz = Some(SaizBox {
+        default_sample_info_size: 8,
+        sample_count: 2,
+        ..Default::default()
+    });
+    let mut mp4_file = Mp4File::new(Cursor::new(write_file(&moov, &mut moof)));
+    let err = mp4_file.read_header().await.unwrap_err();
+    assert!(matches!(err.root(), Error::InvalidData(_)));
+    assert_eq!(err.context().unwrap().track_id, Some(1));
+
+    // without senc and a constant IV the samples have no encryption info
+    moof.trafs[0].senc = None;
+    moof.trafs[0].saiz = None;
+    let mut mp4_file = Mp4File::new(Cursor::new(write_file(&moov, &mut moof)));
+    assert!(mp4_file.read_header().await.unwrap());
+    assert!(mp4_file.tracks[&1].samples[0].encryption.is_none());
+    assert_eq!(mp4_file.warnings.len(), 1);
+    assert_eq!(mp4_file.warnings[0].path, "moof[0]/traf[0]");
+}
+
+#[tokio::test]
+async fn test_write_encrypted_fragments() {
+    let config = common::config(&["iso6", "cenc"]);
+
+    let pssh = PsshBox {
+        version: 1,
+        system_id: [0x10; 16],
+        kids: vec![[0x42; 16]],
+        ..Default::default()
+    };
+
+    let track_config = common::avc_track(1000);
+    let cenc_config = CencConfig {
+        scheme: ProtectionScheme::Cenc,
+        kid: [0x42; 16],
+        key: [0x2b; 16],
+        iv: vec![1, 2, 3, 4, 5, 6, 7, 8],
+        psshs: vec![pssh],
+    };
+
+    let mut writer = Mp4FragmentWriter::write_start(Vec::new(), &config).unwrap();
+    let track_id = writer.add_track(&track_config).unwrap();
+    writer.set_track_protection(track_id, &cenc_config).unwrap();
+
+    let mut samples = Vec::new();
+    for idx in 0..20u64 {
+        let nal_type = if idx % 10 == 0 { 0x65 } else { 0x41 };
+        let payload = (0..40 + idx as usize).map(|x| x as u8);
+        let mut bytes = (41 + idx as u32).to_be_bytes().to_vec();
+        bytes.push(nal_type);
+        bytes.extend(payload);
+        samples.push(bytes.clone());
+
+        writer
+            .write_sample(
+                track_id,
+                &common::sample(idx * 40, 40, idx % 10 == 0, bytes),
+            )
+            .unwrap();
+    }
+
+    let data = writer.write_end().unwrap();
+
+    let mut mp4_file = Mp4File::new(Cursor::new(data));
+    assert!(mp4_file.read_header().await.unwrap());
+
+    let track = &mp4_file.tracks[&track_id];
+    let sinf = track.protection().unwrap();
+    assert_eq!(sinf.scheme_type(), Some(str::parse("cenc").unwrap()));
+    assert_eq!(sinf.frma.original_format, BoxType::Avc1Box.into());
+    assert_eq!(track.samples.len(), 20);
+
+    let info = track.samples[1].encryption.as_ref().unwrap();
+    assert_eq!(info.iv, vec![1, 2, 3, 4, 5, 6, 7, 9]);
+    assert_eq!(
+        info.subsamples,
+        vec![SubsampleEncryption {
+            bytes_of_clear_data: 4 + 1 + 9,
+            bytes_of_protected_data: 32,
+        }]
+    );
+
+    let encrypted = mp4_file
+        .read_sample_data(track_id, 1)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_ne!(encrypted.as_ref(), samples[1].as_slice());
+
+    mp4_file.add_decryption_key([0x42; 16], [0x2b; 16]);
+    for (idx, sample) in samples.iter().enumerate() {
+        let data = mp4_file
+            .read_sample_data(track_id, idx)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(data.as_ref(), sample.as_slice());
+    }
+
+    // the auxiliary info of 45 subsamples doesn't fit the 8 bit saiz sizes
+    let mut writer = Mp4FragmentWriter::write_start(Vec::new(), &config).unwrap();
+    let track_id = writer.add_track(&track_config).unwrap();
+    writer.set_track_protection(track_id, &cenc_config).unwrap();
+
+    let mut bytes = Vec::new();
+    for _ in 0..45 {
+        bytes.extend_from_slice(&[0, 0, 0, 33, 0x41]);
+        bytes.extend_from_slice(&[0; 32]);
+    }
+    writer
+        .write_sample(track_id, &common::sample(0, 40, true, bytes))
+        .unwrap();
+    assert!(matches!(writer.write_end(), Err(Error::InvalidData(_))));
+}
+
+#[tokio::test]
+async fn test_write_chapters() {
+    let config = common::config(&["isom", "mp41"]);
+
+    let mut writer = Mp4FragmentWriter::write_start(Vec::new(), &config).unwrap();
+    writer
+        .set_chapters(&[
+            Chapter {
+                start: Duration::from_secs(2),
+                title: "Chapter 2".to_string(),
+            },
+            Chapter {
+                start: Duration::ZERO,
+                title: "Intro".to_string(),
+            },
+        ])
+        .unwrap();
+
+    let track_id = writer.add_track(&common::aac_track(48000)).unwrap();
+
+    for idx in 0..200u64 {
+        writer
+            .write_sample(
+                track_id,
+                &common::sample(idx * 1024, 1024, true, vec![idx as u8; 8]),
+            )
+            .unwrap();
+    }
+
+    let data = writer.write_end().unwrap();
+
+    let mut mp4_file = Mp4File::new(Cursor::new(data.clone()));
+    assert!(mp4_file.read_header().await.unwrap());
+
+    let chpl = mp4_file.udta.as_ref().unwrap().chpl.as_ref().unwrap();
+    assert_eq!(chpl.chapters.len(), 2);
+    assert_eq!(chpl.chapters[1].start, 20_000_000);
+
+    let chapter_track = &mp4_file.tracks[&1];
+    assert_eq!(chapter_track.tkhd.flags, 0);
+    assert_eq!(chapter_track.samples.len(), 2);
+    assert_eq!(chapter_track.samples[0].duration, 2000);
+
+    // the titles are indexed by the moov, in an mdat of their own ahead of it
+    let layout = BoxTree::parse(Bytes::from(data)).unwrap();
+    let mdat = &layout.boxes[1];
+    assert_eq!(mdat.kind, BoxType::MdatBox);
+    assert_eq!(layout.boxes[2].kind, BoxType::MoovBox);
+    assert_eq!(chapter_track.samples[0].offset, mdat.data_offset());
+    assert_eq!(
+        chapter_track.samples[1].offset + chapter_track.samples[1].size as u64,
+        mdat.end()
+    );
+
+    let chapters = mp4_file.chapters().await.unwrap();
+    assert_eq!(
+        chapters,
+        vec![
+            Chapter {
+                start: Duration::ZERO,
+                title: "Intro".to_string(),
+            },
+            Chapter {
+                start: Duration::from_secs(2),
+                title: "Chapter 2".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_write_too_many_chapters() {
+    let config = common::config(&["isom"]);
+
+    let chapters = (0..256)
+        .map(|idx| Chapter {
+            start: Duration::from_secs(idx),
+            title: format!("Chapter {idx}"),
+        })
+        .collect::<Vec<_>>();
+
+    let mut writer = Mp4FragmentWriter::write_start(Vec::new(), &config).unwrap();
+    assert!(matches!(
+        writer.set_chapters(&chapters),
+        Err(Error::InvalidData(_))
+    ));
+    writer.set_chapters(&chapters[..255]).unwrap();
+}
+
+#[tokio::test]
+async fn test_write_track_references() {
+    let config = common::config(&["isom"]);
+
+    let track_config = common::aac_track(48000);
+
+    let mut writer = Mp4FragmentWriter::write_start(Vec::new(), &config).unwrap();
+    let main_id = writer.add_track(&track_config).unwrap();
+    let described_id = writer.add_track(&track_config).unwrap();
+
+    assert!(writer
+        .add_track_reference(described_id, ReferenceType::ContentDescribes, 7)
+        .is_err());
+    writer
+        .add_track_reference(described_id, ReferenceType::ContentDescribes, main_id)
+        .unwrap();
+    writer
+        .add_track_reference(described_id, ReferenceType::Sync, main_id)
+        .unwrap();
+
+    for track_id in [main_id, described_id] {
+        writer
+            .write_sample(track_id, &common::sample(0, 1024, true, vec![0; 8]))
+            .unwrap();
+    }
+
+    writer.flush_fragment().unwrap();
+    assert!(writer
+        .add_track_reference(main_id, ReferenceType::Thumbnail, described_id)
+        .is_err());
+
+    let data = writer.write_end().unwrap();
+
+    let mut mp4_file = Mp4File::new(Cursor::new(data));
+    assert!(mp4_file.read_header().await.unwrap());
+
+    assert!(mp4_file.tracks[&main_id].references().is_empty());
+
+    let described = &mp4_file.tracks[&described_id];
+    let references = described.references();
+    assert_eq!(references.len(), 2);
+    assert_eq!(references[&ReferenceType::ContentDescribes], vec![main_id]);
+    assert_eq!(described.referenced_tracks(ReferenceType::Sync), &[main_id]);
+}
+
+#[tokio::test]
+async fn test_write_srt_track() {
+    let srt = "1\n00:00:00,500 --> 00:00:01,500\nFirst line\nSecond line\n\n\
+               2\n00:00:02,000 --> 00:00:03,250\nLast\n";
+
+    let config = common::config(&["isom"]);
+
+    let mut writer = Mp4FragmentWriter::write_start(Vec::new(), &config).unwrap();
+    let subtitle_id = writer.add_srt_track(srt, "eng").unwrap();
+    let audio_id = writer.add_track(&common::aac_track(48000)).unwrap();
+
+    for idx in 0..10u64 {
+        writer
+            .write_sample(
+                audio_id,
+                &common::sample(idx * 1024, 1024, true, vec![idx as u8; 8]),
+            )
+            .unwrap();
+    }
+
+    let data = writer.write_end().unwrap();
+
+    let mut mp4_file = Mp4File::new(Cursor::new(data));
+    assert!(mp4_file.read_header().await.unwrap());
+
+    let track = &mp4_file.tracks[&subtitle_id];
+    assert_eq!(track.track_type(), TrackType::Subtitle);
+    // leading gap, first cue, gap, last cue
+    assert_eq!(track.samples.len(), 4);
+    assert_eq!(track.samples[3].start_time, 2000);
+    assert_eq!(track.samples[3].duration, 1250);
+
+    let exported = mp4_file
+        .export_subtitles(subtitle_id, SubtitleFormat::Srt)
+        .await
+        .unwrap();
+    assert_eq!(exported, format!("{srt}\n"));
+
+    let vtt = mp4_file
+        .export_subtitles(subtitle_id, SubtitleFormat::WebVtt)
+        .await
+        .unwrap();
+    assert!(vtt.starts_with("WEBVTT\n\n00:00:00.500 --> 00:00:01.500\nFirst line\n"));
+
+    assert!(mp4_file
+        .export_subtitles(audio_id, SubtitleFormat::Srt)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_write_webvtt_and_ttml_tracks() {
+    let vtt = "WEBVTT\n\nSTYLE\n::cue { color: yellow }\n\n\
+               intro\n00:00.500 --> 00:02.000 line:0\nFirst\n\n\
+               00:01.000 --> 00:03.000\nSecond\n";
+    let ttml = "<tt xmlns=\"http://www.w3.org/ns/ttml\"><body><div><p>Hi</p></div></body></tt>";
+
+    let config = common::config(&["iso6", "cmfc"]);
+
+    let mut writer = Mp4FragmentWriter::write_start(Vec::new(), &config).unwrap();
+    let vtt_id = writer.add_webvtt_track(vtt, "eng").unwrap();
+    let ttml_id = writer
+        .add_track(&TrackConfig {
+            track_type: TrackType::IsoSubtitle,
+            timescale: 1000,
+            language: "eng".to_string(),
+            media_conf: MediaConfig::TtmlConfig(TtmlConfig::default()),
+        })
+        .unwrap();
+
+    writer
+        .write_sample(
+            ttml_id,
+            &common::sample(0, 3000, true, ttml.as_bytes().to_vec()),
+        )
+        .unwrap();
+
+    let data = writer.write_end().unwrap();
+
+    let mut mp4_file = Mp4File::new(Cursor::new(data));
+    assert!(mp4_file.read_header().await.unwrap());
+
+    let track = &mp4_file.tracks[&vtt_id];
+    let wvtt = track.mdia.minf.stbl.stsd.wvtt.as_ref().unwrap();
+    assert_eq!(wvtt.config, "WEBVTT\n\nSTYLE\n::cue { color: yellow }");
+    // empty, first, first + second, second
+    assert_eq!(track.samples.len(), 4);
+    assert_eq!(track.samples[2].start_time, 1000);
+    assert_eq!(track.samples[2].duration, 1000);
+
+    let cues = mp4_file.subtitle_cues(vtt_id).await.unwrap();
+    assert_eq!(cues.len(), 2);
+    assert_eq!(cues[0].id.as_deref(), Some("intro"));
+    assert_eq!(cues[0].end, Duration::from_secs(2));
+    assert_eq!(cues[1].start, Duration::from_secs(1));
+    assert_eq!(cues[1].end, Duration::from_secs(3));
+
+    let exported = mp4_file
+        .export_subtitles(vtt_id, SubtitleFormat::WebVtt)
+        .await
+        .unwrap();
+    assert_eq!(
+        exported,
+        "WEBVTT\n\nintro\n00:00:00.500 --> 00:00:02.000 line:0\nFirst\n\n\
+         00:00:01.000 --> 00:00:03.000\nSecond\n\n"
+    );
+
+    let vtt_track = &mp4_file.tracks[&vtt_id];
+    assert_eq!(vtt_track.track_type(), TrackType::Text);
+    assert!(vtt_track.mdia.minf.nmhd.is_some());
+
+    let ttml_track = &mp4_file.tracks[&ttml_id];
+    assert_eq!(ttml_track.track_type(), TrackType::IsoSubtitle);
+    assert!(ttml_track.mdia.minf.sthd.is_some());
+
+    let stpp = ttml_track.mdia.minf.stbl.stsd.stpp.as_ref();
+    assert_eq!(stpp.unwrap().namespace, "http://www.w3.org/ns/ttml");
+    let sample = mp4_file
+        .read_sample_data(ttml_id, 0)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(&sample[..], ttml.as_bytes());
+}
+
+/// H.264 sample with an ATSC A/53 caption SEI carrying CEA-608 field 1 byte pairs.
+fn caption_sample(pairs: &[[u8; 2]]) -> Vec<u8> {
+    let parity = |b: u8| if b.count_ones() % 2 == 1 { b } else { b | 0x80 };
+
+    let mut payload = vec![0xb5, 0x00, 0x31, b'G', b'A', b'9', b'4', 0x03];
+    payload.extend_from_slice(&[0x40 | pairs.len() as u8, 0xff]);
+    for pair in pairs {
+        payload.extend_from_slice(&[0xfc, parity(pair[0]), parity(pair[1])]);
+    }
+
+    let mut sei = vec![0x06, 0x04, payload.len() as u8];
+    sei.extend_from_slice(&payload);
+    sei.push(0x80);
+
+    let slice = [0x65, 0x88, 0x84, 0x00];
+
+    let mut sample = Vec::new();
+    for nal in [&sei[..], &slice[..]] {
+        sample.extend_from_slice(&(nal.len() as u32).to_be_bytes());
+        sample.extend_from_slice(nal);
+    }
+    sample
+}
+
+#[tokio::test]
+async fn test_read_sei_captions() {
+    let config = common::config(&["isom"]);
+
+    let mut writer = Mp4FragmentWriter::write_start(Vec::new(), &config).unwrap();
+    let video_id = writer.add_track(&common::avc_track(1000)).unwrap();
+
+    // RCL, PAC row 15, "HI", EOC, then EDM
+    let frames: [&[[u8; 2]]; 4] = [
+        &[[0x14, 0x20], [0x14, 0x70]],
+        &[[b'H', b'I'], [0x14, 0x2f]],
+        &[],
+        &[[0x14, 0x2c]],
+    ];
+
+    for (idx, pairs) in frames.iter().enumerate() {
+        writer
+            .write_sample(
+                video_id,
+                &common::sample(idx as u64 * 1000, 1000, idx == 0, caption_sample(pairs)),
+            )
+            .unwrap();
+    }
+
+    let data = writer.write_end().unwrap();
+
+    let mut mp4_file = Mp4File::new(Cursor::new(data));
+    assert!(mp4_file.read_header().await.unwrap());
+
+    let packets = mp4_file.caption_packets(video_id).await.unwrap();
+    assert_eq!(packets.len(), 3);
+    assert_eq!(packets[1].pts, 1000);
+    assert_eq!(packets[1].cc_data.len(), 2);
+
+    let cues = mp4_file
+        .closed_captions(video_id, CaptionChannel::Cc1)
+        .await
+        .unwrap();
+    assert_eq!(
+        SubtitleFormat::Srt.write(&cues),
+        "1\n00:00:01,000 --> 00:00:03,000\nHI\n\n"
+    );
+
+    // remux the captions as a c608 track
+    let mut writer = Mp4FragmentWriter::write_start(Vec::new(), &config).unwrap();
+    let c608_id = writer.add_c608_track(&packets, 1000).unwrap();
+    let data = writer.write_end().unwrap();
+
+    let mut mp4_file = Mp4File::new(Cursor::new(data));
+    assert!(mp4_file.read_header().await.unwrap());
+    assert!(mp4_file.tracks[&c608_id].mdia.minf.stbl.stsd.c608.is_some());
+    assert_eq!(mp4_file.caption_packets(c608_id).await.unwrap(), packets);
+    assert_eq!(
+        mp4_file
+            .closed_captions(c608_id, CaptionChannel::Cc1)
+            .await
+            .unwrap(),
+        cues
+    );
+
+    // a packet lasting longer than u32::MAX timescale units fails instead of wrapping
+    let mut writer = Mp4FragmentWriter::write_start(Vec::new(), &config).unwrap();
+    let mut late = packets[0].clone();
+    late.pts += 1 << 32;
+    assert!(writer
+        .add_c608_track(&[packets[0].clone(), late], 1000)
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_samples_stream() {
+    let config = common::config(&["isom"]);
+
+    let mut writer = Mp4FragmentWriter::write_start(Vec::new(), &config).unwrap();
+    let video_id = writer.add_track(&common::avc_track(90000)).unwrap();
+    let audio_id = writer.add_track(&common::aac_track(48000)).unwrap();
+
+    // 25 fps video and 1024 sample AAC frames over two seconds
+    let mut expected = Vec::new();
+    for idx in 0..50u64 {
+        expected.push((idx * 3600 * 48000, video_id, idx));
+    }
+    for idx in 0..94u64 {
+        expected.push((idx * 1024 * 90000, audio_id, idx));
+    }
+    expected.sort();
+
+    for &(_, track_id, idx) in &expected {
+        let (start_time, duration) = if track_id == video_id {
+            (idx * 3600, 3600)
+        } else {
+            (idx * 1024, 1024)
+        };
+
+        writer
+            .write_sample(
+                track_id,
+                &common::sample(
+                    start_time,
+                    duration,
+                    track_id == audio_id || idx % 25 == 0,
+                    vec![track_id as u8, idx as u8, 0, 1],
+                ),
+            )
+            .unwrap();
+    }
+
+    let data = writer.write_end().unwrap();
+
+    let mut mp4_file = Mp4File::new(Cursor::new(data));
+    assert!(mp4_file.read_header().await.unwrap());
+
+    let samples = mp4_file
+        .samples(&[])
+        .map(|s| s.unwrap())
+        .collect::<Vec<_>>()
+        .await;
+
+    assert_eq!(samples.len(), expected.len());
+    for ((track_id, sample), &(_, expected_id, idx)) in samples.iter().zip(&expected) {
+        assert_eq!(*track_id, expected_id);
+        assert_eq!(&sample.bytes[..2], &[expected_id as u8, idx as u8]);
+    }
+
+    let video = mp4_file
+        .samples(&[video_id])
+        .map(|s| s.unwrap())
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(video.len(), 50);
+    assert!(video[25].1.is_sync);
+    assert_eq!(video[49].1.start_time, 49 * 3600);
+
+    // a sample pointing into the ftyp is an error, not silently skipped
+    mp4_file.tracks.get_mut(&video_id).unwrap().samples[3].offset = 0;
+    let video = mp4_file.samples(&[video_id]).collect::<Vec<_>>().await;
+    assert_eq!(video.len(), 4);
+    assert!(video[..3].iter().all(Result::is_ok));
+    let err = video[3].as_ref().unwrap_err();
+    assert!(matches!(err.root(), Error::InvalidData(_)));
+    assert_eq!(err.context().unwrap().track_id, Some(video_id));
+}
+
+#[derive(Debug, PartialEq)]
+struct FirmwareBox {
+    version: String,
+}
+
+impl Mp4Box for FirmwareBox {
+    const TYPE: BoxType = BoxType::UnknownBox(u32::from_be_bytes(*b"FIRM"));
+
+    fn box_size(&self) -> u64 {
+        HEADER_SIZE + self.version.len() as u64
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        Ok(format!("{{\"version\":{:?}}}", self.version))
+    }
+
+    fn summary(&self) -> Result<String, Error> {
+        Ok(format!("version={}", self.version))
+    }
+}
+
+impl BlockReader for FirmwareBox {
+    fn read_block<'a>(reader: &mut impl Reader<'a>) -> Result<Self, Error> {
+        let data = reader.collect_remaining_bytes();
+
+        Ok(FirmwareBox {
+            version: String::from_utf8(data.to_vec())
+                .map_err(|_| Error::InvalidData("invalid firmware version"))?,
+        })
+    }
+
+    fn size_hint() -> usize {
+        0
+    }
+}
+
+impl<W: std::io::Write> WriteBox<&mut W> for FirmwareBox {
+    fn write_box(&self, writer: &mut W) -> Result<u64, Error> {
+        let size = self.box_size();
+        BoxHeader::new(Self::TYPE, size).write(writer)?;
+        writer.write_all(self.version.as_bytes())?;
+        Ok(size)
+    }
+}
+
+#[tokio::test]
+async fn test_box_registry() {
+    let moov = MoovBox {
+        udta: Some(UdtaBox {
+            unknown: vec![UnknownBox::Raw(
+                FirmwareBox::TYPE,
+                Bytes::from_static(b"HD9.01.01.60.00"),
+            )]
+            .into(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let mut data = Vec::new();
+    moov.write_box(&mut data).unwrap();
+
+    let mut registry = BoxRegistry::new();
+    registry.register::<FirmwareBox>(&[BoxType::MoovBox, BoxType::UdtaBox]);
+
+    let mut mp4_file = Mp4File::new(Cursor::new(data));
+    mp4_file.set_box_registry(registry);
+    mp4_file.read_header().await.unwrap();
+
+    let udta = mp4_file.udta.as_ref().unwrap();
+    let firmware = udta
+        .unknown
+        .iter()
+        .find_map(|bx| bx.downcast_ref::<FirmwareBox>())
+        .unwrap();
+    assert_eq!(firmware.version, "HD9.01.01.60.00");
+}
+
+#[test]
+fn test_box_tree() {
+    let mut f = std::fs::File::open("./assets/videos/cosmoc.mp4f").unwrap();
+    let tree = flowly_mp4::blocking::read_box_tree(&mut f).unwrap();
+
+    let data = std::fs::read("./assets/videos/cosmoc.mp4f").unwrap();
+    assert_eq!(BoxTree::parse(Bytes::from(data.clone())).unwrap(), tree);
+    assert_eq!(tree.boxes.last().unwrap().end(), data.len() as u64);
+
+    let mfro = tree.find("mfra/mfro").unwrap();
+    assert_eq!(mfro.end(), data.len() as u64);
+
+    let moofs = tree.find_all("moof");
+    assert_eq!(moofs.len(), 11);
+    assert_eq!(tree.find_all("mfra/tfra").len(), 2);
+    for moof in moofs {
+        let trun = moof.find("traf/trun").unwrap();
+        assert!(trun.fields.is_some());
+    }
+}
+
+#[test]
+fn test_validate() {
+    let mut f = std::fs::File::open("./assets/videos/cosmoc.mp4f").unwrap();
+    let diagnostics = flowly_mp4::blocking::validate(&mut f).unwrap();
+    assert_eq!(diagnostics, vec![]);
+
+    // cut in the middle of the last fragment's mdat
+    let data = std::fs::read("./assets/videos/cosmoc.mp4f").unwrap();
+    let tree = BoxTree::parse(Bytes::from(data.clone())).unwrap();
+    let mdat = tree.find_all("mdat").pop().unwrap().clone();
+    let cut = &data[..mdat.data_offset() as usize + 16];
+
+    let diagnostics = flowly_mp4::blocking::validate(&mut std::io::Cursor::new(cut)).unwrap();
+    assert_eq!(diagnostics.len(), 3);
+    assert!(diagnostics.iter().all(|d| d.severity == Severity::Error));
+    assert_eq!(diagnostics[0].path, "mdat[10]");
+    assert_eq!(diagnostics[0].offset, mdat.offset);
+    assert_eq!(diagnostics[1].path, "moof[10]/traf[0]/trun");
+    assert_eq!(diagnostics[2].path, "moof[10]/traf[1]/trun");
+}
+
+#[test]
+fn test_error_context() {
+    let mut data = std::fs::read("./assets/videos/cosmoc.mp4f").unwrap();
+    let tree = BoxTree::parse(Bytes::from(data.clone())).unwrap();
+
+    // five samples of their own size in a stsz box without room for them
+    let stsz = tree.find("moov/trak[1]/mdia/minf/stbl/stsz").unwrap();
+    let body = stsz.data_offset() as usize;
+    data[body + 4..body + 8].copy_from_slice(&0u32.to_be_bytes());
+    data[body + 8..body + 12].copy_from_slice(&5u32.to_be_bytes());
+
+    let mut mp4_file = flowly_mp4::blocking::Mp4File::new(std::io::Cursor::new(data));
+    let err = mp4_file.read_header().unwrap_err();
+
+    assert!(matches!(err.root(), Error::InvalidData(_)));
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "moov/trak[1]/mdia/minf/stbl/stsz @ {:#x}: stsz sample_count indicates more values than could fit in the box",
+            body + 12
+        )
+    );
+}
+
+#[test]
+fn test_repair() {
+    let data = std::fs::read("./assets/videos/cosmoc.mp4f").unwrap();
+    let tree = BoxTree::parse(Bytes::from(data.clone())).unwrap();
+
+    // a recording cut before its moov: ftyp and a single mdat holding every sample,
+    // with the size left at 0
+    let mut damaged = data[..tree.find("ftyp").unwrap().end() as usize].to_vec();
+    damaged.extend_from_slice(&[0, 0, 0, 0]);
+    damaged.extend_from_slice(b"mdat");
+    for mdat in tree.find_all("mdat") {
+        damaged.extend_from_slice(&data[mdat.data_offset() as usize..mdat.end() as usize]);
+    }
+
+    let mut reference = flowly_mp4::blocking::Mp4File::new(Cursor::new(data));
+    reference.read_header().unwrap();
+
+    let expected = [1, 2].map(|track_id| {
+        reference
+            .samples(&[track_id])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    });
+
+    let (repaired, report) =
+        flowly_mp4::blocking::repair(&reference, &mut Cursor::new(&damaged), Vec::new()).unwrap();
+    assert_eq!(report.skipped_bytes, 0);
+    assert_eq!(report.samples[&1], expected[0].len());
+    assert_eq!(report.samples[&2], expected[1].len());
+
+    // a plain file: the moov indexes the samples of a single mdat
+    let layout = BoxTree::parse(Bytes::from(repaired.clone())).unwrap();
+    assert!(layout.find("moof").is_none());
+    assert_eq!(layout.find_all("mdat").len(), 1);
+
+    let mut repaired = flowly_mp4::blocking::Mp4File::new(Cursor::new(repaired));
+    repaired.read_header().unwrap();
+
+    for (track_id, expected) in [1, 2].into_iter().zip(&expected) {
+        let samples = repaired
+            .samples(&[track_id])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(samples.len(), expected.len());
+        for ((_, sample), (_, expected)) in samples.iter().zip(expected) {
+            assert_eq!(sample.bytes, expected.bytes);
+            assert_eq!(sample.is_sync, expected.is_sync);
+        }
+    }
+
+    // the last audio frame cut in half by the power loss is dropped
+    let last = expected[1].last().unwrap().1.bytes.len();
+    damaged.truncate(damaged.len() - last / 2);
+
+    let (_, report) =
+        flowly_mp4::blocking::repair(&reference, &mut Cursor::new(&damaged), Vec::new()).unwrap();
+    assert_eq!(report.samples[&1], expected[0].len());
+    assert_eq!(report.samples[&2], expected[1].len() - 1);
+    assert_eq!(report.skipped_bytes, (last - last / 2) as u64);
+
+    // noise holds no samples
+    let mut noise = damaged[..tree.find("ftyp").unwrap().end() as usize].to_vec();
+    noise.extend_from_slice(&[0, 0, 0, 0]);
+    noise.extend_from_slice(b"mdat");
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    for _ in 0..1 << 20 {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        noise.push(state as u8);
+    }
+
+    let (_, report) =
+        flowly_mp4::blocking::repair(&reference, &mut Cursor::new(&noise), Vec::new()).unwrap();
+    assert!(report.samples.values().sum::<usize>() < 10);
+}